@@ -0,0 +1,7 @@
+-- Add migration script here
+ALTER TABLE todos
+    ADD COLUMN due_date TIMESTAMP NULL DEFAULT NULL,
+    ADD COLUMN recurrence_rule VARCHAR(255) NULL,
+    ADD COLUMN series_id VARCHAR(36) NULL,
+    ADD COLUMN occurrence_index INT NOT NULL DEFAULT 1,
+    ADD INDEX idx_todos_series_id (series_id);
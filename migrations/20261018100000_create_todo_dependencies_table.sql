@@ -0,0 +1,9 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS todo_dependencies (
+    todo_id VARCHAR(36) NOT NULL,
+    blocked_by_id VARCHAR(36) NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (todo_id, blocked_by_id),
+    CONSTRAINT fk_dependency_todo FOREIGN KEY (todo_id) REFERENCES todos(id) ON DELETE CASCADE,
+    CONSTRAINT fk_dependency_blocker FOREIGN KEY (blocked_by_id) REFERENCES todos(id) ON DELETE CASCADE
+);
@@ -0,0 +1,36 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS workflows (
+    id VARCHAR(36) PRIMARY KEY,
+    user_id VARCHAR(36) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    is_default BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    CONSTRAINT fk_workflow_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS workflow_statuses (
+    id VARCHAR(36) PRIMARY KEY,
+    workflow_id VARCHAR(36) NOT NULL,
+    status_key VARCHAR(64) NOT NULL,
+    name VARCHAR(255) NOT NULL,
+    category ENUM('todo', 'doing', 'done') NOT NULL,
+    position INT NOT NULL,
+    UNIQUE KEY uq_workflow_status_key (workflow_id, status_key),
+    CONSTRAINT fk_status_workflow FOREIGN KEY (workflow_id) REFERENCES workflows(id) ON DELETE CASCADE
+);
+
+CREATE TABLE IF NOT EXISTS workflow_transitions (
+    workflow_id VARCHAR(36) NOT NULL,
+    from_key VARCHAR(64) NOT NULL,
+    to_key VARCHAR(64) NOT NULL,
+    PRIMARY KEY (workflow_id, from_key, to_key),
+    CONSTRAINT fk_transition_workflow FOREIGN KEY (workflow_id) REFERENCES workflows(id) ON DELETE CASCADE
+);
+
+-- todos.status keeps holding the category of the custom status (todo -> pending,
+-- doing -> in_process, done -> completed)
+ALTER TABLE todos
+    ADD COLUMN workflow_status_id VARCHAR(36) NULL,
+    ADD CONSTRAINT fk_todo_workflow_status FOREIGN KEY (workflow_status_id)
+        REFERENCES workflow_statuses(id) ON DELETE SET NULL;
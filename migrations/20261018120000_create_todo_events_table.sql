@@ -0,0 +1,13 @@
+-- Add migration script here
+CREATE TABLE IF NOT EXISTS todo_events (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    todo_id VARCHAR(36) NOT NULL,
+    actor_id VARCHAR(36) NULL,
+    field VARCHAR(64) NOT NULL,
+    old_value TEXT NULL,
+    new_value TEXT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    INDEX idx_todo_events_todo (todo_id, created_at),
+    CONSTRAINT fk_event_todo FOREIGN KEY (todo_id) REFERENCES todos(id) ON DELETE CASCADE,
+    CONSTRAINT fk_event_actor FOREIGN KEY (actor_id) REFERENCES users(id) ON DELETE SET NULL
+);
@@ -0,0 +1,4 @@
+-- Add migration script here
+ALTER TABLE todos
+    ADD COLUMN deleted_at TIMESTAMP NULL DEFAULT NULL,
+    ADD INDEX idx_todos_deleted_at (deleted_at);
@@ -0,0 +1,12 @@
+-- Add migration script here
+ALTER TABLE todos
+    ADD COLUMN archived_at TIMESTAMP NULL DEFAULT NULL,
+    ADD INDEX idx_todos_archived_at (archived_at);
+
+CREATE TABLE IF NOT EXISTS user_settings (
+    user_id VARCHAR(36) PRIMARY KEY,
+    auto_archive_days INT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    CONSTRAINT fk_settings_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
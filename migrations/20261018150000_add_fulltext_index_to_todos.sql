@@ -0,0 +1,2 @@
+-- Add migration script here
+ALTER TABLE todos ADD FULLTEXT INDEX ft_todos_title_description (title, description);
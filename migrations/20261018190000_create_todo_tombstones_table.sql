@@ -0,0 +1,10 @@
+-- Add migration script here
+-- Remembers permanently deleted todos so delta sync can report them
+CREATE TABLE IF NOT EXISTS todo_tombstones (
+    todo_id VARCHAR(36) NOT NULL,
+    user_id VARCHAR(36) NOT NULL,
+    deleted_at TIMESTAMP NOT NULL,
+    PRIMARY KEY (todo_id, deleted_at),
+    INDEX idx_todo_tombstones_user_deleted_at (user_id, deleted_at),
+    CONSTRAINT fk_tombstone_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
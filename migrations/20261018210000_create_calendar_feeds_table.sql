@@ -0,0 +1,9 @@
+-- Add migration script here
+-- One calendar feed per user; only a hash of the URL token is stored
+CREATE TABLE IF NOT EXISTS calendar_feeds (
+    user_id VARCHAR(36) PRIMARY KEY,
+    token_hash CHAR(64) NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE KEY uq_calendar_feeds_token_hash (token_hash),
+    CONSTRAINT fk_calendar_feed_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
@@ -0,0 +1,12 @@
+-- Add migration script here
+-- CalDAV clients pick their own resource names and UIDs; remember them so the
+-- todo is served back under the same href. Rows outlive purged todos so
+-- sync-collection can still report the deletion under the client's href.
+CREATE TABLE IF NOT EXISTS caldav_resources (
+    todo_id VARCHAR(36) PRIMARY KEY,
+    user_id VARCHAR(36) NOT NULL,
+    resource_name VARCHAR(255) NOT NULL,
+    uid VARCHAR(255) NOT NULL,
+    UNIQUE KEY uq_caldav_resources_name (user_id, resource_name),
+    CONSTRAINT fk_caldav_resource_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
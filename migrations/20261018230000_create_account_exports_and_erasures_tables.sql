@@ -0,0 +1,28 @@
+-- Add migration script here
+-- Data export requests; the archive is built by a background job and kept in
+-- the attachment storage backend under storage_key until the download link
+-- expires
+CREATE TABLE IF NOT EXISTS account_exports (
+    id VARCHAR(36) PRIMARY KEY,
+    user_id VARCHAR(36) NOT NULL,
+    status ENUM('pending', 'processing', 'ready', 'failed', 'expired') NOT NULL DEFAULT 'pending',
+    storage_key VARCHAR(255) NULL,
+    size_bytes BIGINT NULL,
+    error TEXT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    started_at TIMESTAMP NULL,
+    completed_at TIMESTAMP NULL,
+    expires_at TIMESTAMP NULL,
+    INDEX idx_account_exports_status (status, created_at),
+    INDEX idx_account_exports_user (user_id, created_at),
+    CONSTRAINT fk_account_export_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
+
+-- Scheduled account erasures, carried out once the grace period has passed
+CREATE TABLE IF NOT EXISTS account_erasures (
+    user_id VARCHAR(36) PRIMARY KEY,
+    requested_at TIMESTAMP NOT NULL,
+    scheduled_for TIMESTAMP NOT NULL,
+    INDEX idx_account_erasures_scheduled_for (scheduled_for),
+    CONSTRAINT fk_account_erasure_user FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE
+);
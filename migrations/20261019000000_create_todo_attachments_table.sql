@@ -0,0 +1,16 @@
+-- Add migration script here
+-- No foreign keys: rows outlive their todo or user until the cleanup job has
+-- deleted the stored file
+CREATE TABLE IF NOT EXISTS todo_attachments (
+    id VARCHAR(36) PRIMARY KEY,
+    todo_id VARCHAR(36) NOT NULL,
+    user_id VARCHAR(36) NOT NULL,
+    file_name VARCHAR(255) NOT NULL,
+    content_type VARCHAR(100) NOT NULL,
+    size_bytes BIGINT NOT NULL,
+    sha256 CHAR(64) NOT NULL,
+    storage_key VARCHAR(255) NOT NULL,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP,
+    INDEX idx_todo_attachments_todo (todo_id, created_at),
+    INDEX idx_todo_attachments_user (user_id)
+);
@@ -0,0 +1,12 @@
+-- Add migration script here
+-- Todo changes waiting to be relayed to webhooks and connected clients. Rows
+-- are written in the transaction that changes the todo and removed once relayed.
+CREATE TABLE IF NOT EXISTS todo_change_outbox (
+    id BIGINT AUTO_INCREMENT PRIMARY KEY,
+    todo_id VARCHAR(36) NOT NULL,
+    user_id VARCHAR(36) NOT NULL,
+    kind VARCHAR(20) NOT NULL,
+    -- The todo as it was, for changes that remove its row
+    snapshot MEDIUMTEXT NULL,
+    created_at TIMESTAMP NOT NULL
+);
@@ -0,0 +1,7 @@
+-- Add migration script here
+-- Stored files of deleted attachments, waiting for the cleanup job to delete
+-- them from storage
+CREATE TABLE IF NOT EXISTS attachment_file_deletions (
+    storage_key VARCHAR(255) PRIMARY KEY,
+    created_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+);
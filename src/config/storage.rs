@@ -0,0 +1,31 @@
+use std::sync::Arc;
+
+use anyhow::{Context, Result, bail};
+
+use crate::{
+    storage::{AttachmentStorage, local_storage::LocalStorage, s3_storage::S3Storage},
+    utils::get_env_vars::{get_env_var_or, require_env_var},
+};
+
+// STORAGE_BACKEND picks where attachments are kept: "local" (the default,
+// below ATTACHMENTS_DIR) or "s3", configured by the S3_* variables
+pub fn create_attachment_storage() -> Result<Arc<dyn AttachmentStorage>> {
+    match get_env_var_or("STORAGE_BACKEND", "local").as_str() {
+        "local" => Ok(Arc::new(LocalStorage::new(get_env_var_or(
+            "ATTACHMENTS_DIR",
+            "./data/attachments",
+        )))),
+        "s3" => Ok(Arc::new(s3_storage().context("STORAGE_BACKEND is 's3'")?)),
+        other => bail!("Unknown STORAGE_BACKEND '{}'", other),
+    }
+}
+
+fn s3_storage() -> Result<S3Storage> {
+    S3Storage::new(
+        &require_env_var("S3_ENDPOINT")?,
+        require_env_var("S3_BUCKET")?,
+        get_env_var_or("S3_REGION", "us-east-1"),
+        require_env_var("S3_ACCESS_KEY_ID")?,
+        require_env_var("S3_SECRET_ACCESS_KEY")?,
+    )
+}
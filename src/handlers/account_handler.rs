@@ -0,0 +1,293 @@
+use actix_web::{
+    HttpRequest, HttpResponse, Responder,
+    http::header::{self, ContentDisposition, DispositionParam, DispositionType},
+    web,
+};
+use bcrypt::verify;
+use chrono::Utc;
+use sqlx::MySqlPool;
+use uuid::Uuid;
+
+use crate::{
+    middleware::auth_middleware::{AuthenticatedUser, get_current_user},
+    models::account_model::{
+        AccountErasure, AccountExport, AccountExportResponse, AccountExportStatus, ErasureRequest,
+        ExportDownloadQuery,
+    },
+    schema::{
+        account_schema::{
+            cancel_account_erasure, create_account_export, get_account_erasure,
+            get_account_export_by_id, get_account_exports_by_user, get_unfinished_account_export,
+            schedule_account_erasure,
+        },
+        user_schema::get_user_by_id,
+    },
+    storage::AttachmentStorage,
+    utils::account_export::{download_link, verify_download_link},
+};
+
+// Account data export and erasure.
+//
+// Export: POST /users/me/export queues a ZIP archive of the account, which a
+// background job builds. GET /users/me/exports/{id} reports its progress and,
+// once it is ready, a signed download link that works for EXPORT_LINK_HOURS.
+//
+// Erasure: POST /users/me/erasure (or DELETE /users/me) with the account
+// password schedules erasure after a grace period of ERASURE_GRACE_DAYS.
+// Until then the account keeps working and DELETE /users/me/erasure cancels
+// the request. Once due, a background job erases the account as described on
+// `delete_user`.
+
+fn parse_uuid(id: String) -> Result<Uuid, HttpResponse> {
+    Uuid::parse_str(&id).map_err(|_| HttpResponse::BadRequest().json("Invalid UUID format"))
+}
+
+fn export_response(export: AccountExport, secret: &str) -> AccountExportResponse {
+    let download_url = export
+        .expires_at
+        .filter(|_| export.is_downloadable(Utc::now()))
+        .map(|expires_at| download_link(secret, &export.id, expires_at.timestamp()));
+
+    AccountExportResponse {
+        export,
+        download_url,
+    }
+}
+
+pub async fn request_export_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    jwt_secret: web::Data<String>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    // An export that is still being built is returned instead of queueing another
+    match get_unfinished_account_export(&pool, &auth_user.user_id).await {
+        Ok(Some(export)) => {
+            return HttpResponse::Accepted().json(export_response(export, &jwt_secret));
+        }
+        Ok(None) => {}
+        Err(err) => {
+            log::error!("Get account export error: {}", err);
+            return HttpResponse::InternalServerError().json("Error requesting export");
+        }
+    }
+
+    let export = AccountExport::new(&auth_user.user_id);
+    match create_account_export(&pool, &export).await {
+        Ok(()) => HttpResponse::Accepted().json(export_response(export, &jwt_secret)),
+        Err(err) => {
+            log::error!("Create account export error: {}", err);
+            HttpResponse::InternalServerError().json("Error requesting export")
+        }
+    }
+}
+
+pub async fn get_exports_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    jwt_secret: web::Data<String>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    match get_account_exports_by_user(&pool, &auth_user.user_id).await {
+        Ok(exports) => HttpResponse::Ok().json(
+            exports
+                .into_iter()
+                .map(|export| export_response(export, &jwt_secret))
+                .collect::<Vec<_>>(),
+        ),
+        Err(err) => {
+            log::error!("Get account exports error: {}", err);
+            HttpResponse::InternalServerError().json("Error fetching exports")
+        }
+    }
+}
+
+pub async fn get_export_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    jwt_secret: web::Data<String>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let id = match parse_uuid(path.into_inner()) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    match get_account_export_by_id(&pool, &id).await {
+        Ok(Some(export)) if export.user_id == auth_user.user_id.to_string() => {
+            HttpResponse::Ok().json(export_response(export, &jwt_secret))
+        }
+        Ok(_) => HttpResponse::NotFound().json("Export not found"),
+        Err(err) => {
+            log::error!("Get account export error: {}", err);
+            HttpResponse::InternalServerError().json("Error fetching export")
+        }
+    }
+}
+
+// Public: the signed link is the credential. The archive is streamed from
+// storage.
+pub async fn download_export_handler(
+    pool: web::Data<MySqlPool>,
+    storage: web::Data<dyn AttachmentStorage>,
+    jwt_secret: web::Data<String>,
+    path: web::Path<String>,
+    query: web::Query<ExportDownloadQuery>,
+) -> impl Responder {
+    let id = match parse_uuid(path.into_inner()) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    if !verify_download_link(
+        &jwt_secret,
+        &id.to_string(),
+        query.expires,
+        &query.signature,
+    ) {
+        return HttpResponse::Forbidden().json("Download link is invalid or has expired");
+    }
+
+    let export = match get_account_export_by_id(&pool, &id).await {
+        Ok(Some(export)) if export.status == AccountExportStatus::Ready => export,
+        Ok(_) => return HttpResponse::NotFound().json("Export not found"),
+        Err(err) => {
+            log::error!("Get account export error: {}", err);
+            return HttpResponse::InternalServerError().json("Error fetching export");
+        }
+    };
+    let Some(storage_key) = &export.storage_key else {
+        return HttpResponse::NotFound().json("Export not found");
+    };
+
+    match storage.get(storage_key).await {
+        Ok(Some(archive)) => {
+            let mut response = HttpResponse::Ok();
+            response
+                .insert_header((header::CONTENT_TYPE, "application/zip"))
+                .insert_header(ContentDisposition {
+                    disposition: DispositionType::Attachment,
+                    parameters: vec![DispositionParam::Filename(format!(
+                        "account-export-{}.zip",
+                        id
+                    ))],
+                });
+            if let Some(size_bytes) = export.size_bytes {
+                response.insert_header((header::CONTENT_LENGTH, size_bytes.to_string()));
+            }
+            response.streaming(archive)
+        }
+        Ok(None) => {
+            log::error!("Account export {} has no stored archive", export.id);
+            HttpResponse::NotFound().json("Export not found")
+        }
+        Err(err) => {
+            log::error!("Read account export archive error: {}", err);
+            HttpResponse::InternalServerError().json("Error fetching export")
+        }
+    }
+}
+
+async fn confirm_password(
+    pool: &MySqlPool,
+    auth_user: &AuthenticatedUser,
+    password: &str,
+) -> Result<(), HttpResponse> {
+    let user = match get_user_by_id(pool, &auth_user.user_id).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return Err(HttpResponse::NotFound().json("User not found")),
+        Err(err) => {
+            log::error!("Get user error: {}", err);
+            return Err(HttpResponse::InternalServerError().json("Error fetching user"));
+        }
+    };
+
+    match verify(password, &user.password) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(HttpResponse::Forbidden().json("Password is incorrect")),
+        Err(err) => {
+            log::error!("Password verification error: {}", err);
+            Err(HttpResponse::InternalServerError().json("Error verifying password"))
+        }
+    }
+}
+
+pub async fn request_erasure_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    request: web::Json<ErasureRequest>,
+) -> HttpResponse {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    if let Err(resp) = confirm_password(&pool, &auth_user, &request.password).await {
+        return resp;
+    }
+
+    if let Err(err) =
+        schedule_account_erasure(&pool, &AccountErasure::new(&auth_user.user_id)).await
+    {
+        log::error!("Schedule account erasure error: {}", err);
+        return HttpResponse::InternalServerError().json("Error scheduling erasure");
+    }
+
+    // Reports the erasure actually scheduled, which may be an earlier request
+    match get_account_erasure(&pool, &auth_user.user_id).await {
+        Ok(Some(erasure)) => HttpResponse::Accepted().json(erasure),
+        Ok(None) => HttpResponse::NotFound().json("User not found"),
+        Err(err) => {
+            log::error!("Get account erasure error: {}", err);
+            HttpResponse::InternalServerError().json("Error scheduling erasure")
+        }
+    }
+}
+
+pub async fn get_erasure_handler(req: HttpRequest, pool: web::Data<MySqlPool>) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    match get_account_erasure(&pool, &auth_user.user_id).await {
+        Ok(Some(erasure)) => HttpResponse::Ok().json(erasure),
+        Ok(None) => HttpResponse::NotFound().json("No erasure is scheduled"),
+        Err(err) => {
+            log::error!("Get account erasure error: {}", err);
+            HttpResponse::InternalServerError().json("Error fetching erasure")
+        }
+    }
+}
+
+pub async fn cancel_erasure_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    match cancel_account_erasure(&pool, &auth_user.user_id).await {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().json("No erasure is scheduled"),
+        Err(err) => {
+            log::error!("Cancel account erasure error: {}", err);
+            HttpResponse::InternalServerError().json("Error cancelling erasure")
+        }
+    }
+}
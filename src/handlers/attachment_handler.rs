@@ -0,0 +1,318 @@
+use actix_multipart::Multipart;
+use actix_web::{
+    HttpRequest, HttpResponse, Responder,
+    http::header::{self, ContentDisposition, DispositionParam, DispositionType},
+    web,
+    web::Bytes,
+};
+use futures_util::TryStreamExt;
+use sqlx::MySqlPool;
+use uuid::Uuid;
+
+use crate::{
+    handlers::todo_handler::find_owned_todo,
+    middleware::auth_middleware::{AuthenticatedUser, get_current_user},
+    models::attachment_model::{
+        Attachment, MAX_ATTACHMENT_BYTES, STORAGE_QUOTA_BYTES, StorageUsage, validate_content,
+    },
+    schema::attachment_schema::{
+        create_attachment, get_attachment_by_id, get_attachments_by_todo, get_storage_used,
+        remove_attachment,
+    },
+    storage::AttachmentStorage,
+};
+
+fn parse_uuid(id: String) -> Result<Uuid, HttpResponse> {
+    Uuid::parse_str(&id).map_err(|_| HttpResponse::BadRequest().json("Invalid UUID format"))
+}
+
+fn quota_exceeded() -> HttpResponse {
+    HttpResponse::PayloadTooLarge().json(format!(
+        "Storage quota of {} bytes exceeded",
+        STORAGE_QUOTA_BYTES
+    ))
+}
+
+// An uploaded file part
+struct UploadedFile {
+    file_name: String,
+    content_type: String,
+    data: Bytes,
+}
+
+// Reads the `file` part, refusing it as soon as it passes the size limit
+async fn read_file(mut payload: Multipart) -> Result<UploadedFile, HttpResponse> {
+    let invalid = |err: actix_multipart::MultipartError| {
+        HttpResponse::BadRequest().json(format!("Invalid upload: {}", err))
+    };
+
+    while let Some(mut field) = payload.try_next().await.map_err(invalid)? {
+        if field.name() != Some("file") {
+            continue;
+        }
+
+        let file_name = field
+            .content_disposition()
+            .and_then(|cd| cd.get_filename())
+            .unwrap_or_default()
+            .to_string();
+        let content_type = field
+            .content_type()
+            .map(|mime| mime.essence_str().to_string())
+            .unwrap_or_default();
+
+        let mut data = Vec::new();
+        while let Some(chunk) = field.try_next().await.map_err(invalid)? {
+            if data.len() + chunk.len() > MAX_ATTACHMENT_BYTES {
+                return Err(HttpResponse::PayloadTooLarge().json(format!(
+                    "Attachments are limited to {} bytes",
+                    MAX_ATTACHMENT_BYTES
+                )));
+            }
+            data.extend_from_slice(&chunk);
+        }
+
+        return Ok(UploadedFile {
+            file_name,
+            content_type,
+            data: Bytes::from(data),
+        });
+    }
+
+    Err(HttpResponse::BadRequest().json("A file part is required"))
+}
+
+// The attachment, if it belongs to the todo and the todo to the caller
+async fn find_owned_attachment(
+    pool: &MySqlPool,
+    todo_id: &Uuid,
+    attachment_id: &Uuid,
+    auth_user: &AuthenticatedUser,
+) -> Result<Attachment, HttpResponse> {
+    find_owned_todo(pool, todo_id, auth_user).await?;
+
+    match get_attachment_by_id(pool, attachment_id).await {
+        Ok(Some(attachment)) if attachment.todo_id == todo_id.to_string() => Ok(attachment),
+        Ok(_) => Err(HttpResponse::NotFound().json("Attachment not found")),
+        Err(err) => {
+            log::error!("Get attachment error: {}", err);
+            Err(HttpResponse::InternalServerError().json("Error fetching attachment"))
+        }
+    }
+}
+
+pub async fn upload_attachment_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    storage: web::Data<dyn AttachmentStorage>,
+    path: web::Path<String>,
+    payload: Multipart,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let id = match parse_uuid(path.into_inner()) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    let todo = match find_owned_todo(&pool, &id, &auth_user).await {
+        Ok(todo) => todo,
+        Err(resp) => return resp,
+    };
+
+    let file = match read_file(payload).await {
+        Ok(file) => file,
+        Err(resp) => return resp,
+    };
+    if file.data.is_empty() {
+        return HttpResponse::BadRequest().json("The file is empty");
+    }
+
+    let content_type = match validate_content(&file.content_type, &file.data) {
+        Ok(content_type) => content_type,
+        Err(message) => return HttpResponse::UnsupportedMediaType().json(message),
+    };
+
+    // Checked up front to avoid storing a file that can't be kept; the insert
+    // checks again under lock
+    match get_storage_used(&pool, &auth_user.user_id).await {
+        Ok(used) if used + file.data.len() as i64 > STORAGE_QUOTA_BYTES => {
+            return quota_exceeded();
+        }
+        Ok(_) => {}
+        Err(err) => {
+            log::error!("Get storage usage error: {}", err);
+            return HttpResponse::InternalServerError().json("Error checking storage quota");
+        }
+    }
+
+    let attachment = Attachment::new(
+        &todo.id,
+        &todo.user_id,
+        &file.file_name,
+        content_type,
+        &file.data,
+    );
+
+    if let Err(err) = storage
+        .put(&attachment.storage_key, content_type, file.data)
+        .await
+    {
+        log::error!("Store attachment error: {}", err);
+        return HttpResponse::InternalServerError().json("Error storing attachment");
+    }
+
+    let stored = create_attachment(&pool, &attachment, STORAGE_QUOTA_BYTES).await;
+    if !matches!(stored, Ok(true)) {
+        if let Err(err) = storage.delete(&attachment.storage_key).await {
+            log::error!("Delete attachment file error: {}", err);
+        }
+    }
+
+    match stored {
+        Ok(true) => HttpResponse::Created().json(attachment),
+        Ok(false) => quota_exceeded(),
+        Err(err) => {
+            log::error!("Create attachment error: {}", err);
+            HttpResponse::InternalServerError().json("Error saving attachment")
+        }
+    }
+}
+
+pub async fn get_attachments_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let id = match parse_uuid(path.into_inner()) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    if let Err(resp) = find_owned_todo(&pool, &id, &auth_user).await {
+        return resp;
+    }
+
+    match get_attachments_by_todo(&pool, &id).await {
+        Ok(attachments) => HttpResponse::Ok().json(attachments),
+        Err(err) => {
+            log::error!("Fetch attachments error: {}", err);
+            HttpResponse::InternalServerError().json("Error fetching attachments")
+        }
+    }
+}
+
+// Streams the file from storage. It is always served as a download, so an
+// uploaded file can't run as a page of this site.
+pub async fn download_attachment_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    storage: web::Data<dyn AttachmentStorage>,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let (todo_id, attachment_id) = path.into_inner();
+    let todo_id = match parse_uuid(todo_id) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+    let attachment_id = match parse_uuid(attachment_id) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    let attachment = match find_owned_attachment(&pool, &todo_id, &attachment_id, &auth_user).await
+    {
+        Ok(attachment) => attachment,
+        Err(resp) => return resp,
+    };
+
+    match storage.get(&attachment.storage_key).await {
+        Ok(Some(body)) => HttpResponse::Ok()
+            .insert_header((header::CONTENT_TYPE, attachment.content_type.as_str()))
+            .insert_header((header::CONTENT_LENGTH, attachment.size_bytes.to_string()))
+            .insert_header(ContentDisposition {
+                disposition: DispositionType::Attachment,
+                parameters: vec![DispositionParam::Filename(attachment.file_name.clone())],
+            })
+            .insert_header(("X-Content-Type-Options", "nosniff"))
+            .streaming(body),
+        Ok(None) => {
+            log::error!("Attachment {} has no stored file", attachment.id);
+            HttpResponse::NotFound().json("Attachment not found")
+        }
+        Err(err) => {
+            log::error!("Read attachment error: {}", err);
+            HttpResponse::InternalServerError().json("Error reading attachment")
+        }
+    }
+}
+
+pub async fn delete_attachment_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let (todo_id, attachment_id) = path.into_inner();
+    let todo_id = match parse_uuid(todo_id) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+    let attachment_id = match parse_uuid(attachment_id) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    let attachment = match find_owned_attachment(&pool, &todo_id, &attachment_id, &auth_user).await
+    {
+        Ok(attachment) => attachment,
+        Err(resp) => return resp,
+    };
+
+    // The file is deleted from storage by the cleanup job
+    match remove_attachment(&pool, &attachment.id).await {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(err) => {
+            log::error!("Delete attachment error: {}", err);
+            HttpResponse::InternalServerError().json("Error deleting attachment")
+        }
+    }
+}
+
+pub async fn get_storage_usage_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    match get_storage_used(&pool, &auth_user.user_id).await {
+        Ok(used_bytes) => HttpResponse::Ok().json(StorageUsage {
+            used_bytes,
+            quota_bytes: STORAGE_QUOTA_BYTES,
+        }),
+        Err(err) => {
+            log::error!("Get storage usage error: {}", err);
+            HttpResponse::InternalServerError().json("Error fetching storage usage")
+        }
+    }
+}
@@ -1,25 +1,116 @@
-use actix_web::{HttpResponse, Responder, web};
+use actix_web::{HttpRequest, HttpResponse, Responder, body, http::StatusCode, web};
 use bcrypt::{DEFAULT_COST, hash, verify};
 use chrono::{Duration, Utc};
 use jsonwebtoken::{EncodingKey, Header, encode};
 use sqlx::MySqlPool;
+use uuid::Uuid;
 
 use crate::{
     middleware::auth_middleware::Claims,
     models::{
-        auth_model::{AuthResponse, LoginRequest, RegisterRequest, UserInfo},
+        auth_model::{
+            AuthResponse, LoginRequest, RegisterRequest, RegisteredUser, RegistrationIdentity,
+            UserInfo,
+        },
         user_model::User,
     },
-    schema::user_schema::{check_email_exists, create_user, get_user_by_email},
+    schema::user_schema::{check_email_exists, create_user, get_user_by_email, get_user_by_id},
+    utils::idempotency::{REPLAYED_HEADER, idempotent},
 };
 
 pub async fn register_handler(
+    req: HttpRequest,
     pool: web::Data<MySqlPool>,
     register_data: web::Json<RegisterRequest>,
     secret_key: web::Data<String>,
-) -> impl Responder {
+) -> HttpResponse {
+    // There's no user yet, so keys share one scope. Only the new account's id
+    // is stored, and the password isn't part of the request hash; a retry has
+    // to present it again and is issued a fresh token.
+    let identity = RegistrationIdentity {
+        name: &register_data.name,
+        email: &register_data.email,
+    };
+    let registration = {
+        let pool = pool.clone();
+        let register_data = register_data.clone();
+        async move { register_user(&pool, &register_data).await }
+    };
+    let response = idempotent(&req, &pool, "anonymous", &identity, registration).await;
+    if response.status() != StatusCode::CREATED {
+        return response;
+    }
+
+    let replayed = response.headers().contains_key(REPLAYED_HEADER);
+    let registered = match body::to_bytes(response.into_body()).await {
+        Ok(bytes) => serde_json::from_slice::<RegisteredUser>(&bytes).ok(),
+        Err(_) => None,
+    };
+    let user_id = match registered.map(|r| Uuid::parse_str(&r.id)) {
+        Some(Ok(user_id)) => user_id,
+        _ => {
+            log::error!("Stored registration response has no user id");
+            return HttpResponse::InternalServerError().json("Error reading registration");
+        }
+    };
+
+    let user = match get_user_by_id(&pool, &user_id).await {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            return HttpResponse::Gone()
+                .json("The account registered with this Idempotency-Key no longer exists");
+        }
+        Err(err) => {
+            log::error!("Get user error: {}", err);
+            return HttpResponse::InternalServerError().json("Error fetching user");
+        }
+    };
+
+    if replayed {
+        match verify(&register_data.password, &user.password) {
+            Ok(true) => {}
+            Ok(false) => {
+                return HttpResponse::Conflict()
+                    .json("Idempotency-Key was already used for a different request");
+            }
+            Err(err) => {
+                log::error!("Password verification error: {}", err);
+                return HttpResponse::InternalServerError().json("Error verifying password");
+            }
+        }
+    }
+
+    // Generate JWT token
+    let token = match generate_token(&user, &secret_key) {
+        Ok(token) => token,
+        Err(err) => {
+            log::error!("Token generation error: {}", err);
+            return HttpResponse::InternalServerError().json("Error generating token");
+        }
+    };
+
+    let response = AuthResponse {
+        token,
+        user: UserInfo {
+            id: user.id,
+            name: user.name,
+            email: user.email,
+            role: format!("{:?}", user.role).to_lowercase(),
+        },
+    };
+
+    let mut created = HttpResponse::Created();
+    if replayed {
+        created.insert_header((REPLAYED_HEADER, "true"));
+    }
+    created.json(response)
+}
+
+// Creates the account and responds with its id, which is what a retry with
+// the same Idempotency-Key gets back
+async fn register_user(pool: &MySqlPool, register_data: &RegisterRequest) -> HttpResponse {
     // Check if email already exists
-    match check_email_exists(&pool, &register_data.email).await {
+    match check_email_exists(pool, &register_data.email).await {
         Ok(true) => return HttpResponse::Conflict().json("Email already exists"),
         Ok(false) => {}
         Err(err) => {
@@ -45,29 +136,8 @@ pub async fn register_handler(
         None, // Default role (user)
     );
 
-    match create_user(&pool, &new_user).await {
-        Ok(_) => {
-            // Generate JWT token
-            let token = match generate_token(&new_user, &secret_key) {
-                Ok(token) => token,
-                Err(err) => {
-                    log::error!("Token generation error: {}", err);
-                    return HttpResponse::InternalServerError().json("Error generating token");
-                }
-            };
-
-            let response = AuthResponse {
-                token,
-                user: UserInfo {
-                    id: new_user.id,
-                    name: new_user.name,
-                    email: new_user.email,
-                    role: format!("{:?}", new_user.role).to_lowercase(),
-                },
-            };
-
-            HttpResponse::Created().json(response)
-        }
+    match create_user(pool, &new_user).await {
+        Ok(_) => HttpResponse::Created().json(RegisteredUser { id: new_user.id }),
         Err(err) => {
             log::error!("Create user error: {}", err);
             HttpResponse::InternalServerError().json("Error creating user")
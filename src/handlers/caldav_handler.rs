@@ -0,0 +1,978 @@
+use std::collections::HashMap;
+
+use actix_web::{
+    HttpRequest, HttpResponse,
+    http::{
+        StatusCode,
+        header::{self, ETag},
+    },
+    web,
+};
+use base64::{Engine, engine::general_purpose::STANDARD};
+use bcrypt::verify;
+use chrono::{Duration, Utc};
+use quick_xml::escape::escape;
+use sqlx::MySqlPool;
+use uuid::Uuid;
+
+use crate::{
+    handlers::todo_handler::build_new_todo,
+    middleware::auth_middleware::AuthenticatedUser,
+    models::{
+        caldav_model::{
+            CALDAV_NS, CALENDARSERVER_NS, DAV_NS, DavRequest, DavRequestKind, DavResource,
+            PropName, SYNC_TOKEN_PREFIX, VTodoData, calendar_home_href, calendar_href, dav_href,
+            default_resource_name, principal_href,
+        },
+        sync_model::{
+            MAX_SYNC_PAGE_SIZE, SYNC_SETTLE_SECONDS, SyncChange, SyncToken,
+            TOMBSTONE_RETENTION_DAYS,
+        },
+        todo_model::{CreateTodoRequest, Todo, TodoPatch, VersionConflict, starts_work},
+        workflow_model::WorkflowError,
+    },
+    schema::{
+        caldav_schema::{
+            create_dav_todo, get_collection_ctag, get_dav_resource_by_name,
+            get_dav_resource_by_todo, get_dav_resources_by_user,
+        },
+        dependency_schema::get_open_blockers,
+        todo_schema::{
+            delete_todo, get_latest_change_token, get_todo_by_id, get_todo_by_id_with_trashed,
+            get_todo_changes, get_todos_including_archived, update_todo,
+        },
+        user_schema::get_user_by_email,
+        workflow_schema::get_default_workflow,
+    },
+    utils::{
+        dav_xml::{DavResponse, multistatus, parse_dav_request, precondition_error},
+        etag::{entity_tag, if_match, not_modified},
+        ical::{VTodoError, parse_vtodo, render_todo_resource},
+        patch::Patch,
+    },
+};
+
+const OBJECT_CONTENT_TYPE: &str = "text/calendar; charset=utf-8; component=vtodo";
+
+// sync-collection reads at most this many pages of changes per request and
+// has the client continue from its token for the rest
+const MAX_SYNC_PAGES: usize = 10;
+
+// A todo as a calendar object resource
+struct CalendarObject {
+    todo: Todo,
+    name: String,
+    uid: String,
+}
+
+impl CalendarObject {
+    fn new(todo: Todo, resource: Option<&DavResource>) -> Self {
+        let (name, uid) = match resource {
+            Some(resource) => (resource.resource_name.clone(), resource.uid.clone()),
+            None => (default_resource_name(&todo.id), todo.id.clone()),
+        };
+        CalendarObject { todo, name, uid }
+    }
+}
+
+fn unauthorized() -> HttpResponse {
+    HttpResponse::Unauthorized()
+        .insert_header((
+            header::WWW_AUTHENTICATE,
+            "Basic realm=\"Todos\", charset=\"UTF-8\"",
+        ))
+        .json("Invalid or missing credentials")
+}
+
+// CalDAV clients only speak HTTP Basic, so credentials are checked against
+// the same password hashes as login
+async fn authenticate_basic(
+    req: &HttpRequest,
+    pool: &MySqlPool,
+) -> Result<AuthenticatedUser, HttpResponse> {
+    let credentials = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Basic "))
+        .and_then(|encoded| STANDARD.decode(encoded.trim()).ok())
+        .and_then(|decoded| String::from_utf8(decoded).ok());
+
+    let Some((email, password)) = credentials
+        .as_deref()
+        .and_then(|c| c.split_once(':'))
+        .map(|(email, password)| (email.to_string(), password.to_string()))
+    else {
+        return Err(unauthorized());
+    };
+
+    let user = match get_user_by_email(pool, &email).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return Err(unauthorized()),
+        Err(err) => {
+            log::error!("Get user error: {}", err);
+            return Err(HttpResponse::InternalServerError().json("Error fetching user"));
+        }
+    };
+
+    match verify(&password, &user.password) {
+        Ok(true) => Ok(AuthenticatedUser {
+            user_id: Uuid::parse_str(&user.id).map_err(|_| unauthorized())?,
+            email: user.email,
+            role: format!("{:?}", user.role).to_lowercase(),
+        }),
+        Ok(false) => Err(unauthorized()),
+        Err(err) => {
+            log::error!("Password verification error: {}", err);
+            Err(HttpResponse::InternalServerError().json("Error verifying password"))
+        }
+    }
+}
+
+// Authenticates the request and checks it targets the user's own collections
+async fn authorize(
+    req: &HttpRequest,
+    pool: &MySqlPool,
+    path_user_id: &str,
+) -> Result<AuthenticatedUser, HttpResponse> {
+    let auth_user = authenticate_basic(req, pool).await?;
+
+    if !path_user_id.eq_ignore_ascii_case(&auth_user.user_id.to_string()) {
+        return Err(HttpResponse::Forbidden().json("Access denied"));
+    }
+
+    Ok(auth_user)
+}
+
+fn parse_body(body: &[u8]) -> Result<DavRequest, HttpResponse> {
+    if body.iter().all(u8::is_ascii_whitespace) {
+        return Ok(DavRequest::empty_propfind());
+    }
+    parse_dav_request(body).map_err(|message| HttpResponse::BadRequest().json(message))
+}
+
+fn depth(req: &HttpRequest) -> u8 {
+    match req.headers().get("Depth").and_then(|h| h.to_str().ok()) {
+        Some("0") => 0,
+        // "infinity" isn't supported; one level covers every resource here
+        _ => 1,
+    }
+}
+
+fn multistatus_response(responses: &[DavResponse], sync_token: Option<&str>) -> HttpResponse {
+    HttpResponse::build(StatusCode::MULTI_STATUS)
+        .content_type("application/xml; charset=utf-8")
+        .body(multistatus(responses, sync_token))
+}
+
+fn internal_error(context: &str, err: anyhow::Error) -> HttpResponse {
+    log::error!("{} error: {}", context, err);
+    HttpResponse::InternalServerError().json("Error processing CalDAV request")
+}
+
+fn href_value(href: &str) -> String {
+    format!("<d:href>{}</d:href>", escape(href))
+}
+
+fn etag_value(todo: &Todo) -> String {
+    escape(entity_tag(todo.version).to_string()).into_owned()
+}
+
+fn sync_token_uri(token: &SyncToken) -> String {
+    format!("{}{}", SYNC_TOKEN_PREFIX, token.encode())
+}
+
+// Answers the requested properties from those the resource has. allprop
+// returns everything except calendar-data, which must be asked for.
+fn respond_with_props(
+    href: String,
+    available: Vec<(PropName, String)>,
+    requested: &Option<Vec<PropName>>,
+) -> DavResponse {
+    let mut response = DavResponse::new(href);
+
+    match requested {
+        None => {
+            response.found = available
+                .into_iter()
+                .filter(|(prop, _)| !prop.is(CALDAV_NS, "calendar-data"))
+                .collect();
+        }
+        Some(requested) => {
+            for prop in requested {
+                match available.iter().find(|(p, _)| p == prop) {
+                    Some(found) => response.found.push(found.clone()),
+                    None => response.not_found.push(prop.clone()),
+                }
+            }
+        }
+    }
+
+    response
+}
+
+fn principal_props(user_id: &str, email: &str) -> Vec<(PropName, String)> {
+    vec![
+        (
+            PropName::new(DAV_NS, "resourcetype"),
+            "<d:principal/>".to_string(),
+        ),
+        (
+            PropName::new(DAV_NS, "current-user-principal"),
+            href_value(&principal_href(user_id)),
+        ),
+        (
+            PropName::new(DAV_NS, "principal-URL"),
+            href_value(&principal_href(user_id)),
+        ),
+        (
+            PropName::new(DAV_NS, "displayname"),
+            escape(email).into_owned(),
+        ),
+        (
+            PropName::new(CALDAV_NS, "calendar-home-set"),
+            href_value(&calendar_home_href(user_id)),
+        ),
+        (
+            PropName::new(CALDAV_NS, "calendar-user-address-set"),
+            href_value(&format!("mailto:{}", email)),
+        ),
+    ]
+}
+
+fn home_props(user_id: &str) -> Vec<(PropName, String)> {
+    vec![
+        (
+            PropName::new(DAV_NS, "resourcetype"),
+            "<d:collection/>".to_string(),
+        ),
+        (
+            PropName::new(DAV_NS, "current-user-principal"),
+            href_value(&principal_href(user_id)),
+        ),
+    ]
+}
+
+fn calendar_props(user_id: &str, ctag: &str, sync_token: &str) -> Vec<(PropName, String)> {
+    vec![
+        (
+            PropName::new(DAV_NS, "resourcetype"),
+            "<d:collection/><c:calendar/>".to_string(),
+        ),
+        (PropName::new(DAV_NS, "displayname"), "Todos".to_string()),
+        (
+            PropName::new(DAV_NS, "current-user-principal"),
+            href_value(&principal_href(user_id)),
+        ),
+        (
+            PropName::new(DAV_NS, "owner"),
+            href_value(&principal_href(user_id)),
+        ),
+        (
+            PropName::new(DAV_NS, "current-user-privilege-set"),
+            "<d:privilege><d:read/></d:privilege><d:privilege><d:write/></d:privilege>\
+             <d:privilege><d:write-content/></d:privilege><d:privilege><d:bind/></d:privilege>\
+             <d:privilege><d:unbind/></d:privilege>"
+                .to_string(),
+        ),
+        (
+            PropName::new(DAV_NS, "supported-report-set"),
+            "<d:supported-report><d:report><c:calendar-query/></d:report></d:supported-report>\
+             <d:supported-report><d:report><c:calendar-multiget/></d:report></d:supported-report>\
+             <d:supported-report><d:report><d:sync-collection/></d:report></d:supported-report>"
+                .to_string(),
+        ),
+        (
+            PropName::new(DAV_NS, "sync-token"),
+            escape(sync_token).into_owned(),
+        ),
+        (
+            PropName::new(CALDAV_NS, "supported-calendar-component-set"),
+            "<c:comp name=\"VTODO\"/>".to_string(),
+        ),
+        (
+            PropName::new(CALENDARSERVER_NS, "getctag"),
+            escape(ctag).into_owned(),
+        ),
+    ]
+}
+
+fn object_props(object: &CalendarObject) -> Vec<(PropName, String)> {
+    let calendar_data = render_todo_resource(&object.todo, &object.uid, Utc::now());
+
+    vec![
+        (PropName::new(DAV_NS, "resourcetype"), String::new()),
+        (PropName::new(DAV_NS, "getetag"), etag_value(&object.todo)),
+        (
+            PropName::new(DAV_NS, "getcontenttype"),
+            OBJECT_CONTENT_TYPE.to_string(),
+        ),
+        (
+            PropName::new(CALDAV_NS, "calendar-data"),
+            escape(calendar_data.as_str()).into_owned(),
+        ),
+    ]
+}
+
+fn object_response(
+    user_id: &str,
+    object: &CalendarObject,
+    requested: &Option<Vec<PropName>>,
+) -> DavResponse {
+    respond_with_props(
+        dav_href(user_id, &object.name),
+        object_props(object),
+        requested,
+    )
+}
+
+async fn load_objects(pool: &MySqlPool, user_id: &Uuid) -> anyhow::Result<Vec<CalendarObject>> {
+    let todos = get_todos_including_archived(pool, user_id).await?;
+    let resources: HashMap<String, DavResource> = get_dav_resources_by_user(pool, user_id)
+        .await?
+        .into_iter()
+        .map(|resource| (resource.todo_id.clone(), resource))
+        .collect();
+
+    Ok(todos
+        .into_iter()
+        .map(|todo| {
+            let resource = resources.get(&todo.id);
+            CalendarObject::new(todo, resource)
+        })
+        .collect())
+}
+
+// Todos created elsewhere are served as "<id>.ics"; ones created over CalDAV
+// under the name the client chose
+async fn resolve_todo_id(
+    pool: &MySqlPool,
+    user_id: &Uuid,
+    name: &str,
+) -> anyhow::Result<(Option<Uuid>, Option<DavResource>)> {
+    if let Some(resource) = get_dav_resource_by_name(pool, user_id, name).await? {
+        return Ok((Uuid::parse_str(&resource.todo_id).ok(), Some(resource)));
+    }
+
+    let id = name
+        .strip_suffix(".ics")
+        .and_then(|stem| Uuid::parse_str(stem).ok());
+    match id {
+        Some(id) => {
+            let resource = get_dav_resource_by_todo(pool, &id.to_string()).await?;
+            Ok((Some(id), resource))
+        }
+        None => Ok((None, None)),
+    }
+}
+
+async fn find_object(
+    pool: &MySqlPool,
+    user_id: &Uuid,
+    name: &str,
+) -> anyhow::Result<Option<CalendarObject>> {
+    let (Some(id), resource) = resolve_todo_id(pool, user_id, name).await? else {
+        return Ok(None);
+    };
+
+    Ok(get_todo_by_id(pool, &id)
+        .await?
+        .filter(|todo| todo.user_id == user_id.to_string())
+        .map(|todo| CalendarObject::new(todo, resource.as_ref())))
+}
+
+fn object_href_from(user_id: &str, href: &str) -> Option<String> {
+    href.strip_prefix(&calendar_href(user_id))
+        .filter(|name| !name.is_empty() && !name.contains('/'))
+        .map(str::to_string)
+}
+
+pub async fn options_handler() -> HttpResponse {
+    HttpResponse::Ok()
+        .insert_header(("DAV", "1, 3, calendar-access"))
+        .insert_header((header::ALLOW, "OPTIONS, GET, PUT, DELETE, PROPFIND, REPORT"))
+        .finish()
+}
+
+// RFC 6764 service discovery
+pub async fn well_known_handler() -> HttpResponse {
+    HttpResponse::MovedPermanently()
+        .insert_header((header::LOCATION, "/dav/"))
+        .finish()
+}
+
+// /dav/ only tells clients where the user's principal is
+pub async fn root_propfind_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    body: web::Bytes,
+) -> HttpResponse {
+    let auth_user = match authenticate_basic(&req, &pool).await {
+        Ok(user) => user,
+        Err(resp) => return resp,
+    };
+
+    let request = match parse_body(&body) {
+        Ok(request) => request,
+        Err(resp) => return resp,
+    };
+
+    let user_id = auth_user.user_id.to_string();
+    let response = respond_with_props("/dav/".to_string(), home_props(&user_id), &request.props);
+    multistatus_response(&[response], None)
+}
+
+pub async fn principal_propfind_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+    body: web::Bytes,
+) -> HttpResponse {
+    let auth_user = match authorize(&req, &pool, &path).await {
+        Ok(user) => user,
+        Err(resp) => return resp,
+    };
+
+    let request = match parse_body(&body) {
+        Ok(request) => request,
+        Err(resp) => return resp,
+    };
+
+    let user_id = auth_user.user_id.to_string();
+    let response = respond_with_props(
+        principal_href(&user_id),
+        principal_props(&user_id, &auth_user.email),
+        &request.props,
+    );
+    multistatus_response(&[response], None)
+}
+
+async fn calendar_state(pool: &MySqlPool, user_id: &Uuid) -> anyhow::Result<(String, String)> {
+    let ctag = get_collection_ctag(pool, user_id).await?;
+    let horizon = Utc::now() - Duration::seconds(SYNC_SETTLE_SECONDS);
+    let token = get_latest_change_token(pool, user_id, horizon).await?;
+    Ok((ctag, sync_token_uri(&token)))
+}
+
+pub async fn home_propfind_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+    body: web::Bytes,
+) -> HttpResponse {
+    let auth_user = match authorize(&req, &pool, &path).await {
+        Ok(user) => user,
+        Err(resp) => return resp,
+    };
+
+    let request = match parse_body(&body) {
+        Ok(request) => request,
+        Err(resp) => return resp,
+    };
+
+    let user_id = auth_user.user_id.to_string();
+    let mut responses = vec![respond_with_props(
+        calendar_home_href(&user_id),
+        home_props(&user_id),
+        &request.props,
+    )];
+
+    if depth(&req) > 0 {
+        let (ctag, sync_token) = match calendar_state(&pool, &auth_user.user_id).await {
+            Ok(state) => state,
+            Err(err) => return internal_error("Calendar state", err),
+        };
+        responses.push(respond_with_props(
+            calendar_href(&user_id),
+            calendar_props(&user_id, &ctag, &sync_token),
+            &request.props,
+        ));
+    }
+
+    multistatus_response(&responses, None)
+}
+
+pub async fn calendar_propfind_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+    body: web::Bytes,
+) -> HttpResponse {
+    let auth_user = match authorize(&req, &pool, &path).await {
+        Ok(user) => user,
+        Err(resp) => return resp,
+    };
+
+    let request = match parse_body(&body) {
+        Ok(request) => request,
+        Err(resp) => return resp,
+    };
+
+    let user_id = auth_user.user_id.to_string();
+    let (ctag, sync_token) = match calendar_state(&pool, &auth_user.user_id).await {
+        Ok(state) => state,
+        Err(err) => return internal_error("Calendar state", err),
+    };
+
+    let mut responses = vec![respond_with_props(
+        calendar_href(&user_id),
+        calendar_props(&user_id, &ctag, &sync_token),
+        &request.props,
+    )];
+
+    if depth(&req) > 0 {
+        let objects = match load_objects(&pool, &auth_user.user_id).await {
+            Ok(objects) => objects,
+            Err(err) => return internal_error("Load calendar objects", err),
+        };
+        responses.extend(
+            objects
+                .iter()
+                .map(|object| object_response(&user_id, object, &request.props)),
+        );
+    }
+
+    multistatus_response(&responses, None)
+}
+
+pub async fn calendar_report_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+    body: web::Bytes,
+) -> HttpResponse {
+    let auth_user = match authorize(&req, &pool, &path).await {
+        Ok(user) => user,
+        Err(resp) => return resp,
+    };
+
+    let request = match parse_dav_request(&body) {
+        Ok(request) => request,
+        Err(message) => return HttpResponse::BadRequest().json(message),
+    };
+
+    match request.kind {
+        // The collection only holds VTODOs, so every query matches all of them
+        DavRequestKind::CalendarQuery => {
+            let user_id = auth_user.user_id.to_string();
+            match load_objects(&pool, &auth_user.user_id).await {
+                Ok(objects) => {
+                    let responses: Vec<DavResponse> = objects
+                        .iter()
+                        .map(|object| object_response(&user_id, object, &request.props))
+                        .collect();
+                    multistatus_response(&responses, None)
+                }
+                Err(err) => internal_error("Load calendar objects", err),
+            }
+        }
+        DavRequestKind::CalendarMultiget => multiget_report(&pool, &auth_user, &request).await,
+        DavRequestKind::SyncCollection => sync_collection_report(&pool, &auth_user, &request).await,
+        _ => HttpResponse::Forbidden()
+            .content_type("application/xml; charset=utf-8")
+            .body(precondition_error("d", "supported-report")),
+    }
+}
+
+async fn multiget_report(
+    pool: &MySqlPool,
+    auth_user: &AuthenticatedUser,
+    request: &DavRequest,
+) -> HttpResponse {
+    let user_id = auth_user.user_id.to_string();
+    let mut responses = Vec::with_capacity(request.hrefs.len());
+
+    for href in &request.hrefs {
+        let object = match object_href_from(&user_id, href) {
+            Some(name) => match find_object(pool, &auth_user.user_id, &name).await {
+                Ok(object) => object,
+                Err(err) => return internal_error("Find calendar object", err),
+            },
+            None => None,
+        };
+
+        responses.push(match object {
+            Some(object) => {
+                let mut response = object_response(&user_id, &object, &request.props);
+                response.href = href.clone();
+                response
+            }
+            None => DavResponse::with_status(href.clone(), "404 Not Found"),
+        });
+    }
+
+    multistatus_response(&responses, None)
+}
+
+// RFC 6578: changes since the client's token, deletions as 404 responses.
+// Tokens are the same positions GET /sync hands out.
+async fn sync_collection_report(
+    pool: &MySqlPool,
+    auth_user: &AuthenticatedUser,
+    request: &DavRequest,
+) -> HttpResponse {
+    let user_id = auth_user.user_id.to_string();
+    let horizon = Utc::now() - Duration::seconds(SYNC_SETTLE_SECONDS);
+    let invalid_token = || {
+        HttpResponse::Forbidden()
+            .content_type("application/xml; charset=utf-8")
+            .body(precondition_error("d", "valid-sync-token"))
+    };
+
+    let since = match request.sync_token.as_deref().filter(|t| !t.is_empty()) {
+        None => None,
+        Some(uri) => match uri
+            .strip_prefix(SYNC_TOKEN_PREFIX)
+            .and_then(|token| SyncToken::decode(token).ok())
+        {
+            Some(token)
+                if token.is_start()
+                    || token.changed_at
+                        >= Utc::now() - Duration::days(TOMBSTONE_RETENTION_DAYS) =>
+            {
+                Some(token)
+            }
+            _ => return invalid_token(),
+        },
+    };
+
+    // Initial sync: everything there is, plus the token to continue from
+    let Some(mut position) = since else {
+        let token = match get_latest_change_token(pool, &auth_user.user_id, horizon).await {
+            Ok(token) => token,
+            Err(err) => return internal_error("Latest change token", err),
+        };
+        return match load_objects(pool, &auth_user.user_id).await {
+            Ok(objects) => {
+                let responses: Vec<DavResponse> = objects
+                    .iter()
+                    .map(|object| object_response(&user_id, object, &request.props))
+                    .collect();
+                multistatus_response(&responses, Some(&sync_token_uri(&token)))
+            }
+            Err(err) => internal_error("Load calendar objects", err),
+        };
+    };
+
+    let resources: HashMap<String, DavResource> =
+        match get_dav_resources_by_user(pool, &auth_user.user_id).await {
+            Ok(resources) => resources
+                .into_iter()
+                .map(|resource| (resource.todo_id.clone(), resource))
+                .collect(),
+            Err(err) => return internal_error("Load CalDAV resources", err),
+        };
+
+    // Later changes to the same todo replace earlier ones
+    let mut latest: Vec<SyncChange> = Vec::new();
+    let mut latest_index: HashMap<String, usize> = HashMap::new();
+    let mut truncated = false;
+    for page in 0..MAX_SYNC_PAGES {
+        let mut changes = match get_todo_changes(
+            pool,
+            &auth_user.user_id,
+            &position,
+            horizon,
+            MAX_SYNC_PAGE_SIZE,
+        )
+        .await
+        {
+            Ok(changes) => changes,
+            Err(err) => return internal_error("Get todo changes", err),
+        };
+
+        let has_more = changes.len() > MAX_SYNC_PAGE_SIZE as usize;
+        changes.truncate(MAX_SYNC_PAGE_SIZE as usize);
+        if let Some(last) = changes.last() {
+            position = last.token();
+        }
+
+        for change in changes {
+            let id = change.position().1.to_string();
+            match latest_index.get(&id) {
+                Some(&index) => latest[index] = change,
+                None => {
+                    latest_index.insert(id, latest.len());
+                    latest.push(change);
+                }
+            }
+        }
+
+        if !has_more {
+            break;
+        }
+        truncated = page + 1 == MAX_SYNC_PAGES;
+    }
+
+    let mut responses: Vec<DavResponse> = latest
+        .into_iter()
+        .map(|change| match change {
+            SyncChange::Upsert { todo } => {
+                let resource = resources.get(&todo.id);
+                let object = CalendarObject::new(todo, resource);
+                object_response(&user_id, &object, &request.props)
+            }
+            SyncChange::Delete { id, .. } => {
+                let name = resources
+                    .get(&id)
+                    .map(|resource| resource.resource_name.clone())
+                    .unwrap_or_else(|| default_resource_name(&id));
+                DavResponse::with_status(dav_href(&user_id, &name), "404 Not Found")
+            }
+        })
+        .collect();
+
+    // RFC 6578 section 3.6: the token covers only what was returned, so the
+    // client repeats the report with it to fetch the rest
+    if truncated {
+        responses.push(DavResponse {
+            error: Some("number-of-matches-within-limits"),
+            ..DavResponse::with_status(calendar_href(&user_id), "507 Insufficient Storage")
+        });
+    }
+
+    multistatus_response(&responses, Some(&sync_token_uri(&position)))
+}
+
+pub async fn object_get_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<(String, String)>,
+) -> HttpResponse {
+    let (path_user_id, name) = path.into_inner();
+    let auth_user = match authorize(&req, &pool, &path_user_id).await {
+        Ok(user) => user,
+        Err(resp) => return resp,
+    };
+
+    let object = match find_object(&pool, &auth_user.user_id, &name).await {
+        Ok(Some(object)) => object,
+        Ok(None) => return HttpResponse::NotFound().finish(),
+        Err(err) => return internal_error("Find calendar object", err),
+    };
+
+    if not_modified(&req, object.todo.version) {
+        return HttpResponse::NotModified()
+            .insert_header(ETag(entity_tag(object.todo.version)))
+            .finish();
+    }
+
+    HttpResponse::Ok()
+        .insert_header((header::CONTENT_TYPE, OBJECT_CONTENT_TYPE))
+        .insert_header(ETag(entity_tag(object.todo.version)))
+        .body(render_todo_resource(&object.todo, &object.uid, Utc::now()))
+}
+
+pub async fn object_propfind_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<(String, String)>,
+    body: web::Bytes,
+) -> HttpResponse {
+    let (path_user_id, name) = path.into_inner();
+    let auth_user = match authorize(&req, &pool, &path_user_id).await {
+        Ok(user) => user,
+        Err(resp) => return resp,
+    };
+
+    let request = match parse_body(&body) {
+        Ok(request) => request,
+        Err(resp) => return resp,
+    };
+
+    match find_object(&pool, &auth_user.user_id, &name).await {
+        Ok(Some(object)) => {
+            let response = object_response(&auth_user.user_id.to_string(), &object, &request.props);
+            multistatus_response(&[response], None)
+        }
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(err) => internal_error("Find calendar object", err),
+    }
+}
+
+fn written_response(status: StatusCode, todo: &Todo) -> HttpResponse {
+    HttpResponse::build(status)
+        .insert_header(ETag(entity_tag(todo.version)))
+        .finish()
+}
+
+pub async fn object_put_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<(String, String)>,
+    body: web::Bytes,
+) -> HttpResponse {
+    let (path_user_id, name) = path.into_inner();
+    let auth_user = match authorize(&req, &pool, &path_user_id).await {
+        Ok(user) => user,
+        Err(resp) => return resp,
+    };
+
+    let vtodo = match std::str::from_utf8(&body)
+        .map_err(|_| VTodoError::Invalid("Calendar data must be UTF-8".to_string()))
+        .and_then(parse_vtodo)
+    {
+        Ok(vtodo) => vtodo,
+        Err(err @ VTodoError::UnknownTimeZone(_)) => {
+            return HttpResponse::UnsupportedMediaType()
+                .insert_header(("X-Error", err.to_string()))
+                .finish();
+        }
+        Err(err) => {
+            return HttpResponse::Forbidden()
+                .content_type("application/xml; charset=utf-8")
+                .insert_header(("X-Error", err.to_string()))
+                .body(precondition_error("c", "supported-calendar-component"));
+        }
+    };
+
+    let (id, resource) = match resolve_todo_id(&pool, &auth_user.user_id, &name).await {
+        Ok(resolved) => resolved,
+        Err(err) => return internal_error("Resolve calendar object", err),
+    };
+
+    let existing = match id {
+        Some(id) => match get_todo_by_id_with_trashed(&pool, &id).await {
+            Ok(todo) => todo,
+            Err(err) => return internal_error("Get todo", err),
+        },
+        None => None,
+    };
+
+    match existing {
+        Some(todo) if todo.user_id == auth_user.user_id.to_string() => {
+            if todo.deleted_at.is_some() {
+                return HttpResponse::Conflict().json("Todo is in the trash; restore it first");
+            }
+            if req.headers().contains_key(header::IF_NONE_MATCH)
+                || if_match(&req, todo.version) == Some(false)
+            {
+                return HttpResponse::PreconditionFailed().finish();
+            }
+            update_object(&pool, &auth_user, todo, vtodo).await
+        }
+        _ if req.headers().contains_key(header::IF_MATCH) => {
+            HttpResponse::PreconditionFailed().finish()
+        }
+        // A name pointing at someone else's todo gets a fresh id
+        Some(_) => create_object(&pool, &auth_user, &name, None, vtodo).await,
+        None => {
+            let id = id.filter(|_| resource.is_none());
+            create_object(&pool, &auth_user, &name, id, vtodo).await
+        }
+    }
+}
+
+async fn update_object(
+    pool: &MySqlPool,
+    auth_user: &AuthenticatedUser,
+    todo: Todo,
+    vtodo: VTodoData,
+) -> HttpResponse {
+    let patch = TodoPatch {
+        title: vtodo.summary.map_or(Patch::Absent, Patch::Value),
+        description: vtodo.description.into(),
+        status: vtodo.status.map_or(Patch::Absent, Patch::Value),
+        due_date: vtodo.due.into(),
+        ..TodoPatch::default()
+    };
+    if let Err(message) = patch.validate_recurrence(&todo) {
+        return HttpResponse::BadRequest().json(message);
+    }
+
+    if starts_work(&todo, patch.status.as_value()) {
+        match get_open_blockers(pool, &Uuid::parse_str(&todo.id).unwrap_or_default()).await {
+            Ok(blockers) if !blockers.is_empty() => {
+                return HttpResponse::Conflict().json("Todo is blocked");
+            }
+            Ok(_) => {}
+            Err(err) => return internal_error("Get blockers", err),
+        }
+    }
+
+    match update_todo(pool, &todo, &patch, &auth_user.user_id).await {
+        Ok(Some(updated)) => written_response(StatusCode::NO_CONTENT, &updated),
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(err) if err.is::<VersionConflict>() => HttpResponse::PreconditionFailed().finish(),
+        Err(err) => match err.downcast_ref::<WorkflowError>() {
+            Some(workflow_err) => HttpResponse::Conflict().json(workflow_err.to_string()),
+            None => internal_error("Update todo", err),
+        },
+    }
+}
+
+async fn create_object(
+    pool: &MySqlPool,
+    auth_user: &AuthenticatedUser,
+    name: &str,
+    id: Option<Uuid>,
+    vtodo: VTodoData,
+) -> HttpResponse {
+    let workflow = match get_default_workflow(pool, &auth_user.user_id.to_string()).await {
+        Ok(workflow) => workflow,
+        Err(err) => return internal_error("Get workflow", err),
+    };
+
+    let todo_data = CreateTodoRequest {
+        id: id.map(|id| id.to_string()),
+        title: vtodo
+            .summary
+            .filter(|s| !s.trim().is_empty())
+            .unwrap_or_else(|| "Untitled".to_string()),
+        description: vtodo.description,
+        status: vtodo.status,
+        due_date: vtodo.due,
+        recurrence_rule: None,
+        workflow_status: None,
+    };
+
+    let new_todo = match build_new_todo(&todo_data, workflow.as_ref(), &auth_user.user_id) {
+        Ok(todo) => todo,
+        Err(message) => return HttpResponse::BadRequest().json(message),
+    };
+
+    // Keep the client's href and UID when they differ from what the todo
+    // would be served as by default
+    let uid = vtodo.uid.unwrap_or_else(|| new_todo.id.clone());
+    let resource =
+        (name != default_resource_name(&new_todo.id) || uid != new_todo.id).then(|| DavResource {
+            todo_id: new_todo.id.clone(),
+            user_id: new_todo.user_id.clone(),
+            resource_name: name.to_string(),
+            uid,
+        });
+    if let Err(err) = create_dav_todo(pool, &new_todo, resource.as_ref()).await {
+        return internal_error("Create todo", err);
+    }
+
+    written_response(StatusCode::CREATED, &new_todo)
+}
+
+pub async fn object_delete_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<(String, String)>,
+) -> HttpResponse {
+    let (path_user_id, name) = path.into_inner();
+    let auth_user = match authorize(&req, &pool, &path_user_id).await {
+        Ok(user) => user,
+        Err(resp) => return resp,
+    };
+
+    let object = match find_object(&pool, &auth_user.user_id, &name).await {
+        Ok(Some(object)) => object,
+        Ok(None) => return HttpResponse::NotFound().finish(),
+        Err(err) => return internal_error("Find calendar object", err),
+    };
+
+    if if_match(&req, object.todo.version) == Some(false) {
+        return HttpResponse::PreconditionFailed().finish();
+    }
+
+    // Deleting moves the todo to the trash, as it does through the API
+    match delete_todo(&pool, &object.todo, &auth_user.user_id).await {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(err) if err.is::<VersionConflict>() => HttpResponse::PreconditionFailed().finish(),
+        Err(err) => internal_error("Delete todo", err),
+    }
+}
@@ -0,0 +1,88 @@
+use actix_web::{HttpRequest, HttpResponse, Responder, http::header, web};
+use chrono::Utc;
+use sqlx::MySqlPool;
+
+use crate::{
+    middleware::auth_middleware::get_current_user,
+    models::calendar_model::{CalendarFeedToken, hash_feed_token},
+    schema::{
+        calendar_schema::{
+            delete_calendar_feed_token, get_user_id_by_feed_token, set_calendar_feed_token,
+        },
+        todo_schema::get_todos_including_archived,
+    },
+    utils::ical::render_calendar,
+};
+
+const CALENDAR_NAME: &str = "Todos";
+
+// Public: calendar apps can't send a bearer token, so the secret token in the
+// URL stands in for it
+pub async fn calendar_feed_handler(
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let token_hash = hash_feed_token(&path.into_inner());
+
+    // Unknown and revoked tokens look the same
+    let user_id = match get_user_id_by_feed_token(&pool, &token_hash).await {
+        Ok(Some(user_id)) => user_id,
+        Ok(None) => return HttpResponse::NotFound().json("Calendar not found"),
+        Err(err) => {
+            log::error!("Get calendar feed error: {}", err);
+            return HttpResponse::InternalServerError().json("Error fetching calendar");
+        }
+    };
+
+    match get_todos_including_archived(&pool, &user_id).await {
+        Ok(todos) => HttpResponse::Ok()
+            .insert_header((header::CONTENT_TYPE, "text/calendar; charset=utf-8"))
+            .insert_header((header::CACHE_CONTROL, "private, max-age=300"))
+            .body(render_calendar(CALENDAR_NAME, &todos, Utc::now())),
+        Err(err) => {
+            log::error!("Fetch calendar todos error: {}", err);
+            HttpResponse::InternalServerError().json("Error fetching calendar")
+        }
+    }
+}
+
+// Issues a new feed URL; the previous one stops working
+pub async fn regenerate_calendar_token_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let feed_token = CalendarFeedToken::generate();
+    let token_hash = hash_feed_token(&feed_token.token);
+
+    match set_calendar_feed_token(&pool, &auth_user.user_id, &token_hash).await {
+        Ok(()) => HttpResponse::Ok().json(feed_token),
+        Err(err) => {
+            log::error!("Set calendar token error: {}", err);
+            HttpResponse::InternalServerError().json("Error generating calendar token")
+        }
+    }
+}
+
+pub async fn delete_calendar_token_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    match delete_calendar_feed_token(&pool, &auth_user.user_id).await {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().json("No calendar feed configured"),
+        Err(err) => {
+            log::error!("Delete calendar token error: {}", err);
+            HttpResponse::InternalServerError().json("Error revoking calendar token")
+        }
+    }
+}
@@ -0,0 +1,247 @@
+use actix_web::{HttpRequest, HttpResponse, Responder, web};
+use chrono::Utc;
+use sqlx::MySqlPool;
+use uuid::Uuid;
+
+use crate::{
+    middleware::auth_middleware::{AuthenticatedUser, get_current_user},
+    models::{
+        comment_model::{Comment, CommentRequest},
+        todo_model::Todo,
+    },
+    schema::{
+        comment_schema::{
+            create_comment, delete_comment, get_comment_by_id, get_comments_by_todo, update_comment,
+        },
+        todo_schema::get_todo_by_id,
+        user_schema::get_user_by_id,
+    },
+};
+
+fn parse_uuid(id: String) -> Result<Uuid, HttpResponse> {
+    Uuid::parse_str(&id).map_err(|_| HttpResponse::BadRequest().json("Invalid UUID format"))
+}
+
+fn is_admin(auth_user: &AuthenticatedUser) -> bool {
+    auth_user.role == "admin"
+}
+
+// The todo's owner takes part in its discussion; admins can reach every
+// todo's comments to moderate them
+async fn find_commentable_todo(
+    pool: &MySqlPool,
+    id: &Uuid,
+    auth_user: &AuthenticatedUser,
+) -> Result<Todo, HttpResponse> {
+    match get_todo_by_id(pool, id).await {
+        Ok(Some(todo)) if todo.user_id == auth_user.user_id.to_string() || is_admin(auth_user) => {
+            Ok(todo)
+        }
+        Ok(_) => Err(HttpResponse::NotFound().json("Todo not found")),
+        Err(err) => {
+            log::error!("Get todo error: {}", err);
+            Err(HttpResponse::InternalServerError().json("Error fetching todo"))
+        }
+    }
+}
+
+// The comment, if it's on the todo and the caller may change it: its author
+// or an admin
+async fn find_editable_comment(
+    pool: &MySqlPool,
+    path: (String, String),
+    auth_user: &AuthenticatedUser,
+) -> Result<Comment, HttpResponse> {
+    let (todo_id, comment_id) = path;
+    let todo_id = parse_uuid(todo_id)?;
+    let comment_id = parse_uuid(comment_id)?;
+
+    find_commentable_todo(pool, &todo_id, auth_user).await?;
+
+    let comment = match get_comment_by_id(pool, &comment_id).await {
+        Ok(Some(comment)) if comment.todo_id == todo_id.to_string() => comment,
+        Ok(_) => return Err(HttpResponse::NotFound().json("Comment not found")),
+        Err(err) => {
+            log::error!("Get comment error: {}", err);
+            return Err(HttpResponse::InternalServerError().json("Error fetching comment"));
+        }
+    };
+
+    if comment.author_id != auth_user.user_id.to_string() && !is_admin(auth_user) {
+        return Err(HttpResponse::Forbidden().json("Only the author can change this comment"));
+    }
+
+    Ok(comment)
+}
+
+pub async fn create_comment_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+    comment_data: web::Json<CommentRequest>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let id = match parse_uuid(path.into_inner()) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    let body = match comment_data.validated_body() {
+        Ok(body) => body,
+        Err(message) => return HttpResponse::BadRequest().json(message),
+    };
+
+    let todo = match find_commentable_todo(&pool, &id, &auth_user).await {
+        Ok(todo) => todo,
+        Err(resp) => return resp,
+    };
+
+    let author = match get_user_by_id(&pool, &auth_user.user_id).await {
+        Ok(Some(user)) => user,
+        Ok(None) => return HttpResponse::NotFound().json("User not found"),
+        Err(err) => {
+            log::error!("Get user error: {}", err);
+            return HttpResponse::InternalServerError().json("Error fetching user");
+        }
+    };
+
+    let comment = Comment::new(&todo.id, &auth_user.user_id, &author.name, body);
+
+    match create_comment(&pool, &comment).await {
+        Ok(_) => HttpResponse::Created().json(comment),
+        Err(err) => {
+            log::error!("Create comment error: {}", err);
+            HttpResponse::InternalServerError().json("Error creating comment")
+        }
+    }
+}
+
+pub async fn get_comments_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let id = match parse_uuid(path.into_inner()) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    if let Err(resp) = find_commentable_todo(&pool, &id, &auth_user).await {
+        return resp;
+    }
+
+    match get_comments_by_todo(&pool, &id).await {
+        Ok(comments) => HttpResponse::Ok().json(comments),
+        Err(err) => {
+            log::error!("Fetch comments error: {}", err);
+            HttpResponse::InternalServerError().json("Error fetching comments")
+        }
+    }
+}
+
+pub async fn get_comment_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let (todo_id, comment_id) = path.into_inner();
+    let todo_id = match parse_uuid(todo_id) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+    let comment_id = match parse_uuid(comment_id) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    if let Err(resp) = find_commentable_todo(&pool, &todo_id, &auth_user).await {
+        return resp;
+    }
+
+    match get_comment_by_id(&pool, &comment_id).await {
+        Ok(Some(comment)) if comment.todo_id == todo_id.to_string() => {
+            HttpResponse::Ok().json(comment)
+        }
+        Ok(_) => HttpResponse::NotFound().json("Comment not found"),
+        Err(err) => {
+            log::error!("Get comment error: {}", err);
+            HttpResponse::InternalServerError().json("Error fetching comment")
+        }
+    }
+}
+
+pub async fn update_comment_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<(String, String)>,
+    comment_data: web::Json<CommentRequest>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let body = match comment_data.validated_body() {
+        Ok(body) => body,
+        Err(message) => return HttpResponse::BadRequest().json(message),
+    };
+
+    let mut comment = match find_editable_comment(&pool, path.into_inner(), &auth_user).await {
+        Ok(comment) => comment,
+        Err(resp) => return resp,
+    };
+
+    // Saving the same text again isn't an edit
+    if comment.body == body {
+        return HttpResponse::Ok().json(comment);
+    }
+
+    comment.body = body.to_string();
+    comment.edited_at = Some(Utc::now());
+
+    match update_comment(&pool, &comment).await {
+        Ok(_) => HttpResponse::Ok().json(comment),
+        Err(err) => {
+            log::error!("Update comment error: {}", err);
+            HttpResponse::InternalServerError().json("Error updating comment")
+        }
+    }
+}
+
+pub async fn delete_comment_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let comment = match find_editable_comment(&pool, path.into_inner(), &auth_user).await {
+        Ok(comment) => comment,
+        Err(resp) => return resp,
+    };
+
+    match delete_comment(&pool, &comment).await {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(err) => {
+            log::error!("Delete comment error: {}", err);
+            HttpResponse::InternalServerError().json("Error deleting comment")
+        }
+    }
+}
@@ -0,0 +1,153 @@
+use actix_web::{HttpRequest, HttpResponse, Responder, web};
+use sqlx::MySqlPool;
+use uuid::Uuid;
+
+use crate::{
+    handlers::todo_handler::find_owned_todo,
+    middleware::auth_middleware::get_current_user,
+    models::dependency_model::{
+        CreateDependencyRequest, DependencyEdge, DependencyOutcome, build_dependency_graph,
+    },
+    schema::{
+        dependency_schema::{create_dependency, delete_dependency, get_user_dependencies},
+        todo_schema::{get_todo_by_id, get_todos_including_archived},
+    },
+};
+
+fn parse_uuid(id: String) -> Result<Uuid, HttpResponse> {
+    Uuid::parse_str(&id).map_err(|_| HttpResponse::BadRequest().json("Invalid UUID format"))
+}
+
+pub async fn create_dependency_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+    dependency_data: web::Json<CreateDependencyRequest>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let id = match parse_uuid(path.into_inner()) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    let blocked_by_id = match parse_uuid(dependency_data.blocked_by.clone()) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    // Both ends of the dependency must belong to the caller
+    for todo_id in [&id, &blocked_by_id] {
+        if let Err(resp) = find_owned_todo(&pool, todo_id, &auth_user).await {
+            return resp;
+        }
+    }
+
+    let edge = DependencyEdge {
+        todo_id: id.to_string(),
+        blocked_by_id: blocked_by_id.to_string(),
+    };
+
+    let mut response = match create_dependency(&pool, &auth_user.user_id, &edge).await {
+        Ok(DependencyOutcome::Created) => HttpResponse::Created(),
+        // Adding a dependency twice changes nothing
+        Ok(DependencyOutcome::AlreadyExists) => HttpResponse::Ok(),
+        Ok(DependencyOutcome::WouldCreateCycle) => {
+            return HttpResponse::Conflict().json("Dependency would create a cycle");
+        }
+        Err(err) => {
+            log::error!("Create dependency error: {}", err);
+            return HttpResponse::InternalServerError().json("Error creating dependency");
+        }
+    };
+
+    match get_todo_by_id(&pool, &id).await {
+        Ok(Some(todo)) => response.json(todo),
+        Ok(None) => HttpResponse::NotFound().json("Todo not found"),
+        Err(err) => {
+            log::error!("Get todo error: {}", err);
+            HttpResponse::InternalServerError().json("Error fetching todo")
+        }
+    }
+}
+
+pub async fn delete_dependency_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let (id, blocked_by_id) = path.into_inner();
+    let id = match parse_uuid(id) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+    let blocked_by_id = match parse_uuid(blocked_by_id) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    if let Err(resp) = find_owned_todo(&pool, &id, &auth_user).await {
+        return resp;
+    }
+
+    let edge = DependencyEdge {
+        todo_id: id.to_string(),
+        blocked_by_id: blocked_by_id.to_string(),
+    };
+
+    match delete_dependency(&pool, &edge).await {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().json("Dependency not found"),
+        Err(err) => {
+            log::error!("Delete dependency error: {}", err);
+            HttpResponse::InternalServerError().json("Error deleting dependency")
+        }
+    }
+}
+
+pub async fn get_dependency_graph_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let id = match parse_uuid(path.into_inner()) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    if let Err(resp) = find_owned_todo(&pool, &id, &auth_user).await {
+        return resp;
+    }
+
+    // Archived blockers still shape the graph
+    let todos = match get_todos_including_archived(&pool, &auth_user.user_id).await {
+        Ok(todos) => todos,
+        Err(err) => {
+            log::error!("Fetch todos error: {}", err);
+            return HttpResponse::InternalServerError().json("Error fetching todos");
+        }
+    };
+
+    let edges = match get_user_dependencies(&pool, &auth_user.user_id).await {
+        Ok(edges) => edges,
+        Err(err) => {
+            log::error!("Fetch dependencies error: {}", err);
+            return HttpResponse::InternalServerError().json("Error fetching dependencies");
+        }
+    };
+
+    HttpResponse::Ok().json(build_dependency_graph(&id.to_string(), &todos, &edges))
+}
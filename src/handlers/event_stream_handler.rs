@@ -0,0 +1,128 @@
+use std::{collections::VecDeque, sync::Arc, time::Duration};
+
+use actix_web::{
+    HttpRequest, HttpResponse,
+    http::header::{self, CacheControl, CacheDirective},
+    rt, web,
+    web::Bytes,
+};
+use futures_util::stream;
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::{
+    middleware::auth_middleware::get_current_user,
+    models::change_event_model::{ChangeEvent, ChangeStreamQuery},
+    utils::event_hub::EventHub,
+};
+
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+const RETRY_MILLIS: u64 = 3000;
+
+struct EventStream {
+    receiver: broadcast::Receiver<Arc<ChangeEvent>>,
+    pending: VecDeque<Bytes>,
+    keep_alive: rt::time::Interval,
+    user_id: String,
+    last_sent_id: u64,
+}
+
+fn event_frame(event: &ChangeEvent) -> Bytes {
+    let data = serde_json::to_string(&event.todo).unwrap_or_default();
+    Bytes::from(format!(
+        "id: {}\nevent: {}\ndata: {}\n\n",
+        event.id,
+        event.event_name(),
+        data
+    ))
+}
+
+// Missed events can't be replayed; the client should catch up through GET /sync
+fn resync_frame() -> Bytes {
+    Bytes::from_static(b"event: resync_required\ndata: {}\n\n")
+}
+
+// Browsers send Last-Event-ID when they reconnect; other clients may use the
+// query string instead
+fn last_event_id(req: &HttpRequest, query: &ChangeStreamQuery) -> Option<u64> {
+    req.headers()
+        .get("Last-Event-ID")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.trim().parse().ok())
+        .or(query.last_event_id)
+}
+
+pub async fn todo_events_handler(
+    req: HttpRequest,
+    hub: web::Data<EventHub>,
+    query: web::Query<ChangeStreamQuery>,
+) -> HttpResponse {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+    let user_id = auth_user.user_id.to_string();
+
+    // Subscribe before replaying so nothing published in between is lost
+    let receiver = hub.subscribe();
+
+    let mut pending = VecDeque::new();
+    pending.push_back(Bytes::from(format!("retry: {}\n\n", RETRY_MILLIS)));
+
+    let mut last_sent_id = 0;
+    if let Some(last_id) = last_event_id(&req, &query) {
+        match hub.replay_since(last_id, &user_id) {
+            Some(events) => {
+                for event in events {
+                    pending.push_back(event_frame(&event));
+                    last_sent_id = event.id;
+                }
+            }
+            None => pending.push_back(resync_frame()),
+        }
+    }
+
+    let mut keep_alive = rt::time::interval(KEEP_ALIVE_INTERVAL);
+    keep_alive.reset();
+
+    let state = EventStream {
+        receiver,
+        pending,
+        keep_alive,
+        user_id,
+        last_sent_id,
+    };
+
+    let body = stream::unfold(state, |mut state| async move {
+        loop {
+            if let Some(frame) = state.pending.pop_front() {
+                return Some((Ok::<_, actix_web::Error>(frame), state));
+            }
+
+            tokio::select! {
+                _ = state.keep_alive.tick() => {
+                    // A comment line, ignored by clients but enough for proxies
+                    state.pending.push_back(Bytes::from_static(b": keep-alive\n\n"));
+                }
+                event = state.receiver.recv() => match event {
+                    Ok(event)
+                        if event.id > state.last_sent_id
+                            && event.is_visible_to(&state.user_id) =>
+                    {
+                        state.last_sent_id = event.id;
+                        state.pending.push_back(event_frame(&event));
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(_)) => state.pending.push_back(resync_frame()),
+                    Err(RecvError::Closed) => return None,
+                },
+            }
+        }
+    });
+
+    HttpResponse::Ok()
+        .insert_header((header::CONTENT_TYPE, "text/event-stream"))
+        .insert_header(CacheControl(vec![CacheDirective::NoCache]))
+        // Stops nginx from buffering the stream
+        .insert_header(("X-Accel-Buffering", "no"))
+        .streaming(body)
+}
@@ -0,0 +1,116 @@
+use actix_web::{
+    HttpRequest, HttpResponse,
+    http::header::{self, ContentDisposition, DispositionParam, DispositionType},
+    web,
+    web::Bytes,
+};
+use futures_util::stream;
+use sqlx::MySqlPool;
+use uuid::Uuid;
+
+use crate::{
+    middleware::auth_middleware::get_current_user,
+    models::{
+        export_model::{EXPORT_PAGE_SIZE, ExportQuery},
+        todo_query_model::{TodoCursor, TodoFilter},
+    },
+    schema::todo_schema::list_todos,
+    utils::export::ExportRenderer,
+};
+
+// Where a streamed export is up to
+enum ExportStage {
+    Start,
+    Page(Option<TodoCursor>),
+    Finish,
+    Done,
+}
+
+struct ExportStream {
+    pool: web::Data<MySqlPool>,
+    user_id: Uuid,
+    filter: TodoFilter,
+    renderer: ExportRenderer,
+    stage: ExportStage,
+}
+
+// Streams the todos a page at a time, so only one page is held in memory.
+// An error after the response started can only abort the stream.
+pub async fn export_todos_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    query: web::Query<ExportQuery>,
+) -> HttpResponse {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let filter = match query.to_filter() {
+        Ok(filter) => filter,
+        Err(message) => return HttpResponse::BadRequest().json(message),
+    };
+    let format = query.format.unwrap_or_default();
+
+    let state = ExportStream {
+        pool,
+        user_id: auth_user.user_id,
+        filter,
+        renderer: ExportRenderer::new(format),
+        stage: ExportStage::Start,
+    };
+
+    let body = stream::unfold(state, |mut state| async move {
+        let chunk = match std::mem::replace(&mut state.stage, ExportStage::Done) {
+            ExportStage::Start => {
+                state.stage = ExportStage::Page(None);
+                state.renderer.begin()
+            }
+            ExportStage::Page(cursor) => {
+                let page = match list_todos(
+                    &state.pool,
+                    &state.user_id,
+                    &state.filter,
+                    EXPORT_PAGE_SIZE,
+                    cursor.as_ref(),
+                )
+                .await
+                {
+                    Ok(page) => page,
+                    Err(err) => {
+                        log::error!("Export todos error: {}", err);
+                        return Some((
+                            Err(actix_web::error::ErrorInternalServerError(
+                                "Error exporting todos",
+                            )),
+                            state,
+                        ));
+                    }
+                };
+
+                state.stage = match page.items.last() {
+                    Some(last) if page.next_cursor.is_some() => {
+                        ExportStage::Page(Some(TodoCursor::after(last, &state.filter)))
+                    }
+                    _ => ExportStage::Finish,
+                };
+                page.items
+                    .iter()
+                    .map(|todo| state.renderer.render(todo))
+                    .collect()
+            }
+            ExportStage::Finish => state.renderer.finish(),
+            ExportStage::Done => return None,
+        };
+
+        Some((Ok::<_, actix_web::Error>(Bytes::from(chunk)), state))
+    });
+
+    HttpResponse::Ok()
+        .insert_header((header::CONTENT_TYPE, format.content_type()))
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(format.file_name().to_string())],
+        })
+        .streaming(body)
+}
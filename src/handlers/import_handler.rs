@@ -0,0 +1,202 @@
+use std::collections::HashSet;
+
+use actix_multipart::Multipart;
+use actix_web::{HttpRequest, HttpResponse, Responder, web};
+use futures_util::TryStreamExt;
+use sqlx::MySqlPool;
+
+use crate::{
+    handlers::todo_handler::build_new_todo,
+    middleware::auth_middleware::get_current_user,
+    models::{
+        import_model::{
+            FieldMapping, ImportQuery, ImportResponse, ImportRowResult, ImportRowStatus,
+            ImportedTodo, MAX_IMPORT_BYTES, MAX_IMPORT_ROWS,
+        },
+        todo_model::CreateTodoRequest,
+    },
+    schema::{
+        todo_schema::{create_todos, get_todos_including_archived},
+        workflow_schema::get_default_workflow,
+    },
+    utils::import::{detect_format, parse_import},
+};
+
+// The parts of an import upload
+struct ImportUpload {
+    filename: Option<String>,
+    content: Vec<u8>,
+    mapping: Option<Vec<u8>>,
+}
+
+async fn read_upload(mut payload: Multipart) -> Result<ImportUpload, HttpResponse> {
+    let mut upload = ImportUpload {
+        filename: None,
+        content: Vec::new(),
+        mapping: None,
+    };
+    let mut has_file = false;
+    let mut total = 0;
+
+    while let Some(mut field) = payload
+        .try_next()
+        .await
+        .map_err(|err| HttpResponse::BadRequest().json(format!("Invalid upload: {}", err)))?
+    {
+        let name = field.name().unwrap_or_default().to_string();
+        let filename = field
+            .content_disposition()
+            .and_then(|cd| cd.get_filename())
+            .map(str::to_string);
+
+        let mut data = Vec::new();
+        while let Some(chunk) = field
+            .try_next()
+            .await
+            .map_err(|err| HttpResponse::BadRequest().json(format!("Invalid upload: {}", err)))?
+        {
+            total += chunk.len();
+            if total > MAX_IMPORT_BYTES {
+                return Err(HttpResponse::PayloadTooLarge()
+                    .json(format!("Uploads are limited to {} bytes", MAX_IMPORT_BYTES)));
+            }
+            data.extend_from_slice(&chunk);
+        }
+
+        match name.as_str() {
+            "file" => {
+                has_file = true;
+                upload.filename = filename;
+                upload.content = data;
+            }
+            "mapping" => upload.mapping = Some(data),
+            _ => {}
+        }
+    }
+
+    if !has_file {
+        return Err(HttpResponse::BadRequest().json("A file part is required"));
+    }
+
+    Ok(upload)
+}
+
+pub async fn import_todos_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    query: web::Query<ImportQuery>,
+    payload: Multipart,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let upload = match read_upload(payload).await {
+        Ok(upload) => upload,
+        Err(resp) => return resp,
+    };
+
+    let Ok(content) = String::from_utf8(upload.content) else {
+        return HttpResponse::BadRequest().json("The file must be UTF-8 encoded");
+    };
+
+    let mapping = match upload.mapping.as_deref().map(serde_json::from_slice) {
+        Some(Ok(mapping)) => mapping,
+        Some(Err(err)) => {
+            return HttpResponse::BadRequest().json(format!("Invalid mapping: {}", err));
+        }
+        None => FieldMapping::default(),
+    };
+
+    let Some(format) = query
+        .format
+        .or_else(|| detect_format(upload.filename.as_deref(), &content))
+    else {
+        return HttpResponse::BadRequest()
+            .json("Could not detect the file format; pass it as ?format=");
+    };
+
+    let rows = match parse_import(format, &content, &mapping) {
+        Ok(rows) => rows,
+        Err(message) => return HttpResponse::BadRequest().json(message),
+    };
+    if rows.len() > MAX_IMPORT_ROWS {
+        return HttpResponse::BadRequest()
+            .json(format!("At most {} todos per import", MAX_IMPORT_ROWS));
+    }
+
+    let user_id = auth_user.user_id.to_string();
+    let workflow = match get_default_workflow(&pool, &user_id).await {
+        Ok(workflow) => workflow,
+        Err(err) => {
+            log::error!("Get workflow error: {}", err);
+            return HttpResponse::InternalServerError().json("Error fetching workflow");
+        }
+    };
+
+    // Titles already taken, by existing todos or earlier rows of the file
+    let mut titles = match get_todos_including_archived(&pool, &auth_user.user_id).await {
+        Ok(todos) => todos
+            .iter()
+            .map(|todo| ImportedTodo::duplicate_key(&todo.title))
+            .collect::<HashSet<_>>(),
+        Err(err) => {
+            log::error!("Get todos error: {}", err);
+            return HttpResponse::InternalServerError().json("Error fetching todos");
+        }
+    };
+
+    // `positions` maps each todo to be created to its result
+    let mut results = Vec::with_capacity(rows.len());
+    let mut todos = Vec::new();
+    let mut positions = Vec::new();
+    for (row, parsed) in rows {
+        let imported = match parsed {
+            Ok(imported) => imported,
+            Err(message) => {
+                results.push(ImportRowResult::failed(row, message));
+                continue;
+            }
+        };
+
+        let key = ImportedTodo::duplicate_key(&imported.title);
+        if titles.contains(&key) && !query.allow_duplicates {
+            results.push(ImportRowResult::duplicate(row, imported));
+            continue;
+        }
+
+        let request = CreateTodoRequest {
+            id: None,
+            title: imported.title.clone(),
+            description: imported.description.clone(),
+            status: Some(imported.status.clone()),
+            due_date: imported.due_date,
+            recurrence_rule: imported.recurrence_rule.clone(),
+            workflow_status: None,
+        };
+        match build_new_todo(&request, workflow.as_ref(), &auth_user.user_id) {
+            Ok(todo) => {
+                titles.insert(key);
+                positions.push(results.len());
+                results.push(ImportRowResult::valid(row, imported));
+                todos.push(todo);
+            }
+            Err(message) => results.push(ImportRowResult::failed(row, message)),
+        }
+    }
+
+    if !query.dry_run && !todos.is_empty() {
+        if let Err(err) = create_todos(&pool, &todos).await {
+            log::error!("Import todos error: {}", err);
+            return HttpResponse::InternalServerError().json("Error importing todos");
+        }
+
+        for (position, todo) in positions.into_iter().zip(&todos) {
+            results[position].status = ImportRowStatus::Created;
+            results[position].todo_id = Some(todo.id.clone());
+        }
+    }
+
+    HttpResponse::Ok().json(ImportResponse::new(format, query.dry_run, results))
+}
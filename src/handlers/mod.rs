@@ -1,3 +1,17 @@
+pub mod account_handler;
+pub mod attachment_handler;
 pub mod auth_handler;
+pub mod caldav_handler;
+pub mod calendar_handler;
+pub mod comment_handler;
+pub mod dependency_handler;
+pub mod event_stream_handler;
+pub mod export_handler;
+pub mod import_handler;
+pub mod saved_view_handler;
+pub mod sync_handler;
 pub mod todo_handler;
 pub mod user_handler;
+pub mod webhook_handler;
+pub mod workflow_handler;
+pub mod ws_handler;
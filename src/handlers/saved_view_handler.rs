@@ -0,0 +1,280 @@
+use actix_web::{HttpRequest, HttpResponse, Responder, web};
+use chrono::Utc;
+use sqlx::MySqlPool;
+use uuid::Uuid;
+
+use crate::{
+    middleware::auth_middleware::{AuthenticatedUser, get_current_user},
+    models::{
+        saved_view_model::{
+            CreateSavedViewRequest, SavedView, SavedViewCount, UpdateSavedViewRequest,
+            ViewTodosQuery,
+        },
+        todo_query_model::TodoCursor,
+    },
+    schema::{
+        saved_view_schema::{
+            create_saved_view, delete_saved_view, get_saved_view_by_id, get_saved_views_by_user,
+            update_saved_view,
+        },
+        todo_schema::{count_todos, list_todos},
+    },
+};
+
+fn parse_uuid(id: String) -> Result<Uuid, HttpResponse> {
+    Uuid::parse_str(&id).map_err(|_| HttpResponse::BadRequest().json("Invalid UUID format"))
+}
+
+async fn find_owned_view(
+    pool: &MySqlPool,
+    id: &Uuid,
+    auth_user: &AuthenticatedUser,
+) -> Result<SavedView, HttpResponse> {
+    match get_saved_view_by_id(pool, id).await {
+        Ok(Some(view)) if view.user_id == auth_user.user_id.to_string() => Ok(view),
+        Ok(_) => Err(HttpResponse::NotFound().json("View not found")),
+        Err(err) => {
+            log::error!("Get view error: {}", err);
+            Err(HttpResponse::InternalServerError().json("Error fetching view"))
+        }
+    }
+}
+
+pub async fn create_view_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    view_data: web::Json<CreateSavedViewRequest>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let view_data = view_data.into_inner();
+    if view_data.name.trim().is_empty() {
+        return HttpResponse::BadRequest().json("View name is required");
+    }
+    if let Err(message) = view_data.definition.validate() {
+        return HttpResponse::BadRequest().json(message);
+    }
+
+    let new_view = SavedView::new(
+        auth_user.user_id.to_string(),
+        view_data.name,
+        view_data.definition,
+    );
+
+    match create_saved_view(&pool, &new_view).await {
+        Ok(_) => HttpResponse::Created().json(&new_view),
+        Err(err) => {
+            log::error!("Create view error: {}", err);
+            HttpResponse::InternalServerError().json("Error creating view")
+        }
+    }
+}
+
+pub async fn get_views_handler(req: HttpRequest, pool: web::Data<MySqlPool>) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    match get_saved_views_by_user(&pool, &auth_user.user_id).await {
+        Ok(views) => HttpResponse::Ok().json(views),
+        Err(err) => {
+            log::error!("Fetch views error: {}", err);
+            HttpResponse::InternalServerError().json("Error fetching views")
+        }
+    }
+}
+
+// Number of todos each view currently matches, for sidebar badges
+pub async fn get_view_counts_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let views = match get_saved_views_by_user(&pool, &auth_user.user_id).await {
+        Ok(views) => views,
+        Err(err) => {
+            log::error!("Fetch views error: {}", err);
+            return HttpResponse::InternalServerError().json("Error fetching views");
+        }
+    };
+
+    let now = Utc::now();
+    let mut counts = Vec::with_capacity(views.len());
+    for view in views {
+        let filter = view.definition.resolve(now);
+        match count_todos(&pool, &auth_user.user_id, &filter).await {
+            Ok(count) => counts.push(SavedViewCount {
+                id: view.id,
+                name: view.name,
+                count,
+            }),
+            Err(err) => {
+                log::error!("Count view todos error: {}", err);
+                return HttpResponse::InternalServerError().json("Error counting view todos");
+            }
+        }
+    }
+
+    HttpResponse::Ok().json(counts)
+}
+
+pub async fn get_view_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let id = match parse_uuid(path.into_inner()) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    match find_owned_view(&pool, &id, &auth_user).await {
+        Ok(view) => HttpResponse::Ok().json(view),
+        Err(resp) => resp,
+    }
+}
+
+pub async fn get_view_todos_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+    query: web::Query<ViewTodosQuery>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let id = match parse_uuid(path.into_inner()) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    let view = match find_owned_view(&pool, &id, &auth_user).await {
+        Ok(view) => view,
+        Err(resp) => return resp,
+    };
+
+    let filter = view.definition.resolve(Utc::now());
+
+    let cursor = match query.cursor.as_deref() {
+        Some(cursor) => match TodoCursor::decode(cursor, &filter) {
+            Ok(cursor) => Some(cursor),
+            Err(message) => return HttpResponse::BadRequest().json(message),
+        },
+        None => None,
+    };
+
+    let total = match count_todos(&pool, &auth_user.user_id, &filter).await {
+        Ok(total) => total,
+        Err(err) => {
+            log::error!("Count todos error: {}", err);
+            return HttpResponse::InternalServerError().json("Error fetching todos");
+        }
+    };
+
+    match list_todos(
+        &pool,
+        &auth_user.user_id,
+        &filter,
+        query.page_size(),
+        cursor.as_ref(),
+    )
+    .await
+    {
+        Ok(page) => HttpResponse::Ok()
+            .insert_header(("X-Total-Count", total.to_string()))
+            .json(page),
+        Err(err) => {
+            log::error!("Fetch todos error: {}", err);
+            HttpResponse::InternalServerError().json("Error fetching todos")
+        }
+    }
+}
+
+pub async fn update_view_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+    update_data: web::Json<UpdateSavedViewRequest>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let id = match parse_uuid(path.into_inner()) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    let mut view = match find_owned_view(&pool, &id, &auth_user).await {
+        Ok(view) => view,
+        Err(resp) => return resp,
+    };
+
+    let update_data = update_data.into_inner();
+    if let Some(name) = update_data.name {
+        if name.trim().is_empty() {
+            return HttpResponse::BadRequest().json("View name is required");
+        }
+        view.name = name;
+    }
+    if let Some(definition) = update_data.definition {
+        if let Err(message) = definition.validate() {
+            return HttpResponse::BadRequest().json(message);
+        }
+        view.definition = definition;
+    }
+
+    match update_saved_view(&pool, &view).await {
+        Ok(Some(updated)) => HttpResponse::Ok().json(updated),
+        Ok(None) => HttpResponse::NotFound().json("View not found"),
+        Err(err) => {
+            log::error!("Update view error: {}", err);
+            HttpResponse::InternalServerError().json("Error updating view")
+        }
+    }
+}
+
+pub async fn delete_view_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let id = match parse_uuid(path.into_inner()) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    if let Err(resp) = find_owned_view(&pool, &id, &auth_user).await {
+        return resp;
+    }
+
+    match delete_saved_view(&pool, &id).await {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().json("View not found"),
+        Err(err) => {
+            log::error!("Delete view error: {}", err);
+            HttpResponse::InternalServerError().json("Error deleting view")
+        }
+    }
+}
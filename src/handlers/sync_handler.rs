@@ -0,0 +1,279 @@
+use actix_web::{HttpRequest, HttpResponse, Responder, web};
+use chrono::{Duration, Utc};
+use sqlx::MySqlPool;
+use uuid::Uuid;
+
+use crate::{
+    handlers::todo_handler::{build_new_todo, normalize_recurrence_rule},
+    middleware::auth_middleware::get_current_user,
+    models::{
+        sync_model::{
+            MAX_SYNC_MUTATIONS, SYNC_SETTLE_SECONDS, SyncMutation, SyncMutationResult, SyncOutcome,
+            SyncPushRequest, SyncPushResponse, SyncQuery, SyncResponse, SyncToken,
+            TOMBSTONE_RETENTION_DAYS,
+        },
+        todo_model::{
+            CreateTodoRequest, Todo, TodoPatch, UpdateTodoRequest, VersionConflict, starts_work,
+        },
+        workflow_model::{Workflow, WorkflowError},
+    },
+    schema::{
+        dependency_schema::get_open_blockers,
+        todo_schema::{
+            create_todo, delete_todo, get_todo_by_id_with_trashed, get_todo_changes, update_todo,
+        },
+        workflow_schema::get_default_workflow,
+    },
+    utils::patch::Patch,
+};
+
+pub async fn get_changes_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    query: web::Query<SyncQuery>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let since = match query.since.as_deref() {
+        Some(token) => match SyncToken::decode(token) {
+            Ok(token) => token,
+            Err(message) => return HttpResponse::BadRequest().json(message),
+        },
+        None => SyncToken::start(),
+    };
+
+    // Purges older than the tombstone retention can no longer be reported
+    let now = Utc::now();
+    if !since.is_start() && since.changed_at < now - Duration::days(TOMBSTONE_RETENTION_DAYS) {
+        return HttpResponse::Gone().json("Sync token has expired; start a full sync");
+    }
+
+    let limit = query.page_size();
+    let horizon = now - Duration::seconds(SYNC_SETTLE_SECONDS);
+    let mut changes =
+        match get_todo_changes(&pool, &auth_user.user_id, &since, horizon, limit).await {
+            Ok(changes) => changes,
+            Err(err) => {
+                log::error!("Get todo changes error: {}", err);
+                return HttpResponse::InternalServerError().json("Error fetching changes");
+            }
+        };
+
+    let has_more = changes.len() > limit as usize;
+    changes.truncate(limit as usize);
+
+    // With nothing new, everything up to the horizon has been seen. Moving the
+    // token there keeps idle clients' tokens from expiring.
+    let next_token = match changes.last() {
+        Some(change) => change.token(),
+        None if since.changed_at < horizon => SyncToken::at(horizon),
+        None => since,
+    }
+    .encode();
+
+    HttpResponse::Ok().json(SyncResponse {
+        changes,
+        next_token,
+        has_more,
+    })
+}
+
+// Applies a batch of offline changes in order. Each mutation succeeds or fails
+// on its own; conflicts are reported with the server's copy of the todo.
+pub async fn push_changes_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    push_data: web::Json<SyncPushRequest>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    if push_data.mutations.len() > MAX_SYNC_MUTATIONS {
+        return HttpResponse::BadRequest().json(format!(
+            "A sync batch can't have more than {} mutations",
+            MAX_SYNC_MUTATIONS
+        ));
+    }
+
+    let workflow = match get_default_workflow(&pool, &auth_user.user_id.to_string()).await {
+        Ok(workflow) => workflow,
+        Err(err) => {
+            log::error!("Get workflow error: {}", err);
+            return HttpResponse::InternalServerError().json("Error fetching workflow");
+        }
+    };
+
+    let mut results = Vec::with_capacity(push_data.mutations.len());
+    for mutation in &push_data.mutations {
+        let result = match Uuid::parse_str(mutation.id()) {
+            Ok(id) => {
+                match apply_mutation(&pool, workflow.as_ref(), &auth_user.user_id, &id, mutation)
+                    .await
+                {
+                    Ok(result) => result,
+                    Err(err) => {
+                        log::error!("Sync mutation error: {}", err);
+                        SyncMutationResult::rejected(mutation.id(), "Error applying change")
+                    }
+                }
+            }
+            Err(_) => SyncMutationResult::rejected(mutation.id(), "Todo id must be a UUID"),
+        };
+        results.push(result);
+    }
+
+    HttpResponse::Ok().json(SyncPushResponse { results })
+}
+
+async fn apply_mutation(
+    pool: &MySqlPool,
+    workflow: Option<&Workflow>,
+    user_id: &Uuid,
+    id: &Uuid,
+    mutation: &SyncMutation,
+) -> anyhow::Result<SyncMutationResult> {
+    let raw_id = mutation.id();
+    let existing = get_todo_by_id_with_trashed(pool, id).await?;
+
+    if let Some(todo) = &existing
+        && todo.user_id != user_id.to_string()
+    {
+        return Ok(SyncMutationResult::rejected(
+            raw_id,
+            "A todo with this id already exists",
+        ));
+    }
+
+    match mutation {
+        SyncMutation::Upsert {
+            base_version,
+            todo: todo_data,
+            ..
+        } => match existing {
+            None => create_from_sync(pool, workflow, user_id, raw_id, todo_data).await,
+            Some(todo) if todo.deleted_at.is_some() => Ok(SyncMutationResult {
+                error: Some("Todo has been deleted".to_string()),
+                ..SyncMutationResult::new(raw_id, SyncOutcome::Conflict, None)
+            }),
+            Some(todo) if base_version.is_some_and(|v| v != todo.version) => Ok(
+                SyncMutationResult::new(raw_id, SyncOutcome::Conflict, Some(todo)),
+            ),
+            Some(todo) => update_from_sync(pool, workflow, user_id, todo, todo_data).await,
+        },
+        SyncMutation::Delete { base_version, .. } => match existing {
+            None => Ok(SyncMutationResult::new(raw_id, SyncOutcome::Deleted, None)),
+            Some(todo) if todo.deleted_at.is_some() => {
+                Ok(SyncMutationResult::new(raw_id, SyncOutcome::Deleted, None))
+            }
+            Some(todo) if base_version.is_some_and(|v| v != todo.version) => Ok(
+                SyncMutationResult::new(raw_id, SyncOutcome::Conflict, Some(todo)),
+            ),
+            Some(todo) => match delete_todo(pool, &todo, user_id).await {
+                Ok(()) => Ok(SyncMutationResult::new(raw_id, SyncOutcome::Deleted, None)),
+                Err(err) if err.is::<VersionConflict>() => conflict_with_latest(pool, id).await,
+                Err(err) => Err(err),
+            },
+        },
+    }
+}
+
+async fn create_from_sync(
+    pool: &MySqlPool,
+    workflow: Option<&Workflow>,
+    user_id: &Uuid,
+    raw_id: &str,
+    todo_data: &UpdateTodoRequest,
+) -> anyhow::Result<SyncMutationResult> {
+    let todo_data = CreateTodoRequest {
+        id: Some(raw_id.to_string()),
+        title: todo_data.title.clone(),
+        description: todo_data.description.clone(),
+        status: todo_data.status.clone(),
+        due_date: todo_data.due_date,
+        recurrence_rule: todo_data.recurrence_rule.clone(),
+        workflow_status: todo_data.workflow_status.clone(),
+    };
+
+    let new_todo = match build_new_todo(&todo_data, workflow, user_id) {
+        Ok(todo) => todo,
+        Err(message) => return Ok(SyncMutationResult::rejected(raw_id, message)),
+    };
+
+    create_todo(pool, &new_todo).await?;
+    Ok(SyncMutationResult::new(
+        raw_id,
+        SyncOutcome::Created,
+        Some(new_todo),
+    ))
+}
+
+async fn update_from_sync(
+    pool: &MySqlPool,
+    workflow: Option<&Workflow>,
+    user_id: &Uuid,
+    todo: Todo,
+    todo_data: &UpdateTodoRequest,
+) -> anyhow::Result<SyncMutationResult> {
+    let id = Uuid::parse_str(&todo.id)?;
+    let mut patch = TodoPatch::from(todo_data.clone());
+    if let Err(message) = patch.validate_recurrence(&todo) {
+        return Ok(SyncMutationResult::rejected(&todo.id, message));
+    }
+
+    if let Patch::Value(rule) = &patch.recurrence_rule {
+        patch.recurrence_rule = match normalize_recurrence_rule(Some(rule)) {
+            Ok(rule) => rule.into(),
+            Err(message) => return Ok(SyncMutationResult::rejected(&todo.id, message)),
+        };
+    }
+
+    // Offline changes don't get to skip blockers either
+    let requested_status = match patch.workflow_status.as_value() {
+        Some(key) => workflow
+            .and_then(|w| w.status_by_key(key))
+            .map(|s| s.category.to_todo_status()),
+        None => patch.status.as_value().cloned(),
+    };
+    if starts_work(&todo, requested_status.as_ref())
+        && !get_open_blockers(pool, &id).await?.is_empty()
+    {
+        return Ok(SyncMutationResult::rejected(&todo.id, "Todo is blocked"));
+    }
+
+    match update_todo(pool, &todo, &patch, user_id).await {
+        Ok(Some(updated)) => Ok(SyncMutationResult::new(
+            &todo.id,
+            SyncOutcome::Updated,
+            Some(updated),
+        )),
+        Ok(None) => Ok(SyncMutationResult {
+            error: Some("Todo has been deleted".to_string()),
+            ..SyncMutationResult::new(&todo.id, SyncOutcome::Conflict, None)
+        }),
+        Err(err) if err.is::<VersionConflict>() => conflict_with_latest(pool, &id).await,
+        Err(err) => match err.downcast_ref::<WorkflowError>() {
+            Some(workflow_err) => Ok(SyncMutationResult::rejected(
+                &todo.id,
+                workflow_err.to_string(),
+            )),
+            None => Err(err),
+        },
+    }
+}
+
+// Another write landed between reading the todo and applying the change
+async fn conflict_with_latest(pool: &MySqlPool, id: &Uuid) -> anyhow::Result<SyncMutationResult> {
+    let latest = get_todo_by_id_with_trashed(pool, id)
+        .await?
+        .filter(|todo| todo.deleted_at.is_none());
+    Ok(SyncMutationResult::new(
+        &id.to_string(),
+        SyncOutcome::Conflict,
+        latest,
+    ))
+}
@@ -1,36 +1,225 @@
-use actix_web::{HttpRequest, HttpResponse, Responder, web};
-use sqlx::MySqlPool;
+use actix_web::{
+    HttpRequest, HttpResponse, Responder,
+    http::header::{self, ETag},
+    web,
+};
+use sqlx::{MySqlPool, mysql::MySqlDatabaseError};
 use uuid::Uuid;
 
 use crate::{
-    middleware::auth_middleware::get_current_user,
-    models::todo_model::{CreateTodoRequest, Todo, UpdateTodoRequest},
-    schema::todo_schema::{create_todo, delete_todo, get_all_todos, get_todo_by_id, update_todo},
+    middleware::auth_middleware::{AuthenticatedUser, get_current_user},
+    models::{
+        bulk_model::{BulkItemError, BulkItems, BulkMode, BulkTodoRequest},
+        search_model::{SearchBackend, SearchQuery, SearchResult, search_criteria, search_terms},
+        todo_model::{
+            CreateTodoRequest, DeleteTodoQuery, Todo, TodoPatch, UpdateTodoQuery,
+            UpdateTodoRequest, VersionConflict, starts_work,
+        },
+        todo_query_model::{TodoCursor, TodoListQuery},
+        workflow_model::{Workflow, WorkflowError},
+    },
+    schema::{
+        dependency_schema::get_open_blockers,
+        todo_event_schema::get_todo_events,
+        todo_schema::{
+            archive_completed_todos, archive_todo, bulk_apply, count_todos, create_todo,
+            delete_todo, get_todo_by_id, get_todo_by_id_with_trashed, get_trashed_todos,
+            list_todos, purge_todo, reschedule_occurrence, restore_todo, search_todos_fulltext,
+            search_todos_like, stop_recurrence, unarchive_todo, update_todo,
+        },
+        workflow_schema::get_default_workflow,
+    },
+    utils::{
+        etag::{entity_tag, if_match, not_modified},
+        idempotency::idempotent,
+        patch::Patch,
+        recurrence::RecurrenceRule,
+    },
 };
 
 fn parse_uuid(id: String) -> Result<Uuid, HttpResponse> {
     Uuid::parse_str(&id).map_err(|_| HttpResponse::BadRequest().json("Invalid UUID format"))
 }
 
+// Validates an RRULE and returns it in canonical form for storage
+pub fn normalize_recurrence_rule(rule: Option<&String>) -> Result<Option<String>, String> {
+    match rule {
+        Some(rule) => rule
+            .parse::<RecurrenceRule>()
+            .map(|parsed| Some(parsed.to_string()))
+            .map_err(|err| format!("Invalid recurrence rule: {}", err)),
+        None => Ok(None),
+    }
+}
+
+async fn find_owned_todo_with_trashed(
+    pool: &MySqlPool,
+    id: &Uuid,
+    auth_user: &AuthenticatedUser,
+) -> Result<Todo, HttpResponse> {
+    match get_todo_by_id_with_trashed(pool, id).await {
+        Ok(Some(todo)) if todo.user_id == auth_user.user_id.to_string() => Ok(todo),
+        Ok(_) => Err(HttpResponse::NotFound().json("Todo not found")),
+        Err(err) => {
+            log::error!("Get todo error: {}", err);
+            Err(HttpResponse::InternalServerError().json("Error fetching todo"))
+        }
+    }
+}
+
+fn is_duplicate_key(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<sqlx::Error>()
+        .and_then(|err| err.as_database_error())
+        .is_some_and(|err| err.is_unique_violation())
+}
+
+// MySQL rejects malformed boolean-mode queries with a parse error (1064)
+fn is_syntax_error(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<sqlx::Error>()
+        .and_then(|err| err.as_database_error())
+        .and_then(|err| err.try_downcast_ref::<MySqlDatabaseError>())
+        .is_some_and(|err| err.number() == 1064)
+}
+
+fn workflow_error_response(err: &WorkflowError) -> HttpResponse {
+    match err {
+        WorkflowError::TransitionNotAllowed { .. } => {
+            HttpResponse::Conflict().json(err.to_string())
+        }
+        _ => HttpResponse::BadRequest().json(err.to_string()),
+    }
+}
+
+fn precondition_failed(todo: &Todo) -> HttpResponse {
+    HttpResponse::PreconditionFailed()
+        .insert_header(ETag(entity_tag(todo.version)))
+        .json("Todo has been modified; fetch the latest version and retry")
+}
+
+// A write lost a race with another one. With If-Match the client asked for
+// exactly this check; without it the conflict is reported as such.
+fn version_conflict_response(had_if_match: bool) -> HttpResponse {
+    let message = VersionConflict.to_string();
+    if had_if_match {
+        HttpResponse::PreconditionFailed().json(message)
+    } else {
+        HttpResponse::Conflict().json(message)
+    }
+}
+
+pub async fn find_owned_todo(
+    pool: &MySqlPool,
+    id: &Uuid,
+    auth_user: &AuthenticatedUser,
+) -> Result<Todo, HttpResponse> {
+    match get_todo_by_id(pool, id).await {
+        Ok(Some(todo)) if todo.user_id == auth_user.user_id.to_string() => Ok(todo),
+        Ok(_) => Err(HttpResponse::NotFound().json("Todo not found")),
+        Err(err) => {
+            log::error!("Get todo error: {}", err);
+            Err(HttpResponse::InternalServerError().json("Error fetching todo"))
+        }
+    }
+}
+
 pub async fn create_todo_handler(
     req: HttpRequest,
     pool: web::Data<MySqlPool>,
     todo_data: web::Json<CreateTodoRequest>,
-) -> impl Responder {
+) -> HttpResponse {
     let auth_user = match get_current_user(&req) {
         Ok(user) => user,
         Err(response) => return response,
     };
 
-    let new_todo = Todo::new(
+    // Retries carrying the same Idempotency-Key get the original response
+    let scope = auth_user.user_id.to_string();
+    let creation = {
+        let pool = pool.clone();
+        let todo_data = todo_data.clone();
+        async move { insert_new_todo(&pool, &auth_user, &todo_data).await }
+    };
+    idempotent(&req, &pool, &scope, &*todo_data, creation).await
+}
+
+// Validates a creation request and builds the todo it describes. A new todo
+// has no current status, so every workflow error here is a bad request.
+pub fn build_new_todo(
+    todo_data: &CreateTodoRequest,
+    workflow: Option<&Workflow>,
+    user_id: &Uuid,
+) -> Result<Todo, String> {
+    let client_id = todo_data.client_id()?;
+
+    let recurrence_rule = normalize_recurrence_rule(todo_data.recurrence_rule.as_ref())?;
+
+    if recurrence_rule.is_some() && todo_data.due_date.is_none() {
+        return Err("Recurring todos require a due_date".to_string());
+    }
+
+    // With a custom workflow the todo starts in one of its statuses, and that
+    // status' category decides the TodoStatus
+    let (status, workflow_status_id) = match workflow {
+        Some(workflow) => {
+            let requested = todo_data.status.clone().unwrap_or_default();
+            match workflow
+                .resolve(None, todo_data.workflow_status.as_deref(), Some(&requested))
+                .map_err(|err| err.to_string())?
+            {
+                Some(target) => (
+                    Some(target.category.to_todo_status()),
+                    Some(target.id.clone()),
+                ),
+                None => (todo_data.status.clone(), None),
+            }
+        }
+        None if todo_data.workflow_status.is_some() => {
+            return Err(WorkflowError::NoDefaultWorkflow.to_string());
+        }
+        None => (todo_data.status.clone(), None),
+    };
+
+    let mut new_todo = Todo::new(
         todo_data.title.clone(),
         todo_data.description.clone(),
-        todo_data.status.clone(),
-        auth_user.user_id.to_string(),
+        status,
+        todo_data.due_date,
+        recurrence_rule,
+        user_id.to_string(),
     );
+    new_todo.workflow_status_id = workflow_status_id;
+    if let Some(id) = client_id {
+        new_todo = new_todo.with_id(id.to_string());
+    }
+
+    Ok(new_todo)
+}
+
+async fn insert_new_todo(
+    pool: &MySqlPool,
+    auth_user: &AuthenticatedUser,
+    todo_data: &CreateTodoRequest,
+) -> HttpResponse {
+    let workflow = match get_default_workflow(pool, &auth_user.user_id.to_string()).await {
+        Ok(workflow) => workflow,
+        Err(err) => {
+            log::error!("Get workflow error: {}", err);
+            return HttpResponse::InternalServerError().json("Error fetching workflow");
+        }
+    };
+
+    let new_todo = match build_new_todo(todo_data, workflow.as_ref(), &auth_user.user_id) {
+        Ok(todo) => todo,
+        Err(message) => return HttpResponse::BadRequest().json(message),
+    };
 
-    match create_todo(&pool, &new_todo).await {
-        Ok(_) => HttpResponse::Created().json(&new_todo),
+    match create_todo(pool, &new_todo).await {
+        Ok(_) => HttpResponse::Created()
+            .insert_header(ETag(entity_tag(new_todo.version)))
+            .json(&new_todo),
+        Err(err) if is_duplicate_key(&err) => {
+            HttpResponse::Conflict().json("A todo with this id already exists")
+        }
         Err(err) => {
             log::error!("Create todo error: {}", err);
             HttpResponse::InternalServerError().json("Error creating todo")
@@ -38,14 +227,49 @@ pub async fn create_todo_handler(
     }
 }
 
-pub async fn get_todos_handler(req: HttpRequest, pool: web::Data<MySqlPool>) -> impl Responder {
+pub async fn get_todos_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    query: web::Query<TodoListQuery>,
+) -> impl Responder {
     let auth_user = match get_current_user(&req) {
         Ok(user) => user,
         Err(response) => return response,
     };
 
-    match get_all_todos(&pool, &auth_user.user_id).await {
-        Ok(todos) => HttpResponse::Ok().json(todos),
+    let filter = match query.to_filter() {
+        Ok(filter) => filter,
+        Err(message) => return HttpResponse::BadRequest().json(message),
+    };
+
+    let cursor = match query.cursor.as_deref() {
+        Some(cursor) => match TodoCursor::decode(cursor, &filter) {
+            Ok(cursor) => Some(cursor),
+            Err(message) => return HttpResponse::BadRequest().json(message),
+        },
+        None => None,
+    };
+
+    let total = match count_todos(&pool, &auth_user.user_id, &filter).await {
+        Ok(total) => total,
+        Err(err) => {
+            log::error!("Count todos error: {}", err);
+            return HttpResponse::InternalServerError().json("Error fetching todos");
+        }
+    };
+
+    match list_todos(
+        &pool,
+        &auth_user.user_id,
+        &filter,
+        query.page_size(),
+        cursor.as_ref(),
+    )
+    .await
+    {
+        Ok(page) => HttpResponse::Ok()
+            .insert_header(("X-Total-Count", total.to_string()))
+            .json(page),
         Err(err) => {
             log::error!("Fetch todos error: {}", err);
             HttpResponse::InternalServerError().json("Error fetching todos")
@@ -53,18 +277,151 @@ pub async fn get_todos_handler(req: HttpRequest, pool: web::Data<MySqlPool>) ->
     }
 }
 
+pub async fn search_todos_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    search_backend: web::Data<SearchBackend>,
+    query: web::Query<SearchQuery>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    if query.q.trim().is_empty() {
+        return HttpResponse::BadRequest().json("Search query is required");
+    }
+    let terms = search_terms(&query.q);
+    if terms.is_empty() {
+        return HttpResponse::BadRequest()
+            .json("Search query needs a word of at least 3 characters that isn't a stop word");
+    }
+
+    let mode = query.mode.unwrap_or_default();
+    let results = match search_backend.get_ref() {
+        SearchBackend::FullText => {
+            search_todos_fulltext(&pool, &auth_user.user_id, &query.q, mode, query.limit()).await
+        }
+        SearchBackend::Like => {
+            let criteria = search_criteria(&query.q, mode);
+            search_todos_like(&pool, &auth_user.user_id, &criteria, query.limit()).await
+        }
+    };
+
+    match results {
+        Ok(results) => {
+            let results: Vec<SearchResult> = results
+                .into_iter()
+                .map(|(todo, relevance)| SearchResult::new(todo, relevance, &terms))
+                .collect();
+            HttpResponse::Ok().json(results)
+        }
+        Err(err) if is_syntax_error(&err) => {
+            HttpResponse::BadRequest().json("Invalid boolean search syntax")
+        }
+        Err(err) => {
+            log::error!("Search todos error: {}", err);
+            HttpResponse::InternalServerError().json("Error searching todos")
+        }
+    }
+}
+
 pub async fn get_todo_handler(
+    req: HttpRequest,
     pool: web::Data<MySqlPool>,
     path: web::Path<String>,
 ) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
     let id = match parse_uuid(path.into_inner()) {
         Ok(uuid) => uuid,
         Err(resp) => return resp,
     };
 
-    match get_todo_by_id(&pool, &id).await {
-        Ok(Some(todo)) => HttpResponse::Ok().json(todo),
-        Ok(None) => HttpResponse::NotFound().json("Todo not found"),
+    let todo = match find_owned_todo(&pool, &id, &auth_user).await {
+        Ok(todo) => todo,
+        Err(resp) => return resp,
+    };
+
+    if not_modified(&req, todo.version) {
+        return HttpResponse::NotModified()
+            .insert_header(ETag(entity_tag(todo.version)))
+            .finish();
+    }
+
+    HttpResponse::Ok()
+        .insert_header(ETag(entity_tag(todo.version)))
+        .json(todo)
+}
+
+// PUT replaces the todo's content, or creates it under the given id if there
+// is no such todo yet
+pub async fn update_todo_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+    query: web::Query<UpdateTodoQuery>,
+    update_data: web::Json<UpdateTodoRequest>,
+) -> HttpResponse {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let scope = auth_user.user_id.to_string();
+    let update = replace_or_create_todo(
+        req.clone(),
+        pool.clone(),
+        path,
+        query,
+        auth_user,
+        update_data.clone(),
+    );
+    idempotent(&req, &pool, &scope, &*update_data, update).await
+}
+
+async fn replace_or_create_todo(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+    query: web::Query<UpdateTodoQuery>,
+    auth_user: AuthenticatedUser,
+    update_data: UpdateTodoRequest,
+) -> HttpResponse {
+    let id = match parse_uuid(path.to_string()) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    match get_todo_by_id_with_trashed(&pool, &id).await {
+        Ok(Some(todo)) if todo.user_id != auth_user.user_id.to_string() => {
+            HttpResponse::Conflict().json("A todo with this id already exists")
+        }
+        Ok(Some(todo)) if todo.deleted_at.is_some() => {
+            HttpResponse::Conflict().json("Todo is in the trash; restore it first")
+        }
+        Ok(Some(_)) => {
+            let patch = TodoPatch::from(update_data);
+            apply_todo_patch(req, pool, path, query, patch).await
+        }
+        Ok(None) if req.headers().contains_key(header::IF_MATCH) => {
+            HttpResponse::PreconditionFailed().json("Todo not found")
+        }
+        Ok(None) => {
+            let todo_data = CreateTodoRequest {
+                id: Some(id.to_string()),
+                title: update_data.title,
+                description: update_data.description,
+                status: update_data.status,
+                due_date: update_data.due_date,
+                recurrence_rule: update_data.recurrence_rule,
+                workflow_status: update_data.workflow_status,
+            };
+            insert_new_todo(&pool, &auth_user, &todo_data).await
+        }
         Err(err) => {
             log::error!("Get todo error: {}", err);
             HttpResponse::InternalServerError().json("Error fetching todo")
@@ -72,41 +429,465 @@ pub async fn get_todo_handler(
     }
 }
 
-pub async fn update_todo_handler(
+// PATCH applies a JSON Merge Patch (application/merge-patch+json)
+pub async fn patch_todo_handler(
+    req: HttpRequest,
     pool: web::Data<MySqlPool>,
     path: web::Path<String>,
-    update_data: web::Json<UpdateTodoRequest>,
-) -> impl Responder {
+    query: web::Query<UpdateTodoQuery>,
+    patch: web::Json<TodoPatch>,
+) -> HttpResponse {
+    apply_todo_patch(req, pool, path, query, patch.into_inner()).await
+}
+
+async fn apply_todo_patch(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+    query: web::Query<UpdateTodoQuery>,
+    mut patch: TodoPatch,
+) -> HttpResponse {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
     let id = match parse_uuid(path.into_inner()) {
         Ok(uuid) => uuid,
         Err(resp) => return resp,
     };
 
-    match update_todo(&pool, &id, &update_data).await {
-        Ok(Some(updated)) => HttpResponse::Ok().json(updated),
+    let todo = match find_owned_todo(&pool, &id, &auth_user).await {
+        Ok(todo) => todo,
+        Err(resp) => return resp,
+    };
+
+    if let Err(message) = patch
+        .validate()
+        .and_then(|_| patch.validate_recurrence(&todo))
+    {
+        return HttpResponse::BadRequest().json(message);
+    }
+
+    let if_match = if_match(&req, todo.version);
+    if if_match == Some(false) {
+        return precondition_failed(&todo);
+    }
+
+    // A custom workflow status is judged by its category
+    let requested_status = match patch.workflow_status.as_value() {
+        Some(key) => match get_default_workflow(&pool, &todo.user_id).await {
+            Ok(workflow) => workflow
+                .as_ref()
+                .and_then(|w| w.status_by_key(key))
+                .map(|s| s.category.to_todo_status()),
+            Err(err) => {
+                log::error!("Get workflow error: {}", err);
+                return HttpResponse::InternalServerError().json("Error fetching workflow");
+            }
+        },
+        None => patch.status.as_value().cloned(),
+    };
+
+    if starts_work(&todo, requested_status.as_ref()) && !query.force.unwrap_or(false) {
+        match get_open_blockers(&pool, &id).await {
+            Ok(blockers) if !blockers.is_empty() => {
+                return HttpResponse::Conflict().json(serde_json::json!({
+                    "error": "Todo is blocked",
+                    "message": "Complete the blocking todos first or retry with ?force=true",
+                    "blocked_by": blockers
+                }));
+            }
+            Ok(_) => {}
+            Err(err) => {
+                log::error!("Get blockers error: {}", err);
+                return HttpResponse::InternalServerError().json("Error checking dependencies");
+            }
+        }
+    }
+
+    if let Patch::Value(rule) = &patch.recurrence_rule {
+        patch.recurrence_rule = match normalize_recurrence_rule(Some(rule)) {
+            Ok(rule) => rule.into(),
+            Err(message) => return HttpResponse::BadRequest().json(message),
+        };
+    }
+
+    match update_todo(&pool, &todo, &patch, &auth_user.user_id).await {
+        Ok(Some(updated)) => HttpResponse::Ok()
+            .insert_header(ETag(entity_tag(updated.version)))
+            .json(updated),
         Ok(None) => HttpResponse::NotFound().json("Todo not found"),
+        Err(err) if err.is::<VersionConflict>() => version_conflict_response(if_match.is_some()),
+        Err(err) => match err.downcast_ref::<WorkflowError>() {
+            Some(workflow_err) => workflow_error_response(workflow_err),
+            None => {
+                log::error!("Update todo error: {}", err);
+                HttpResponse::InternalServerError().json("Error updating todo")
+            }
+        },
+    }
+}
+
+pub async fn bulk_todos_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    request: web::Json<BulkTodoRequest>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let request = request.into_inner();
+    if let Err(message) = request.validate() {
+        return HttpResponse::BadRequest().json(message);
+    }
+
+    let workflow = match get_default_workflow(&pool, &auth_user.user_id.to_string()).await {
+        Ok(workflow) => workflow,
+        Err(err) => {
+            log::error!("Get workflow error: {}", err);
+            return HttpResponse::InternalServerError().json("Error fetching workflow");
+        }
+    };
+
+    let items = BulkItems::parse(&request.ids);
+    if request.mode == BulkMode::AllOrNothing && !items.all_valid() {
+        return HttpResponse::UnprocessableEntity().json(items.into_response(request.mode, vec![]));
+    }
+
+    let outcomes = match bulk_apply(
+        &pool,
+        &auth_user.user_id,
+        &items.ids,
+        &request,
+        workflow.as_ref(),
+        &auth_user.user_id,
+    )
+    .await
+    {
+        Ok(outcomes) => outcomes,
         Err(err) => {
-            log::error!("Update todo error: {}", err);
-            HttpResponse::InternalServerError().json("Error updating todo")
+            log::error!("Bulk todos error: {}", err);
+            return HttpResponse::InternalServerError().json("Error applying bulk action");
         }
+    };
+
+    let outcomes = outcomes
+        .into_iter()
+        .map(|outcome| {
+            outcome.map_err(|err| {
+                if err.is::<VersionConflict>() || err.is::<BulkItemError>() {
+                    return err.to_string();
+                }
+                match err.downcast_ref::<WorkflowError>() {
+                    Some(workflow_err) => workflow_err.to_string(),
+                    None => {
+                        log::error!("Bulk todo item error: {}", err);
+                        "Error updating todo".to_string()
+                    }
+                }
+            })
+        })
+        .collect();
+
+    let response = items.into_response(request.mode, outcomes);
+    if response.committed {
+        HttpResponse::Ok().json(response)
+    } else {
+        HttpResponse::UnprocessableEntity().json(response)
     }
 }
 
 pub async fn delete_todo_handler(
+    req: HttpRequest,
     pool: web::Data<MySqlPool>,
     path: web::Path<String>,
+    query: web::Query<DeleteTodoQuery>,
 ) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
     let id = match parse_uuid(path.into_inner()) {
         Ok(uuid) => uuid,
         Err(resp) => return resp,
     };
 
-    match delete_todo(&pool, &id).await {
-        Ok(true) => HttpResponse::NoContent().finish(),
-        Ok(false) => HttpResponse::NotFound().json("Todo not found"),
+    // Trashed todos can still be deleted permanently
+    let todo = match find_owned_todo_with_trashed(&pool, &id, &auth_user).await {
+        Ok(todo) => todo,
+        Err(resp) => return resp,
+    };
+
+    let if_match = if_match(&req, todo.version);
+    if if_match == Some(false) {
+        return precondition_failed(&todo);
+    }
+
+    if query.permanent.unwrap_or(false) {
+        return match purge_todo(&pool, &todo).await {
+            Ok(true) => HttpResponse::NoContent().finish(),
+            Ok(false) => HttpResponse::NotFound().json("Todo not found"),
+            Err(err) => {
+                log::error!("Delete todo error: {}", err);
+                HttpResponse::InternalServerError().json("Error deleting todo")
+            }
+        };
+    }
+
+    if todo.deleted_at.is_some() {
+        return HttpResponse::NotFound().json("Todo not found");
+    }
+
+    match delete_todo(&pool, &todo, &auth_user.user_id).await {
+        Ok(()) => HttpResponse::NoContent().finish(),
+        Err(err) if err.is::<VersionConflict>() => version_conflict_response(if_match.is_some()),
         Err(err) => {
             log::error!("Delete todo error: {}", err);
             HttpResponse::InternalServerError().json("Error deleting todo")
         }
     }
 }
+
+pub async fn get_trash_handler(req: HttpRequest, pool: web::Data<MySqlPool>) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    match get_trashed_todos(&pool, &auth_user.user_id).await {
+        Ok(todos) => HttpResponse::Ok().json(todos),
+        Err(err) => {
+            log::error!("Fetch trash error: {}", err);
+            HttpResponse::InternalServerError().json("Error fetching trash")
+        }
+    }
+}
+
+pub async fn restore_todo_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let id = match parse_uuid(path.into_inner()) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    let todo = match find_owned_todo_with_trashed(&pool, &id, &auth_user).await {
+        Ok(todo) => todo,
+        Err(resp) => return resp,
+    };
+
+    if todo.deleted_at.is_none() {
+        return HttpResponse::Conflict().json("Todo is not in the trash");
+    }
+
+    match restore_todo(&pool, &todo, &auth_user.user_id).await {
+        Ok(Some(restored)) => HttpResponse::Ok().json(restored),
+        Ok(None) => HttpResponse::NotFound().json("Todo not found"),
+        Err(err) => {
+            log::error!("Restore todo error: {}", err);
+            HttpResponse::InternalServerError().json("Error restoring todo")
+        }
+    }
+}
+
+pub async fn skip_occurrence_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let id = match parse_uuid(path.into_inner()) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    let todo = match find_owned_todo(&pool, &id, &auth_user).await {
+        Ok(todo) => todo,
+        Err(resp) => return resp,
+    };
+
+    if todo.recurrence_rule.is_none() {
+        return HttpResponse::BadRequest().json("Todo is not recurring");
+    }
+
+    // Skipping moves this occurrence on to the next date in the series
+    let due_date = match todo.next_due_date() {
+        Some(due_date) => due_date,
+        None => return HttpResponse::Conflict().json("No further occurrences in this series"),
+    };
+
+    match reschedule_occurrence(&pool, &todo, due_date, &auth_user.user_id).await {
+        Ok(Some(updated)) => HttpResponse::Ok().json(updated),
+        Ok(None) => HttpResponse::NotFound().json("Todo not found"),
+        Err(err) => {
+            log::error!("Skip occurrence error: {}", err);
+            HttpResponse::InternalServerError().json("Error skipping occurrence")
+        }
+    }
+}
+
+pub async fn stop_recurrence_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let id = match parse_uuid(path.into_inner()) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    let todo = match find_owned_todo(&pool, &id, &auth_user).await {
+        Ok(todo) => todo,
+        Err(resp) => return resp,
+    };
+
+    if todo.recurrence_rule.is_none() {
+        return HttpResponse::BadRequest().json("Todo is not recurring");
+    }
+
+    match stop_recurrence(&pool, &todo, &auth_user.user_id).await {
+        Ok(Some(updated)) => HttpResponse::Ok().json(updated),
+        Ok(None) => HttpResponse::NotFound().json("Todo not found"),
+        Err(err) => {
+            log::error!("Stop recurrence error: {}", err);
+            HttpResponse::InternalServerError().json("Error stopping recurrence")
+        }
+    }
+}
+
+pub async fn get_todo_history_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let id = match parse_uuid(path.into_inner()) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    if let Err(resp) = find_owned_todo(&pool, &id, &auth_user).await {
+        return resp;
+    }
+
+    match get_todo_events(&pool, &id).await {
+        Ok(events) => HttpResponse::Ok().json(events),
+        Err(err) => {
+            log::error!("Fetch todo history error: {}", err);
+            HttpResponse::InternalServerError().json("Error fetching todo history")
+        }
+    }
+}
+
+pub async fn archive_completed_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    match archive_completed_todos(&pool, &auth_user.user_id).await {
+        Ok(archived) => HttpResponse::Ok().json(serde_json::json!({ "archived": archived })),
+        Err(err) => {
+            log::error!("Archive completed todos error: {}", err);
+            HttpResponse::InternalServerError().json("Error archiving todos")
+        }
+    }
+}
+
+pub async fn archive_todo_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let id = match parse_uuid(path.into_inner()) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    let todo = match find_owned_todo(&pool, &id, &auth_user).await {
+        Ok(todo) => todo,
+        Err(resp) => return resp,
+    };
+
+    if !todo.can_be_archived() {
+        return HttpResponse::Conflict().json("Only completed todos can be archived");
+    }
+    if todo.archived_at.is_some() {
+        return HttpResponse::Ok().json(todo);
+    }
+
+    match archive_todo(&pool, &todo, &auth_user.user_id).await {
+        Ok(Some(archived)) => HttpResponse::Ok().json(archived),
+        Ok(None) => HttpResponse::NotFound().json("Todo not found"),
+        Err(err) => {
+            log::error!("Archive todo error: {}", err);
+            HttpResponse::InternalServerError().json("Error archiving todo")
+        }
+    }
+}
+
+pub async fn unarchive_todo_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let id = match parse_uuid(path.into_inner()) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    let todo = match find_owned_todo(&pool, &id, &auth_user).await {
+        Ok(todo) => todo,
+        Err(resp) => return resp,
+    };
+
+    if todo.archived_at.is_none() {
+        return HttpResponse::Ok().json(todo);
+    }
+
+    match unarchive_todo(&pool, &todo, &auth_user.user_id).await {
+        Ok(Some(unarchived)) => HttpResponse::Ok().json(unarchived),
+        Ok(None) => HttpResponse::NotFound().json("Todo not found"),
+        Err(err) => {
+            log::error!("Unarchive todo error: {}", err);
+            HttpResponse::InternalServerError().json("Error unarchiving todo")
+        }
+    }
+}
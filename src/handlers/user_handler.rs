@@ -4,10 +4,18 @@ use uuid::Uuid;
 
 use crate::{
     middleware::auth_middleware::get_current_user,
-    models::user_model::UpdateUserRequest,
-    schema::user_schema::{
-        check_email_exists, delete_user, get_all_users, get_user_by_id, update_user,
+    models::{
+        user_model::{UpdateUserRequest, UserPatch},
+        user_settings_model::UserSettings,
     },
+    schema::{
+        user_schema::{
+            check_email_exists, delete_user, get_all_users, get_user_by_id, update_user,
+        },
+        user_settings_schema::{get_user_settings, save_user_settings},
+    },
+    storage::AttachmentStorage,
+    utils::account_export::delete_export_archives,
 };
 
 fn parse_uuid(id: String) -> Result<Uuid, HttpResponse> {
@@ -46,7 +54,22 @@ pub async fn update_me_handler(
     update_user_handler(pool, user_id_path, update_data).await
 }
 
-pub async fn delete_me_handler(req: HttpRequest, pool: web::Data<MySqlPool>) -> HttpResponse {
+pub async fn patch_me_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    patch: web::Json<UserPatch>,
+) -> HttpResponse {
+    match UpdateUserRequest::try_from(patch.into_inner()) {
+        Ok(update_data) => update_me_handler(req, pool, web::Json(update_data)).await,
+        Err(message) => HttpResponse::BadRequest().json(message),
+    }
+}
+
+pub async fn delete_me_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    storage: web::Data<dyn AttachmentStorage>,
+) -> HttpResponse {
     let auth_user = match get_current_user(&req) {
         Ok(user) => user,
         Err(response) => return response,
@@ -55,7 +78,48 @@ pub async fn delete_me_handler(req: HttpRequest, pool: web::Data<MySqlPool>) ->
     let user_id_str = auth_user.user_id.to_string();
     let user_id_path = web::Path::from(user_id_str);
 
-    delete_user_handler(pool, user_id_path).await
+    delete_user_handler(pool, storage, user_id_path).await
+}
+
+pub async fn get_my_settings_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    match get_user_settings(&pool, &auth_user.user_id).await {
+        Ok(settings) => HttpResponse::Ok().json(settings),
+        Err(err) => {
+            log::error!("Get settings error: {}", err);
+            HttpResponse::InternalServerError().json("Error fetching settings")
+        }
+    }
+}
+
+pub async fn update_my_settings_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    settings: web::Json<UserSettings>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    if let Err(message) = settings.validate() {
+        return HttpResponse::BadRequest().json(message);
+    }
+
+    match save_user_settings(&pool, &auth_user.user_id, &settings).await {
+        Ok(_) => HttpResponse::Ok().json(settings.into_inner()),
+        Err(err) => {
+            log::error!("Update settings error: {}", err);
+            HttpResponse::InternalServerError().json("Error updating settings")
+        }
+    }
 }
 
 pub async fn get_users_handler(pool: web::Data<MySqlPool>) -> impl Responder {
@@ -136,8 +200,20 @@ pub async fn update_user_handler(
     }
 }
 
+pub async fn patch_user_handler(
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+    patch: web::Json<UserPatch>,
+) -> HttpResponse {
+    match UpdateUserRequest::try_from(patch.into_inner()) {
+        Ok(update_data) => update_user_handler(pool, path, web::Json(update_data)).await,
+        Err(message) => HttpResponse::BadRequest().json(message),
+    }
+}
+
 pub async fn delete_user_handler(
     pool: web::Data<MySqlPool>,
+    storage: web::Data<dyn AttachmentStorage>,
     path: web::Path<String>,
 ) -> HttpResponse {
     let id = match parse_uuid(path.into_inner()) {
@@ -145,6 +221,11 @@ pub async fn delete_user_handler(
         Err(resp) => return resp,
     };
 
+    if let Err(err) = delete_export_archives(&pool, storage.as_ref(), &id).await {
+        log::error!("Delete export archives error: {}", err);
+        return HttpResponse::InternalServerError().json("Error deleting user");
+    }
+
     match delete_user(&pool, &id).await {
         Ok(true) => HttpResponse::NoContent().finish(),
         Ok(false) => HttpResponse::NotFound().json("User not found"),
@@ -0,0 +1,355 @@
+use actix_web::{HttpRequest, HttpResponse, Responder, web};
+use chrono::{Duration, Utc};
+use sqlx::MySqlPool;
+use uuid::Uuid;
+
+use crate::{
+    middleware::auth_middleware::{AuthenticatedUser, get_current_user},
+    models::webhook_model::{
+        CLAIM_LEASE_SECONDS, CreateWebhookRequest, DeliveryDetails, UpdateWebhookRequest, Webhook,
+        WebhookDelivery, WebhookEvent, WebhookTargets, WebhookWithSecret, generate_webhook_secret,
+    },
+    schema::webhook_schema::{
+        cancel_pending_deliveries, create_webhook, delete_webhook, enqueue_delivery,
+        get_deliveries_by_webhook, get_delivery_attempts, get_delivery_by_id, get_webhook_by_id,
+        get_webhooks_by_user, update_webhook,
+    },
+    utils::webhooks::{attempt_delivery, validate_webhook_url},
+};
+
+const DELIVERY_LOG_LIMIT: i64 = 50;
+const MIN_SECRET_LEN: usize = 16;
+
+fn parse_uuid(id: String) -> Result<Uuid, HttpResponse> {
+    Uuid::parse_str(&id).map_err(|_| HttpResponse::BadRequest().json("Invalid UUID format"))
+}
+
+fn validate_events(events: &[WebhookEvent]) -> Result<(), String> {
+    if events.is_empty() {
+        return Err("A webhook must subscribe to at least one event".to_string());
+    }
+    if events.contains(&WebhookEvent::Test) {
+        return Err("webhook.test can't be subscribed to".to_string());
+    }
+    Ok(())
+}
+
+async fn find_owned_webhook(
+    pool: &MySqlPool,
+    id: &Uuid,
+    auth_user: &AuthenticatedUser,
+) -> Result<Webhook, HttpResponse> {
+    match get_webhook_by_id(pool, id).await {
+        Ok(Some(webhook)) if webhook.user_id == auth_user.user_id.to_string() => Ok(webhook),
+        Ok(_) => Err(HttpResponse::NotFound().json("Webhook not found")),
+        Err(err) => {
+            log::error!("Get webhook error: {}", err);
+            Err(HttpResponse::InternalServerError().json("Error fetching webhook"))
+        }
+    }
+}
+
+pub async fn create_webhook_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    targets: web::Data<WebhookTargets>,
+    webhook_data: web::Json<CreateWebhookRequest>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let webhook_data = webhook_data.into_inner();
+    if let Err(message) = validate_webhook_url(&webhook_data.url, **targets).await {
+        return HttpResponse::BadRequest().json(message);
+    }
+
+    let events = webhook_data
+        .events
+        .unwrap_or_else(|| WebhookEvent::TODO_EVENTS.to_vec());
+    if let Err(message) = validate_events(&events) {
+        return HttpResponse::BadRequest().json(message);
+    }
+
+    let secret = match webhook_data.secret {
+        Some(secret) if secret.len() < MIN_SECRET_LEN => {
+            return HttpResponse::BadRequest().json(format!(
+                "Webhook secret must be at least {} characters",
+                MIN_SECRET_LEN
+            ));
+        }
+        Some(secret) => secret,
+        None => generate_webhook_secret(),
+    };
+
+    let mut new_webhook = Webhook::new(
+        auth_user.user_id.to_string(),
+        webhook_data.url,
+        secret,
+        events,
+    );
+    new_webhook.active = webhook_data.active.unwrap_or(true);
+
+    match create_webhook(&pool, &new_webhook).await {
+        Ok(_) => HttpResponse::Created().json(WebhookWithSecret {
+            secret: new_webhook.secret.clone(),
+            webhook: new_webhook,
+        }),
+        Err(err) => {
+            log::error!("Create webhook error: {}", err);
+            HttpResponse::InternalServerError().json("Error creating webhook")
+        }
+    }
+}
+
+pub async fn get_webhooks_handler(req: HttpRequest, pool: web::Data<MySqlPool>) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    match get_webhooks_by_user(&pool, &auth_user.user_id).await {
+        Ok(webhooks) => HttpResponse::Ok().json(webhooks),
+        Err(err) => {
+            log::error!("Fetch webhooks error: {}", err);
+            HttpResponse::InternalServerError().json("Error fetching webhooks")
+        }
+    }
+}
+
+pub async fn get_webhook_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let id = match parse_uuid(path.into_inner()) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    match find_owned_webhook(&pool, &id, &auth_user).await {
+        Ok(webhook) => HttpResponse::Ok().json(webhook),
+        Err(resp) => resp,
+    }
+}
+
+pub async fn update_webhook_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    targets: web::Data<WebhookTargets>,
+    path: web::Path<String>,
+    webhook_data: web::Json<UpdateWebhookRequest>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let id = match parse_uuid(path.into_inner()) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    let mut webhook = match find_owned_webhook(&pool, &id, &auth_user).await {
+        Ok(webhook) => webhook,
+        Err(resp) => return resp,
+    };
+
+    let webhook_data = webhook_data.into_inner();
+    if let Some(url) = webhook_data.url {
+        if let Err(message) = validate_webhook_url(&url, **targets).await {
+            return HttpResponse::BadRequest().json(message);
+        }
+        webhook.url = url;
+    }
+    if let Some(events) = webhook_data.events {
+        if let Err(message) = validate_events(&events) {
+            return HttpResponse::BadRequest().json(message);
+        }
+        webhook.events = events;
+    }
+    let deactivated = webhook.active && webhook_data.active == Some(false);
+    if let Some(active) = webhook_data.active {
+        webhook.active = active;
+    }
+    if webhook_data.rotate_secret {
+        webhook.secret = generate_webhook_secret();
+    }
+
+    let updated = match update_webhook(&pool, &webhook).await {
+        Ok(Some(updated)) => updated,
+        Ok(None) => return HttpResponse::NotFound().json("Webhook not found"),
+        Err(err) => {
+            log::error!("Update webhook error: {}", err);
+            return HttpResponse::InternalServerError().json("Error updating webhook");
+        }
+    };
+
+    if deactivated {
+        if let Err(err) = cancel_pending_deliveries(&pool, &updated.id).await {
+            log::error!("Cancel webhook deliveries error: {}", err);
+        }
+    }
+
+    if webhook_data.rotate_secret {
+        HttpResponse::Ok().json(WebhookWithSecret {
+            secret: updated.secret.clone(),
+            webhook: updated,
+        })
+    } else {
+        HttpResponse::Ok().json(updated)
+    }
+}
+
+pub async fn delete_webhook_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let id = match parse_uuid(path.into_inner()) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    if let Err(resp) = find_owned_webhook(&pool, &id, &auth_user).await {
+        return resp;
+    }
+
+    match delete_webhook(&pool, &id).await {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().json("Webhook not found"),
+        Err(err) => {
+            log::error!("Delete webhook error: {}", err);
+            HttpResponse::InternalServerError().json("Error deleting webhook")
+        }
+    }
+}
+
+// Sends a webhook.test event right away and reports how the receiver
+// answered. A failed test is retried like any other delivery.
+pub async fn test_webhook_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    client: web::Data<reqwest::Client>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let id = match parse_uuid(path.into_inner()) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    let webhook = match find_owned_webhook(&pool, &id, &auth_user).await {
+        Ok(webhook) => webhook,
+        Err(resp) => return resp,
+    };
+
+    // The test event is sent right here. It's queued already leased, so the
+    // delivery job only retries it if this attempt never gets recorded.
+    let mut delivery = WebhookDelivery::new(&webhook, WebhookEvent::Test, None);
+    delivery.next_attempt_at = Utc::now() + Duration::seconds(CLAIM_LEASE_SECONDS);
+    let enqueued = match pool.acquire().await {
+        Ok(mut conn) => enqueue_delivery(&mut conn, &delivery).await,
+        Err(err) => Err(err.into()),
+    };
+    if let Err(err) = enqueued {
+        log::error!("Enqueue webhook delivery error: {}", err);
+        return HttpResponse::InternalServerError().json("Error sending test event");
+    }
+
+    match attempt_delivery(&pool, &client, &webhook, &mut delivery).await {
+        Ok(()) => HttpResponse::Ok().json(delivery),
+        Err(err) => {
+            log::error!("Webhook delivery {} error: {}", delivery.id, err);
+            HttpResponse::InternalServerError().json("Error sending test event")
+        }
+    }
+}
+
+pub async fn get_deliveries_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let id = match parse_uuid(path.into_inner()) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    if let Err(resp) = find_owned_webhook(&pool, &id, &auth_user).await {
+        return resp;
+    }
+
+    match get_deliveries_by_webhook(&pool, &id, DELIVERY_LOG_LIMIT).await {
+        Ok(deliveries) => HttpResponse::Ok().json(deliveries),
+        Err(err) => {
+            log::error!("Fetch webhook deliveries error: {}", err);
+            HttpResponse::InternalServerError().json("Error fetching deliveries")
+        }
+    }
+}
+
+pub async fn get_delivery_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<(String, String)>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let (webhook_id, delivery_id) = path.into_inner();
+    let webhook_id = match parse_uuid(webhook_id) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+    let delivery_id = match parse_uuid(delivery_id) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    if let Err(resp) = find_owned_webhook(&pool, &webhook_id, &auth_user).await {
+        return resp;
+    }
+
+    let delivery = match get_delivery_by_id(&pool, &delivery_id).await {
+        Ok(Some(delivery)) if delivery.webhook_id == webhook_id.to_string() => delivery,
+        Ok(_) => return HttpResponse::NotFound().json("Delivery not found"),
+        Err(err) => {
+            log::error!("Get webhook delivery error: {}", err);
+            return HttpResponse::InternalServerError().json("Error fetching delivery");
+        }
+    };
+
+    match get_delivery_attempts(&pool, &delivery_id).await {
+        Ok(attempts_log) => HttpResponse::Ok().json(DeliveryDetails {
+            delivery,
+            attempts_log,
+        }),
+        Err(err) => {
+            log::error!("Get delivery attempts error: {}", err);
+            HttpResponse::InternalServerError().json("Error fetching delivery")
+        }
+    }
+}
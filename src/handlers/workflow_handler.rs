@@ -0,0 +1,193 @@
+use actix_web::{HttpRequest, HttpResponse, Responder, web};
+use sqlx::MySqlPool;
+use uuid::Uuid;
+
+use crate::{
+    middleware::auth_middleware::{AuthenticatedUser, get_current_user},
+    models::workflow_model::{CreateWorkflowRequest, UpdateWorkflowRequest, Workflow},
+    schema::workflow_schema::{
+        create_workflow, delete_workflow, get_default_workflow, get_workflow_by_id,
+        get_workflows_by_user, update_workflow,
+    },
+};
+
+fn parse_uuid(id: String) -> Result<Uuid, HttpResponse> {
+    Uuid::parse_str(&id).map_err(|_| HttpResponse::BadRequest().json("Invalid UUID format"))
+}
+
+async fn find_owned_workflow(
+    pool: &MySqlPool,
+    id: &Uuid,
+    auth_user: &AuthenticatedUser,
+) -> Result<Workflow, HttpResponse> {
+    match get_workflow_by_id(pool, id).await {
+        Ok(Some(workflow)) if workflow.user_id == auth_user.user_id.to_string() => Ok(workflow),
+        Ok(_) => Err(HttpResponse::NotFound().json("Workflow not found")),
+        Err(err) => {
+            log::error!("Get workflow error: {}", err);
+            Err(HttpResponse::InternalServerError().json("Error fetching workflow"))
+        }
+    }
+}
+
+pub async fn create_workflow_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    workflow_data: web::Json<CreateWorkflowRequest>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+    let user_id = auth_user.user_id.to_string();
+
+    // The first workflow a user creates becomes their default
+    let has_default = match get_default_workflow(&pool, &user_id).await {
+        Ok(workflow) => workflow.is_some(),
+        Err(err) => {
+            log::error!("Get workflow error: {}", err);
+            return HttpResponse::InternalServerError().json("Error fetching workflow");
+        }
+    };
+
+    let workflow_data = workflow_data.into_inner();
+    let new_workflow = Workflow::new(
+        user_id,
+        workflow_data.name,
+        workflow_data.statuses,
+        workflow_data.transitions.unwrap_or_default(),
+        workflow_data.is_default.unwrap_or(!has_default),
+    );
+
+    if let Err(message) = new_workflow.validate() {
+        return HttpResponse::BadRequest().json(message);
+    }
+
+    match create_workflow(&pool, &new_workflow).await {
+        Ok(_) => HttpResponse::Created().json(&new_workflow),
+        Err(err) => {
+            log::error!("Create workflow error: {}", err);
+            HttpResponse::InternalServerError().json("Error creating workflow")
+        }
+    }
+}
+
+pub async fn get_workflows_handler(req: HttpRequest, pool: web::Data<MySqlPool>) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    match get_workflows_by_user(&pool, &auth_user.user_id).await {
+        Ok(workflows) => HttpResponse::Ok().json(workflows),
+        Err(err) => {
+            log::error!("Fetch workflows error: {}", err);
+            HttpResponse::InternalServerError().json("Error fetching workflows")
+        }
+    }
+}
+
+pub async fn get_workflow_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let id = match parse_uuid(path.into_inner()) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    match find_owned_workflow(&pool, &id, &auth_user).await {
+        Ok(workflow) => HttpResponse::Ok().json(workflow),
+        Err(resp) => resp,
+    }
+}
+
+pub async fn update_workflow_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+    update_data: web::Json<UpdateWorkflowRequest>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let id = match parse_uuid(path.into_inner()) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    let mut workflow = match find_owned_workflow(&pool, &id, &auth_user).await {
+        Ok(workflow) => workflow,
+        Err(resp) => return resp,
+    };
+
+    let update_data = update_data.into_inner();
+    if let Some(name) = update_data.name {
+        workflow.name = name;
+    }
+    if let Some(statuses) = update_data.statuses {
+        workflow.set_statuses(statuses);
+        // Rules pointing at removed statuses go away with them
+        let kept: Vec<String> = workflow.statuses.iter().map(|s| s.key.clone()).collect();
+        workflow
+            .transitions
+            .retain(|t| kept.contains(&t.from) && kept.contains(&t.to));
+    }
+    if let Some(transitions) = update_data.transitions {
+        workflow.transitions = transitions;
+    }
+    if let Some(is_default) = update_data.is_default {
+        workflow.is_default = is_default;
+    }
+
+    if let Err(message) = workflow.validate() {
+        return HttpResponse::BadRequest().json(message);
+    }
+
+    match update_workflow(&pool, &workflow).await {
+        Ok(Some(updated)) => HttpResponse::Ok().json(updated),
+        Ok(None) => HttpResponse::NotFound().json("Workflow not found"),
+        Err(err) => {
+            log::error!("Update workflow error: {}", err);
+            HttpResponse::InternalServerError().json("Error updating workflow")
+        }
+    }
+}
+
+pub async fn delete_workflow_handler(
+    req: HttpRequest,
+    pool: web::Data<MySqlPool>,
+    path: web::Path<String>,
+) -> impl Responder {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    let id = match parse_uuid(path.into_inner()) {
+        Ok(uuid) => uuid,
+        Err(resp) => return resp,
+    };
+
+    if let Err(resp) = find_owned_workflow(&pool, &id, &auth_user).await {
+        return resp;
+    }
+
+    // Todos in its statuses fall back to their plain TodoStatus
+    match delete_workflow(&pool, &id).await {
+        Ok(true) => HttpResponse::NoContent().finish(),
+        Ok(false) => HttpResponse::NotFound().json("Workflow not found"),
+        Err(err) => {
+            log::error!("Delete workflow error: {}", err);
+            HttpResponse::InternalServerError().json("Error deleting workflow")
+        }
+    }
+}
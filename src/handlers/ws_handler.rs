@@ -0,0 +1,184 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use actix_web::{HttpRequest, HttpResponse, rt, web};
+use actix_ws::{CloseCode, CloseReason, Message, MessageStream, Session};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::{
+    middleware::auth_middleware::{AuthenticatedUser, authenticate_token, get_current_user},
+    models::change_event_model::{ChangeEvent, ChangeStreamQuery, StreamNotice, WsTicketResponse},
+    utils::{
+        event_hub::EventHub,
+        ws_tickets::{TICKET_TTL, WsTicketStore},
+    },
+};
+
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(45);
+
+// The token comes from the Authorization header like everywhere else; browser
+// clients pass a ticket in the query string instead
+fn authenticate(
+    req: &HttpRequest,
+    query: &ChangeStreamQuery,
+    secret_key: &str,
+    tickets: &WsTicketStore,
+) -> Option<AuthenticatedUser> {
+    let header_token = req
+        .headers()
+        .get("Authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Bearer "));
+
+    match (header_token, query.ticket.as_deref()) {
+        (Some(token), _) => authenticate_token(token, secret_key),
+        (None, Some(ticket)) => tickets.redeem(ticket),
+        (None, None) => None,
+    }
+}
+
+pub async fn create_ws_ticket_handler(
+    req: HttpRequest,
+    tickets: web::Data<WsTicketStore>,
+) -> HttpResponse {
+    let auth_user = match get_current_user(&req) {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+
+    HttpResponse::Created().json(WsTicketResponse {
+        ticket: tickets.issue(auth_user),
+        expires_in: TICKET_TTL.as_secs(),
+    })
+}
+
+pub async fn todo_changes_ws_handler(
+    req: HttpRequest,
+    body: web::Payload,
+    hub: web::Data<EventHub>,
+    tickets: web::Data<WsTicketStore>,
+    jwt_secret: web::Data<String>,
+    query: web::Query<ChangeStreamQuery>,
+) -> HttpResponse {
+    let auth_user = match authenticate(&req, &query, &jwt_secret, &tickets) {
+        Some(user) => user,
+        None => {
+            return HttpResponse::Unauthorized().json("Invalid or missing authorization token");
+        }
+    };
+
+    let (response, session, stream) = match actix_ws::handle(&req, body) {
+        Ok(upgrade) => upgrade,
+        Err(err) => return HttpResponse::from_error(err),
+    };
+
+    // Subscribe before replaying so nothing published in between is lost
+    let receiver = hub.subscribe();
+    let replay = query
+        .last_event_id
+        .map(|last_id| hub.replay_since(last_id, &auth_user.user_id.to_string()));
+
+    rt::spawn(run_session(
+        session,
+        stream,
+        receiver,
+        replay,
+        auth_user.user_id.to_string(),
+    ));
+
+    response
+}
+
+async fn send_json(session: &mut Session, message: &impl serde::Serialize) -> bool {
+    match serde_json::to_string(message) {
+        Ok(text) => session.text(text).await.is_ok(),
+        Err(err) => {
+            log::error!("Serialize change event error: {}", err);
+            true
+        }
+    }
+}
+
+async fn run_session(
+    mut session: Session,
+    mut stream: MessageStream,
+    mut receiver: broadcast::Receiver<Arc<ChangeEvent>>,
+    replay: Option<Option<Vec<Arc<ChangeEvent>>>>,
+    user_id: String,
+) {
+    let mut last_sent_id = 0;
+
+    match replay {
+        Some(Some(events)) => {
+            for event in events {
+                if !send_json(&mut session, &*event).await {
+                    return;
+                }
+                last_sent_id = event.id;
+            }
+        }
+        Some(None) => {
+            if !send_json(&mut session, &StreamNotice::ResyncRequired).await {
+                return;
+            }
+        }
+        None => {}
+    }
+
+    let mut heartbeat = rt::time::interval(HEARTBEAT_INTERVAL);
+    let mut last_seen = Instant::now();
+
+    let close_reason = loop {
+        tokio::select! {
+            _ = heartbeat.tick() => {
+                if last_seen.elapsed() > CLIENT_TIMEOUT {
+                    break Some(CloseReason::from(CloseCode::Away));
+                }
+                if session.ping(b"").await.is_err() {
+                    return;
+                }
+            }
+            message = stream.recv() => {
+                match message {
+                    Some(Ok(Message::Ping(bytes))) => {
+                        last_seen = Instant::now();
+                        if session.pong(&bytes).await.is_err() {
+                            return;
+                        }
+                    }
+                    Some(Ok(Message::Close(reason))) => break reason,
+                    // The stream is server to client; anything else only
+                    // counts as a sign of life
+                    Some(Ok(_)) => last_seen = Instant::now(),
+                    Some(Err(err)) => {
+                        log::error!("WebSocket protocol error: {}", err);
+                        break None;
+                    }
+                    None => break None,
+                }
+            }
+            event = receiver.recv() => {
+                match event {
+                    Ok(event) if event.id > last_sent_id && event.is_visible_to(&user_id) => {
+                        if !send_json(&mut session, &*event).await {
+                            return;
+                        }
+                        last_sent_id = event.id;
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(_)) => {
+                        if !send_json(&mut session, &StreamNotice::ResyncRequired).await {
+                            return;
+                        }
+                    }
+                    Err(RecvError::Closed) => break None,
+                }
+            }
+        }
+    };
+
+    let _ = session.close(close_reason).await;
+}
@@ -0,0 +1,44 @@
+use std::{sync::Arc, time::Duration};
+
+use actix_web::rt;
+use chrono::Utc;
+use sqlx::MySqlPool;
+
+use crate::{
+    schema::{account_schema::get_due_account_erasures, user_schema::delete_user},
+    storage::AttachmentStorage,
+    utils::account_export::delete_export_archives,
+};
+
+const ERASURE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// Erases the accounts whose erasure grace period has passed
+pub fn spawn_account_erasure_job(pool: MySqlPool, storage: Arc<dyn AttachmentStorage>) {
+    rt::spawn(async move {
+        let mut interval = rt::time::interval(ERASURE_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let user_ids = match get_due_account_erasures(&pool, Utc::now()).await {
+                Ok(user_ids) => user_ids,
+                Err(err) => {
+                    log::error!("Get due account erasures error: {}", err);
+                    continue;
+                }
+            };
+
+            for user_id in user_ids {
+                if let Err(err) = delete_export_archives(&pool, storage.as_ref(), &user_id).await {
+                    log::error!("Delete account {} exports error: {}", user_id, err);
+                    continue;
+                }
+
+                match delete_user(&pool, &user_id).await {
+                    Ok(_) => log::info!("Erased account {}", user_id),
+                    Err(err) => log::error!("Erase account {} error: {}", user_id, err),
+                }
+            }
+        }
+    });
+}
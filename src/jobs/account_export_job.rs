@@ -0,0 +1,113 @@
+use std::{env, path::Path, sync::Arc, time::Duration};
+
+use actix_web::rt;
+use chrono::Utc;
+use sqlx::MySqlPool;
+use tokio::fs;
+use uuid::Uuid;
+
+use crate::{
+    models::account_model::{AccountExport, EXPORT_LINK_HOURS},
+    schema::account_schema::{
+        claim_account_export, complete_account_export, expire_account_export, fail_account_export,
+        get_expired_account_exports,
+    },
+    storage::AttachmentStorage,
+    utils::account_export::build_account_archive,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(10);
+
+// How long an export may stay in processing before another worker takes it over
+const CLAIM_LEASE_SECONDS: i64 = 30 * 60;
+
+// Builds the archive in a temporary file and uploads it from there
+async fn store_archive(
+    pool: &MySqlPool,
+    storage: &dyn AttachmentStorage,
+    export: &AccountExport,
+    path: &Path,
+) -> anyhow::Result<i64> {
+    let user_id = Uuid::parse_str(&export.user_id)?;
+    let size = build_account_archive(pool, storage, &user_id, path).await?;
+    storage
+        .put_file(&export.archive_storage_key(), "application/zip", path)
+        .await?;
+
+    Ok(size as i64)
+}
+
+async fn process_export(
+    pool: &MySqlPool,
+    storage: &dyn AttachmentStorage,
+    export: &AccountExport,
+) -> anyhow::Result<()> {
+    let path = env::temp_dir().join(format!("account-export-{}.zip", export.id));
+    let stored = store_archive(pool, storage, export, &path).await;
+    if let Err(err) = fs::remove_file(&path).await {
+        log::error!("Remove account export {} file error: {}", export.id, err);
+    }
+
+    match stored {
+        Ok(size_bytes) => {
+            let key = export.archive_storage_key();
+            let expires_at = Utc::now() + chrono::Duration::hours(EXPORT_LINK_HOURS);
+            if !complete_account_export(pool, &export.id, &key, size_bytes, expires_at).await? {
+                storage.delete(&key).await?;
+            }
+            Ok(())
+        }
+        Err(err) => {
+            log::error!("Build account export {} error: {}", export.id, err);
+            fail_account_export(pool, &export.id, "Error building the archive").await
+        }
+    }
+}
+
+// An archive that can't be deleted now keeps its export ready, so the next
+// run tries again
+async fn expire_exports(pool: &MySqlPool, storage: &dyn AttachmentStorage) -> anyhow::Result<()> {
+    for export in get_expired_account_exports(pool, Utc::now()).await? {
+        if let Some(key) = &export.storage_key {
+            if let Err(err) = storage.delete(key).await {
+                log::error!("Delete account export {} archive error: {}", export.id, err);
+                continue;
+            }
+        }
+        expire_account_export(pool, &export.id).await?;
+    }
+
+    Ok(())
+}
+
+// Builds requested account exports one at a time, and deletes archives whose
+// download link has expired
+pub fn spawn_account_export_job(pool: MySqlPool, storage: Arc<dyn AttachmentStorage>) {
+    rt::spawn(async move {
+        let mut interval = rt::time::interval(POLL_INTERVAL);
+        let lease = chrono::Duration::seconds(CLAIM_LEASE_SECONDS);
+
+        loop {
+            interval.tick().await;
+
+            if let Err(err) = expire_exports(&pool, storage.as_ref()).await {
+                log::error!("Expire account exports error: {}", err);
+            }
+
+            loop {
+                let export = match claim_account_export(&pool, lease).await {
+                    Ok(Some(export)) => export,
+                    Ok(None) => break,
+                    Err(err) => {
+                        log::error!("Claim account export error: {}", err);
+                        break;
+                    }
+                };
+
+                if let Err(err) = process_export(&pool, storage.as_ref(), &export).await {
+                    log::error!("Account export {} error: {}", export.id, err);
+                }
+            }
+        }
+    });
+}
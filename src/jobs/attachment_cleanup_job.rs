@@ -0,0 +1,61 @@
+use std::{sync::Arc, time::Duration};
+
+use actix_web::rt;
+use sqlx::MySqlPool;
+
+use crate::{
+    schema::attachment_schema::{
+        delete_attachment, delete_pending_file_deletion, get_orphaned_attachments,
+        get_pending_file_deletions,
+    },
+    storage::AttachmentStorage,
+};
+
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const BATCH_SIZE: i64 = 500;
+
+// Deletes the stored files of deleted attachments, and of attachments whose
+// todo no longer exists along with their rows. A failed file deletion is
+// retried on the next run.
+pub fn spawn_attachment_cleanup_job(pool: MySqlPool, storage: Arc<dyn AttachmentStorage>) {
+    rt::spawn(async move {
+        let mut interval = rt::time::interval(CLEANUP_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            match get_pending_file_deletions(&pool, BATCH_SIZE).await {
+                Ok(storage_keys) => {
+                    for storage_key in storage_keys {
+                        if let Err(err) = storage.delete(&storage_key).await {
+                            log::error!("Delete attachment file {} error: {}", storage_key, err);
+                            continue;
+                        }
+                        if let Err(err) = delete_pending_file_deletion(&pool, &storage_key).await {
+                            log::error!("Delete file deletion {} error: {}", storage_key, err);
+                        }
+                    }
+                }
+                Err(err) => log::error!("Get pending file deletions error: {}", err),
+            }
+
+            let attachments = match get_orphaned_attachments(&pool, BATCH_SIZE).await {
+                Ok(attachments) => attachments,
+                Err(err) => {
+                    log::error!("Get orphaned attachments error: {}", err);
+                    continue;
+                }
+            };
+
+            for attachment in attachments {
+                if let Err(err) = storage.delete(&attachment.storage_key).await {
+                    log::error!("Delete attachment {} file error: {}", attachment.id, err);
+                    continue;
+                }
+                if let Err(err) = delete_attachment(&pool, &attachment.id).await {
+                    log::error!("Delete attachment {} error: {}", attachment.id, err);
+                }
+            }
+        }
+    });
+}
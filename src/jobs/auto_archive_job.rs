@@ -0,0 +1,25 @@
+use std::time::Duration;
+
+use actix_web::rt;
+use sqlx::MySqlPool;
+
+use crate::schema::todo_schema::auto_archive_completed_todos;
+
+const AUTO_ARCHIVE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// Applies each user's "auto-archive completed after N days" setting
+pub fn spawn_auto_archive_job(pool: MySqlPool) {
+    rt::spawn(async move {
+        let mut interval = rt::time::interval(AUTO_ARCHIVE_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            match auto_archive_completed_todos(&pool).await {
+                Ok(0) => {}
+                Ok(archived) => log::info!("Auto-archived {} completed todos", archived),
+                Err(err) => log::error!("Auto-archive error: {}", err),
+            }
+        }
+    });
+}
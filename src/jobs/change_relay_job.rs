@@ -0,0 +1,105 @@
+use std::{collections::HashMap, time::Duration};
+
+use actix_web::{rt, web};
+use sqlx::MySqlPool;
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        change_event_model::ChangeKind,
+        todo_model::Todo,
+        webhook_model::{Webhook, WebhookDelivery, WebhookEvent},
+    },
+    schema::{
+        change_outbox_schema::{claim_pending_changes, delete_pending_changes},
+        todo_schema::get_todos_by_ids_with_trashed,
+        webhook_schema::{enqueue_delivery, get_webhooks_by_user},
+    },
+    utils::event_hub::EventHub,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+const BATCH_SIZE: i64 = 100;
+
+// Relays one batch of recorded changes, returning how many were taken. The
+// webhook deliveries are queued in the transaction that removes the changes,
+// so a change is either still pending or has all of its deliveries. Events
+// carry the todo as it is when relayed, or the copy kept when it was removed.
+async fn relay_changes(pool: &MySqlPool, hub: &EventHub) -> anyhow::Result<usize> {
+    let mut tx = pool.begin().await?;
+
+    let pending = claim_pending_changes(&mut tx, BATCH_SIZE).await?;
+    if pending.is_empty() {
+        return Ok(0);
+    }
+
+    let todo_ids: Vec<String> = pending.iter().map(|c| c.todo_id.clone()).collect();
+    let todos: HashMap<String, Todo> = get_todos_by_ids_with_trashed(pool, &todo_ids)
+        .await?
+        .into_iter()
+        .map(|todo| (todo.id.clone(), todo))
+        .collect();
+
+    let events: Vec<(ChangeKind, Todo)> = pending
+        .iter()
+        .filter_map(|change| {
+            let kind = ChangeKind::from_db(&change.kind)?;
+            let todo = match &change.snapshot {
+                Some(snapshot) => serde_json::from_str(snapshot).ok(),
+                None => todos.get(&change.todo_id).cloned(),
+            }?;
+            Some((kind, todo))
+        })
+        .collect();
+
+    let mut webhooks: HashMap<String, Vec<Webhook>> = HashMap::new();
+    for (kind, todo) in &events {
+        if !webhooks.contains_key(&todo.user_id) {
+            let user_webhooks =
+                get_webhooks_by_user(pool, &Uuid::parse_str(&todo.user_id)?).await?;
+            webhooks.insert(todo.user_id.clone(), user_webhooks);
+        }
+
+        let event_type = WebhookEvent::from(*kind);
+        for webhook in &webhooks[&todo.user_id] {
+            if webhook.subscribes_to(event_type) {
+                let delivery = WebhookDelivery::new(webhook, event_type, Some(todo));
+                enqueue_delivery(&mut tx, &delivery).await?;
+            }
+        }
+    }
+
+    let ids: Vec<i64> = pending.iter().map(|c| c.id).collect();
+    delete_pending_changes(&mut tx, &ids).await?;
+    tx.commit().await?;
+
+    for (kind, todo) in &events {
+        hub.publish(*kind, todo);
+    }
+
+    Ok(pending.len())
+}
+
+// Hands changes recorded by todo writes to webhooks and the event hub once
+// they have committed, whichever code path made them
+pub fn spawn_change_relay_job(pool: MySqlPool, hub: web::Data<EventHub>) {
+    rt::spawn(async move {
+        let mut interval = rt::time::interval(POLL_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            // A full batch means there may be more waiting
+            loop {
+                match relay_changes(&pool, &hub).await {
+                    Ok(relayed) if relayed as i64 == BATCH_SIZE => {}
+                    Ok(_) => break,
+                    Err(err) => {
+                        log::error!("Relay todo changes error: {}", err);
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
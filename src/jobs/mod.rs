@@ -0,0 +1,8 @@
+pub mod account_erasure_job;
+pub mod account_export_job;
+pub mod attachment_cleanup_job;
+pub mod auto_archive_job;
+pub mod change_relay_job;
+pub mod purge_idempotency_keys_job;
+pub mod purge_trash_job;
+pub mod webhook_delivery_job;
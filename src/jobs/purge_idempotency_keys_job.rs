@@ -0,0 +1,25 @@
+use std::time::Duration;
+
+use actix_web::rt;
+use sqlx::MySqlPool;
+
+use crate::schema::idempotency_schema::purge_expired_idempotency_keys;
+
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// Expired keys are already ignored on lookup; this just keeps the table small
+pub fn spawn_purge_idempotency_keys_job(pool: MySqlPool) {
+    rt::spawn(async move {
+        let mut interval = rt::time::interval(PURGE_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            match purge_expired_idempotency_keys(&pool).await {
+                Ok(0) => {}
+                Ok(purged) => log::info!("Purged {} expired idempotency keys", purged),
+                Err(err) => log::error!("Purge idempotency keys error: {}", err),
+            }
+        }
+    });
+}
@@ -0,0 +1,36 @@
+use std::time::Duration;
+
+use actix_web::rt;
+use chrono::Utc;
+use sqlx::MySqlPool;
+
+use crate::{
+    models::{sync_model::TOMBSTONE_RETENTION_DAYS, todo_model::TrashRetention},
+    schema::todo_schema::{purge_tombstones, purge_trash},
+};
+
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+// Permanently deletes todos that have been in the trash for longer than
+// `retention`, and sync tombstones past their own retention
+pub fn spawn_purge_trash_job(pool: MySqlPool, retention: TrashRetention) {
+    rt::spawn(async move {
+        let mut interval = rt::time::interval(PURGE_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let cutoff = retention.purge_cutoff(Utc::now());
+            match purge_trash(&pool, cutoff).await {
+                Ok(0) => {}
+                Ok(purged) => log::info!("Purged {} todos from the trash", purged),
+                Err(err) => log::error!("Purge trash error: {}", err),
+            }
+
+            let tombstone_cutoff = Utc::now() - chrono::Duration::days(TOMBSTONE_RETENTION_DAYS);
+            if let Err(err) = purge_tombstones(&pool, tombstone_cutoff).await {
+                log::error!("Purge tombstones error: {}", err);
+            }
+        }
+    });
+}
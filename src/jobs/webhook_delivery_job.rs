@@ -0,0 +1,54 @@
+use std::time::Duration;
+
+use actix_web::rt;
+use sqlx::MySqlPool;
+use uuid::Uuid;
+
+use crate::{
+    models::webhook_model::CLAIM_LEASE_SECONDS,
+    schema::webhook_schema::{claim_due_deliveries, get_webhook_by_id},
+    utils::webhooks::attempt_delivery,
+};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const BATCH_SIZE: i64 = 20;
+
+// Sends queued deliveries once they are due, retrying failures with backoff
+pub fn spawn_webhook_delivery_job(pool: MySqlPool, client: reqwest::Client) {
+    rt::spawn(async move {
+        let mut interval = rt::time::interval(POLL_INTERVAL);
+        let lease = chrono::Duration::seconds(CLAIM_LEASE_SECONDS);
+
+        loop {
+            interval.tick().await;
+
+            let deliveries = match claim_due_deliveries(&pool, BATCH_SIZE, lease).await {
+                Ok(deliveries) => deliveries,
+                Err(err) => {
+                    log::error!("Claim webhook deliveries error: {}", err);
+                    continue;
+                }
+            };
+
+            for mut delivery in deliveries {
+                let webhook = match Uuid::parse_str(&delivery.webhook_id) {
+                    Ok(id) => get_webhook_by_id(&pool, &id).await,
+                    Err(err) => Err(err.into()),
+                };
+
+                let result = match webhook {
+                    Ok(Some(webhook)) => {
+                        attempt_delivery(&pool, &client, &webhook, &mut delivery).await
+                    }
+                    // Deleting the webhook also deletes its deliveries
+                    Ok(None) => Ok(()),
+                    Err(err) => Err(err),
+                };
+
+                if let Err(err) = result {
+                    log::error!("Webhook delivery {} error: {}", delivery.id, err);
+                }
+            }
+        }
+    });
+}
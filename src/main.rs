@@ -1,17 +1,33 @@
 mod config;
 mod handlers;
+mod jobs;
 mod middleware;
 mod models;
 mod routes;
 mod schema;
+mod storage;
 mod utils;
 
 use crate::routes::config_routes;
 use actix_web::{App, HttpServer, middleware::Logger, web};
-use config::database::create_connection_pool;
+use config::{database::create_connection_pool, storage::create_attachment_storage};
 use dotenv::dotenv;
-use env_logger;
-use utils::get_env_vars::get_env_var;
+use jobs::{
+    account_erasure_job::spawn_account_erasure_job, account_export_job::spawn_account_export_job,
+    attachment_cleanup_job::spawn_attachment_cleanup_job, auto_archive_job::spawn_auto_archive_job,
+    change_relay_job::spawn_change_relay_job,
+    purge_idempotency_keys_job::spawn_purge_idempotency_keys_job,
+    purge_trash_job::spawn_purge_trash_job, webhook_delivery_job::spawn_webhook_delivery_job,
+};
+use models::{
+    search_model::SearchBackend, todo_model::TrashRetention, webhook_model::WebhookTargets,
+};
+use utils::{
+    event_hub::EventHub,
+    get_env_vars::{get_env_var, get_env_var_or},
+    webhooks::build_webhook_client,
+    ws_tickets::WsTicketStore,
+};
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
@@ -22,10 +38,37 @@ async fn main() -> std::io::Result<()> {
     let host = get_env_var("SERVER_HOST");
     let port: u16 = get_env_var("SERVER_PORT").parse().unwrap();
     let jwt_secret = get_env_var("JWT_SECRET");
+    let trash_retention =
+        TrashRetention::from_env_value(&get_env_var_or("TRASH_RETENTION_DAYS", "30"))
+            .map_err(std::io::Error::other)?;
+    let search_backend =
+        SearchBackend::from_env_value(&get_env_var_or("SEARCH_BACKEND", "fulltext"));
+    let webhook_targets =
+        WebhookTargets::from_env_value(&get_env_var_or("WEBHOOK_ALLOW_PRIVATE_TARGETS", "false"));
 
     let pool = create_connection_pool(&database_url)
         .await
         .expect("Failed to create database connection pool");
+    // Reported as a startup error rather than a panic, naming what's missing
+    let attachment_storage = create_attachment_storage().map_err(|err| {
+        std::io::Error::other(format!("Failed to configure attachment storage: {:#}", err))
+    })?;
+
+    spawn_purge_trash_job(pool.clone(), trash_retention);
+    spawn_auto_archive_job(pool.clone());
+    spawn_purge_idempotency_keys_job(pool.clone());
+    spawn_account_export_job(pool.clone(), attachment_storage.clone());
+    spawn_account_erasure_job(pool.clone(), attachment_storage.clone());
+    spawn_attachment_cleanup_job(pool.clone(), attachment_storage.clone());
+
+    // Shared by all workers so every connection sees every change
+    let event_hub = web::Data::new(EventHub::new());
+    spawn_change_relay_job(pool.clone(), event_hub.clone());
+    let ws_tickets = web::Data::new(WsTicketStore::new());
+
+    let webhook_client =
+        build_webhook_client(webhook_targets).expect("Failed to create webhook HTTP client");
+    spawn_webhook_delivery_job(pool.clone(), webhook_client.clone());
 
     println!("🚀 Starting server at http://{}:{}", host, port);
 
@@ -33,6 +76,12 @@ async fn main() -> std::io::Result<()> {
         App::new()
             .app_data(web::Data::new(pool.clone()))
             .app_data(web::Data::new(jwt_secret.clone()))
+            .app_data(web::Data::new(search_backend))
+            .app_data(event_hub.clone())
+            .app_data(ws_tickets.clone())
+            .app_data(web::Data::new(webhook_client.clone()))
+            .app_data(web::Data::new(webhook_targets))
+            .app_data(web::Data::from(attachment_storage.clone()))
             .wrap(Logger::default())
             .configure(config_routes)
     })
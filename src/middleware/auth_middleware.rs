@@ -77,28 +77,12 @@ where
 
             if let Some(auth_str) = auth_header {
                 if let Some(token) = auth_str.strip_prefix("Bearer ") {
-                    // Decode JWT token
-                    let decoding_key = DecodingKey::from_secret(secret_key.as_ref());
-                    let validation = Validation::new(Algorithm::HS256);
-
-                    match decode::<Claims>(token, &decoding_key, &validation) {
-                        Ok(token_data) => {
-                            // Parse user_id from claims
-                            if let Ok(user_id) = Uuid::parse_str(&token_data.claims.sub) {
-                                // Store user info in request extensions
-                                req.extensions_mut().insert(AuthenticatedUser {
-                                    user_id,
-                                    email: token_data.claims.email,
-                                    role: token_data.claims.role,
-                                });
-
-                                // Continue with the request
-                                return service.call(req).await;
-                            }
-                        }
-                        Err(e) => {
-                            log::error!("JWT decode error: {}", e);
-                        }
+                    if let Some(user) = authenticate_token(token, &secret_key) {
+                        // Store user info in request extensions
+                        req.extensions_mut().insert(user);
+
+                        // Continue with the request
+                        return service.call(req).await;
                     }
                 }
             }
@@ -117,6 +101,30 @@ where
     }
 }
 
+// Decodes and validates a bearer token. Shared with endpoints that can't sit
+// behind the middleware, such as the WebSocket upgrade.
+pub fn authenticate_token(token: &str, secret_key: &str) -> Option<AuthenticatedUser> {
+    let decoding_key = DecodingKey::from_secret(secret_key.as_ref());
+    let validation = Validation::new(Algorithm::HS256);
+
+    match decode::<Claims>(token, &decoding_key, &validation) {
+        Ok(token_data) => {
+            // Parse user_id from claims
+            Uuid::parse_str(&token_data.claims.sub)
+                .ok()
+                .map(|user_id| AuthenticatedUser {
+                    user_id,
+                    email: token_data.claims.email,
+                    role: token_data.claims.role,
+                })
+        }
+        Err(e) => {
+            log::error!("JWT decode error: {}", e);
+            None
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct AuthenticatedUser {
     pub user_id: Uuid,
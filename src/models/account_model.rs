@@ -0,0 +1,218 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+// How long the download link of a finished export works
+pub const EXPORT_LINK_HOURS: i64 = 24;
+// Time between requesting erasure and the account being erased, during which
+// the request can be cancelled
+pub const ERASURE_GRACE_DAYS: i64 = 14;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountExportStatus {
+    Pending,
+    Processing,
+    Ready,
+    Failed,
+    // The archive has been deleted
+    Expired,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct AccountExport {
+    pub id: String,
+    #[serde(skip_serializing)]
+    pub user_id: String,
+    pub status: AccountExportStatus,
+    // Where the archive is stored once it has been built
+    #[serde(skip_serializing)]
+    pub storage_key: Option<String>,
+    pub size_bytes: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccountExportResponse {
+    #[serde(flatten)]
+    pub export: AccountExport,
+    // Only while the archive is ready; works without authentication until it expires
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub download_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportDownloadQuery {
+    pub expires: i64,
+    pub signature: String,
+}
+
+// Re-entering the password confirms that the account holder asked for erasure
+#[derive(Debug, Deserialize)]
+pub struct ErasureRequest {
+    pub password: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AccountErasure {
+    #[serde(skip_serializing)]
+    pub user_id: String,
+    pub requested_at: DateTime<Utc>,
+    pub scheduled_for: DateTime<Utc>,
+}
+
+impl AccountExportStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AccountExportStatus::Pending => "pending",
+            AccountExportStatus::Processing => "processing",
+            AccountExportStatus::Ready => "ready",
+            AccountExportStatus::Failed => "failed",
+            AccountExportStatus::Expired => "expired",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "processing" => AccountExportStatus::Processing,
+            "ready" => AccountExportStatus::Ready,
+            "failed" => AccountExportStatus::Failed,
+            "expired" => AccountExportStatus::Expired,
+            _ => AccountExportStatus::Pending,
+        }
+    }
+}
+
+impl AccountExport {
+    pub fn new(user_id: &Uuid) -> Self {
+        AccountExport {
+            id: Uuid::new_v4().to_string(),
+            user_id: user_id.to_string(),
+            status: AccountExportStatus::Pending,
+            storage_key: None,
+            size_bytes: None,
+            error: None,
+            created_at: Utc::now(),
+            completed_at: None,
+            expires_at: None,
+        }
+    }
+
+    pub fn archive_storage_key(&self) -> String {
+        format!("exports/{}", self.id)
+    }
+
+    pub fn is_downloadable(&self, now: DateTime<Utc>) -> bool {
+        self.status == AccountExportStatus::Ready && self.expires_at.is_some_and(|at| at > now)
+    }
+}
+
+impl AccountErasure {
+    pub fn new(user_id: &Uuid) -> Self {
+        let now = Utc::now();
+
+        AccountErasure {
+            user_id: user_id.to_string(),
+            requested_at: now,
+            scheduled_for: now + Duration::days(ERASURE_GRACE_DAYS),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ready_export(expires_at: DateTime<Utc>) -> AccountExport {
+        let mut export = AccountExport::new(&Uuid::new_v4());
+        export.status = AccountExportStatus::Ready;
+        export.storage_key = Some(export.archive_storage_key());
+        export.completed_at = Some(Utc::now());
+        export.expires_at = Some(expires_at);
+        export
+    }
+
+    #[test]
+    fn new_exports_are_pending_and_stored_under_their_id() {
+        let export = AccountExport::new(&Uuid::new_v4());
+
+        assert_eq!(export.status, AccountExportStatus::Pending);
+        assert_eq!(export.storage_key, None);
+        assert_eq!(
+            export.archive_storage_key(),
+            format!("exports/{}", export.id)
+        );
+    }
+
+    #[test]
+    fn statuses_round_trip_through_the_database() {
+        for status in [
+            AccountExportStatus::Pending,
+            AccountExportStatus::Processing,
+            AccountExportStatus::Ready,
+            AccountExportStatus::Failed,
+            AccountExportStatus::Expired,
+        ] {
+            assert_eq!(AccountExportStatus::from_db(status.as_str()), status);
+        }
+        assert_eq!(
+            AccountExportStatus::from_db("unknown"),
+            AccountExportStatus::Pending
+        );
+    }
+
+    #[test]
+    fn only_ready_unexpired_exports_are_downloadable() {
+        let now = Utc::now();
+
+        assert!(ready_export(now + Duration::hours(EXPORT_LINK_HOURS)).is_downloadable(now));
+        assert!(!ready_export(now).is_downloadable(now));
+        assert!(!ready_export(now - Duration::hours(1)).is_downloadable(now));
+
+        let mut expired = ready_export(now + Duration::hours(1));
+        expired.status = AccountExportStatus::Expired;
+        assert!(!expired.is_downloadable(now));
+
+        let pending = AccountExport::new(&Uuid::new_v4());
+        assert!(!pending.is_downloadable(now));
+    }
+
+    #[test]
+    fn exports_do_not_expose_their_owner_or_storage_key() {
+        let export = ready_export(Utc::now() + Duration::hours(1));
+        let json = serde_json::to_value(AccountExportResponse {
+            export,
+            download_url: None,
+        })
+        .unwrap();
+
+        assert_eq!(json["status"], "ready");
+        assert!(json.get("user_id").is_none());
+        assert!(json.get("storage_key").is_none());
+        assert!(json.get("download_url").is_none());
+        assert!(json.get("error").is_none());
+    }
+
+    #[test]
+    fn erasure_is_scheduled_after_the_grace_period() {
+        let user_id = Uuid::new_v4();
+        let erasure = AccountErasure::new(&user_id);
+
+        assert_eq!(erasure.user_id, user_id.to_string());
+        assert_eq!(
+            erasure.scheduled_for - erasure.requested_at,
+            Duration::days(ERASURE_GRACE_DAYS)
+        );
+    }
+
+    #[test]
+    fn erasure_requests_require_a_password() {
+        assert!(serde_json::from_str::<ErasureRequest>("{}").is_err());
+        let request: ErasureRequest = serde_json::from_str(r#"{"password": "secret"}"#).unwrap();
+        assert_eq!(request.password, "secret");
+    }
+}
@@ -0,0 +1,183 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+pub const MAX_ATTACHMENT_BYTES: usize = 10 * 1024 * 1024;
+// Total size of the attachments one user may store
+pub const STORAGE_QUOTA_BYTES: i64 = 100 * 1024 * 1024;
+const MAX_FILE_NAME_CHARS: usize = 255;
+
+// Bytes that identify a file format, as (offset, bytes) parts that must all match
+type Signature = &'static [(usize, &'static [u8])];
+
+// Accepted content types, with the signatures a file of that type has. Text
+// has no signature and is checked for UTF-8 instead.
+const ALLOWED_TYPES: &[(&str, &[Signature])] = &[
+    ("image/png", &[&[(0, b"\x89PNG\r\n\x1a\n")]]),
+    ("image/jpeg", &[&[(0, b"\xff\xd8\xff")]]),
+    ("image/gif", &[&[(0, b"GIF87a")], &[(0, b"GIF89a")]]),
+    // A RIFF container whose form type, after the chunk size, is WEBP
+    ("image/webp", &[&[(0, b"RIFF"), (8, b"WEBP")]]),
+    ("application/pdf", &[&[(0, b"%PDF-")]]),
+    ("text/plain", &[]),
+    ("text/csv", &[]),
+    ("text/markdown", &[]),
+];
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Attachment {
+    pub id: String,
+    pub todo_id: String,
+    #[serde(skip_serializing)]
+    pub user_id: String,
+    pub file_name: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub sha256: String,
+    #[serde(skip_serializing)]
+    pub storage_key: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StorageUsage {
+    pub used_bytes: i64,
+    pub quota_bytes: i64,
+}
+
+impl Attachment {
+    pub fn new(
+        todo_id: &str,
+        user_id: &str,
+        file_name: &str,
+        content_type: &str,
+        data: &[u8],
+    ) -> Self {
+        let id = Uuid::new_v4().to_string();
+
+        Attachment {
+            storage_key: format!("{}/{}", user_id, id),
+            id,
+            todo_id: todo_id.to_string(),
+            user_id: user_id.to_string(),
+            file_name: sanitize_file_name(file_name),
+            content_type: content_type.to_string(),
+            size_bytes: data.len() as i64,
+            sha256: hex::encode(Sha256::digest(data)),
+            created_at: Utc::now(),
+        }
+    }
+}
+
+// Keeps the last path component, without control characters or quotes that
+// would break the Content-Disposition header
+pub fn sanitize_file_name(name: &str) -> String {
+    let base = name.rsplit(['/', '\\']).next().unwrap_or_default();
+    let cleaned = base
+        .chars()
+        .filter(|c| !c.is_control() && *c != '"')
+        .take(MAX_FILE_NAME_CHARS)
+        .collect::<String>();
+
+    match cleaned.trim() {
+        "" | "." | ".." => "attachment".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+// Checks the declared type is accepted and that the content matches it, and
+// returns the type without parameters
+pub fn validate_content(content_type: &str, data: &[u8]) -> Result<&'static str, String> {
+    let essence = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase();
+
+    let Some((allowed_type, signatures)) = ALLOWED_TYPES.iter().find(|(t, _)| *t == essence) else {
+        let allowed = ALLOWED_TYPES.iter().map(|(t, _)| *t).collect::<Vec<_>>();
+        return Err(format!(
+            "Unsupported file type '{}'; allowed: {}",
+            essence,
+            allowed.join(", ")
+        ));
+    };
+
+    let matches = if signatures.is_empty() {
+        std::str::from_utf8(data).is_ok()
+    } else {
+        signatures.iter().any(|signature| {
+            signature
+                .iter()
+                .all(|(offset, bytes)| data.get(*offset..offset + bytes.len()) == Some(*bytes))
+        })
+    };
+    if !matches {
+        return Err(format!("File content doesn't match type '{}'", essence));
+    }
+
+    Ok(allowed_type)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_content_matching_its_type() {
+        assert_eq!(
+            validate_content("image/png", b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"),
+            Ok("image/png")
+        );
+        assert_eq!(
+            validate_content("IMAGE/GIF", b"GIF87a\x01\0"),
+            Ok("image/gif")
+        );
+        assert_eq!(
+            validate_content("image/webp", b"RIFF\x24\0\0\0WEBPVP8 "),
+            Ok("image/webp")
+        );
+        assert_eq!(
+            validate_content("text/plain; charset=utf-8", "Grüße".as_bytes()),
+            Ok("text/plain")
+        );
+    }
+
+    #[test]
+    fn rejects_content_that_does_not_match() {
+        // A RIFF container holding audio rather than an image
+        assert_eq!(
+            validate_content("image/webp", b"RIFF\x24\0\0\0WAVEfmt "),
+            Err("File content doesn't match type 'image/webp'".to_string())
+        );
+        assert!(validate_content("image/webp", b"RIFF").is_err());
+        assert!(validate_content("application/pdf", b"<html>").is_err());
+        assert!(validate_content("text/csv", b"a,b\n\xff\xfe").is_err());
+    }
+
+    #[test]
+    fn rejects_unsupported_types() {
+        let err = validate_content("text/html", b"<p>hi</p>").unwrap_err();
+
+        assert!(err.starts_with("Unsupported file type 'text/html'; allowed: image/png, "));
+    }
+
+    #[test]
+    fn sanitizes_file_names() {
+        assert_eq!(sanitize_file_name("../../etc/passwd"), "passwd");
+        assert_eq!(
+            sanitize_file_name("C:\\Users\\me\\report.pdf"),
+            "report.pdf"
+        );
+        assert_eq!(sanitize_file_name("say \"hi\"\r\n.txt"), "say hi.txt");
+        assert_eq!(sanitize_file_name(" notes.md "), "notes.md");
+        assert_eq!(sanitize_file_name("uploads/.."), "attachment");
+        assert_eq!(sanitize_file_name("dir/"), "attachment");
+        assert_eq!(
+            sanitize_file_name(&"é".repeat(300)).chars().count(),
+            MAX_FILE_NAME_CHARS
+        );
+    }
+}
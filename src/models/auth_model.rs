@@ -6,13 +6,27 @@ pub struct LoginRequest {
     pub password: String,
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, Clone)]
 pub struct RegisterRequest {
     pub name: String,
     pub email: String,
     pub password: String,
 }
 
+// The part of a registration that identifies it for Idempotency-Key
+// matching; the password is checked against the account instead
+#[derive(Serialize)]
+pub struct RegistrationIdentity<'a> {
+    pub name: &'a str,
+    pub email: &'a str,
+}
+
+// What is stored for replaying a registration, so that no token is kept
+#[derive(Serialize, Deserialize)]
+pub struct RegisteredUser {
+    pub id: String,
+}
+
 #[derive(Serialize)]
 pub struct AuthResponse {
     pub token: String,
@@ -0,0 +1,433 @@
+use std::{collections::HashSet, fmt};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        todo_model::{TodoPatch, TodoStatus},
+        workflow_model::Workflow,
+    },
+    utils::patch::Patch,
+};
+
+pub const MAX_BULK_ITEMS: usize = 500;
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BulkAction {
+    SetStatus {
+        status: Option<TodoStatus>,
+        workflow_status: Option<String>,
+    },
+    ReplaceDescription {
+        description: String,
+    },
+    AppendDescription {
+        text: String,
+    },
+    Delete,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkMode {
+    // Any failing item rolls back the whole batch
+    #[default]
+    AllOrNothing,
+    // Failing items are skipped, the others are committed
+    BestEffort,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct BulkTodoRequest {
+    pub ids: Vec<String>,
+    pub action: BulkAction,
+    #[serde(default)]
+    pub mode: BulkMode,
+    // Same as ?force=true on a single update: ignore open blockers
+    #[serde(default)]
+    pub force: bool,
+}
+
+// What a bulk action amounts to for one todo
+#[derive(Debug)]
+pub enum BulkOperation {
+    Update(TodoPatch),
+    Delete,
+}
+
+// Why one todo of a bulk request can't be changed, e.g. it doesn't exist or is blocked
+#[derive(Debug)]
+pub struct BulkItemError(pub String);
+
+impl fmt::Display for BulkItemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for BulkItemError {}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum BulkItemStatus {
+    Succeeded,
+    Failed,
+    // Valid, but not applied because another item failed in all-or-nothing mode
+    RolledBack,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BulkItemResult {
+    pub id: String,
+    pub status: BulkItemStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+// The items of a bulk request, each id once and in request order. Invalid ids
+// fail right away; the valid ones are applied and reported at their position.
+#[derive(Debug)]
+pub struct BulkItems {
+    pub results: Vec<BulkItemResult>,
+    pub ids: Vec<Uuid>,
+    positions: Vec<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BulkTodoResponse {
+    pub mode: BulkMode,
+    pub committed: bool,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub results: Vec<BulkItemResult>,
+}
+
+impl BulkAction {
+    pub fn operation_for(&self, current_description: Option<&str>) -> BulkOperation {
+        match self {
+            BulkAction::SetStatus {
+                status,
+                workflow_status,
+            } => BulkOperation::Update(TodoPatch {
+                status: status.clone().map_or(Patch::Absent, Patch::Value),
+                workflow_status: workflow_status.clone().map_or(Patch::Absent, Patch::Value),
+                ..Default::default()
+            }),
+            BulkAction::ReplaceDescription { description } => BulkOperation::Update(TodoPatch {
+                description: Patch::Value(description.clone()),
+                ..Default::default()
+            }),
+            BulkAction::AppendDescription { text } => {
+                let description = match current_description {
+                    Some(current) if !current.is_empty() => format!("{}\n{}", current, text),
+                    _ => text.clone(),
+                };
+                BulkOperation::Update(TodoPatch {
+                    description: Patch::Value(description),
+                    ..Default::default()
+                })
+            }
+            BulkAction::Delete => BulkOperation::Delete,
+        }
+    }
+
+    // The status a set_status action moves todos to, if it resolves to one
+    pub fn requested_status(&self, workflow: Option<&Workflow>) -> Option<TodoStatus> {
+        match self {
+            BulkAction::SetStatus {
+                status,
+                workflow_status,
+            } => match workflow_status.as_deref() {
+                Some(key) => workflow
+                    .and_then(|w| w.status_by_key(key))
+                    .map(|s| s.category.to_todo_status()),
+                None => status.clone(),
+            },
+            _ => None,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        match self {
+            BulkAction::SetStatus {
+                status: None,
+                workflow_status: None,
+            } => Err("set_status requires status or workflow_status".to_string()),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl BulkTodoRequest {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.ids.is_empty() {
+            return Err("At least one todo id is required".to_string());
+        }
+        if self.ids.len() > MAX_BULK_ITEMS {
+            return Err(format!("At most {} todos per request", MAX_BULK_ITEMS));
+        }
+        self.action.validate()
+    }
+}
+
+impl BulkItemResult {
+    pub fn succeeded(id: String) -> Self {
+        BulkItemResult {
+            id,
+            status: BulkItemStatus::Succeeded,
+            error: None,
+        }
+    }
+
+    pub fn failed(id: String, error: impl Into<String>) -> Self {
+        BulkItemResult {
+            id,
+            status: BulkItemStatus::Failed,
+            error: Some(error.into()),
+        }
+    }
+
+    pub fn rolled_back(id: String) -> Self {
+        BulkItemResult {
+            id,
+            status: BulkItemStatus::RolledBack,
+            error: None,
+        }
+    }
+}
+
+impl BulkItems {
+    pub fn parse(raw_ids: &[String]) -> Self {
+        let mut seen = HashSet::new();
+        let mut items = BulkItems {
+            results: Vec::new(),
+            ids: Vec::new(),
+            positions: Vec::new(),
+        };
+
+        for raw_id in raw_ids {
+            if !seen.insert(raw_id.as_str()) {
+                continue;
+            }
+
+            match Uuid::parse_str(raw_id) {
+                Ok(id) => {
+                    items.positions.push(items.results.len());
+                    items
+                        .results
+                        .push(BulkItemResult::rolled_back(raw_id.clone()));
+                    items.ids.push(id);
+                }
+                Err(_) => items.results.push(BulkItemResult::failed(
+                    raw_id.clone(),
+                    "Invalid UUID format",
+                )),
+            }
+        }
+
+        items
+    }
+
+    pub fn all_valid(&self) -> bool {
+        self.ids.len() == self.results.len()
+    }
+
+    // Reports how each of `ids` went. In all-or-nothing mode a single failure
+    // means nothing was committed, so the items that worked are rolled back.
+    pub fn into_response(
+        mut self,
+        mode: BulkMode,
+        outcomes: Vec<Result<(), String>>,
+    ) -> BulkTodoResponse {
+        let committed = mode == BulkMode::BestEffort
+            || (self.all_valid() && outcomes.iter().all(|outcome| outcome.is_ok()));
+
+        for (position, outcome) in self.positions.into_iter().zip(outcomes) {
+            let id = self.results[position].id.clone();
+            self.results[position] = match outcome {
+                Ok(()) if committed => BulkItemResult::succeeded(id),
+                Ok(()) => BulkItemResult::rolled_back(id),
+                Err(error) => BulkItemResult::failed(id, error),
+            };
+        }
+
+        BulkTodoResponse::new(mode, committed, self.results)
+    }
+}
+
+impl BulkTodoResponse {
+    pub fn new(mode: BulkMode, committed: bool, results: Vec<BulkItemResult>) -> Self {
+        let count = |status| results.iter().filter(|r| r.status == status).count();
+
+        BulkTodoResponse {
+            mode,
+            committed,
+            succeeded: count(BulkItemStatus::Succeeded),
+            failed: count(BulkItemStatus::Failed),
+            results,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(ids: Vec<String>, action: BulkAction) -> BulkTodoRequest {
+        BulkTodoRequest {
+            ids,
+            action,
+            mode: BulkMode::AllOrNothing,
+            force: false,
+        }
+    }
+
+    fn ids(count: usize) -> Vec<String> {
+        (0..count).map(|_| Uuid::new_v4().to_string()).collect()
+    }
+
+    fn statuses(response: &BulkTodoResponse) -> Vec<BulkItemStatus> {
+        response.results.iter().map(|r| r.status).collect()
+    }
+
+    #[test]
+    fn requests_need_between_one_and_the_maximum_of_ids() {
+        assert_eq!(
+            request(vec![], BulkAction::Delete).validate().unwrap_err(),
+            "At least one todo id is required"
+        );
+        assert!(
+            request(ids(MAX_BULK_ITEMS), BulkAction::Delete)
+                .validate()
+                .is_ok()
+        );
+        assert_eq!(
+            request(ids(MAX_BULK_ITEMS + 1), BulkAction::Delete)
+                .validate()
+                .unwrap_err(),
+            format!("At most {} todos per request", MAX_BULK_ITEMS)
+        );
+    }
+
+    #[test]
+    fn set_status_needs_a_status() {
+        let action = BulkAction::SetStatus {
+            status: None,
+            workflow_status: None,
+        };
+
+        assert_eq!(
+            request(ids(1), action).validate().unwrap_err(),
+            "set_status requires status or workflow_status"
+        );
+    }
+
+    #[test]
+    fn duplicate_ids_are_applied_once() {
+        let id = Uuid::new_v4().to_string();
+        let other = Uuid::new_v4().to_string();
+        let items = BulkItems::parse(&[id.clone(), other.clone(), id.clone()]);
+
+        assert_eq!(items.ids.len(), 2);
+        let results: Vec<&str> = items.results.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(results, vec![id.as_str(), other.as_str()]);
+    }
+
+    #[test]
+    fn invalid_ids_fail_without_being_applied() {
+        let valid = ids(2);
+        let items = BulkItems::parse(&[valid[0].clone(), "nope".to_string(), valid[1].clone()]);
+
+        assert!(!items.all_valid());
+        assert_eq!(items.ids.len(), 2);
+        assert_eq!(
+            items.results[1].error.as_deref(),
+            Some("Invalid UUID format")
+        );
+    }
+
+    #[test]
+    fn all_or_nothing_rolls_back_when_an_item_fails() {
+        let items = BulkItems::parse(&ids(3));
+        let response = items.into_response(
+            BulkMode::AllOrNothing,
+            vec![Ok(()), Err("Todo not found".to_string()), Ok(())],
+        );
+
+        assert!(!response.committed);
+        assert_eq!(
+            statuses(&response),
+            vec![
+                BulkItemStatus::RolledBack,
+                BulkItemStatus::Failed,
+                BulkItemStatus::RolledBack
+            ]
+        );
+        assert_eq!((response.succeeded, response.failed), (0, 1));
+        assert_eq!(response.results[1].error.as_deref(), Some("Todo not found"));
+    }
+
+    #[test]
+    fn all_or_nothing_with_an_invalid_id_applies_nothing() {
+        let mut raw_ids = ids(2);
+        raw_ids.push("nope".to_string());
+        let response = BulkItems::parse(&raw_ids).into_response(BulkMode::AllOrNothing, vec![]);
+
+        assert!(!response.committed);
+        assert_eq!(
+            statuses(&response),
+            vec![
+                BulkItemStatus::RolledBack,
+                BulkItemStatus::RolledBack,
+                BulkItemStatus::Failed
+            ]
+        );
+    }
+
+    #[test]
+    fn best_effort_commits_the_items_that_worked() {
+        let mut raw_ids = ids(2);
+        raw_ids.insert(1, "nope".to_string());
+        let response = BulkItems::parse(&raw_ids).into_response(
+            BulkMode::BestEffort,
+            vec![Ok(()), Err("Todo is blocked".to_string())],
+        );
+
+        assert!(response.committed);
+        assert_eq!(
+            statuses(&response),
+            vec![
+                BulkItemStatus::Succeeded,
+                BulkItemStatus::Failed,
+                BulkItemStatus::Failed
+            ]
+        );
+        assert_eq!((response.succeeded, response.failed), (1, 2));
+        assert_eq!(
+            response.results[2].error.as_deref(),
+            Some("Todo is blocked")
+        );
+    }
+
+    #[test]
+    fn append_description_adds_a_line() {
+        let action = BulkAction::AppendDescription {
+            text: "Call back".to_string(),
+        };
+
+        for (current, expected) in [
+            (Some("Left a message"), "Left a message\nCall back"),
+            (Some(""), "Call back"),
+            (None, "Call back"),
+        ] {
+            match action.operation_for(current) {
+                BulkOperation::Update(patch) => {
+                    assert_eq!(patch.description, Patch::Value(expected.to_string()))
+                }
+                BulkOperation::Delete => panic!("expected an update"),
+            }
+        }
+    }
+}
@@ -0,0 +1,109 @@
+use chrono::{DateTime, Utc};
+
+use crate::models::todo_model::TodoStatus;
+
+pub const DAV_NS: &str = "DAV:";
+pub const CALDAV_NS: &str = "urn:ietf:params:xml:ns:caldav";
+pub const CALENDARSERVER_NS: &str = "http://calendarserver.org/ns/";
+
+// Prefix of sync-token URIs; the rest is an encoded SyncToken
+pub const SYNC_TOKEN_PREFIX: &str = "urn:todo-app:sync:";
+
+// Name of the one calendar collection each user has
+pub const CALENDAR_NAME: &str = "todos";
+
+// Client-chosen name and UID of a todo created over CalDAV
+#[derive(Debug, Clone)]
+pub struct DavResource {
+    pub todo_id: String,
+    pub user_id: String,
+    pub resource_name: String,
+    pub uid: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropName {
+    pub namespace: String,
+    pub name: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum DavRequestKind {
+    Propfind,
+    CalendarQuery,
+    CalendarMultiget,
+    SyncCollection,
+    Other(String),
+}
+
+// The parts of a PROPFIND or REPORT body the server acts on
+#[derive(Debug)]
+pub struct DavRequest {
+    pub kind: DavRequestKind,
+    // None asks for every property (allprop, or no body at all)
+    pub props: Option<Vec<PropName>>,
+    pub hrefs: Vec<String>,
+    pub sync_token: Option<String>,
+}
+
+// Fields of a VTODO sent by a client
+#[derive(Debug, Default)]
+pub struct VTodoData {
+    pub uid: Option<String>,
+    pub summary: Option<String>,
+    pub description: Option<String>,
+    pub status: Option<TodoStatus>,
+    pub due: Option<DateTime<Utc>>,
+}
+
+impl PropName {
+    pub fn new(namespace: &str, name: &str) -> Self {
+        PropName {
+            namespace: namespace.to_string(),
+            name: name.to_string(),
+        }
+    }
+
+    pub fn is(&self, namespace: &str, name: &str) -> bool {
+        self.namespace == namespace && self.name == name
+    }
+}
+
+impl DavRequest {
+    // A bodyless PROPFIND is treated as allprop
+    pub fn empty_propfind() -> Self {
+        DavRequest {
+            kind: DavRequestKind::Propfind,
+            props: None,
+            hrefs: Vec::new(),
+            sync_token: None,
+        }
+    }
+}
+
+impl DavResource {
+    pub fn href(&self) -> String {
+        dav_href(&self.user_id, &self.resource_name)
+    }
+}
+
+pub fn principal_href(user_id: &str) -> String {
+    format!("/dav/principals/{}/", user_id)
+}
+
+pub fn calendar_home_href(user_id: &str) -> String {
+    format!("/dav/calendars/{}/", user_id)
+}
+
+pub fn calendar_href(user_id: &str) -> String {
+    format!("/dav/calendars/{}/{}/", user_id, CALENDAR_NAME)
+}
+
+pub fn dav_href(user_id: &str, resource_name: &str) -> String {
+    format!("{}{}", calendar_href(user_id), resource_name)
+}
+
+// Todos created through the API are served as "<id>.ics"
+pub fn default_resource_name(todo_id: &str) -> String {
+    format!("{}.ics", todo_id)
+}
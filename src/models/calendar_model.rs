@@ -0,0 +1,26 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use uuid::Uuid;
+
+// Returned once when the token is (re)generated; afterwards only its hash is
+// known to the server
+#[derive(Debug, Serialize)]
+pub struct CalendarFeedToken {
+    pub token: String,
+    pub url: String,
+}
+
+impl CalendarFeedToken {
+    pub fn generate() -> Self {
+        let token = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+
+        CalendarFeedToken {
+            url: format!("/api/v1/calendar/{}.ics", token),
+            token,
+        }
+    }
+}
+
+pub fn hash_feed_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
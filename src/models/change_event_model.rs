@@ -0,0 +1,91 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::todo_model::Todo;
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+pub enum ChangeKind {
+    #[serde(rename = "todo.created")]
+    Created,
+    #[serde(rename = "todo.updated")]
+    Updated,
+    #[serde(rename = "todo.deleted")]
+    Deleted,
+}
+
+// A change to a todo, pushed to connected clients. Ids increase across the
+// lifetime of the process, so a client can resume after the last one it saw.
+#[derive(Debug, Serialize, Clone)]
+pub struct ChangeEvent {
+    pub id: u64,
+    #[serde(rename = "type")]
+    pub kind: ChangeKind,
+    pub todo_id: String,
+    #[serde(skip_serializing)]
+    pub user_id: String,
+    // The todo as it is after the change, or as it was when deleted
+    pub todo: Todo,
+    pub occurred_at: DateTime<Utc>,
+}
+
+// A change recorded with the write that made it, waiting to be relayed
+#[derive(Debug)]
+pub struct PendingChange {
+    pub id: i64,
+    pub todo_id: String,
+    pub user_id: String,
+    pub kind: String,
+    pub snapshot: Option<String>,
+}
+
+// Control messages sent alongside change events
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamNotice {
+    // Events were missed and can't be replayed; the client should catch up
+    // through GET /sync
+    ResyncRequired,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChangeStreamQuery {
+    // Browsers can't set headers on a WebSocket handshake, so they pass a
+    // ticket from POST /ws/ticket instead
+    pub ticket: Option<String>,
+    pub last_event_id: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WsTicketResponse {
+    pub ticket: String,
+    pub expires_in: u64,
+}
+
+impl ChangeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ChangeKind::Created => "todo.created",
+            ChangeKind::Updated => "todo.updated",
+            ChangeKind::Deleted => "todo.deleted",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Option<Self> {
+        match value {
+            "todo.created" => Some(ChangeKind::Created),
+            "todo.updated" => Some(ChangeKind::Updated),
+            "todo.deleted" => Some(ChangeKind::Deleted),
+            _ => None,
+        }
+    }
+}
+
+impl ChangeEvent {
+    pub fn is_visible_to(&self, user_id: &str) -> bool {
+        self.user_id == user_id
+    }
+
+    pub fn event_name(&self) -> &'static str {
+        self.kind.as_str()
+    }
+}
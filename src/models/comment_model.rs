@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::todo_model::Todo;
+
+const MAX_COMMENT_CHARS: usize = 10_000;
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Comment {
+    pub id: String,
+    pub todo_id: String,
+    pub author_id: String,
+    pub author_name: String,
+    // Markdown
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    pub edited_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CommentRequest {
+    pub body: String,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct CommentCount {
+    pub todo_id: String,
+    pub count: i64,
+}
+
+impl Comment {
+    pub fn new(todo_id: &str, author_id: &Uuid, author_name: &str, body: &str) -> Self {
+        Comment {
+            id: Uuid::new_v4().to_string(),
+            todo_id: todo_id.to_string(),
+            author_id: author_id.to_string(),
+            author_name: author_name.to_string(),
+            body: body.to_string(),
+            created_at: Utc::now(),
+            edited_at: None,
+        }
+    }
+}
+
+impl CommentRequest {
+    // The body without surrounding whitespace, if it's acceptable
+    pub fn validated_body(&self) -> Result<&str, String> {
+        let body = self.body.trim();
+
+        if body.is_empty() {
+            return Err("Comment body cannot be empty".to_string());
+        }
+        if body.chars().count() > MAX_COMMENT_CHARS {
+            return Err(format!(
+                "Comments are limited to {} characters",
+                MAX_COMMENT_CHARS
+            ));
+        }
+
+        Ok(body)
+    }
+}
+
+pub fn attach_comment_counts(todos: &mut [Todo], counts: &[CommentCount]) {
+    let by_todo: HashMap<&str, i64> = counts
+        .iter()
+        .map(|c| (c.todo_id.as_str(), c.count))
+        .collect();
+
+    for todo in todos.iter_mut() {
+        todo.comment_count = by_todo.get(todo.id.as_str()).copied().unwrap_or(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(body: &str) -> CommentRequest {
+        CommentRequest {
+            body: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn trims_the_body() {
+        assert_eq!(
+            request("  Looks good **to me**\n").validated_body(),
+            Ok("Looks good **to me**")
+        );
+    }
+
+    #[test]
+    fn rejects_empty_bodies() {
+        assert_eq!(
+            request(" \n\t ").validated_body(),
+            Err("Comment body cannot be empty".to_string())
+        );
+    }
+
+    #[test]
+    fn limits_the_body_in_characters() {
+        // Multibyte characters count once each
+        let longest = "é".repeat(MAX_COMMENT_CHARS);
+        let too_long = format!("{}é", longest);
+
+        assert_eq!(request(&longest).validated_body(), Ok(longest.as_str()));
+        assert_eq!(
+            request(&too_long).validated_body(),
+            Err("Comments are limited to 10000 characters".to_string())
+        );
+    }
+
+    #[test]
+    fn attaches_counts_to_their_todos() {
+        let todo = |id: &str| {
+            Todo::new(id.to_string(), None, None, None, None, "user-1".to_string())
+                .with_id(id.to_string())
+        };
+        let mut todos = [todo("a"), todo("b")];
+        todos[1].comment_count = 7;
+        let counts = [CommentCount {
+            todo_id: "a".to_string(),
+            count: 3,
+        }];
+
+        attach_comment_counts(&mut todos, &counts);
+
+        assert_eq!(todos[0].comment_count, 3);
+        assert_eq!(todos[1].comment_count, 0);
+    }
+}
@@ -0,0 +1,192 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::todo_model::{Todo, TodoStatus};
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, sqlx::FromRow)]
+pub struct DependencyEdge {
+    pub todo_id: String,
+    pub blocked_by_id: String,
+}
+
+// What adding a dependency did
+#[derive(Debug, PartialEq)]
+pub enum DependencyOutcome {
+    Created,
+    AlreadyExists,
+    WouldCreateCycle,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateDependencyRequest {
+    pub blocked_by: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DependencyNode {
+    pub id: String,
+    pub title: String,
+    pub status: TodoStatus,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DependencyGraph {
+    pub root: String,
+    pub nodes: Vec<DependencyNode>,
+    pub edges: Vec<DependencyEdge>,
+}
+
+// Adding "todo_id is blocked by blocked_by_id" closes a cycle if todo_id already
+// (transitively) blocks blocked_by_id, i.e. it is reachable by following blockers.
+pub fn would_create_cycle(edges: &[DependencyEdge], todo_id: &str, blocked_by_id: &str) -> bool {
+    if todo_id == blocked_by_id {
+        return true;
+    }
+
+    let mut blockers: HashMap<&str, Vec<&str>> = HashMap::new();
+    for edge in edges {
+        blockers
+            .entry(edge.todo_id.as_str())
+            .or_default()
+            .push(edge.blocked_by_id.as_str());
+    }
+
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::from([blocked_by_id]);
+
+    while let Some(current) = queue.pop_front() {
+        if current == todo_id {
+            return true;
+        }
+        if !visited.insert(current) {
+            continue;
+        }
+        if let Some(next) = blockers.get(current) {
+            queue.extend(next.iter().copied());
+        }
+    }
+
+    false
+}
+
+// Collects every todo connected to `root` through dependencies in either direction
+pub fn build_dependency_graph(
+    root: &str,
+    todos: &[Todo],
+    edges: &[DependencyEdge],
+) -> DependencyGraph {
+    let mut neighbours: HashMap<&str, Vec<&str>> = HashMap::new();
+    for edge in edges {
+        neighbours
+            .entry(edge.todo_id.as_str())
+            .or_default()
+            .push(edge.blocked_by_id.as_str());
+        neighbours
+            .entry(edge.blocked_by_id.as_str())
+            .or_default()
+            .push(edge.todo_id.as_str());
+    }
+
+    let mut visited = HashSet::new();
+    let mut queue = VecDeque::from([root]);
+
+    while let Some(current) = queue.pop_front() {
+        if !visited.insert(current) {
+            continue;
+        }
+        if let Some(next) = neighbours.get(current) {
+            queue.extend(next.iter().copied());
+        }
+    }
+
+    let nodes = todos
+        .iter()
+        .filter(|todo| visited.contains(todo.id.as_str()))
+        .map(|todo| DependencyNode {
+            id: todo.id.clone(),
+            title: todo.title.clone(),
+            status: todo.status.clone(),
+        })
+        .collect();
+
+    let edges = edges
+        .iter()
+        .filter(|edge| visited.contains(edge.todo_id.as_str()))
+        .cloned()
+        .collect();
+
+    DependencyGraph {
+        root: root.to_string(),
+        nodes,
+        edges,
+    }
+}
+
+pub fn attach_dependencies(todos: &mut [Todo], edges: &[DependencyEdge]) {
+    let mut blocked_by: HashMap<&str, Vec<String>> = HashMap::new();
+    let mut blocking: HashMap<&str, Vec<String>> = HashMap::new();
+    for edge in edges {
+        blocked_by
+            .entry(edge.todo_id.as_str())
+            .or_default()
+            .push(edge.blocked_by_id.clone());
+        blocking
+            .entry(edge.blocked_by_id.as_str())
+            .or_default()
+            .push(edge.todo_id.clone());
+    }
+
+    for todo in todos.iter_mut() {
+        todo.blocked_by = blocked_by.remove(todo.id.as_str()).unwrap_or_default();
+        todo.blocking = blocking.remove(todo.id.as_str()).unwrap_or_default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // "todo is blocked by blocker" for each pair
+    fn edges(pairs: &[(&str, &str)]) -> Vec<DependencyEdge> {
+        pairs
+            .iter()
+            .map(|(todo_id, blocked_by_id)| DependencyEdge {
+                todo_id: todo_id.to_string(),
+                blocked_by_id: blocked_by_id.to_string(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn a_todo_cannot_block_itself() {
+        assert!(would_create_cycle(&[], "a", "a"));
+    }
+
+    #[test]
+    fn detects_direct_and_transitive_cycles() {
+        let chain = edges(&[("a", "b"), ("b", "c")]);
+
+        assert!(would_create_cycle(&chain, "b", "a"));
+        assert!(would_create_cycle(&chain, "c", "a"));
+    }
+
+    #[test]
+    fn allows_dependencies_that_keep_the_graph_acyclic() {
+        let chain = edges(&[("a", "b"), ("b", "c")]);
+
+        // Already implied by the chain, but not a cycle
+        assert!(!would_create_cycle(&chain, "a", "c"));
+        assert!(!would_create_cycle(&chain, "d", "a"));
+        assert!(!would_create_cycle(&chain, "c", "d"));
+    }
+
+    #[test]
+    fn shared_blockers_are_visited_once() {
+        // a is blocked by b and c, which are both blocked by d
+        let diamond = edges(&[("a", "b"), ("a", "c"), ("b", "d"), ("c", "d")]);
+
+        assert!(would_create_cycle(&diamond, "d", "a"));
+        assert!(!would_create_cycle(&diamond, "e", "a"));
+    }
+}
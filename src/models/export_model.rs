@@ -0,0 +1,75 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::todo_query_model::{SortOrder, TodoFilter, TodoSortField, parse_status_list};
+
+// Todos fetched per query while streaming an export
+pub const EXPORT_PAGE_SIZE: u32 = 200;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    Csv,
+    #[default]
+    Json,
+    Ndjson,
+    Markdown,
+    TodoTxt,
+}
+
+// Query string of GET /todos/export. `status` takes a comma-separated list.
+#[derive(Debug, Deserialize)]
+pub struct ExportQuery {
+    pub format: Option<ExportFormat>,
+    pub archived: Option<bool>,
+    pub status: Option<String>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub updated_after: Option<DateTime<Utc>>,
+    pub updated_before: Option<DateTime<Utc>>,
+}
+
+impl ExportFormat {
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "text/csv; charset=utf-8",
+            ExportFormat::Json => "application/json",
+            ExportFormat::Ndjson => "application/x-ndjson",
+            ExportFormat::Markdown => "text/markdown; charset=utf-8",
+            ExportFormat::TodoTxt => "text/plain; charset=utf-8",
+        }
+    }
+
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            ExportFormat::Csv => "todos.csv",
+            ExportFormat::Json => "todos.json",
+            ExportFormat::Ndjson => "todos.ndjson",
+            ExportFormat::Markdown => "todos.md",
+            ExportFormat::TodoTxt => "todo.txt",
+        }
+    }
+}
+
+impl ExportQuery {
+    // Markdown groups todos by status, so it is read in status order; the
+    // other formats list todos oldest first
+    pub fn to_filter(&self) -> Result<TodoFilter, String> {
+        let sort = match self.format.unwrap_or_default() {
+            ExportFormat::Markdown => TodoSortField::Status,
+            _ => TodoSortField::CreatedAt,
+        };
+
+        Ok(TodoFilter {
+            statuses: parse_status_list(self.status.as_deref())?,
+            q: None,
+            created_after: self.created_after,
+            created_before: self.created_before,
+            updated_after: self.updated_after,
+            updated_before: self.updated_before,
+            archived: self.archived.unwrap_or(false),
+            sort,
+            order: SortOrder::Asc,
+        })
+    }
+}
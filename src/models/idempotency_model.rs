@@ -0,0 +1,15 @@
+// A request made with an Idempotency-Key and, once it has finished, the
+// response to replay for retries
+#[derive(Debug)]
+pub struct IdempotencyRecord {
+    pub request_hash: String,
+    pub status_code: Option<i32>,
+    pub response_headers: Option<String>,
+    pub response_body: Option<Vec<u8>>,
+}
+
+impl IdempotencyRecord {
+    pub fn is_complete(&self) -> bool {
+        self.status_code.is_some()
+    }
+}
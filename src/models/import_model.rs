@@ -0,0 +1,197 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::todo_model::TodoStatus;
+
+pub const MAX_IMPORT_ROWS: usize = 1000;
+pub const MAX_IMPORT_BYTES: usize = 5 * 1024 * 1024;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportFormat {
+    Csv,
+    // The shape of our own todo responses
+    Json,
+    TodoTxt,
+    Todoist,
+    Trello,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportQuery {
+    // Detected from the file when omitted
+    pub format: Option<ImportFormat>,
+    #[serde(default)]
+    pub dry_run: bool,
+    // Import rows even when a todo with the same title already exists
+    #[serde(default)]
+    pub allow_duplicates: bool,
+}
+
+// Which source column feeds each todo field; the `mapping` part of a CSV
+// upload. Unset fields fall back to the usual column names.
+#[derive(Debug, Deserialize, Default)]
+pub struct FieldMapping {
+    pub title: Option<String>,
+    pub description: Option<String>,
+    pub status: Option<String>,
+    pub due_date: Option<String>,
+}
+
+// One row of the source file, mapped onto a todo
+#[derive(Debug, Serialize, Clone)]
+pub struct ImportedTodo {
+    pub title: String,
+    pub description: Option<String>,
+    pub status: TodoStatus,
+    pub due_date: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub recurrence_rule: Option<String>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportRowStatus {
+    Created,
+    // Dry run: the row is valid and would be created
+    Valid,
+    Duplicate,
+    Failed,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportRowResult {
+    // 1-based position of the row in the source (line number for text formats)
+    pub row: usize,
+    pub status: ImportRowStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub todo_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub todo: Option<ImportedTodo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportResponse {
+    pub format: ImportFormat,
+    pub dry_run: bool,
+    pub created: usize,
+    // Rows a dry run would create
+    pub valid: usize,
+    pub duplicates: usize,
+    pub failed: usize,
+    pub results: Vec<ImportRowResult>,
+}
+
+impl ImportedTodo {
+    // Attributes that have no todo field are kept as "Name: value" lines
+    // after the description
+    pub fn new(
+        title: &str,
+        description: Option<&str>,
+        status: TodoStatus,
+        due_date: Option<DateTime<Utc>>,
+        extra: &[(String, String)],
+    ) -> Result<Self, String> {
+        let title = title.trim();
+        if title.is_empty() {
+            return Err("Title is required".to_string());
+        }
+
+        let mut parts = Vec::new();
+        if let Some(description) = description.map(str::trim).filter(|d| !d.is_empty()) {
+            parts.push(description.to_string());
+        }
+        let extra = extra
+            .iter()
+            .filter(|(_, value)| !value.trim().is_empty())
+            .map(|(name, value)| format!("{}: {}", name, value.trim()))
+            .collect::<Vec<_>>();
+        if !extra.is_empty() {
+            parts.push(extra.join("\n"));
+        }
+
+        Ok(ImportedTodo {
+            title: title.to_string(),
+            description: (!parts.is_empty()).then(|| parts.join("\n\n")),
+            status,
+            due_date,
+            recurrence_rule: None,
+        })
+    }
+
+    // Titles are compared case- and whitespace-insensitively
+    pub fn duplicate_key(title: &str) -> String {
+        title
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+            .to_lowercase()
+    }
+}
+
+impl ImportRowResult {
+    pub fn valid(row: usize, todo: ImportedTodo) -> Self {
+        ImportRowResult {
+            row,
+            status: ImportRowStatus::Valid,
+            todo_id: None,
+            todo: Some(todo),
+            error: None,
+        }
+    }
+
+    pub fn duplicate(row: usize, todo: ImportedTodo) -> Self {
+        ImportRowResult {
+            row,
+            status: ImportRowStatus::Duplicate,
+            todo_id: None,
+            todo: Some(todo),
+            error: None,
+        }
+    }
+
+    pub fn failed(row: usize, error: impl Into<String>) -> Self {
+        ImportRowResult {
+            row,
+            status: ImportRowStatus::Failed,
+            todo_id: None,
+            todo: None,
+            error: Some(error.into()),
+        }
+    }
+}
+
+impl ImportResponse {
+    pub fn new(format: ImportFormat, dry_run: bool, results: Vec<ImportRowResult>) -> Self {
+        let count = |status| results.iter().filter(|r| r.status == status).count();
+
+        ImportResponse {
+            format,
+            dry_run,
+            created: count(ImportRowStatus::Created),
+            valid: count(ImportRowStatus::Valid),
+            duplicates: count(ImportRowStatus::Duplicate),
+            failed: count(ImportRowStatus::Failed),
+            results,
+        }
+    }
+}
+
+// Maps the status vocabularies of other tools onto ours. Unknown values
+// return None so the caller can keep them in the description.
+pub fn map_status(value: &str) -> Option<TodoStatus> {
+    let normalized = value.trim().to_lowercase().replace(['-', ' '], "_");
+    match normalized.as_str() {
+        "" | "pending" | "todo" | "to_do" | "open" | "new" | "not_started" | "backlog"
+        | "false" | "no" | "0" => Some(TodoStatus::Pending),
+        "in_process" | "in_progress" | "doing" | "started" | "active" | "wip" => {
+            Some(TodoStatus::InProcess)
+        }
+        "completed" | "complete" | "done" | "closed" | "finished" | "x" | "true" | "yes" | "1" => {
+            Some(TodoStatus::Completed)
+        }
+        _ => None,
+    }
+}
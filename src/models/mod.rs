@@ -1,3 +1,22 @@
+pub mod account_model;
+pub mod attachment_model;
 pub mod auth_model;
+pub mod bulk_model;
+pub mod caldav_model;
+pub mod calendar_model;
+pub mod change_event_model;
+pub mod comment_model;
+pub mod dependency_model;
+pub mod export_model;
+pub mod idempotency_model;
+pub mod import_model;
+pub mod saved_view_model;
+pub mod search_model;
+pub mod sync_model;
+pub mod todo_event_model;
 pub mod todo_model;
+pub mod todo_query_model;
 pub mod user_model;
+pub mod user_settings_model;
+pub mod webhook_model;
+pub mod workflow_model;
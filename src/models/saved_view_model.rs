@@ -0,0 +1,193 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::todo_query_model::{DEFAULT_PAGE_SIZE, MAX_PAGE_SIZE, TodoFilter};
+
+// Longest rolling date window a view can have, about a century
+const MAX_WINDOW_DAYS: u32 = 36500;
+
+// Stored filter of a saved view. Besides absolute date bounds it can hold
+// rolling windows ("updated in the last 7 days") resolved when the view runs.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ViewDefinition {
+    #[serde(flatten)]
+    pub filter: TodoFilter,
+    pub created_within_days: Option<u32>,
+    pub updated_within_days: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SavedView {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub definition: ViewDefinition,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateSavedViewRequest {
+    pub name: String,
+    pub definition: ViewDefinition,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateSavedViewRequest {
+    pub name: Option<String>,
+    pub definition: Option<ViewDefinition>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ViewTodosQuery {
+    pub limit: Option<u32>,
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SavedViewCount {
+    pub id: String,
+    pub name: String,
+    pub count: i64,
+}
+
+impl ViewDefinition {
+    pub fn validate(&self) -> Result<(), String> {
+        let filter = &self.filter;
+
+        if let (Some(after), Some(before)) = (filter.created_after, filter.created_before) {
+            if after >= before {
+                return Err("created_after must be before created_before".to_string());
+            }
+        }
+        if let (Some(after), Some(before)) = (filter.updated_after, filter.updated_before) {
+            if after >= before {
+                return Err("updated_after must be before updated_before".to_string());
+            }
+        }
+        for days in [self.created_within_days, self.updated_within_days]
+            .into_iter()
+            .flatten()
+        {
+            if !(1..=MAX_WINDOW_DAYS).contains(&days) {
+                return Err(format!(
+                    "Rolling date windows must span 1 to {} days",
+                    MAX_WINDOW_DAYS
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    // The filter to run at `now`; a rolling window narrows any absolute lower
+    // bound. A window reaching past the earliest representable date has none.
+    pub fn resolve(&self, now: DateTime<Utc>) -> TodoFilter {
+        let window_start =
+            |days: Option<u32>| days.and_then(|d| now.checked_sub_signed(Duration::days(d as i64)));
+
+        let mut filter = self.filter.clone();
+        filter.q = filter.q.filter(|q| !q.trim().is_empty());
+        filter.created_after = filter
+            .created_after
+            .max(window_start(self.created_within_days));
+        filter.updated_after = filter
+            .updated_after
+            .max(window_start(self.updated_within_days));
+        filter
+    }
+}
+
+impl SavedView {
+    pub fn new(user_id: String, name: String, definition: ViewDefinition) -> Self {
+        let now = Utc::now();
+
+        SavedView {
+            id: Uuid::new_v4().to_string(),
+            user_id,
+            name,
+            definition,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+}
+
+impl ViewTodosQuery {
+    pub fn page_size(&self) -> u32 {
+        self.limit
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value).unwrap().to_utc()
+    }
+
+    fn windows(created: Option<u32>, updated: Option<u32>) -> ViewDefinition {
+        ViewDefinition {
+            created_within_days: created,
+            updated_within_days: updated,
+            ..ViewDefinition::default()
+        }
+    }
+
+    #[test]
+    fn validates_date_bounds() {
+        let mut definition = ViewDefinition::default();
+        definition.filter.created_after = Some(utc("2026-10-18T00:00:00Z"));
+        definition.filter.created_before = Some(utc("2026-10-18T00:00:00Z"));
+        assert!(definition.validate().is_err());
+
+        definition.filter.created_before = Some(utc("2026-10-19T00:00:00Z"));
+        assert_eq!(definition.validate(), Ok(()));
+
+        definition.filter.updated_after = Some(utc("2026-10-20T00:00:00Z"));
+        definition.filter.updated_before = Some(utc("2026-10-19T00:00:00Z"));
+        assert!(definition.validate().is_err());
+    }
+
+    #[test]
+    fn validates_rolling_windows() {
+        assert_eq!(windows(Some(1), Some(MAX_WINDOW_DAYS)).validate(), Ok(()));
+        for (created, updated) in [
+            (Some(0), None),
+            (None, Some(0)),
+            (Some(MAX_WINDOW_DAYS + 1), None),
+            (None, Some(u32::MAX)),
+        ] {
+            assert!(windows(created, updated).validate().is_err());
+        }
+    }
+
+    #[test]
+    fn resolves_rolling_windows_against_now() {
+        let now = utc("2026-10-18T12:00:00Z");
+        let mut definition = windows(Some(7), Some(30));
+        definition.filter.q = Some("  ".to_string());
+        // The later of the absolute bound and the window start wins
+        definition.filter.created_after = Some(utc("2026-10-15T00:00:00Z"));
+        definition.filter.updated_after = Some(utc("2026-01-01T00:00:00Z"));
+
+        let filter = definition.resolve(now);
+
+        assert_eq!(filter.q, None);
+        assert_eq!(filter.created_after, Some(utc("2026-10-15T00:00:00Z")));
+        assert_eq!(filter.updated_after, Some(utc("2026-09-18T12:00:00Z")));
+    }
+
+    #[test]
+    fn resolves_windows_past_the_earliest_date_without_panicking() {
+        // Views stored before the window limit existed
+        let filter = windows(Some(u32::MAX), Some(u32::MAX)).resolve(Utc::now());
+
+        assert_eq!(filter.created_after, None);
+        assert_eq!(filter.updated_after, None);
+    }
+}
@@ -0,0 +1,399 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::todo_model::Todo;
+
+pub const DEFAULT_SEARCH_LIMIT: u32 = 20;
+pub const MAX_SEARCH_LIMIT: u32 = 100;
+const SNIPPET_LENGTH: usize = 160;
+
+// FULLTEXT needs the index from the migrations; LIKE works on any MySQL setup
+// (e.g. test databases) at the cost of speed and ranking quality
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchBackend {
+    FullText,
+    Like,
+}
+
+impl SearchBackend {
+    pub fn from_env_value(value: &str) -> Self {
+        match value.to_lowercase().as_str() {
+            "like" => SearchBackend::Like,
+            _ => SearchBackend::FullText,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchMode {
+    #[default]
+    Natural,
+    Boolean,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+    pub mode: Option<SearchMode>,
+    pub limit: Option<u32>,
+}
+
+// A query split the way FULLTEXT reads it. In natural language mode any term
+// may match. Boolean mode adds +required and -excluded terms; once a query
+// has required terms, the plain ones only affect ranking.
+#[derive(Debug, Default, PartialEq)]
+pub struct SearchCriteria {
+    pub required: Vec<String>,
+    pub optional: Vec<String>,
+    pub excluded: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchHighlights {
+    pub title: String,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchResult {
+    pub todo: Todo,
+    pub relevance: f64,
+    pub highlights: SearchHighlights,
+}
+
+impl SearchQuery {
+    pub fn limit(&self) -> u32 {
+        self.limit
+            .unwrap_or(DEFAULT_SEARCH_LIMIT)
+            .clamp(1, MAX_SEARCH_LIMIT)
+    }
+}
+
+const OPERATORS: &str = "+-~<>()*@";
+// InnoDB's default FULLTEXT settings: shorter words and these stop words are
+// not indexed, so they never match
+const MIN_WORD_LENGTH: usize = 3;
+const STOP_WORDS: &[&str] = &[
+    "a", "about", "an", "are", "as", "at", "be", "by", "com", "de", "en", "for", "from", "how",
+    "i", "in", "is", "it", "la", "of", "on", "or", "that", "the", "this", "to", "was", "what",
+    "when", "where", "who", "will", "with", "und", "www",
+];
+
+fn is_indexed(word: &str) -> bool {
+    word.chars().count() >= MIN_WORD_LENGTH && !STOP_WORDS.contains(&word)
+}
+
+// The words and "quoted phrases" of a query, lowercased, each with the
+// boolean-mode operator in front of it. Phrases are kept whole so they only
+// match text containing them literally.
+fn query_terms(q: &str) -> Vec<(Option<char>, String)> {
+    let mut terms = Vec::new();
+    let mut chars = q.chars().peekable();
+
+    while chars.peek().is_some() {
+        let mut operator = None;
+        while let Some(c) = chars.next_if(|c| c.is_whitespace() || OPERATORS.contains(*c)) {
+            if c.is_whitespace() {
+                operator = None;
+            } else if operator.is_none() {
+                operator = Some(c);
+            }
+        }
+
+        if chars.next_if_eq(&'"').is_some() {
+            let phrase: String = chars.by_ref().take_while(|c| *c != '"').collect();
+            let phrase = phrase.split_whitespace().collect::<Vec<_>>().join(" ");
+            if !phrase.is_empty() {
+                terms.push((operator, phrase.to_lowercase()));
+            }
+        } else {
+            let mut word = String::new();
+            while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != '"') {
+                word.push(c);
+            }
+            let word = word
+                .trim_matches(|c: char| OPERATORS.contains(c))
+                .to_lowercase();
+            if is_indexed(&word) {
+                terms.push((operator, word));
+            }
+        }
+    }
+
+    terms
+}
+
+// Words and phrases to look for, without boolean-mode operators. Excluded (-word) terms are dropped.
+pub fn search_terms(q: &str) -> Vec<String> {
+    query_terms(q)
+        .into_iter()
+        .filter(|(operator, _)| *operator != Some('-'))
+        .map(|(_, term)| term)
+        .collect()
+}
+
+pub fn search_criteria(q: &str, mode: SearchMode) -> SearchCriteria {
+    let mut criteria = SearchCriteria::default();
+
+    for (operator, term) in query_terms(q) {
+        let terms = match (mode, operator) {
+            (SearchMode::Boolean, Some('+')) => &mut criteria.required,
+            (SearchMode::Boolean, Some('-')) => &mut criteria.excluded,
+            _ => &mut criteria.optional,
+        };
+        terms.push(term);
+    }
+
+    criteria
+}
+
+// Non-overlapping (start, end) char ranges where any of the terms occur, case-insensitively
+fn find_matches(chars: &[char], terms: &[Vec<char>]) -> Vec<(usize, usize)> {
+    let mut matches = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let longest = terms
+            .iter()
+            .filter(|term| {
+                i + term.len() <= chars.len()
+                    && term
+                        .iter()
+                        .zip(&chars[i..])
+                        .all(|(t, c)| c.to_lowercase().next() == Some(*t))
+            })
+            .map(|term| term.len())
+            .max();
+
+        match longest {
+            Some(len) if len > 0 => {
+                matches.push((i, i + len));
+                i += len;
+            }
+            _ => i += 1,
+        }
+    }
+
+    matches
+}
+
+fn term_chars(terms: &[String]) -> Vec<Vec<char>> {
+    terms.iter().map(|t| t.chars().collect()).collect()
+}
+
+fn escape_html(c: char, out: &mut String) {
+    match c {
+        '<' => out.push_str("&lt;"),
+        '>' => out.push_str("&gt;"),
+        '&' => out.push_str("&amp;"),
+        '"' => out.push_str("&quot;"),
+        '\'' => out.push_str("&#39;"),
+        _ => out.push(c),
+    }
+}
+
+// HTML-escaped excerpt of `text` around the first match, with matches wrapped in <mark>
+pub fn highlight(text: &str, terms: &[String]) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let matches = find_matches(&chars, &term_chars(terms));
+
+    let (start, end) = if chars.len() <= SNIPPET_LENGTH {
+        (0, chars.len())
+    } else {
+        let first = matches.first().map(|m| m.0).unwrap_or(0);
+        let start = first.saturating_sub(SNIPPET_LENGTH / 3);
+        let end = (start + SNIPPET_LENGTH).min(chars.len());
+        (end.saturating_sub(SNIPPET_LENGTH), end)
+    };
+
+    let mut out = String::new();
+    if start > 0 {
+        out.push('…');
+    }
+
+    let mut pending = matches.iter().peekable();
+    let mut i = start;
+    while i < end {
+        while pending.peek().is_some_and(|m| m.1 <= i) {
+            pending.next();
+        }
+
+        match pending.peek() {
+            Some(&&(match_start, match_end)) if match_start <= i => {
+                let stop = match_end.min(end);
+                out.push_str("<mark>");
+                for c in &chars[i..stop] {
+                    escape_html(*c, &mut out);
+                }
+                out.push_str("</mark>");
+                i = stop;
+            }
+            _ => {
+                escape_html(chars[i], &mut out);
+                i += 1;
+            }
+        }
+    }
+
+    if end < chars.len() {
+        out.push('…');
+    }
+
+    out
+}
+
+impl SearchResult {
+    pub fn new(todo: Todo, relevance: f64, terms: &[String]) -> Self {
+        let highlights = SearchHighlights {
+            title: highlight(&todo.title, terms),
+            description: todo.description.as_deref().map(|d| highlight(d, terms)),
+        };
+
+        SearchResult {
+            todo,
+            relevance,
+            highlights,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(terms: &[&str]) -> Vec<String> {
+        terms.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn quoted_phrases_stay_whole() {
+        assert_eq!(
+            search_terms(r#"call "Buy  Milk" today"#),
+            strings(&["call", "buy milk", "today"])
+        );
+        assert_eq!(
+            search_criteria(r#"+"buy milk" -"oat milk""#, SearchMode::Boolean),
+            SearchCriteria {
+                required: strings(&["buy milk"]),
+                optional: vec![],
+                excluded: strings(&["oat milk"]),
+            }
+        );
+    }
+
+    #[test]
+    fn unterminated_and_empty_phrases() {
+        assert_eq!(search_terms(r#""buy milk"#), strings(&["buy milk"]));
+        assert_eq!(search_terms(r#""" milk"#), strings(&["milk"]));
+    }
+
+    #[test]
+    fn boolean_operators_sort_terms() {
+        assert_eq!(
+            search_criteria(
+                "+report -draft ~budget >quarterly review* (plan)",
+                SearchMode::Boolean
+            ),
+            SearchCriteria {
+                required: strings(&["report"]),
+                optional: strings(&["budget", "quarterly", "review", "plan"]),
+                excluded: strings(&["draft"]),
+            }
+        );
+    }
+
+    #[test]
+    fn natural_mode_ignores_operators() {
+        assert_eq!(
+            search_criteria("+report -draft", SearchMode::Natural),
+            SearchCriteria {
+                required: vec![],
+                optional: strings(&["report", "draft"]),
+                excluded: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn excluded_terms_are_not_highlighted() {
+        assert_eq!(search_terms("report -draft"), strings(&["report"]));
+    }
+
+    #[test]
+    fn short_and_stop_words_are_dropped() {
+        assert_eq!(search_terms("Go to the gym"), strings(&["gym"]));
+        assert_eq!(search_terms("what is it"), Vec::<String>::new());
+        assert_eq!(
+            search_criteria("+the +gym -an", SearchMode::Boolean),
+            SearchCriteria {
+                required: strings(&["gym"]),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn stop_words_inside_phrases_are_kept() {
+        assert_eq!(
+            search_terms(r#""go to the gym""#),
+            strings(&["go to the gym"])
+        );
+    }
+
+    #[test]
+    fn highlights_are_case_insensitive_and_escaped() {
+        assert_eq!(
+            highlight("Buy <b>MILK</b> & bread", &strings(&["milk"])),
+            "Buy &lt;b&gt;<mark>MILK</mark>&lt;/b&gt; &amp; bread"
+        );
+    }
+
+    #[test]
+    fn highlights_prefer_the_longest_term() {
+        assert_eq!(
+            highlight("buy milk now", &strings(&["milk", "buy milk"])),
+            "<mark>buy milk</mark> now"
+        );
+    }
+
+    #[test]
+    fn highlights_multibyte_text_on_char_boundaries() {
+        assert_eq!(
+            highlight("Café Über Straße", &strings(&["über", "straße"])),
+            "Café <mark>Über</mark> <mark>Straße</mark>"
+        );
+        assert_eq!(
+            highlight("日本語のメモ", &strings(&["メモ"])),
+            "日本語の<mark>メモ</mark>"
+        );
+    }
+
+    #[test]
+    fn long_text_is_cut_around_the_first_match() {
+        let text = format!("{}needle{}", "é".repeat(200), "ü".repeat(200));
+        let snippet = highlight(&text, &strings(&["needle"]));
+
+        assert!(snippet.starts_with('…'));
+        assert!(snippet.ends_with('…'));
+        assert!(snippet.contains("<mark>needle</mark>"));
+        let visible = snippet.replace("<mark>", "").replace("</mark>", "");
+        assert_eq!(visible.chars().count(), SNIPPET_LENGTH + 2);
+        assert_eq!(
+            visible.chars().position(|c| c == 'n'),
+            Some(SNIPPET_LENGTH / 3 + 1)
+        );
+    }
+
+    #[test]
+    fn search_limit_is_clamped() {
+        let query = |limit| SearchQuery {
+            q: "milk".to_string(),
+            mode: None,
+            limit,
+        };
+
+        assert_eq!(query(None).limit(), DEFAULT_SEARCH_LIMIT);
+        assert_eq!(query(Some(0)).limit(), 1);
+        assert_eq!(query(Some(1000)).limit(), MAX_SEARCH_LIMIT);
+    }
+}
@@ -0,0 +1,247 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::todo_model::{Todo, UpdateTodoRequest};
+
+pub const DEFAULT_SYNC_PAGE_SIZE: u32 = 500;
+pub const MAX_SYNC_PAGE_SIZE: u32 = 1000;
+pub const MAX_SYNC_MUTATIONS: usize = 500;
+
+// Tombstones of purged todos are kept this long; clients holding an older
+// token have to do a full sync
+pub const TOMBSTONE_RETENTION_DAYS: i64 = 90;
+
+// Changes are only handed out once they are this old. Timestamps have second
+// resolution and concurrent writes can commit out of order, so the most recent
+// second is still moving.
+pub const SYNC_SETTLE_SECONDS: i64 = 5;
+
+#[derive(Debug, Deserialize)]
+pub struct SyncQuery {
+    pub since: Option<String>,
+    pub limit: Option<u32>,
+}
+
+// Opaque change token: the time and id of the last change a client has seen
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct SyncToken {
+    #[serde(rename = "t")]
+    pub changed_at: DateTime<Utc>,
+    pub id: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SyncChange {
+    Upsert {
+        todo: Todo,
+    },
+    // Trashed and purged todos alike
+    Delete {
+        id: String,
+        deleted_at: DateTime<Utc>,
+    },
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncResponse {
+    pub changes: Vec<SyncChange>,
+    pub next_token: String,
+    pub has_more: bool,
+}
+
+// A client-side change. Without a base_version the write always wins.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum SyncMutation {
+    Upsert {
+        id: String,
+        base_version: Option<i32>,
+        todo: UpdateTodoRequest,
+    },
+    Delete {
+        id: String,
+        base_version: Option<i32>,
+    },
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SyncPushRequest {
+    pub mutations: Vec<SyncMutation>,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncOutcome {
+    Created,
+    Updated,
+    Deleted,
+    Conflict,
+    Rejected,
+}
+
+// On a conflict `todo` carries the server's copy so the client can merge
+#[derive(Debug, Serialize)]
+pub struct SyncMutationResult {
+    pub id: String,
+    pub outcome: SyncOutcome,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub todo: Option<Todo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncPushResponse {
+    pub results: Vec<SyncMutationResult>,
+}
+
+impl SyncQuery {
+    pub fn page_size(&self) -> u32 {
+        self.limit
+            .unwrap_or(DEFAULT_SYNC_PAGE_SIZE)
+            .clamp(1, MAX_SYNC_PAGE_SIZE)
+    }
+}
+
+impl SyncToken {
+    // Position before every change, used for an initial sync
+    pub fn start() -> Self {
+        SyncToken::at(DateTime::UNIX_EPOCH)
+    }
+
+    // Position before any change made at `changed_at` or later
+    pub fn at(changed_at: DateTime<Utc>) -> Self {
+        SyncToken {
+            changed_at,
+            id: String::new(),
+        }
+    }
+
+    pub fn is_start(&self) -> bool {
+        *self == SyncToken::start()
+    }
+
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    pub fn decode(token: &str) -> Result<Self, String> {
+        let invalid = || "Invalid sync token".to_string();
+
+        let bytes = URL_SAFE_NO_PAD.decode(token).map_err(|_| invalid())?;
+        serde_json::from_slice(&bytes).map_err(|_| invalid())
+    }
+}
+
+impl SyncChange {
+    pub fn position(&self) -> (DateTime<Utc>, &str) {
+        match self {
+            SyncChange::Upsert { todo } => (todo.updated_at, &todo.id),
+            SyncChange::Delete { id, deleted_at } => (*deleted_at, id),
+        }
+    }
+
+    pub fn token(&self) -> SyncToken {
+        let (changed_at, id) = self.position();
+        SyncToken {
+            changed_at,
+            id: id.to_string(),
+        }
+    }
+}
+
+impl SyncMutation {
+    pub fn id(&self) -> &str {
+        match self {
+            SyncMutation::Upsert { id, .. } | SyncMutation::Delete { id, .. } => id,
+        }
+    }
+}
+
+impl SyncMutationResult {
+    pub fn new(id: &str, outcome: SyncOutcome, todo: Option<Todo>) -> Self {
+        SyncMutationResult {
+            id: id.to_string(),
+            outcome,
+            todo,
+            error: None,
+        }
+    }
+
+    pub fn rejected(id: &str, error: impl Into<String>) -> Self {
+        SyncMutationResult {
+            id: id.to_string(),
+            outcome: SyncOutcome::Rejected,
+            todo: None,
+            error: Some(error.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    #[test]
+    fn tokens_survive_encoding() {
+        let token = SyncToken {
+            changed_at: Utc.with_ymd_and_hms(2026, 10, 18, 12, 30, 5).unwrap(),
+            id: "8f14e45f-ceea-467f-a0e6-5a6a7e2b8c1d".to_string(),
+        };
+        let encoded = token.encode();
+
+        assert!(
+            encoded
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        );
+        assert_eq!(SyncToken::decode(&encoded), Ok(token));
+    }
+
+    #[test]
+    fn only_the_start_token_is_the_start() {
+        assert!(
+            SyncToken::decode(&SyncToken::start().encode())
+                .unwrap()
+                .is_start()
+        );
+        assert!(!SyncToken::at(Utc::now()).is_start());
+    }
+
+    #[test]
+    fn delete_changes_are_positioned_at_their_deletion() {
+        let deleted_at = Utc.with_ymd_and_hms(2026, 10, 18, 8, 0, 0).unwrap();
+        let change = SyncChange::Delete {
+            id: "a".to_string(),
+            deleted_at,
+        };
+
+        assert_eq!(
+            change.token(),
+            SyncToken {
+                changed_at: deleted_at,
+                id: "a".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_tokens() {
+        for token in ["", "not a token", "bm90IGpzb24", "eyJ0IjoxfQ"] {
+            assert!(SyncToken::decode(token).is_err(), "{}", token);
+        }
+    }
+
+    #[test]
+    fn page_size_is_clamped() {
+        let query = |limit| SyncQuery { since: None, limit };
+
+        assert_eq!(query(None).page_size(), DEFAULT_SYNC_PAGE_SIZE);
+        assert_eq!(query(Some(0)).page_size(), 1);
+        assert_eq!(query(Some(5000)).page_size(), MAX_SYNC_PAGE_SIZE);
+    }
+}
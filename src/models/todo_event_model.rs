@@ -0,0 +1,142 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::models::todo_model::{Todo, TodoStatus};
+
+#[derive(Debug, Serialize)]
+pub struct TodoEvent {
+    pub id: i64,
+    pub todo_id: String,
+    pub actor_id: Option<String>,
+    pub field: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+// A single field change, recorded as a todo event
+#[derive(Debug)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+}
+
+impl FieldChange {
+    pub fn diff(
+        field: &'static str,
+        old_value: Option<String>,
+        new_value: Option<String>,
+    ) -> Option<Self> {
+        (old_value != new_value).then_some(FieldChange {
+            field,
+            old_value,
+            new_value,
+        })
+    }
+}
+
+// When a todo first left pending and when it last became completed,
+// derived from its status events
+#[derive(Debug, sqlx::FromRow)]
+pub struct StatusTimestamps {
+    pub todo_id: String,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+pub fn attach_status_timestamps(todos: &mut [Todo], timestamps: &[StatusTimestamps]) {
+    let by_todo: HashMap<&str, &StatusTimestamps> =
+        timestamps.iter().map(|t| (t.todo_id.as_str(), t)).collect();
+
+    for todo in todos.iter_mut() {
+        let Some(found) = by_todo.get(todo.id.as_str()) else {
+            continue;
+        };
+
+        todo.started_at = found.started_at;
+        // Reopened todos aren't completed anymore
+        todo.completed_at = found
+            .completed_at
+            .filter(|_| todo.status == TodoStatus::Completed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value).unwrap().to_utc()
+    }
+
+    fn todo(id: &str, status: TodoStatus) -> Todo {
+        Todo::new(
+            id.to_string(),
+            None,
+            Some(status),
+            None,
+            None,
+            "user-1".to_string(),
+        )
+        .with_id(id.to_string())
+    }
+
+    fn timestamps(todo_id: &str, started_at: &str, completed_at: &str) -> StatusTimestamps {
+        StatusTimestamps {
+            todo_id: todo_id.to_string(),
+            started_at: Some(utc(started_at)),
+            completed_at: Some(utc(completed_at)),
+        }
+    }
+
+    #[test]
+    fn only_changed_fields_are_recorded() {
+        assert!(FieldChange::diff("title", Some("a".to_string()), Some("a".to_string())).is_none());
+        assert!(FieldChange::diff("description", None, None).is_none());
+
+        let change = FieldChange::diff("description", Some("old".to_string()), None).unwrap();
+        assert_eq!(change.field, "description");
+        assert_eq!(change.old_value.as_deref(), Some("old"));
+        assert_eq!(change.new_value, None);
+    }
+
+    #[test]
+    fn status_timestamps_come_from_history() {
+        let mut todos = vec![
+            todo("done", TodoStatus::Completed),
+            todo("untracked", TodoStatus::Completed),
+        ];
+        attach_status_timestamps(
+            &mut todos,
+            &[timestamps(
+                "done",
+                "2026-10-01T08:00:00Z",
+                "2026-10-02T08:00:00Z",
+            )],
+        );
+
+        assert_eq!(todos[0].started_at, Some(utc("2026-10-01T08:00:00Z")));
+        assert_eq!(todos[0].completed_at, Some(utc("2026-10-02T08:00:00Z")));
+        assert_eq!(todos[1].started_at, None);
+        assert_eq!(todos[1].completed_at, None);
+    }
+
+    #[test]
+    fn reopened_todos_keep_their_start_but_lose_their_completion() {
+        let mut todos = vec![todo("reopened", TodoStatus::InProcess)];
+        attach_status_timestamps(
+            &mut todos,
+            &[timestamps(
+                "reopened",
+                "2026-10-01T08:00:00Z",
+                "2026-10-02T08:00:00Z",
+            )],
+        );
+
+        assert_eq!(todos[0].started_at, Some(utc("2026-10-01T08:00:00Z")));
+        assert_eq!(todos[0].completed_at, None);
+    }
+}
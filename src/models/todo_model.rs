@@ -1,7 +1,11 @@
-use chrono::{DateTime, Utc};
+use std::{fmt, str::FromStr};
+
+use chrono::{DateTime, Duration, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::utils::{patch::Patch, recurrence::RecurrenceRule};
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, sqlx::Type)]
 #[sqlx(type_name = "status", rename_all = "snake_case")]
 pub enum TodoStatus {
@@ -16,29 +20,184 @@ impl Default for TodoStatus {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+impl TodoStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TodoStatus::Pending => "pending",
+            TodoStatus::InProcess => "in_process",
+            TodoStatus::Completed => "completed",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Self {
+        value.parse().unwrap_or_default()
+    }
+
+    // Index of the value in the todos.status ENUM, which is what MySQL sorts by
+    pub fn position(&self) -> i64 {
+        match self {
+            TodoStatus::Pending => 1,
+            TodoStatus::InProcess => 2,
+            TodoStatus::Completed => 3,
+        }
+    }
+}
+
+impl FromStr for TodoStatus {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(TodoStatus::Pending),
+            "in_process" => Ok(TodoStatus::InProcess),
+            "completed" => Ok(TodoStatus::Completed),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Todo {
     pub id: String,
     pub title: String,
     pub description: Option<String>,
     pub status: TodoStatus,
     pub user_id: String,
+    pub due_date: Option<DateTime<Utc>>,
+    pub recurrence_rule: Option<String>,
+    pub series_id: Option<String>,
+    pub occurrence_index: i32,
+    pub workflow_status_id: Option<String>,
+    // Incremented on every write; exposed as the ETag
+    pub version: i32,
+    #[serde(default)]
+    pub blocked_by: Vec<String>,
+    #[serde(default)]
+    pub blocking: Vec<String>,
+    #[serde(default)]
+    pub comment_count: i64,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+    pub archived_at: Option<DateTime<Utc>>,
+    pub deleted_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CreateTodoRequest {
+    // Lets offline clients create todos under an id they generated
+    pub id: Option<String>,
     pub title: String,
     pub description: Option<String>,
     pub status: Option<TodoStatus>,
+    pub due_date: Option<DateTime<Utc>>,
+    pub recurrence_rule: Option<String>,
+    pub workflow_status: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+// Body of PUT /todos/{id}: replaces the todo's content, so omitted optional
+// fields are cleared. The status only changes when one is given.
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct UpdateTodoRequest {
-    pub title: Option<String>,
+    pub title: String,
     pub description: Option<String>,
     pub status: Option<TodoStatus>,
+    pub due_date: Option<DateTime<Utc>>,
+    pub recurrence_rule: Option<String>,
+    pub workflow_status: Option<String>,
+}
+
+// Body of PATCH /todos/{id} (JSON Merge Patch): omitted fields are kept and
+// null clears a field
+#[derive(Debug, Deserialize, Default)]
+pub struct TodoPatch {
+    #[serde(default)]
+    pub title: Patch<String>,
+    #[serde(default)]
+    pub description: Patch<String>,
+    #[serde(default)]
+    pub status: Patch<TodoStatus>,
+    #[serde(default)]
+    pub due_date: Patch<DateTime<Utc>>,
+    #[serde(default)]
+    pub recurrence_rule: Patch<String>,
+    #[serde(default)]
+    pub workflow_status: Patch<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateTodoQuery {
+    pub force: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteTodoQuery {
+    pub permanent: Option<bool>,
+}
+
+// How long deleted todos stay in the trash before they are purged for good
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TrashRetention {
+    days: i64,
+}
+
+// The todo changed between being read and written
+#[derive(Debug)]
+pub struct VersionConflict;
+
+impl fmt::Display for VersionConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Todo was modified by another request")
+    }
+}
+
+impl std::error::Error for VersionConflict {}
+
+impl From<UpdateTodoRequest> for TodoPatch {
+    fn from(request: UpdateTodoRequest) -> Self {
+        TodoPatch {
+            title: Patch::Value(request.title),
+            description: request.description.into(),
+            status: request.status.map_or(Patch::Absent, Patch::Value),
+            due_date: request.due_date.into(),
+            recurrence_rule: request.recurrence_rule.into(),
+            workflow_status: request.workflow_status.map_or(Patch::Absent, Patch::Value),
+        }
+    }
+}
+
+impl TodoPatch {
+    // Fields that can't be null in a todo
+    pub fn validate(&self) -> Result<(), String> {
+        for (field, is_null) in [
+            ("title", self.title.is_null()),
+            ("status", self.status.is_null()),
+            ("workflow_status", self.workflow_status.is_null()),
+        ] {
+            if is_null {
+                return Err(format!("{} cannot be null", field));
+            }
+        }
+
+        Ok(())
+    }
+
+    // The next occurrence is scheduled from the due date, so a todo that
+    // recurs after the patch must still have one
+    pub fn validate_recurrence(&self, todo: &Todo) -> Result<(), String> {
+        let recurring = self
+            .recurrence_rule
+            .apply(todo.recurrence_rule.as_ref())
+            .is_some();
+        let has_due_date = self.due_date.apply(todo.due_date.as_ref()).is_some();
+
+        if recurring && !has_due_date {
+            return Err("Recurring todos require a due_date".to_string());
+        }
+
+        Ok(())
+    }
 }
 
 impl Todo {
@@ -46,19 +205,231 @@ impl Todo {
         title: String,
         description: Option<String>,
         status: Option<TodoStatus>,
+        due_date: Option<DateTime<Utc>>,
+        recurrence_rule: Option<String>,
         user_id: String,
     ) -> Self {
         let now = Utc::now();
+        let id = Uuid::new_v4().to_string();
         let status = status.unwrap_or_default();
+        // The first occurrence of a recurring todo names its series
+        let series_id = recurrence_rule.as_ref().map(|_| id.clone());
 
         Self {
-            id: Uuid::new_v4().to_string(),
+            id,
             title,
             description,
             status,
             user_id,
+            due_date,
+            recurrence_rule,
+            series_id,
+            occurrence_index: 1,
+            workflow_status_id: None,
+            version: 1,
+            blocked_by: Vec::new(),
+            blocking: Vec::new(),
+            comment_count: 0,
+            started_at: None,
+            completed_at: None,
+            archived_at: None,
+            deleted_at: None,
             created_at: now,
             updated_at: now,
         }
     }
+
+    // Only completed todos that are still in use can be archived; archiving an
+    // archived one again changes nothing
+    pub fn can_be_archived(&self) -> bool {
+        self.status == TodoStatus::Completed && self.deleted_at.is_none()
+    }
+
+    pub fn with_id(mut self, id: String) -> Self {
+        if self.series_id.as_ref() == Some(&self.id) {
+            self.series_id = Some(id.clone());
+        }
+        self.id = id;
+        self
+    }
+
+    // Due date of the occurrence after this one, if the todo recurs and its series continues
+    pub fn next_due_date(&self) -> Option<DateTime<Utc>> {
+        let rule: RecurrenceRule = self.recurrence_rule.as_ref()?.parse().ok()?;
+        rule.next_after(self.due_date?, self.occurrence_index as u32)
+    }
+
+    pub fn next_occurrence(&self) -> Option<Todo> {
+        let due_date = self.next_due_date()?;
+        let mut next = Todo::new(
+            self.title.clone(),
+            self.description.clone(),
+            None,
+            Some(due_date),
+            self.recurrence_rule.clone(),
+            self.user_id.clone(),
+        );
+        next.series_id = self.series_id.clone().or_else(|| Some(self.id.clone()));
+        next.occurrence_index = self.occurrence_index + 1;
+
+        Some(next)
+    }
+}
+
+// A blocked todo can't be started or completed while its blockers are open,
+// unless the caller explicitly forces it
+pub fn starts_work(todo: &Todo, requested_status: Option<&TodoStatus>) -> bool {
+    matches!(
+        requested_status,
+        Some(TodoStatus::InProcess) | Some(TodoStatus::Completed)
+    ) && requested_status != Some(&todo.status)
+}
+
+impl CreateTodoRequest {
+    // The id a client generated for the todo, if any. Any UUID spelling is
+    // accepted and stored hyphenated in lower case; the nil UUID isn't.
+    pub fn client_id(&self) -> Result<Option<Uuid>, String> {
+        let Some(id) = self.id.as_deref() else {
+            return Ok(None);
+        };
+
+        match Uuid::parse_str(id) {
+            Ok(id) if !id.is_nil() => Ok(Some(id)),
+            _ => Err("Todo id must be a UUID".to_string()),
+        }
+    }
+}
+
+impl TrashRetention {
+    pub fn from_env_value(value: &str) -> Result<Self, String> {
+        match value.trim().parse::<i64>() {
+            Ok(days) if days >= 1 => Ok(TrashRetention { days }),
+            _ => Err(format!(
+                "TRASH_RETENTION_DAYS must be a whole number of days, at least 1, got '{}'",
+                value
+            )),
+        }
+    }
+
+    // Todos deleted before this are purged
+    pub fn purge_cutoff(&self, now: DateTime<Utc>) -> DateTime<Utc> {
+        now - Duration::days(self.days)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value).unwrap().to_utc()
+    }
+
+    fn todo(status: TodoStatus) -> Todo {
+        Todo::new(
+            "Buy milk".to_string(),
+            None,
+            Some(status),
+            None,
+            None,
+            "user-1".to_string(),
+        )
+    }
+
+    #[test]
+    fn only_completed_todos_can_be_archived() {
+        assert!(todo(TodoStatus::Completed).can_be_archived());
+        assert!(!todo(TodoStatus::Pending).can_be_archived());
+        assert!(!todo(TodoStatus::InProcess).can_be_archived());
+
+        let mut archived = todo(TodoStatus::Completed);
+        archived.archived_at = Some(Utc::now());
+        assert!(archived.can_be_archived());
+
+        let mut trashed = todo(TodoStatus::Completed);
+        trashed.deleted_at = Some(Utc::now());
+        assert!(!trashed.can_be_archived());
+    }
+
+    fn create_request(id: Option<&str>) -> CreateTodoRequest {
+        CreateTodoRequest {
+            id: id.map(str::to_string),
+            title: "Buy milk".to_string(),
+            description: None,
+            status: None,
+            due_date: None,
+            recurrence_rule: None,
+            workflow_status: None,
+        }
+    }
+
+    #[test]
+    fn client_ids_are_normalized() {
+        let id = "67e55044-10b1-426f-9247-bb680e5fe0c8";
+
+        assert_eq!(create_request(None).client_id(), Ok(None));
+        for spelling in [
+            id.to_string(),
+            id.to_uppercase(),
+            id.replace('-', ""),
+            format!("urn:uuid:{}", id),
+        ] {
+            assert_eq!(
+                create_request(Some(&spelling))
+                    .client_id()
+                    .unwrap()
+                    .map(|id| id.to_string()),
+                Some(id.to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_client_ids_are_rejected() {
+        for id in [
+            "",
+            "42",
+            "not-a-uuid",
+            "00000000-0000-0000-0000-000000000000",
+        ] {
+            assert_eq!(
+                create_request(Some(id)).client_id(),
+                Err("Todo id must be a UUID".to_string())
+            );
+        }
+    }
+
+    #[test]
+    fn a_client_id_also_names_the_series() {
+        let mut recurring = todo(TodoStatus::Pending);
+        recurring.series_id = Some(recurring.id.clone());
+        let recurring = recurring.with_id("client-id".to_string());
+        assert_eq!(recurring.series_id.as_deref(), Some("client-id"));
+
+        let mut occurrence = todo(TodoStatus::Pending);
+        occurrence.series_id = Some("series".to_string());
+        let occurrence = occurrence.with_id("client-id".to_string());
+        assert_eq!(occurrence.series_id.as_deref(), Some("series"));
+    }
+
+    #[test]
+    fn trash_retention_is_read_from_the_environment() {
+        assert_eq!(
+            TrashRetention::from_env_value(" 30 "),
+            Ok(TrashRetention { days: 30 })
+        );
+        for value in ["0", "-1", "", "30d", "1.5"] {
+            assert!(TrashRetention::from_env_value(value).is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn todos_are_purged_after_the_retention_period() {
+        let retention = TrashRetention::from_env_value("30").unwrap();
+
+        assert_eq!(
+            retention.purge_cutoff(utc("2026-10-31T12:00:00Z")),
+            utc("2026-10-01T12:00:00Z")
+        );
+    }
 }
@@ -0,0 +1,295 @@
+use base64::{Engine, engine::general_purpose::URL_SAFE_NO_PAD};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::models::todo_model::{Todo, TodoStatus};
+
+pub const DEFAULT_PAGE_SIZE: u32 = 50;
+pub const MAX_PAGE_SIZE: u32 = 200;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum TodoSortField {
+    #[default]
+    CreatedAt,
+    UpdatedAt,
+    Title,
+    Status,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+// Query string of GET /todos. `status` takes a comma-separated list.
+#[derive(Debug, Deserialize)]
+pub struct TodoListQuery {
+    pub archived: Option<bool>,
+    pub status: Option<String>,
+    pub q: Option<String>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub updated_after: Option<DateTime<Utc>>,
+    pub updated_before: Option<DateTime<Utc>>,
+    pub sort: Option<TodoSortField>,
+    pub order: Option<SortOrder>,
+    pub limit: Option<u32>,
+    pub cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TodoFilter {
+    #[serde(default)]
+    pub statuses: Vec<TodoStatus>,
+    pub q: Option<String>,
+    pub created_after: Option<DateTime<Utc>>,
+    pub created_before: Option<DateTime<Utc>>,
+    pub updated_after: Option<DateTime<Utc>>,
+    pub updated_before: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub archived: bool,
+    #[serde(default)]
+    pub sort: TodoSortField,
+    #[serde(default)]
+    pub order: SortOrder,
+}
+
+// Position after which the next page starts: the sort key and id of the last
+// todo of the previous page
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TodoCursor {
+    pub sort: TodoSortField,
+    pub order: SortOrder,
+    pub value: String,
+    pub id: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TodoPage {
+    pub items: Vec<Todo>,
+    pub next_cursor: Option<String>,
+}
+
+// Parses a comma-separated `status` query parameter
+pub fn parse_status_list(list: Option<&str>) -> Result<Vec<TodoStatus>, String> {
+    match list {
+        Some(list) => list
+            .split(',')
+            .filter(|s| !s.trim().is_empty())
+            .map(|s| {
+                s.trim()
+                    .parse::<TodoStatus>()
+                    .map_err(|_| format!("Invalid status '{}'", s.trim()))
+            })
+            .collect(),
+        None => Ok(Vec::new()),
+    }
+}
+
+impl TodoListQuery {
+    pub fn to_filter(&self) -> Result<TodoFilter, String> {
+        let statuses = parse_status_list(self.status.as_deref())?;
+
+        Ok(TodoFilter {
+            statuses,
+            q: self.q.clone().filter(|q| !q.trim().is_empty()),
+            created_after: self.created_after,
+            created_before: self.created_before,
+            updated_after: self.updated_after,
+            updated_before: self.updated_before,
+            archived: self.archived.unwrap_or(false),
+            sort: self.sort.unwrap_or_default(),
+            order: self.order.unwrap_or_default(),
+        })
+    }
+
+    pub fn page_size(&self) -> u32 {
+        self.limit
+            .unwrap_or(DEFAULT_PAGE_SIZE)
+            .clamp(1, MAX_PAGE_SIZE)
+    }
+}
+
+impl TodoCursor {
+    pub fn after(todo: &Todo, filter: &TodoFilter) -> Self {
+        let value = match filter.sort {
+            TodoSortField::CreatedAt => todo.created_at.to_rfc3339(),
+            TodoSortField::UpdatedAt => todo.updated_at.to_rfc3339(),
+            TodoSortField::Title => todo.title.clone(),
+            TodoSortField::Status => todo.status.position().to_string(),
+        };
+
+        TodoCursor {
+            sort: filter.sort,
+            order: filter.order,
+            value,
+            id: todo.id.clone(),
+        }
+    }
+
+    pub fn encode(&self) -> String {
+        URL_SAFE_NO_PAD.encode(serde_json::to_vec(self).unwrap_or_default())
+    }
+
+    // A cursor is only valid for the ordering it was issued for
+    pub fn decode(cursor: &str, filter: &TodoFilter) -> Result<Self, String> {
+        let invalid = || "Invalid cursor".to_string();
+
+        let bytes = URL_SAFE_NO_PAD.decode(cursor).map_err(|_| invalid())?;
+        let cursor: TodoCursor = serde_json::from_slice(&bytes).map_err(|_| invalid())?;
+
+        if cursor.sort != filter.sort || cursor.order != filter.order {
+            return Err("Cursor doesn't match the requested sort order".to_string());
+        }
+
+        // The value ends up bound as the sort key's type, so check it parses as one
+        let valid = match cursor.sort {
+            TodoSortField::CreatedAt | TodoSortField::UpdatedAt => {
+                DateTime::parse_from_rfc3339(&cursor.value).is_ok()
+            }
+            TodoSortField::Title => true,
+            TodoSortField::Status => cursor.value.parse::<i64>().is_ok(),
+        };
+        if !valid {
+            return Err(invalid());
+        }
+
+        Ok(cursor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(sort: TodoSortField, order: SortOrder) -> TodoFilter {
+        TodoFilter {
+            sort,
+            order,
+            ..Default::default()
+        }
+    }
+
+    fn todo() -> Todo {
+        let mut todo = Todo::new(
+            "Büy milk".to_string(),
+            None,
+            Some(TodoStatus::InProcess),
+            None,
+            None,
+            "user-1".to_string(),
+        )
+        .with_id("todo-1".to_string());
+        todo.created_at = DateTime::parse_from_rfc3339("2026-10-01T08:00:00Z")
+            .unwrap()
+            .to_utc();
+        todo.updated_at = DateTime::parse_from_rfc3339("2026-10-02T08:00:00.5Z")
+            .unwrap()
+            .to_utc();
+        todo
+    }
+
+    fn encoded(json: &str) -> String {
+        URL_SAFE_NO_PAD.encode(json)
+    }
+
+    #[test]
+    fn cursors_round_trip_for_every_sort_key() {
+        let todo = todo();
+        let cases = [
+            (TodoSortField::CreatedAt, "2026-10-01T08:00:00+00:00"),
+            (TodoSortField::UpdatedAt, "2026-10-02T08:00:00.500+00:00"),
+            (TodoSortField::Title, "Büy milk"),
+            (TodoSortField::Status, "2"),
+        ];
+
+        for (sort, value) in cases {
+            for order in [SortOrder::Asc, SortOrder::Desc] {
+                let filter = filter(sort, order);
+                let cursor =
+                    TodoCursor::decode(&TodoCursor::after(&todo, &filter).encode(), &filter)
+                        .unwrap();
+
+                assert_eq!(cursor.sort, sort);
+                assert_eq!(cursor.order, order);
+                assert_eq!(cursor.value, value);
+                assert_eq!(cursor.id, "todo-1");
+            }
+        }
+    }
+
+    #[test]
+    fn cursors_only_work_for_their_sort_order() {
+        let issued = filter(TodoSortField::Title, SortOrder::Asc);
+        let cursor = TodoCursor::after(&todo(), &issued).encode();
+
+        for other in [
+            filter(TodoSortField::Title, SortOrder::Desc),
+            filter(TodoSortField::CreatedAt, SortOrder::Asc),
+        ] {
+            assert_eq!(
+                TodoCursor::decode(&cursor, &other).unwrap_err(),
+                "Cursor doesn't match the requested sort order"
+            );
+        }
+    }
+
+    #[test]
+    fn garbage_cursors_are_rejected() {
+        let filter = filter(TodoSortField::CreatedAt, SortOrder::Desc);
+
+        for cursor in [
+            "",
+            "not base64!",
+            &encoded("not json"),
+            &encoded("{}"),
+            &encoded("[1, 2]"),
+        ] {
+            assert_eq!(
+                TodoCursor::decode(cursor, &filter).unwrap_err(),
+                "Invalid cursor"
+            );
+        }
+    }
+
+    #[test]
+    fn tampered_cursor_values_are_rejected() {
+        let cases = [
+            (TodoSortField::CreatedAt, "yesterday"),
+            (TodoSortField::UpdatedAt, "2026-13-01T00:00:00Z"),
+            (TodoSortField::Status, "done"),
+            (TodoSortField::Status, "99999999999999999999"),
+        ];
+
+        for (sort, value) in cases {
+            let cursor = TodoCursor {
+                sort,
+                order: SortOrder::Desc,
+                value: value.to_string(),
+                id: "todo-1".to_string(),
+            };
+
+            assert_eq!(
+                TodoCursor::decode(&cursor.encode(), &filter(sort, SortOrder::Desc)).unwrap_err(),
+                "Invalid cursor"
+            );
+        }
+    }
+
+    #[test]
+    fn status_lists_are_parsed() {
+        assert_eq!(
+            parse_status_list(Some("pending, completed,")).unwrap(),
+            vec![TodoStatus::Pending, TodoStatus::Completed]
+        );
+        assert_eq!(parse_status_list(None).unwrap(), vec![]);
+        assert_eq!(
+            parse_status_list(Some("pending,done")).unwrap_err(),
+            "Invalid status 'done'"
+        );
+    }
+}
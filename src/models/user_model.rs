@@ -2,6 +2,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::utils::patch::Patch;
+
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, sqlx::Type)]
 #[sqlx(type_name = "role", rename_all = "snake_case")]
 pub enum UserRole {
@@ -34,6 +36,20 @@ pub struct UpdateUserRequest {
     pub role: Option<UserRole>,
 }
 
+// Body of PATCH (JSON Merge Patch). Every user field is required, so null is
+// rejected rather than clearing anything.
+#[derive(Debug, Deserialize)]
+pub struct UserPatch {
+    #[serde(default)]
+    pub name: Patch<String>,
+    #[serde(default)]
+    pub email: Patch<String>,
+    #[serde(default)]
+    pub password: Patch<String>,
+    #[serde(default)]
+    pub role: Patch<UserRole>,
+}
+
 #[derive(Debug, Serialize)]
 pub struct UserResponse {
     pub id: String,
@@ -72,6 +88,27 @@ impl User {
     }
 }
 
+impl TryFrom<UserPatch> for UpdateUserRequest {
+    type Error = String;
+
+    fn try_from(patch: UserPatch) -> Result<Self, Self::Error> {
+        fn required<T>(field: &str, value: Patch<T>) -> Result<Option<T>, String> {
+            match value {
+                Patch::Absent => Ok(None),
+                Patch::Null => Err(format!("{} cannot be null", field)),
+                Patch::Value(value) => Ok(Some(value)),
+            }
+        }
+
+        Ok(UpdateUserRequest {
+            name: required("name", patch.name)?,
+            email: required("email", patch.email)?,
+            password: required("password", patch.password)?,
+            role: required("role", patch.role)?,
+        })
+    }
+}
+
 impl From<User> for UserResponse {
     fn from(user: User) -> Self {
         user.to_response()
@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+pub struct UserSettings {
+    // Todos completed at least this many days ago are archived automatically
+    pub auto_archive_days: Option<i32>,
+}
+
+impl UserSettings {
+    pub fn validate(&self) -> Result<(), String> {
+        if matches!(self.auto_archive_days, Some(days) if days < 1) {
+            return Err("auto_archive_days must be at least 1".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings(auto_archive_days: Option<i32>) -> UserSettings {
+        UserSettings { auto_archive_days }
+    }
+
+    #[test]
+    fn auto_archiving_needs_at_least_a_day() {
+        assert!(settings(None).validate().is_ok());
+        assert!(settings(Some(1)).validate().is_ok());
+        assert_eq!(
+            settings(Some(0)).validate().unwrap_err(),
+            "auto_archive_days must be at least 1"
+        );
+        assert!(settings(Some(-5)).validate().is_err());
+    }
+}
@@ -0,0 +1,375 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::{change_event_model::ChangeKind, todo_model::Todo};
+
+pub const MAX_DELIVERY_ATTEMPTS: i32 = 8;
+const RETRY_BASE_SECONDS: i64 = 30;
+// How long a delivery being sent is left alone before a worker may retry it
+pub const CLAIM_LEASE_SECONDS: i64 = 5 * 60;
+
+// Which addresses webhooks may be sent to. Private and loopback addresses are
+// only for trying webhooks against a local receiver.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WebhookTargets {
+    PublicOnly,
+    AnyAddress,
+}
+
+impl WebhookTargets {
+    pub fn from_env_value(value: &str) -> Self {
+        match value.to_lowercase().as_str() {
+            "true" | "1" => WebhookTargets::AnyAddress,
+            _ => WebhookTargets::PublicOnly,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub enum WebhookEvent {
+    #[serde(rename = "todo.created")]
+    TodoCreated,
+    #[serde(rename = "todo.updated")]
+    TodoUpdated,
+    #[serde(rename = "todo.deleted")]
+    TodoDeleted,
+    // Only sent on request, whatever the webhook subscribes to
+    #[serde(rename = "webhook.test")]
+    Test,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum DeliveryStatus {
+    Pending,
+    Succeeded,
+    Failed,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct Webhook {
+    pub id: String,
+    pub user_id: String,
+    pub url: String,
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub events: Vec<WebhookEvent>,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+// The secret is only shown when it is set
+#[derive(Debug, Serialize)]
+pub struct WebhookWithSecret {
+    #[serde(flatten)]
+    pub webhook: Webhook,
+    pub secret: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateWebhookRequest {
+    pub url: String,
+    // Generated when omitted
+    pub secret: Option<String>,
+    // Defaults to every todo event
+    pub events: Option<Vec<WebhookEvent>>,
+    pub active: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateWebhookRequest {
+    pub url: Option<String>,
+    pub events: Option<Vec<WebhookEvent>>,
+    pub active: Option<bool>,
+    // Replaces the secret with a newly generated one
+    #[serde(default)]
+    pub rotate_secret: bool,
+}
+
+#[derive(Debug, Serialize, Clone)]
+pub struct WebhookDelivery {
+    pub id: String,
+    pub webhook_id: String,
+    pub event_type: WebhookEvent,
+    #[serde(skip_serializing)]
+    pub payload: String,
+    pub status: DeliveryStatus,
+    pub attempts: i32,
+    pub next_attempt_at: DateTime<Utc>,
+    pub last_response_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub delivered_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeliveryAttempt {
+    pub attempt: i32,
+    pub response_code: Option<i32>,
+    pub error: Option<String>,
+    pub duration_ms: i32,
+    pub attempted_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DeliveryDetails {
+    #[serde(flatten)]
+    pub delivery: WebhookDelivery,
+    pub attempts_log: Vec<DeliveryAttempt>,
+}
+
+// Result of one HTTP attempt: a response code, or an error when no response
+// came back at all
+#[derive(Debug)]
+pub struct AttemptOutcome {
+    pub response_code: Option<i32>,
+    pub error: Option<String>,
+    pub duration_ms: i32,
+}
+
+// Body posted to the webhook URL
+#[derive(Debug, Serialize)]
+pub struct WebhookPayload<'a> {
+    pub id: &'a str,
+    #[serde(rename = "type")]
+    pub event_type: WebhookEvent,
+    pub occurred_at: DateTime<Utc>,
+    pub data: WebhookPayloadData<'a>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct WebhookPayloadData<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub todo: Option<&'a Todo>,
+}
+
+impl WebhookEvent {
+    pub const TODO_EVENTS: [WebhookEvent; 3] = [
+        WebhookEvent::TodoCreated,
+        WebhookEvent::TodoUpdated,
+        WebhookEvent::TodoDeleted,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookEvent::TodoCreated => "todo.created",
+            WebhookEvent::TodoUpdated => "todo.updated",
+            WebhookEvent::TodoDeleted => "todo.deleted",
+            WebhookEvent::Test => "webhook.test",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "todo.created" => WebhookEvent::TodoCreated,
+            "todo.updated" => WebhookEvent::TodoUpdated,
+            "todo.deleted" => WebhookEvent::TodoDeleted,
+            _ => WebhookEvent::Test,
+        }
+    }
+}
+
+impl From<ChangeKind> for WebhookEvent {
+    fn from(kind: ChangeKind) -> Self {
+        match kind {
+            ChangeKind::Created => WebhookEvent::TodoCreated,
+            ChangeKind::Updated => WebhookEvent::TodoUpdated,
+            ChangeKind::Deleted => WebhookEvent::TodoDeleted,
+        }
+    }
+}
+
+impl DeliveryStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DeliveryStatus::Pending => "pending",
+            DeliveryStatus::Succeeded => "succeeded",
+            DeliveryStatus::Failed => "failed",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "succeeded" => DeliveryStatus::Succeeded,
+            "failed" => DeliveryStatus::Failed,
+            _ => DeliveryStatus::Pending,
+        }
+    }
+}
+
+// Two v4 UUIDs give 244 random bits
+pub fn generate_webhook_secret() -> String {
+    format!(
+        "whsec_{}{}",
+        Uuid::new_v4().simple(),
+        Uuid::new_v4().simple()
+    )
+}
+
+impl Webhook {
+    pub fn new(user_id: String, url: String, secret: String, events: Vec<WebhookEvent>) -> Self {
+        let now = Utc::now();
+
+        Webhook {
+            id: Uuid::new_v4().to_string(),
+            user_id,
+            url,
+            secret,
+            events,
+            active: true,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    pub fn subscribes_to(&self, event: WebhookEvent) -> bool {
+        self.active && self.events.contains(&event)
+    }
+}
+
+impl WebhookDelivery {
+    pub fn new(webhook: &Webhook, event_type: WebhookEvent, todo: Option<&Todo>) -> Self {
+        let now = Utc::now();
+        let id = Uuid::new_v4().to_string();
+
+        let payload = WebhookPayload {
+            id: &id,
+            event_type,
+            occurred_at: now,
+            data: WebhookPayloadData { todo },
+        };
+
+        WebhookDelivery {
+            payload: serde_json::to_string(&payload).unwrap_or_default(),
+            id,
+            webhook_id: webhook.id.clone(),
+            event_type,
+            status: DeliveryStatus::Pending,
+            attempts: 0,
+            next_attempt_at: now,
+            last_response_code: None,
+            last_error: None,
+            delivered_at: None,
+            created_at: now,
+        }
+    }
+
+    // Applies the outcome of an attempt: 2xx delivers, anything else is
+    // retried with exponential backoff until the attempts run out
+    pub fn record(&mut self, outcome: &AttemptOutcome, now: DateTime<Utc>) {
+        self.attempts += 1;
+        self.last_response_code = outcome.response_code;
+        self.last_error = outcome.error.clone();
+
+        if outcome.is_success() {
+            self.status = DeliveryStatus::Succeeded;
+            self.delivered_at = Some(now);
+        } else if self.attempts >= MAX_DELIVERY_ATTEMPTS {
+            self.status = DeliveryStatus::Failed;
+        } else {
+            let delay = RETRY_BASE_SECONDS << (self.attempts - 1);
+            self.next_attempt_at = now + Duration::seconds(delay);
+        }
+    }
+}
+
+impl AttemptOutcome {
+    pub fn is_success(&self) -> bool {
+        self.response_code
+            .is_some_and(|code| (200..300).contains(&code))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delivery() -> WebhookDelivery {
+        let webhook = Webhook::new(
+            "user".to_string(),
+            "https://example.com/hooks".to_string(),
+            generate_webhook_secret(),
+            WebhookEvent::TODO_EVENTS.to_vec(),
+        );
+        WebhookDelivery::new(&webhook, WebhookEvent::TodoCreated, None)
+    }
+
+    fn outcome(response_code: Option<i32>) -> AttemptOutcome {
+        AttemptOutcome {
+            response_code,
+            error: match response_code {
+                Some(code) if (200..300).contains(&code) => None,
+                Some(code) => Some(format!("Receiver responded with {}", code)),
+                None => Some("connection refused".to_string()),
+            },
+            duration_ms: 12,
+        }
+    }
+
+    #[test]
+    fn retries_with_exponential_backoff_until_attempts_run_out() {
+        let mut delivery = delivery();
+        let now = Utc::now();
+
+        for delay in [30, 60, 120, 240, 480, 960, 1920] {
+            delivery.record(&outcome(Some(503)), now);
+            assert_eq!(delivery.status, DeliveryStatus::Pending);
+            assert_eq!(delivery.next_attempt_at, now + Duration::seconds(delay));
+        }
+
+        delivery.record(&outcome(None), now);
+        assert_eq!(delivery.status, DeliveryStatus::Failed);
+        assert_eq!(delivery.attempts, MAX_DELIVERY_ATTEMPTS);
+        assert_eq!(delivery.last_response_code, None);
+        assert_eq!(delivery.last_error.as_deref(), Some("connection refused"));
+    }
+
+    #[test]
+    fn a_2xx_response_delivers() {
+        let mut delivery = delivery();
+        let now = Utc::now();
+
+        delivery.record(&outcome(Some(500)), now);
+        delivery.record(&outcome(Some(202)), now);
+
+        assert_eq!(delivery.status, DeliveryStatus::Succeeded);
+        assert_eq!(delivery.delivered_at, Some(now));
+        assert_eq!(delivery.last_response_code, Some(202));
+        assert_eq!(delivery.last_error, None);
+    }
+
+    #[test]
+    fn redirects_are_not_success() {
+        assert!(!outcome(Some(302)).is_success());
+        assert!(!outcome(None).is_success());
+        assert!(outcome(Some(204)).is_success());
+    }
+
+    #[test]
+    fn payload_names_the_delivery_and_event() {
+        let delivery = delivery();
+        let payload: serde_json::Value = serde_json::from_str(&delivery.payload).unwrap();
+
+        assert_eq!(payload["id"], delivery.id.as_str());
+        assert_eq!(payload["type"], "todo.created");
+        assert!(payload["data"].get("todo").is_none());
+    }
+
+    #[test]
+    fn only_true_allows_private_targets() {
+        assert_eq!(
+            WebhookTargets::from_env_value("TRUE"),
+            WebhookTargets::AnyAddress
+        );
+        assert_eq!(
+            WebhookTargets::from_env_value("1"),
+            WebhookTargets::AnyAddress
+        );
+        assert_eq!(
+            WebhookTargets::from_env_value("yes please"),
+            WebhookTargets::PublicOnly
+        );
+    }
+}
@@ -0,0 +1,476 @@
+use std::{collections::HashSet, fmt};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::todo_model::TodoStatus;
+
+// Every custom status belongs to one of these, so code that reasons about
+// pending/in-process/completed keeps working regardless of the workflow.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusCategory {
+    Todo,
+    Doing,
+    Done,
+}
+
+impl StatusCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StatusCategory::Todo => "todo",
+            StatusCategory::Doing => "doing",
+            StatusCategory::Done => "done",
+        }
+    }
+
+    pub fn from_db(value: &str) -> Self {
+        match value {
+            "doing" => StatusCategory::Doing,
+            "done" => StatusCategory::Done,
+            _ => StatusCategory::Todo,
+        }
+    }
+
+    pub fn to_todo_status(self) -> TodoStatus {
+        match self {
+            StatusCategory::Todo => TodoStatus::Pending,
+            StatusCategory::Doing => TodoStatus::InProcess,
+            StatusCategory::Done => TodoStatus::Completed,
+        }
+    }
+
+    pub fn from_todo_status(status: &TodoStatus) -> Self {
+        match status {
+            TodoStatus::Pending => StatusCategory::Todo,
+            TodoStatus::InProcess => StatusCategory::Doing,
+            TodoStatus::Completed => StatusCategory::Done,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct WorkflowStatus {
+    pub id: String,
+    pub key: String,
+    pub name: String,
+    pub category: StatusCategory,
+    pub position: i32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WorkflowTransition {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Workflow {
+    pub id: String,
+    pub user_id: String,
+    pub name: String,
+    pub is_default: bool,
+    pub statuses: Vec<WorkflowStatus>,
+    // Empty means any status may move to any other
+    pub transitions: Vec<WorkflowTransition>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WorkflowStatusRequest {
+    pub key: String,
+    pub name: String,
+    pub category: StatusCategory,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CreateWorkflowRequest {
+    pub name: String,
+    pub statuses: Vec<WorkflowStatusRequest>,
+    pub transitions: Option<Vec<WorkflowTransition>>,
+    pub is_default: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateWorkflowRequest {
+    pub name: Option<String>,
+    pub statuses: Option<Vec<WorkflowStatusRequest>>,
+    pub transitions: Option<Vec<WorkflowTransition>>,
+    pub is_default: Option<bool>,
+}
+
+#[derive(Debug)]
+pub enum WorkflowError {
+    NoDefaultWorkflow,
+    UnknownStatus(String),
+    NoStatusForCategory(StatusCategory),
+    TransitionNotAllowed { from: String, to: String },
+}
+
+impl fmt::Display for WorkflowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorkflowError::NoDefaultWorkflow => write!(f, "No default workflow is configured"),
+            WorkflowError::UnknownStatus(key) => write!(f, "Unknown workflow status '{}'", key),
+            WorkflowError::NoStatusForCategory(category) => write!(
+                f,
+                "Workflow has no status in category '{}'",
+                category.as_str()
+            ),
+            WorkflowError::TransitionNotAllowed { from, to } => {
+                write!(f, "Transition from '{}' to '{}' is not allowed", from, to)
+            }
+        }
+    }
+}
+
+impl std::error::Error for WorkflowError {}
+
+impl Workflow {
+    pub fn new(
+        user_id: String,
+        name: String,
+        statuses: Vec<WorkflowStatusRequest>,
+        transitions: Vec<WorkflowTransition>,
+        is_default: bool,
+    ) -> Self {
+        let now = Utc::now();
+
+        let mut workflow = Self {
+            id: Uuid::new_v4().to_string(),
+            user_id,
+            name,
+            is_default,
+            statuses: Vec::new(),
+            transitions,
+            created_at: now,
+            updated_at: now,
+        };
+        workflow.set_statuses(statuses);
+
+        workflow
+    }
+
+    // Replaces the status list, keeping the ids of statuses whose key survives so
+    // todos already in those statuses stay put
+    pub fn set_statuses(&mut self, statuses: Vec<WorkflowStatusRequest>) {
+        self.statuses = statuses
+            .into_iter()
+            .enumerate()
+            .map(|(position, status)| WorkflowStatus {
+                id: self
+                    .status_by_key(&status.key)
+                    .map(|existing| existing.id.clone())
+                    .unwrap_or_else(|| Uuid::new_v4().to_string()),
+                key: status.key,
+                name: status.name,
+                category: status.category,
+                position: position as i32,
+            })
+            .collect();
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.name.trim().is_empty() {
+            return Err("Workflow name is required".to_string());
+        }
+        if self.statuses.is_empty() {
+            return Err("Workflow needs at least one status".to_string());
+        }
+
+        let mut keys = HashSet::new();
+        for status in &self.statuses {
+            if status.key.trim().is_empty() {
+                return Err("Status keys can't be empty".to_string());
+            }
+            if !keys.insert(status.key.as_str()) {
+                return Err(format!("Duplicate status key '{}'", status.key));
+            }
+        }
+
+        for transition in &self.transitions {
+            for key in [&transition.from, &transition.to] {
+                if !keys.contains(key.as_str()) {
+                    return Err(format!("Transition references unknown status '{}'", key));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn status_by_key(&self, key: &str) -> Option<&WorkflowStatus> {
+        self.statuses.iter().find(|s| s.key == key)
+    }
+
+    pub fn status_by_id(&self, id: &str) -> Option<&WorkflowStatus> {
+        self.statuses.iter().find(|s| s.id == id)
+    }
+
+    // First status (by position) of a category, used when only a TodoStatus is given
+    pub fn initial_status(&self, category: StatusCategory) -> Option<&WorkflowStatus> {
+        self.statuses
+            .iter()
+            .filter(|s| s.category == category)
+            .min_by_key(|s| s.position)
+    }
+
+    pub fn allows(&self, from: &WorkflowStatus, to: &WorkflowStatus) -> bool {
+        from.id == to.id
+            || self.transitions.is_empty()
+            || self
+                .transitions
+                .iter()
+                .any(|t| t.from == from.key && t.to == to.key)
+    }
+
+    // The status an existing todo is in. One without a workflow status (made
+    // before the workflow, or whose status was removed) counts as being in
+    // the first status of its category, so transition rules still apply.
+    pub fn current_status(
+        &self,
+        status_id: Option<&str>,
+        status: &TodoStatus,
+    ) -> Option<&WorkflowStatus> {
+        status_id
+            .and_then(|id| self.status_by_id(id))
+            .or_else(|| self.initial_status(StatusCategory::from_todo_status(status)))
+    }
+
+    // Works out the status a todo moves to from a requested key or TodoStatus.
+    // `current` is None for a new todo. Ok(None) means the request doesn't
+    // touch the status.
+    pub fn resolve<'a>(
+        &'a self,
+        current: Option<&'a WorkflowStatus>,
+        key: Option<&str>,
+        status: Option<&TodoStatus>,
+    ) -> Result<Option<&'a WorkflowStatus>, WorkflowError> {
+        let target = match (key, status) {
+            (Some(key), _) => self
+                .status_by_key(key)
+                .ok_or_else(|| WorkflowError::UnknownStatus(key.to_string()))?,
+            (None, Some(status)) => {
+                let category = StatusCategory::from_todo_status(status);
+                match current {
+                    Some(current) if current.category == category => current,
+                    _ => self
+                        .initial_status(category)
+                        .ok_or(WorkflowError::NoStatusForCategory(category))?,
+                }
+            }
+            (None, None) => return Ok(None),
+        };
+
+        if let Some(current) = current {
+            if !self.allows(current, target) {
+                return Err(WorkflowError::TransitionNotAllowed {
+                    from: current.key.clone(),
+                    to: target.key.clone(),
+                });
+            }
+        }
+
+        Ok(Some(target))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(key: &str, category: StatusCategory) -> WorkflowStatusRequest {
+        WorkflowStatusRequest {
+            key: key.to_string(),
+            name: key.to_string(),
+            category,
+        }
+    }
+
+    fn transition(from: &str, to: &str) -> WorkflowTransition {
+        WorkflowTransition {
+            from: from.to_string(),
+            to: to.to_string(),
+        }
+    }
+
+    // backlog -> ready -> doing <-> review -> done
+    fn review_workflow() -> Workflow {
+        Workflow::new(
+            "user".to_string(),
+            "Review".to_string(),
+            vec![
+                status("backlog", StatusCategory::Todo),
+                status("ready", StatusCategory::Todo),
+                status("doing", StatusCategory::Doing),
+                status("review", StatusCategory::Doing),
+                status("done", StatusCategory::Done),
+            ],
+            vec![
+                transition("backlog", "ready"),
+                transition("ready", "doing"),
+                transition("doing", "review"),
+                transition("review", "doing"),
+                transition("review", "done"),
+            ],
+            false,
+        )
+    }
+
+    fn id_of(workflow: &Workflow, key: &str) -> String {
+        workflow.status_by_key(key).unwrap().id.clone()
+    }
+
+    #[test]
+    fn allows_only_listed_transitions_and_staying_put() {
+        let workflow = review_workflow();
+        let by_key = |key| workflow.status_by_key(key).unwrap();
+
+        assert!(workflow.allows(by_key("ready"), by_key("doing")));
+        assert!(workflow.allows(by_key("backlog"), by_key("backlog")));
+        assert!(!workflow.allows(by_key("doing"), by_key("ready")));
+        assert!(!workflow.allows(by_key("backlog"), by_key("done")));
+    }
+
+    #[test]
+    fn without_transitions_any_move_is_allowed() {
+        let mut workflow = review_workflow();
+        workflow.transitions.clear();
+
+        assert!(workflow.allows(
+            workflow.status_by_key("done").unwrap(),
+            workflow.status_by_key("backlog").unwrap()
+        ));
+    }
+
+    #[test]
+    fn resolves_a_requested_key() {
+        let workflow = review_workflow();
+        let ready = workflow.status_by_key("ready");
+
+        let target = workflow.resolve(ready, Some("doing"), None).unwrap();
+        assert_eq!(target.map(|s| s.key.as_str()), Some("doing"));
+
+        assert!(matches!(
+            workflow.resolve(ready, Some("shipped"), None),
+            Err(WorkflowError::UnknownStatus(key)) if key == "shipped"
+        ));
+        assert!(matches!(
+            workflow.resolve(ready, Some("done"), None),
+            Err(WorkflowError::TransitionNotAllowed { from, to }) if from == "ready" && to == "done"
+        ));
+    }
+
+    #[test]
+    fn resolves_a_todo_status_to_its_category() {
+        let workflow = review_workflow();
+        let review = workflow.status_by_key("review");
+
+        // Already in the category: the todo stays in its status
+        let target = workflow
+            .resolve(review, None, Some(&TodoStatus::InProcess))
+            .unwrap();
+        assert_eq!(target.map(|s| s.key.as_str()), Some("review"));
+
+        // Otherwise the first status of the category
+        let target = workflow
+            .resolve(review, None, Some(&TodoStatus::Completed))
+            .unwrap();
+        assert_eq!(target.map(|s| s.key.as_str()), Some("done"));
+
+        // A new todo has no current status to check transitions from
+        let target = workflow
+            .resolve(None, None, Some(&TodoStatus::Pending))
+            .unwrap();
+        assert_eq!(target.map(|s| s.key.as_str()), Some("backlog"));
+    }
+
+    #[test]
+    fn resolve_without_a_request_leaves_the_status() {
+        let workflow = review_workflow();
+        let ready = workflow.status_by_key("ready");
+
+        assert!(workflow.resolve(ready, None, None).unwrap().is_none());
+    }
+
+    #[test]
+    fn a_todo_without_a_workflow_status_is_in_the_first_of_its_category() {
+        let workflow = review_workflow();
+        let review = id_of(&workflow, "review");
+
+        let current =
+            |id: Option<&str>, status| workflow.current_status(id, &status).map(|s| s.key.as_str());
+        assert_eq!(current(Some(&review), TodoStatus::Pending), Some("review"));
+        assert_eq!(current(None, TodoStatus::Pending), Some("backlog"));
+        assert_eq!(
+            current(Some("removed"), TodoStatus::InProcess),
+            Some("doing")
+        );
+
+        // So a pending todo can't skip ahead to done, by key or by status
+        let pending = workflow.current_status(None, &TodoStatus::Pending);
+        assert!(matches!(
+            workflow.resolve(pending, Some("done"), None),
+            Err(WorkflowError::TransitionNotAllowed { from, to }) if from == "backlog" && to == "done"
+        ));
+        assert!(matches!(
+            workflow.resolve(pending, None, Some(&TodoStatus::Completed)),
+            Err(WorkflowError::TransitionNotAllowed { .. })
+        ));
+    }
+
+    #[test]
+    fn resolve_fails_for_a_category_without_statuses() {
+        let workflow = Workflow::new(
+            "user".to_string(),
+            "Open".to_string(),
+            vec![status("open", StatusCategory::Todo)],
+            Vec::new(),
+            false,
+        );
+
+        assert!(matches!(
+            workflow.resolve(None, None, Some(&TodoStatus::Completed)),
+            Err(WorkflowError::NoStatusForCategory(StatusCategory::Done))
+        ));
+    }
+
+    #[test]
+    fn replacing_statuses_keeps_the_ids_of_surviving_keys() {
+        let mut workflow = review_workflow();
+        let doing = id_of(&workflow, "doing");
+        let review = id_of(&workflow, "review");
+
+        workflow.set_statuses(vec![
+            status("doing", StatusCategory::Doing),
+            status("qa", StatusCategory::Doing),
+        ]);
+
+        assert_eq!(id_of(&workflow, "doing"), doing);
+        assert_ne!(id_of(&workflow, "qa"), review);
+        assert_eq!(workflow.status_by_key("doing").unwrap().position, 0);
+    }
+
+    #[test]
+    fn validate_rejects_duplicate_keys_and_unknown_transitions() {
+        let mut workflow = review_workflow();
+        assert!(workflow.validate().is_ok());
+
+        workflow.transitions.push(transition("done", "archived"));
+        assert!(workflow.validate().is_err());
+
+        let duplicate = Workflow::new(
+            "user".to_string(),
+            "Duplicate".to_string(),
+            vec![
+                status("open", StatusCategory::Todo),
+                status("open", StatusCategory::Done),
+            ],
+            Vec::new(),
+            false,
+        );
+        assert!(duplicate.validate().is_err());
+    }
+}
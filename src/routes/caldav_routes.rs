@@ -0,0 +1,77 @@
+use actix_web::{Route, http::Method, web};
+
+use crate::{
+    handlers::caldav_handler::{
+        calendar_propfind_handler, calendar_report_handler, home_propfind_handler,
+        object_delete_handler, object_get_handler, object_propfind_handler, object_put_handler,
+        options_handler, principal_propfind_handler, root_propfind_handler, well_known_handler,
+    },
+    models::caldav_model::CALENDAR_NAME,
+};
+
+fn dav_method(name: &'static str) -> Route {
+    web::method(Method::from_bytes(name.as_bytes()).expect("valid HTTP method"))
+}
+
+// CalDAV lives outside /api/v1 and authenticates with HTTP Basic itself.
+// Collections are registered with and without the trailing slash, since
+// clients aren't consistent about it.
+pub fn configure_caldav_routes(cfg: &mut web::ServiceConfig) {
+    let calendar = format!("/calendars/{{user_id}}/{}", CALENDAR_NAME);
+
+    cfg.route("/.well-known/caldav", web::route().to(well_known_handler));
+    cfg.service(
+        web::scope("/dav")
+            .route(
+                "/{tail:.*}",
+                web::method(Method::OPTIONS).to(options_handler),
+            )
+            .route("", dav_method("PROPFIND").to(root_propfind_handler))
+            .route("/", dav_method("PROPFIND").to(root_propfind_handler))
+            .route(
+                "/principals/{user_id}",
+                dav_method("PROPFIND").to(principal_propfind_handler),
+            )
+            .route(
+                "/principals/{user_id}/",
+                dav_method("PROPFIND").to(principal_propfind_handler),
+            )
+            .route(
+                "/calendars/{user_id}",
+                dav_method("PROPFIND").to(home_propfind_handler),
+            )
+            .route(
+                "/calendars/{user_id}/",
+                dav_method("PROPFIND").to(home_propfind_handler),
+            )
+            .route(
+                &calendar,
+                dav_method("PROPFIND").to(calendar_propfind_handler),
+            )
+            .route(
+                &format!("{}/", calendar),
+                dav_method("PROPFIND").to(calendar_propfind_handler),
+            )
+            .route(&calendar, dav_method("REPORT").to(calendar_report_handler))
+            .route(
+                &format!("{}/", calendar),
+                dav_method("REPORT").to(calendar_report_handler),
+            )
+            .route(
+                &format!("{}/{{name}}", calendar),
+                web::get().to(object_get_handler),
+            )
+            .route(
+                &format!("{}/{{name}}", calendar),
+                web::put().to(object_put_handler),
+            )
+            .route(
+                &format!("{}/{{name}}", calendar),
+                web::delete().to(object_delete_handler),
+            )
+            .route(
+                &format!("{}/{{name}}", calendar),
+                dav_method("PROPFIND").to(object_propfind_handler),
+            ),
+    );
+}
@@ -0,0 +1,9 @@
+use crate::handlers::calendar_handler::calendar_feed_handler;
+use actix_web::web;
+
+// Not behind AuthMiddleware: the token in the URL authenticates the feed
+pub fn configure_calendar_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/calendar").route("/{token}.ics", web::get().to(calendar_feed_handler)),
+    );
+}
@@ -1,12 +1,21 @@
 pub mod auth_routes;
+pub mod caldav_routes;
+pub mod calendar_routes;
+pub mod saved_view_routes;
+pub mod sync_routes;
 pub mod todo_routes;
 pub mod user_routes;
+pub mod workflow_routes;
+pub mod ws_routes;
 
 use actix_web::web::{self, ServiceConfig};
 
 use crate::routes::{
-    auth_routes::configure_auth_routes, todo_routes::configure_todo_routes,
-    user_routes::configure_user_routes,
+    auth_routes::configure_auth_routes, caldav_routes::configure_caldav_routes,
+    calendar_routes::configure_calendar_routes, saved_view_routes::configure_saved_view_routes,
+    sync_routes::configure_sync_routes, todo_routes::configure_todo_routes,
+    user_routes::configure_user_routes, workflow_routes::configure_workflow_routes,
+    ws_routes::configure_ws_routes,
 };
 
 pub fn config_routes(cfg: &mut ServiceConfig) {
@@ -14,6 +23,12 @@ pub fn config_routes(cfg: &mut ServiceConfig) {
         web::scope("/api/v1")
             .configure(configure_todo_routes)
             .configure(configure_user_routes)
-            .configure(configure_auth_routes),
+            .configure(configure_workflow_routes)
+            .configure(configure_saved_view_routes)
+            .configure(configure_sync_routes)
+            .configure(configure_auth_routes)
+            .configure(configure_ws_routes)
+            .configure(configure_calendar_routes),
     );
+    cfg.configure(configure_caldav_routes);
 }
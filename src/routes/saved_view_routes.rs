@@ -0,0 +1,23 @@
+use crate::{
+    handlers::saved_view_handler::{
+        create_view_handler, delete_view_handler, get_view_counts_handler, get_view_handler,
+        get_view_todos_handler, get_views_handler, update_view_handler,
+    },
+    middleware::auth_middleware::AuthMiddleware,
+    utils::get_env_vars::get_env_var,
+};
+use actix_web::web;
+
+pub fn configure_saved_view_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/views")
+            .wrap(AuthMiddleware::new(get_env_var("JWT_SECRET")))
+            .route("", web::get().to(get_views_handler))
+            .route("", web::post().to(create_view_handler))
+            .route("/counts", web::get().to(get_view_counts_handler))
+            .route("/{id}", web::get().to(get_view_handler))
+            .route("/{id}", web::put().to(update_view_handler))
+            .route("/{id}", web::delete().to(delete_view_handler))
+            .route("/{id}/todos", web::get().to(get_view_todos_handler)),
+    );
+}
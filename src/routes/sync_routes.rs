@@ -0,0 +1,15 @@
+use crate::{
+    handlers::sync_handler::{get_changes_handler, push_changes_handler},
+    middleware::auth_middleware::AuthMiddleware,
+    utils::get_env_vars::get_env_var,
+};
+use actix_web::web;
+
+pub fn configure_sync_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/sync")
+            .wrap(AuthMiddleware::new(get_env_var("JWT_SECRET")))
+            .route("", web::get().to(get_changes_handler))
+            .route("", web::post().to(push_changes_handler)),
+    );
+}
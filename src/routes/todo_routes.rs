@@ -1,7 +1,26 @@
 use crate::{
-    handlers::todo_handler::{
-        create_todo_handler, delete_todo_handler, get_todo_handler, get_todos_handler,
-        update_todo_handler,
+    handlers::{
+        attachment_handler::{
+            delete_attachment_handler, download_attachment_handler, get_attachments_handler,
+            upload_attachment_handler,
+        },
+        comment_handler::{
+            create_comment_handler, delete_comment_handler, get_comment_handler,
+            get_comments_handler, update_comment_handler,
+        },
+        dependency_handler::{
+            create_dependency_handler, delete_dependency_handler, get_dependency_graph_handler,
+        },
+        event_stream_handler::todo_events_handler,
+        export_handler::export_todos_handler,
+        import_handler::import_todos_handler,
+        todo_handler::{
+            archive_completed_handler, archive_todo_handler, bulk_todos_handler,
+            create_todo_handler, delete_todo_handler, get_todo_handler, get_todo_history_handler,
+            get_todos_handler, get_trash_handler, patch_todo_handler, restore_todo_handler,
+            search_todos_handler, skip_occurrence_handler, stop_recurrence_handler,
+            unarchive_todo_handler, update_todo_handler,
+        },
     },
     middleware::auth_middleware::AuthMiddleware,
     utils::get_env_vars::get_env_var,
@@ -15,9 +34,71 @@ pub fn configure_todo_routes(cfg: &mut web::ServiceConfig) {
                 .wrap(AuthMiddleware::new(get_env_var("JWT_SECRET")))
                 .route("", web::get().to(get_todos_handler))
                 .route("", web::post().to(create_todo_handler))
+                .route("/bulk", web::post().to(bulk_todos_handler))
+                .route("/import", web::post().to(import_todos_handler))
+                .route("/export", web::get().to(export_todos_handler))
+                .route("/search", web::get().to(search_todos_handler))
+                .route("/events", web::get().to(todo_events_handler))
+                .route("/trash", web::get().to(get_trash_handler))
+                .route(
+                    "/archive-completed",
+                    web::post().to(archive_completed_handler),
+                )
                 .route("/{id}", web::get().to(get_todo_handler))
                 .route("/{id}", web::put().to(update_todo_handler))
-                .route("/{id}", web::delete().to(delete_todo_handler)),
+                .route("/{id}", web::patch().to(patch_todo_handler))
+                .route("/{id}", web::delete().to(delete_todo_handler))
+                .route("/{id}/restore", web::post().to(restore_todo_handler))
+                .route("/{id}/archive", web::post().to(archive_todo_handler))
+                .route("/{id}/unarchive", web::post().to(unarchive_todo_handler))
+                .route(
+                    "/{id}/recurrence/skip",
+                    web::post().to(skip_occurrence_handler),
+                )
+                .route(
+                    "/{id}/recurrence/stop",
+                    web::post().to(stop_recurrence_handler),
+                )
+                .route("/{id}/history", web::get().to(get_todo_history_handler))
+                .route(
+                    "/{id}/dependencies",
+                    web::post().to(create_dependency_handler),
+                )
+                .route(
+                    "/{id}/dependencies/{blocked_by_id}",
+                    web::delete().to(delete_dependency_handler),
+                )
+                .route(
+                    "/{id}/dependency-graph",
+                    web::get().to(get_dependency_graph_handler),
+                )
+                .route(
+                    "/{id}/attachments",
+                    web::post().to(upload_attachment_handler),
+                )
+                .route("/{id}/attachments", web::get().to(get_attachments_handler))
+                .route(
+                    "/{id}/attachments/{attachment_id}",
+                    web::get().to(download_attachment_handler),
+                )
+                .route(
+                    "/{id}/attachments/{attachment_id}",
+                    web::delete().to(delete_attachment_handler),
+                )
+                .route("/{id}/comments", web::get().to(get_comments_handler))
+                .route("/{id}/comments", web::post().to(create_comment_handler))
+                .route(
+                    "/{id}/comments/{comment_id}",
+                    web::get().to(get_comment_handler),
+                )
+                .route(
+                    "/{id}/comments/{comment_id}",
+                    web::put().to(update_comment_handler),
+                )
+                .route(
+                    "/{id}/comments/{comment_id}",
+                    web::delete().to(delete_comment_handler),
+                ),
         ),
     );
 }
@@ -1,9 +1,24 @@
 use actix_web::web;
 
 use crate::{
-    handlers::user_handler::{
-        delete_me_handler, delete_user_handler, get_me_handler, get_user_handler,
-        get_users_handler, update_me_handler, update_user_handler,
+    handlers::{
+        account_handler::{
+            cancel_erasure_handler, download_export_handler, get_erasure_handler,
+            get_export_handler, get_exports_handler, request_erasure_handler,
+            request_export_handler,
+        },
+        attachment_handler::get_storage_usage_handler,
+        calendar_handler::{delete_calendar_token_handler, regenerate_calendar_token_handler},
+        user_handler::{
+            delete_me_handler, delete_user_handler, get_me_handler, get_my_settings_handler,
+            get_user_handler, get_users_handler, patch_me_handler, patch_user_handler,
+            update_me_handler, update_my_settings_handler, update_user_handler,
+        },
+        webhook_handler::{
+            create_webhook_handler, delete_webhook_handler, get_deliveries_handler,
+            get_delivery_handler, get_webhook_handler, get_webhooks_handler, test_webhook_handler,
+            update_webhook_handler,
+        },
     },
     middleware::{
         auth_middleware::AuthMiddleware, authorization_middleware::AuthorizationMiddleware,
@@ -17,6 +32,11 @@ pub fn configure_user_routes(cfg: &mut web::ServiceConfig) {
     // Public routes - no authentication
     cfg.service(web::scope("/users/public").route("/health", web::get().to(|| async { "OK" })));
 
+    // Public, authorized by the signed link instead
+    cfg.service(
+        web::scope("/exports").route("/{id}/download", web::get().to(download_export_handler)),
+    );
+
     // Admin-only routes
     cfg.service(
         web::scope("/users/admin")
@@ -25,6 +45,7 @@ pub fn configure_user_routes(cfg: &mut web::ServiceConfig) {
             .route("", web::get().to(get_users_handler))
             .route("/{id}", web::get().to(get_user_handler))
             .route("/{id}", web::put().to(update_user_handler))
+            .route("/{id}", web::patch().to(patch_user_handler))
             .route("/{id}", web::delete().to(delete_user_handler)),
     );
 
@@ -34,6 +55,44 @@ pub fn configure_user_routes(cfg: &mut web::ServiceConfig) {
             .wrap(AuthMiddleware::new(jwt_secret.clone()))
             .route("/me", web::get().to(get_me_handler))
             .route("/me", web::put().to(update_me_handler))
-            .route("/me", web::delete().to(delete_me_handler)),
+            .route("/me", web::patch().to(patch_me_handler))
+            .route("/me", web::delete().to(delete_me_handler))
+            .route("/me/settings", web::get().to(get_my_settings_handler))
+            .route("/me/settings", web::put().to(update_my_settings_handler))
+            .route("/me/export", web::post().to(request_export_handler))
+            .route("/me/exports", web::get().to(get_exports_handler))
+            .route("/me/exports/{id}", web::get().to(get_export_handler))
+            .route("/me/erasure", web::post().to(request_erasure_handler))
+            .route("/me/erasure", web::get().to(get_erasure_handler))
+            .route("/me/erasure", web::delete().to(cancel_erasure_handler))
+            .route("/me/storage", web::get().to(get_storage_usage_handler))
+            .route(
+                "/me/calendar-token",
+                web::post().to(regenerate_calendar_token_handler),
+            )
+            .route(
+                "/me/calendar-token",
+                web::delete().to(delete_calendar_token_handler),
+            )
+            .route("/me/webhooks", web::get().to(get_webhooks_handler))
+            .route("/me/webhooks", web::post().to(create_webhook_handler))
+            .route("/me/webhooks/{id}", web::get().to(get_webhook_handler))
+            .route("/me/webhooks/{id}", web::patch().to(update_webhook_handler))
+            .route(
+                "/me/webhooks/{id}",
+                web::delete().to(delete_webhook_handler),
+            )
+            .route(
+                "/me/webhooks/{id}/test",
+                web::post().to(test_webhook_handler),
+            )
+            .route(
+                "/me/webhooks/{id}/deliveries",
+                web::get().to(get_deliveries_handler),
+            )
+            .route(
+                "/me/webhooks/{id}/deliveries/{delivery_id}",
+                web::get().to(get_delivery_handler),
+            ),
     );
 }
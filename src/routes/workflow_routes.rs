@@ -0,0 +1,21 @@
+use crate::{
+    handlers::workflow_handler::{
+        create_workflow_handler, delete_workflow_handler, get_workflow_handler,
+        get_workflows_handler, update_workflow_handler,
+    },
+    middleware::auth_middleware::AuthMiddleware,
+    utils::get_env_vars::get_env_var,
+};
+use actix_web::web;
+
+pub fn configure_workflow_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/workflows")
+            .wrap(AuthMiddleware::new(get_env_var("JWT_SECRET")))
+            .route("", web::get().to(get_workflows_handler))
+            .route("", web::post().to(create_workflow_handler))
+            .route("/{id}", web::get().to(get_workflow_handler))
+            .route("/{id}", web::put().to(update_workflow_handler))
+            .route("/{id}", web::delete().to(delete_workflow_handler)),
+    );
+}
@@ -0,0 +1,17 @@
+use crate::{
+    handlers::ws_handler::{create_ws_ticket_handler, todo_changes_ws_handler},
+    middleware::auth_middleware::AuthMiddleware,
+    utils::get_env_vars::get_env_var,
+};
+use actix_web::web;
+
+// The upgrade isn't behind AuthMiddleware: the handler checks the token itself
+// so browsers can authenticate with a ticket in the query string
+pub fn configure_ws_routes(cfg: &mut web::ServiceConfig) {
+    cfg.route("/ws", web::get().to(todo_changes_ws_handler))
+        .service(
+            web::resource("/ws/ticket")
+                .wrap(AuthMiddleware::new(get_env_var("JWT_SECRET")))
+                .route(web::post().to(create_ws_ticket_handler)),
+        );
+}
@@ -0,0 +1,301 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use sqlx::MySqlPool;
+use uuid::Uuid;
+
+use crate::models::account_model::{AccountErasure, AccountExport, AccountExportStatus};
+
+#[derive(sqlx::FromRow)]
+struct AccountExportRow {
+    id: String,
+    user_id: String,
+    status: String,
+    storage_key: Option<String>,
+    size_bytes: Option<i64>,
+    error: Option<String>,
+    created_at: Option<DateTime<Utc>>,
+    completed_at: Option<DateTime<Utc>>,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+impl From<AccountExportRow> for AccountExport {
+    fn from(row: AccountExportRow) -> Self {
+        AccountExport {
+            id: row.id,
+            user_id: row.user_id,
+            status: AccountExportStatus::from_db(&row.status),
+            storage_key: row.storage_key,
+            size_bytes: row.size_bytes,
+            error: row.error,
+            created_at: row.created_at.unwrap_or_else(Utc::now),
+            completed_at: row.completed_at,
+            expires_at: row.expires_at,
+        }
+    }
+}
+
+pub async fn create_account_export(pool: &MySqlPool, export: &AccountExport) -> Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO account_exports (id, user_id, status, created_at)
+        VALUES (?, ?, ?, ?)
+        "#,
+        export.id,
+        export.user_id,
+        export.status.as_str(),
+        export.created_at
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_account_exports_by_user(
+    pool: &MySqlPool,
+    user_id: &Uuid,
+) -> Result<Vec<AccountExport>> {
+    let rows = sqlx::query_as!(
+        AccountExportRow,
+        r#"
+        SELECT id, user_id, status, storage_key, size_bytes, error, created_at, completed_at,
+            expires_at
+        FROM account_exports
+        WHERE user_id = ?
+        ORDER BY created_at DESC
+        "#,
+        user_id.to_string()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(AccountExport::from).collect())
+}
+
+pub async fn get_account_export_by_id(
+    pool: &MySqlPool,
+    id: &Uuid,
+) -> Result<Option<AccountExport>> {
+    let row = sqlx::query_as!(
+        AccountExportRow,
+        r#"
+        SELECT id, user_id, status, storage_key, size_bytes, error, created_at, completed_at,
+            expires_at
+        FROM account_exports
+        WHERE id = ?
+        "#,
+        id.to_string()
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(AccountExport::from))
+}
+
+// The user's export that is still queued or being built, if any
+pub async fn get_unfinished_account_export(
+    pool: &MySqlPool,
+    user_id: &Uuid,
+) -> Result<Option<AccountExport>> {
+    let row = sqlx::query_as!(
+        AccountExportRow,
+        r#"
+        SELECT id, user_id, status, storage_key, size_bytes, error, created_at, completed_at,
+            expires_at
+        FROM account_exports
+        WHERE user_id = ? AND status IN ('pending', 'processing')
+        ORDER BY created_at DESC
+        LIMIT 1
+        "#,
+        user_id.to_string()
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(AccountExport::from))
+}
+
+// Takes the oldest queued export. One left processing for longer than
+// `lease` belonged to a worker that died and is taken over.
+pub async fn claim_account_export(
+    pool: &MySqlPool,
+    lease: Duration,
+) -> Result<Option<AccountExport>> {
+    let mut tx = pool.begin().await?;
+    let now = Utc::now();
+
+    let row = sqlx::query_as!(
+        AccountExportRow,
+        r#"
+        SELECT id, user_id, status, storage_key, size_bytes, error, created_at, completed_at,
+            expires_at
+        FROM account_exports
+        WHERE status = 'pending' OR (status = 'processing' AND started_at <= ?)
+        ORDER BY created_at
+        LIMIT 1
+        FOR UPDATE SKIP LOCKED
+        "#,
+        now - lease
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    if let Some(row) = &row {
+        sqlx::query!(
+            "UPDATE account_exports SET status = 'processing', started_at = ? WHERE id = ?",
+            now,
+            row.id
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(row.map(|row| {
+        let mut export = AccountExport::from(row);
+        export.status = AccountExportStatus::Processing;
+        export
+    }))
+}
+
+// False when the export is gone, e.g. because the account was erased while
+// the archive was being built
+pub async fn complete_account_export(
+    pool: &MySqlPool,
+    id: &str,
+    storage_key: &str,
+    size_bytes: i64,
+    expires_at: DateTime<Utc>,
+) -> Result<bool> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE account_exports
+        SET status = 'ready', storage_key = ?, size_bytes = ?, completed_at = ?, expires_at = ?
+        WHERE id = ?
+        "#,
+        storage_key,
+        size_bytes,
+        Utc::now(),
+        expires_at,
+        id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn fail_account_export(pool: &MySqlPool, id: &str, error: &str) -> Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE account_exports
+        SET status = 'failed', error = ?, completed_at = ?
+        WHERE id = ?
+        "#,
+        error,
+        Utc::now(),
+        id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// Ready exports whose download link has expired
+pub async fn get_expired_account_exports(
+    pool: &MySqlPool,
+    now: DateTime<Utc>,
+) -> Result<Vec<AccountExport>> {
+    let rows = sqlx::query_as!(
+        AccountExportRow,
+        r#"
+        SELECT id, user_id, status, storage_key, size_bytes, error, created_at, completed_at,
+            expires_at
+        FROM account_exports
+        WHERE status = 'ready' AND expires_at <= ?
+        "#,
+        now
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(AccountExport::from).collect())
+}
+
+// Called once the archive has been deleted from storage
+pub async fn expire_account_export(pool: &MySqlPool, id: &str) -> Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE account_exports
+        SET status = 'expired', storage_key = NULL
+        WHERE id = ?
+        "#,
+        id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// Keeps an already scheduled erasure, so requesting again doesn't postpone it
+pub async fn schedule_account_erasure(pool: &MySqlPool, erasure: &AccountErasure) -> Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT IGNORE INTO account_erasures (user_id, requested_at, scheduled_for)
+        VALUES (?, ?, ?)
+        "#,
+        erasure.user_id,
+        erasure.requested_at,
+        erasure.scheduled_for
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_account_erasure(
+    pool: &MySqlPool,
+    user_id: &Uuid,
+) -> Result<Option<AccountErasure>> {
+    let row = sqlx::query_as!(
+        AccountErasure,
+        r#"
+        SELECT user_id, requested_at, scheduled_for
+        FROM account_erasures
+        WHERE user_id = ?
+        "#,
+        user_id.to_string()
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row)
+}
+
+pub async fn cancel_account_erasure(pool: &MySqlPool, user_id: &Uuid) -> Result<bool> {
+    let result = sqlx::query!(
+        "DELETE FROM account_erasures WHERE user_id = ?",
+        user_id.to_string()
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn get_due_account_erasures(pool: &MySqlPool, now: DateTime<Utc>) -> Result<Vec<Uuid>> {
+    let rows = sqlx::query!(
+        "SELECT user_id FROM account_erasures WHERE scheduled_for <= ?",
+        now
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter()
+        .map(|row| Uuid::parse_str(&row.user_id).map_err(Into::into))
+        .collect()
+}
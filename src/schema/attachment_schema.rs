@@ -0,0 +1,231 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::MySqlPool;
+use uuid::Uuid;
+
+use crate::models::attachment_model::Attachment;
+
+#[derive(sqlx::FromRow)]
+struct AttachmentRow {
+    id: String,
+    todo_id: String,
+    user_id: String,
+    file_name: String,
+    content_type: String,
+    size_bytes: i64,
+    sha256: String,
+    storage_key: String,
+    created_at: Option<DateTime<Utc>>,
+}
+
+impl From<AttachmentRow> for Attachment {
+    fn from(row: AttachmentRow) -> Self {
+        Attachment {
+            id: row.id,
+            todo_id: row.todo_id,
+            user_id: row.user_id,
+            file_name: row.file_name,
+            content_type: row.content_type,
+            size_bytes: row.size_bytes,
+            sha256: row.sha256,
+            storage_key: row.storage_key,
+            created_at: row.created_at.unwrap_or_else(Utc::now),
+        }
+    }
+}
+
+// Records the attachment unless it would take the user over `quota_bytes`.
+// The user's rows stay locked until commit, so concurrent uploads can't both
+// fit into the same remaining space.
+pub async fn create_attachment(
+    pool: &MySqlPool,
+    attachment: &Attachment,
+    quota_bytes: i64,
+) -> Result<bool> {
+    let mut tx = pool.begin().await?;
+
+    let used = sqlx::query_scalar!(
+        r#"
+        SELECT CAST(COALESCE(SUM(size_bytes), 0) AS SIGNED) AS `used!: i64`
+        FROM todo_attachments
+        WHERE user_id = ?
+        FOR UPDATE
+        "#,
+        attachment.user_id
+    )
+    .fetch_one(&mut *tx)
+    .await?;
+
+    if used + attachment.size_bytes > quota_bytes {
+        tx.rollback().await?;
+        return Ok(false);
+    }
+
+    sqlx::query!(
+        r#"
+        INSERT INTO todo_attachments (id, todo_id, user_id, file_name, content_type, size_bytes,
+            sha256, storage_key, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+        attachment.id,
+        attachment.todo_id,
+        attachment.user_id,
+        attachment.file_name,
+        attachment.content_type,
+        attachment.size_bytes,
+        attachment.sha256,
+        attachment.storage_key,
+        attachment.created_at
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(true)
+}
+
+pub async fn get_storage_used(pool: &MySqlPool, user_id: &Uuid) -> Result<i64> {
+    let used = sqlx::query_scalar!(
+        r#"
+        SELECT CAST(COALESCE(SUM(size_bytes), 0) AS SIGNED) AS `used!: i64`
+        FROM todo_attachments
+        WHERE user_id = ?
+        "#,
+        user_id.to_string()
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(used)
+}
+
+pub async fn get_attachments_by_todo(pool: &MySqlPool, todo_id: &Uuid) -> Result<Vec<Attachment>> {
+    let rows = sqlx::query_as!(
+        AttachmentRow,
+        r#"
+        SELECT id, todo_id, user_id, file_name, content_type, size_bytes, sha256, storage_key,
+            created_at
+        FROM todo_attachments
+        WHERE todo_id = ?
+        ORDER BY created_at, id
+        "#,
+        todo_id.to_string()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(Attachment::from).collect())
+}
+
+// Attachments of the user's todos, trashed ones included; rows waiting for the
+// cleanup job are left out
+pub async fn get_attachments_by_user(pool: &MySqlPool, user_id: &Uuid) -> Result<Vec<Attachment>> {
+    let rows = sqlx::query_as!(
+        AttachmentRow,
+        r#"
+        SELECT a.id, a.todo_id, a.user_id, a.file_name, a.content_type, a.size_bytes, a.sha256,
+            a.storage_key, a.created_at
+        FROM todo_attachments a
+        JOIN todos t ON t.id = a.todo_id
+        WHERE a.user_id = ?
+        ORDER BY a.todo_id, a.created_at, a.id
+        "#,
+        user_id.to_string()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(Attachment::from).collect())
+}
+
+pub async fn get_attachment_by_id(pool: &MySqlPool, id: &Uuid) -> Result<Option<Attachment>> {
+    let row = sqlx::query_as!(
+        AttachmentRow,
+        r#"
+        SELECT id, todo_id, user_id, file_name, content_type, size_bytes, sha256, storage_key,
+            created_at
+        FROM todo_attachments
+        WHERE id = ?
+        "#,
+        id.to_string()
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(Attachment::from))
+}
+
+pub async fn delete_attachment(pool: &MySqlPool, id: &str) -> Result<bool> {
+    let result = sqlx::query!("DELETE FROM todo_attachments WHERE id = ?", id)
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// Removes the attachment and queues its stored file for the cleanup job, so
+// a failing storage backend can't leave a row pointing at a deleted file
+pub async fn remove_attachment(pool: &MySqlPool, id: &str) -> Result<bool> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query!(
+        r#"
+        INSERT IGNORE INTO attachment_file_deletions (storage_key)
+        SELECT storage_key FROM todo_attachments WHERE id = ?
+        "#,
+        id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let result = sqlx::query!("DELETE FROM todo_attachments WHERE id = ?", id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn get_pending_file_deletions(pool: &MySqlPool, limit: i64) -> Result<Vec<String>> {
+    let rows = sqlx::query!(
+        "SELECT storage_key FROM attachment_file_deletions ORDER BY created_at LIMIT ?",
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| row.storage_key).collect())
+}
+
+pub async fn delete_pending_file_deletion(pool: &MySqlPool, storage_key: &str) -> Result<()> {
+    sqlx::query!(
+        "DELETE FROM attachment_file_deletions WHERE storage_key = ?",
+        storage_key
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+// Attachments whose todo has been purged, including by the user's erasure
+pub async fn get_orphaned_attachments(pool: &MySqlPool, limit: i64) -> Result<Vec<Attachment>> {
+    let rows = sqlx::query_as!(
+        AttachmentRow,
+        r#"
+        SELECT a.id, a.todo_id, a.user_id, a.file_name, a.content_type, a.size_bytes, a.sha256,
+            a.storage_key, a.created_at
+        FROM todo_attachments a
+        LEFT JOIN todos t ON t.id = a.todo_id
+        WHERE t.id IS NULL
+        LIMIT ?
+        "#,
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(Attachment::from).collect())
+}
@@ -0,0 +1,143 @@
+use anyhow::Result;
+use sqlx::{MySqlConnection, MySqlPool};
+use uuid::Uuid;
+
+use crate::{
+    models::{caldav_model::DavResource, todo_model::Todo},
+    schema::todo_schema::insert_todo,
+};
+
+pub async fn get_dav_resources_by_user(
+    pool: &MySqlPool,
+    user_id: &Uuid,
+) -> Result<Vec<DavResource>> {
+    let resources = sqlx::query_as!(
+        DavResource,
+        r#"
+        SELECT todo_id, user_id, resource_name, uid
+        FROM caldav_resources
+        WHERE user_id = ?
+        "#,
+        user_id.to_string()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(resources)
+}
+
+pub async fn get_dav_resource_by_name(
+    pool: &MySqlPool,
+    user_id: &Uuid,
+    resource_name: &str,
+) -> Result<Option<DavResource>> {
+    let resource = sqlx::query_as!(
+        DavResource,
+        r#"
+        SELECT todo_id, user_id, resource_name, uid
+        FROM caldav_resources
+        WHERE user_id = ? AND resource_name = ?
+        "#,
+        user_id.to_string(),
+        resource_name
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(resource)
+}
+
+pub async fn get_dav_resource_by_todo(
+    pool: &MySqlPool,
+    todo_id: &str,
+) -> Result<Option<DavResource>> {
+    let resource = sqlx::query_as!(
+        DavResource,
+        r#"
+        SELECT todo_id, user_id, resource_name, uid
+        FROM caldav_resources
+        WHERE todo_id = ?
+        "#,
+        todo_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(resource)
+}
+
+// Creates the todo together with the href and UID it's served under, so a
+// failed resource write doesn't leave a todo the client can't address
+pub async fn create_dav_todo(
+    pool: &MySqlPool,
+    todo: &Todo,
+    resource: Option<&DavResource>,
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    insert_todo(&mut tx, todo).await?;
+    if let Some(resource) = resource {
+        save_dav_resource(&mut tx, resource).await?;
+    }
+    tx.commit().await?;
+
+    Ok(())
+}
+
+// A name left behind by another todo is dropped first so the upsert can only
+// ever conflict on the todo's own row
+async fn save_dav_resource(conn: &mut MySqlConnection, resource: &DavResource) -> Result<()> {
+    sqlx::query!(
+        r#"
+        DELETE FROM caldav_resources
+        WHERE user_id = ? AND resource_name = ? AND todo_id <> ?
+        "#,
+        resource.user_id,
+        resource.resource_name,
+        resource.todo_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO caldav_resources (todo_id, user_id, resource_name, uid)
+        VALUES (?, ?, ?, ?)
+        ON DUPLICATE KEY UPDATE resource_name = VALUES(resource_name), uid = VALUES(uid)
+        "#,
+        resource.todo_id,
+        resource.user_id,
+        resource.resource_name,
+        resource.uid
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(())
+}
+
+// Changes whenever any of the user's todos does: every write bumps a version,
+// trashing keeps the row and purging leaves a tombstone
+pub async fn get_collection_ctag(pool: &MySqlPool, user_id: &Uuid) -> Result<String> {
+    let todos = sqlx::query!(
+        r#"
+        SELECT COUNT(*) AS count, CAST(COALESCE(SUM(version), 0) AS SIGNED) AS version_sum
+        FROM todos
+        WHERE user_id = ?
+        "#,
+        user_id.to_string()
+    )
+    .fetch_one(pool)
+    .await?;
+
+    let tombstones = sqlx::query!(
+        "SELECT COUNT(*) AS count FROM todo_tombstones WHERE user_id = ?",
+        user_id.to_string()
+    )
+    .fetch_one(pool)
+    .await?;
+
+    Ok(format!(
+        "{}-{}-{}",
+        todos.count, todos.version_sum, tombstones.count
+    ))
+}
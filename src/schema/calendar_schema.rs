@@ -0,0 +1,49 @@
+use anyhow::Result;
+use chrono::Utc;
+use sqlx::MySqlPool;
+use uuid::Uuid;
+
+// Replaces the user's feed token, which invalidates the previous URL
+pub async fn set_calendar_feed_token(
+    pool: &MySqlPool,
+    user_id: &Uuid,
+    token_hash: &str,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO calendar_feeds (user_id, token_hash, created_at)
+        VALUES (?, ?, ?)
+        ON DUPLICATE KEY UPDATE token_hash = VALUES(token_hash), created_at = VALUES(created_at)
+        "#,
+        user_id.to_string(),
+        token_hash,
+        Utc::now()
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn delete_calendar_feed_token(pool: &MySqlPool, user_id: &Uuid) -> Result<bool> {
+    let result = sqlx::query!(
+        "DELETE FROM calendar_feeds WHERE user_id = ?",
+        user_id.to_string()
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn get_user_id_by_feed_token(pool: &MySqlPool, token_hash: &str) -> Result<Option<Uuid>> {
+    let row = sqlx::query!(
+        "SELECT user_id FROM calendar_feeds WHERE token_hash = ?",
+        token_hash
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    row.map(|row| Uuid::parse_str(&row.user_id).map_err(Into::into))
+        .transpose()
+}
@@ -0,0 +1,89 @@
+use anyhow::Result;
+use chrono::Utc;
+use sqlx::{MySqlConnection, QueryBuilder};
+
+use crate::models::{
+    change_event_model::{ChangeKind, PendingChange},
+    todo_model::Todo,
+};
+
+// Records a change to the todo for the change relay. Called inside the write's
+// transaction, so the change is relayed exactly when the write commits.
+pub async fn record_change(
+    conn: &mut MySqlConnection,
+    kind: ChangeKind,
+    todo_id: &str,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO todo_change_outbox (todo_id, user_id, kind, created_at)
+        SELECT id, user_id, ?, ? FROM todos WHERE id = ?
+        "#,
+        kind.as_str(),
+        Utc::now(),
+        todo_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+// Records the deletion of a todo whose row is about to go, keeping a copy of it
+pub async fn record_removal(conn: &mut MySqlConnection, todo: &Todo) -> Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO todo_change_outbox (todo_id, user_id, kind, snapshot, created_at)
+        VALUES (?, ?, ?, ?, ?)
+        "#,
+        todo.id,
+        todo.user_id,
+        ChangeKind::Deleted.as_str(),
+        serde_json::to_string(todo)?,
+        Utc::now()
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+// Oldest recorded changes, locked until the caller's transaction ends. Locked
+// rows are skipped, so concurrent relays take separate batches.
+pub async fn claim_pending_changes(
+    conn: &mut MySqlConnection,
+    limit: i64,
+) -> Result<Vec<PendingChange>> {
+    let changes = sqlx::query_as!(
+        PendingChange,
+        r#"
+        SELECT id, todo_id, user_id, kind, snapshot
+        FROM todo_change_outbox
+        ORDER BY id
+        LIMIT ?
+        FOR UPDATE SKIP LOCKED
+        "#,
+        limit
+    )
+    .fetch_all(conn)
+    .await?;
+
+    Ok(changes)
+}
+
+pub async fn delete_pending_changes(conn: &mut MySqlConnection, ids: &[i64]) -> Result<()> {
+    if ids.is_empty() {
+        return Ok(());
+    }
+
+    let mut builder = QueryBuilder::new("DELETE FROM todo_change_outbox WHERE id IN (");
+    let mut separated = builder.separated(", ");
+    for id in ids {
+        separated.push_bind(id);
+    }
+    builder.push(")");
+
+    builder.build().execute(conn).await?;
+
+    Ok(())
+}
@@ -0,0 +1,188 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::{MySqlConnection, MySqlPool, QueryBuilder};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        change_event_model::ChangeKind,
+        comment_model::{Comment, CommentCount},
+    },
+    schema::change_outbox_schema::record_change,
+};
+
+#[derive(sqlx::FromRow)]
+struct CommentRow {
+    id: String,
+    todo_id: String,
+    author_id: String,
+    author_name: String,
+    body: String,
+    created_at: Option<DateTime<Utc>>,
+    edited_at: Option<DateTime<Utc>>,
+}
+
+impl From<CommentRow> for Comment {
+    fn from(row: CommentRow) -> Self {
+        Comment {
+            id: row.id,
+            todo_id: row.todo_id,
+            author_id: row.author_id,
+            author_name: row.author_name,
+            body: row.body,
+            created_at: row.created_at.unwrap_or_else(Utc::now),
+            edited_at: row.edited_at,
+        }
+    }
+}
+
+// The comment count is part of the todo, so adding or removing a comment is a
+// new version of the todo as well
+async fn touch_todo(conn: &mut MySqlConnection, todo_id: &str) -> Result<()> {
+    sqlx::query!(
+        "UPDATE todos SET version = version + 1, updated_at = ? WHERE id = ?",
+        Utc::now(),
+        todo_id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    record_change(conn, ChangeKind::Updated, todo_id).await?;
+
+    Ok(())
+}
+
+pub async fn create_comment(pool: &MySqlPool, comment: &Comment) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO todo_comments (id, todo_id, author_id, body, created_at)
+        VALUES (?, ?, ?, ?, ?)
+        "#,
+        comment.id,
+        comment.todo_id,
+        comment.author_id,
+        comment.body,
+        comment.created_at
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    touch_todo(&mut tx, &comment.todo_id).await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub async fn get_comments_by_todo(pool: &MySqlPool, todo_id: &Uuid) -> Result<Vec<Comment>> {
+    let rows = sqlx::query_as!(
+        CommentRow,
+        r#"
+        SELECT c.id, c.todo_id, c.author_id, u.name AS author_name, c.body, c.created_at,
+            c.edited_at
+        FROM todo_comments c
+        JOIN users u ON u.id = c.author_id
+        WHERE c.todo_id = ?
+        ORDER BY c.created_at, c.id
+        "#,
+        todo_id.to_string()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(Comment::from).collect())
+}
+
+pub async fn get_comments_by_author(pool: &MySqlPool, author_id: &Uuid) -> Result<Vec<Comment>> {
+    let rows = sqlx::query_as!(
+        CommentRow,
+        r#"
+        SELECT c.id, c.todo_id, c.author_id, u.name AS author_name, c.body, c.created_at,
+            c.edited_at
+        FROM todo_comments c
+        JOIN users u ON u.id = c.author_id
+        WHERE c.author_id = ?
+        ORDER BY c.created_at, c.id
+        "#,
+        author_id.to_string()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(Comment::from).collect())
+}
+
+pub async fn get_comment_by_id(pool: &MySqlPool, id: &Uuid) -> Result<Option<Comment>> {
+    let row = sqlx::query_as!(
+        CommentRow,
+        r#"
+        SELECT c.id, c.todo_id, c.author_id, u.name AS author_name, c.body, c.created_at,
+            c.edited_at
+        FROM todo_comments c
+        JOIN users u ON u.id = c.author_id
+        WHERE c.id = ?
+        "#,
+        id.to_string()
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(Comment::from))
+}
+
+pub async fn update_comment(pool: &MySqlPool, comment: &Comment) -> Result<()> {
+    sqlx::query!(
+        "UPDATE todo_comments SET body = ?, edited_at = ? WHERE id = ?",
+        comment.body,
+        comment.edited_at,
+        comment.id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn delete_comment(pool: &MySqlPool, comment: &Comment) -> Result<bool> {
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query!("DELETE FROM todo_comments WHERE id = ?", comment.id)
+        .execute(&mut *tx)
+        .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    touch_todo(&mut tx, &comment.todo_id).await?;
+    tx.commit().await?;
+
+    Ok(true)
+}
+
+// Comment counts of those of the given todos that have comments
+pub async fn get_comment_counts(
+    pool: &MySqlPool,
+    todo_ids: &[String],
+) -> Result<Vec<CommentCount>> {
+    if todo_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut builder = QueryBuilder::new(
+        "SELECT todo_id, COUNT(*) AS count FROM todo_comments WHERE todo_id IN (",
+    );
+    let mut separated = builder.separated(", ");
+    for id in todo_ids {
+        separated.push_bind(id);
+    }
+    builder.push(") GROUP BY todo_id");
+
+    let counts = builder
+        .build_query_as::<CommentCount>()
+        .fetch_all(pool)
+        .await?;
+
+    Ok(counts)
+}
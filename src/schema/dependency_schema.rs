@@ -0,0 +1,215 @@
+use anyhow::Result;
+use chrono::Utc;
+use sqlx::{MySqlConnection, MySqlPool, QueryBuilder};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        change_event_model::ChangeKind,
+        dependency_model::{DependencyEdge, DependencyOutcome, would_create_cycle},
+    },
+    schema::change_outbox_schema::record_change,
+};
+
+// blocked_by and blocking are part of both todos, so adding or removing an
+// edge is a new version of each
+async fn touch_todos(conn: &mut MySqlConnection, edge: &DependencyEdge) -> Result<()> {
+    for todo_id in [&edge.todo_id, &edge.blocked_by_id] {
+        sqlx::query!(
+            "UPDATE todos SET version = version + 1, updated_at = ? WHERE id = ?",
+            Utc::now(),
+            todo_id
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        record_change(conn, ChangeKind::Updated, todo_id).await?;
+    }
+
+    Ok(())
+}
+
+pub async fn get_user_dependencies(
+    pool: &MySqlPool,
+    user_id: &Uuid,
+) -> Result<Vec<DependencyEdge>> {
+    let edges = sqlx::query_as!(
+        DependencyEdge,
+        r#"
+        SELECT d.todo_id, d.blocked_by_id
+        FROM todo_dependencies d
+        JOIN todos t ON t.id = d.todo_id
+        JOIN todos b ON b.id = d.blocked_by_id
+        WHERE t.user_id = ? AND t.deleted_at IS NULL AND b.deleted_at IS NULL
+        "#,
+        user_id.to_string()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(edges)
+}
+
+// Dependencies of the given todos in either direction, between todos that
+// aren't in the trash
+pub async fn get_dependencies_of(
+    pool: &MySqlPool,
+    todo_ids: &[String],
+) -> Result<Vec<DependencyEdge>> {
+    if todo_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut builder = QueryBuilder::new(
+        r#"
+        SELECT d.todo_id, d.blocked_by_id
+        FROM todo_dependencies d
+        JOIN todos t ON t.id = d.todo_id
+        JOIN todos b ON b.id = d.blocked_by_id
+        WHERE t.deleted_at IS NULL AND b.deleted_at IS NULL AND (d.todo_id IN (
+        "#,
+    );
+    let mut separated = builder.separated(", ");
+    for id in todo_ids {
+        separated.push_bind(id);
+    }
+    builder.push(") OR d.blocked_by_id IN (");
+    let mut separated = builder.separated(", ");
+    for id in todo_ids {
+        separated.push_bind(id);
+    }
+    builder.push("))");
+
+    let edges = builder
+        .build_query_as::<DependencyEdge>()
+        .fetch_all(pool)
+        .await?;
+
+    Ok(edges)
+}
+
+// Ids of the blockers of a todo that are not completed yet
+pub async fn get_open_blockers(pool: &MySqlPool, todo_id: &Uuid) -> Result<Vec<String>> {
+    let rows = sqlx::query!(
+        r#"
+        SELECT t.id
+        FROM todo_dependencies d
+        JOIN todos t ON t.id = d.blocked_by_id
+        WHERE d.todo_id = ? AND t.status <> 'completed' AND t.deleted_at IS NULL
+        "#,
+        todo_id.to_string()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(|row| row.id).collect())
+}
+
+// Edges from the given todos to their blockers that are not completed yet
+pub async fn get_open_blockers_of(
+    conn: &mut MySqlConnection,
+    todo_ids: &[String],
+) -> Result<Vec<DependencyEdge>> {
+    if todo_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut builder = QueryBuilder::new(
+        r#"
+        SELECT d.todo_id, d.blocked_by_id
+        FROM todo_dependencies d
+        JOIN todos t ON t.id = d.blocked_by_id
+        WHERE t.status <> 'completed' AND t.deleted_at IS NULL AND d.todo_id IN (
+        "#,
+    );
+    let mut separated = builder.separated(", ");
+    for id in todo_ids {
+        separated.push_bind(id);
+    }
+    builder.push(")");
+
+    let edges = builder
+        .build_query_as::<DependencyEdge>()
+        .fetch_all(conn)
+        .await?;
+
+    Ok(edges)
+}
+
+// Adds the edge unless it would close a cycle. The user's row is locked for the
+// check and the insert, so two requests adding opposite edges can't both see a
+// graph without the other one and pass. Edges of trashed todos stay in the
+// table and come back on restore, so they count towards cycles too.
+pub async fn create_dependency(
+    pool: &MySqlPool,
+    user_id: &Uuid,
+    edge: &DependencyEdge,
+) -> Result<DependencyOutcome> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query!(
+        "SELECT id FROM users WHERE id = ? FOR UPDATE",
+        user_id.to_string()
+    )
+    .fetch_optional(&mut *tx)
+    .await?;
+
+    let edges = sqlx::query_as!(
+        DependencyEdge,
+        r#"
+        SELECT d.todo_id, d.blocked_by_id
+        FROM todo_dependencies d
+        JOIN todos t ON t.id = d.todo_id
+        WHERE t.user_id = ?
+        FOR UPDATE
+        "#,
+        user_id.to_string()
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    if edges.contains(edge) {
+        return Ok(DependencyOutcome::AlreadyExists);
+    }
+    if would_create_cycle(&edges, &edge.todo_id, &edge.blocked_by_id) {
+        return Ok(DependencyOutcome::WouldCreateCycle);
+    }
+
+    let result = sqlx::query!(
+        "INSERT IGNORE INTO todo_dependencies (todo_id, blocked_by_id) VALUES (?, ?)",
+        edge.todo_id,
+        edge.blocked_by_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(DependencyOutcome::AlreadyExists);
+    }
+
+    touch_todos(&mut tx, edge).await?;
+    tx.commit().await?;
+
+    Ok(DependencyOutcome::Created)
+}
+
+pub async fn delete_dependency(pool: &MySqlPool, edge: &DependencyEdge) -> Result<bool> {
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query!(
+        "DELETE FROM todo_dependencies WHERE todo_id = ? AND blocked_by_id = ?",
+        edge.todo_id,
+        edge.blocked_by_id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    touch_todos(&mut tx, edge).await?;
+    tx.commit().await?;
+
+    Ok(true)
+}
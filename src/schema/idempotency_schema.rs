@@ -0,0 +1,138 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::MySqlPool;
+
+use crate::models::idempotency_model::IdempotencyRecord;
+
+pub async fn get_idempotency_record(
+    pool: &MySqlPool,
+    scope: &str,
+    key: &str,
+) -> Result<Option<IdempotencyRecord>> {
+    let record = sqlx::query_as!(
+        IdempotencyRecord,
+        r#"
+        SELECT request_hash, status_code, response_headers, response_body
+        FROM idempotency_keys
+        WHERE scope = ? AND idempotency_key = ? AND expires_at > ?
+        "#,
+        scope,
+        key,
+        Utc::now()
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(record)
+}
+
+// Claims a key for a new request. Returns false if another request holds it.
+// Expired records, and in-progress ones not refreshed since `stale_before`
+// (whose request died), are released first.
+pub async fn reserve_idempotency_key(
+    pool: &MySqlPool,
+    scope: &str,
+    key: &str,
+    request_hash: &str,
+    expires_at: DateTime<Utc>,
+    stale_before: DateTime<Utc>,
+) -> Result<bool> {
+    sqlx::query!(
+        r#"
+        DELETE FROM idempotency_keys
+        WHERE scope = ? AND idempotency_key = ?
+            AND (expires_at <= ? OR (status_code IS NULL AND locked_at < ?))
+        "#,
+        scope,
+        key,
+        Utc::now(),
+        stale_before
+    )
+    .execute(pool)
+    .await?;
+
+    let result = sqlx::query!(
+        r#"
+        INSERT IGNORE INTO idempotency_keys
+            (scope, idempotency_key, request_hash, locked_at, created_at, expires_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+        scope,
+        key,
+        request_hash,
+        Utc::now(),
+        Utc::now(),
+        expires_at
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+// Marks the request holding the key as still running
+pub async fn refresh_idempotency_key(pool: &MySqlPool, scope: &str, key: &str) -> Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE idempotency_keys
+        SET locked_at = ?
+        WHERE scope = ? AND idempotency_key = ? AND status_code IS NULL
+        "#,
+        Utc::now(),
+        scope,
+        key
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn save_idempotent_response(
+    pool: &MySqlPool,
+    scope: &str,
+    key: &str,
+    status_code: i32,
+    headers: &str,
+    body: &[u8],
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+        UPDATE idempotency_keys
+        SET status_code = ?, response_headers = ?, response_body = ?
+        WHERE scope = ? AND idempotency_key = ?
+        "#,
+        status_code,
+        headers,
+        body,
+        scope,
+        key
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn release_idempotency_key(pool: &MySqlPool, scope: &str, key: &str) -> Result<()> {
+    sqlx::query!(
+        "DELETE FROM idempotency_keys WHERE scope = ? AND idempotency_key = ?",
+        scope,
+        key
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn purge_expired_idempotency_keys(pool: &MySqlPool) -> Result<u64> {
+    let result = sqlx::query!(
+        "DELETE FROM idempotency_keys WHERE expires_at <= ?",
+        Utc::now()
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
@@ -1,2 +1,15 @@
+pub mod account_schema;
+pub mod attachment_schema;
+pub mod caldav_schema;
+pub mod calendar_schema;
+pub mod change_outbox_schema;
+pub mod comment_schema;
+pub mod dependency_schema;
+pub mod idempotency_schema;
+pub mod saved_view_schema;
+pub mod todo_event_schema;
 pub mod todo_schema;
 pub mod user_schema;
+pub mod user_settings_schema;
+pub mod webhook_schema;
+pub mod workflow_schema;
@@ -0,0 +1,112 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::MySqlPool;
+use uuid::Uuid;
+
+use crate::models::saved_view_model::{SavedView, ViewDefinition};
+
+#[derive(sqlx::FromRow)]
+struct SavedViewRow {
+    id: String,
+    user_id: String,
+    name: String,
+    definition: String,
+    created_at: Option<DateTime<Utc>>,
+    updated_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<SavedViewRow> for SavedView {
+    type Error = anyhow::Error;
+
+    fn try_from(row: SavedViewRow) -> Result<Self> {
+        let now = Utc::now();
+        let definition: ViewDefinition = serde_json::from_str(&row.definition)?;
+
+        Ok(SavedView {
+            id: row.id,
+            user_id: row.user_id,
+            name: row.name,
+            definition,
+            created_at: row.created_at.unwrap_or(now),
+            updated_at: row.updated_at.unwrap_or(now),
+        })
+    }
+}
+
+pub async fn create_saved_view(pool: &MySqlPool, view: &SavedView) -> Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO saved_views (id, user_id, name, definition, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+        view.id,
+        view.user_id,
+        view.name,
+        serde_json::to_string(&view.definition)?,
+        view.created_at,
+        view.updated_at
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_saved_views_by_user(pool: &MySqlPool, user_id: &Uuid) -> Result<Vec<SavedView>> {
+    let rows = sqlx::query_as!(
+        SavedViewRow,
+        r#"
+        SELECT id, user_id, name, definition, created_at, updated_at
+        FROM saved_views
+        WHERE user_id = ?
+        ORDER BY name
+        "#,
+        user_id.to_string()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter().map(SavedView::try_from).collect()
+}
+
+pub async fn get_saved_view_by_id(pool: &MySqlPool, id: &Uuid) -> Result<Option<SavedView>> {
+    let row = sqlx::query_as!(
+        SavedViewRow,
+        r#"
+        SELECT id, user_id, name, definition, created_at, updated_at
+        FROM saved_views
+        WHERE id = ?
+        "#,
+        id.to_string()
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    row.map(SavedView::try_from).transpose()
+}
+
+pub async fn update_saved_view(pool: &MySqlPool, view: &SavedView) -> Result<Option<SavedView>> {
+    sqlx::query!(
+        r#"
+        UPDATE saved_views
+        SET name = ?, definition = ?, updated_at = ?
+        WHERE id = ?
+        "#,
+        view.name,
+        serde_json::to_string(&view.definition)?,
+        Utc::now(),
+        view.id
+    )
+    .execute(pool)
+    .await?;
+
+    get_saved_view_by_id(pool, &Uuid::parse_str(&view.id)?).await
+}
+
+pub async fn delete_saved_view(pool: &MySqlPool, id: &Uuid) -> Result<bool> {
+    let result = sqlx::query!("DELETE FROM saved_views WHERE id = ?", id.to_string())
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
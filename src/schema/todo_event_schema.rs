@@ -0,0 +1,109 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::{MySqlConnection, MySqlPool, QueryBuilder};
+use uuid::Uuid;
+
+use crate::models::todo_event_model::{FieldChange, StatusTimestamps, TodoEvent};
+
+#[derive(sqlx::FromRow)]
+struct TodoEventRow {
+    id: i64,
+    todo_id: String,
+    actor_id: Option<String>,
+    field: String,
+    old_value: Option<String>,
+    new_value: Option<String>,
+    created_at: Option<DateTime<Utc>>,
+}
+
+impl From<TodoEventRow> for TodoEvent {
+    fn from(row: TodoEventRow) -> Self {
+        TodoEvent {
+            id: row.id,
+            todo_id: row.todo_id,
+            actor_id: row.actor_id,
+            field: row.field,
+            old_value: row.old_value,
+            new_value: row.new_value,
+            created_at: row.created_at.unwrap_or_else(Utc::now),
+        }
+    }
+}
+
+pub async fn record_changes(
+    conn: &mut MySqlConnection,
+    todo_id: &str,
+    actor_id: Option<&str>,
+    changes: &[FieldChange],
+) -> Result<()> {
+    let now = Utc::now();
+
+    for change in changes {
+        sqlx::query!(
+            r#"
+            INSERT INTO todo_events (todo_id, actor_id, field, old_value, new_value, created_at)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+            todo_id,
+            actor_id,
+            change.field,
+            change.old_value,
+            change.new_value,
+            now
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+pub async fn get_todo_events(pool: &MySqlPool, todo_id: &Uuid) -> Result<Vec<TodoEvent>> {
+    let rows = sqlx::query_as!(
+        TodoEventRow,
+        r#"
+        SELECT id, todo_id, actor_id, field, old_value, new_value, created_at
+        FROM todo_events
+        WHERE todo_id = ?
+        ORDER BY created_at, id
+        "#,
+        todo_id.to_string()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(TodoEvent::from).collect())
+}
+
+// Status timestamps of the given todos, those without status events left out
+pub async fn get_status_timestamps(
+    pool: &MySqlPool,
+    todo_ids: &[String],
+) -> Result<Vec<StatusTimestamps>> {
+    if todo_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut builder = QueryBuilder::new(
+        r#"
+        SELECT todo_id,
+            MIN(CASE WHEN new_value IN ('in_process', 'completed') THEN created_at END)
+                AS started_at,
+            MAX(CASE WHEN new_value = 'completed' THEN created_at END) AS completed_at
+        FROM todo_events
+        WHERE field = 'status' AND todo_id IN (
+        "#,
+    );
+    let mut separated = builder.separated(", ");
+    for id in todo_ids {
+        separated.push_bind(id);
+    }
+    builder.push(") GROUP BY todo_id");
+
+    let timestamps = builder
+        .build_query_as::<StatusTimestamps>()
+        .fetch_all(pool)
+        .await?;
+
+    Ok(timestamps)
+}
@@ -1,9 +1,36 @@
+use std::collections::HashMap;
+
 use anyhow::Result;
 use chrono::{DateTime, Utc};
-use sqlx::MySqlPool;
+use sqlx::{Connection, MySql, MySqlConnection, MySqlPool, QueryBuilder};
 use uuid::Uuid;
 
-use crate::models::todo_model::{Todo, TodoStatus, UpdateTodoRequest};
+use crate::{
+    models::{
+        bulk_model::{BulkItemError, BulkMode, BulkOperation, BulkTodoRequest},
+        change_event_model::ChangeKind,
+        comment_model::attach_comment_counts,
+        dependency_model::attach_dependencies,
+        search_model::{SearchCriteria, SearchMode},
+        sync_model::{SyncChange, SyncToken},
+        todo_event_model::{FieldChange, attach_status_timestamps},
+        todo_model::{Todo, TodoPatch, TodoStatus, VersionConflict, starts_work},
+        todo_query_model::{SortOrder, TodoCursor, TodoFilter, TodoPage, TodoSortField},
+        workflow_model::{StatusCategory, Workflow, WorkflowError},
+    },
+    schema::{
+        change_outbox_schema::{record_change, record_removal},
+        comment_schema::get_comment_counts,
+        dependency_schema::{get_dependencies_of, get_open_blockers_of},
+        todo_event_schema::{get_status_timestamps, record_changes},
+        workflow_schema::get_default_workflow,
+    },
+};
+
+// Column list for queries assembled at runtime; keep in sync with TodoRow
+const TODO_COLUMNS: &str = "id, title, description, status, user_id, due_date, recurrence_rule, \
+    series_id, occurrence_index, workflow_status_id, version, archived_at, deleted_at, created_at, \
+    updated_at";
 
 #[derive(sqlx::FromRow)]
 struct TodoRow {
@@ -12,18 +39,28 @@ struct TodoRow {
     description: Option<String>,
     status: String,
     user_id: String,
+    due_date: Option<DateTime<Utc>>,
+    recurrence_rule: Option<String>,
+    series_id: Option<String>,
+    occurrence_index: i32,
+    workflow_status_id: Option<String>,
+    version: i32,
+    archived_at: Option<DateTime<Utc>>,
+    deleted_at: Option<DateTime<Utc>>,
     created_at: Option<DateTime<Utc>>,
     updated_at: Option<DateTime<Utc>>,
 }
 
+#[derive(sqlx::FromRow)]
+struct ScoredTodoRow {
+    #[sqlx(flatten)]
+    todo: TodoRow,
+    relevance: f64,
+}
+
 impl From<TodoRow> for Todo {
     fn from(row: TodoRow) -> Self {
-        let status = match row.status.as_str() {
-            "pending" => TodoStatus::Pending,
-            "in_process" => TodoStatus::InProcess,
-            "completed" => TodoStatus::Completed,
-            _ => TodoStatus::Pending,
-        };
+        let status = TodoStatus::from_db(&row.status);
 
         let now = Utc::now();
         let created_at = row.created_at.unwrap_or(now);
@@ -35,45 +72,93 @@ impl From<TodoRow> for Todo {
             description: row.description,
             status,
             user_id: row.user_id,
+            due_date: row.due_date,
+            recurrence_rule: row.recurrence_rule,
+            series_id: row.series_id,
+            occurrence_index: row.occurrence_index,
+            workflow_status_id: row.workflow_status_id,
+            version: row.version,
+            blocked_by: Vec::new(),
+            blocking: Vec::new(),
+            comment_count: 0,
+            started_at: None,
+            completed_at: None,
+            archived_at: row.archived_at,
+            deleted_at: row.deleted_at,
             created_at,
             updated_at,
         }
     }
 }
 
+// The todo and the event opening its history are written together
 pub async fn create_todo(pool: &MySqlPool, todo: &Todo) -> Result<()> {
-    let status_str = match todo.status {
-        TodoStatus::Pending => "pending",
-        TodoStatus::InProcess => "in_process",
-        TodoStatus::Completed => "completed",
-    };
+    let mut tx = pool.begin().await?;
+    insert_todo(&mut tx, todo).await?;
+    tx.commit().await?;
 
+    Ok(())
+}
+
+// Inserts all of the todos or, if any insert fails, none of them
+pub async fn create_todos(pool: &MySqlPool, todos: &[Todo]) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    for todo in todos {
+        insert_todo(&mut tx, todo).await?;
+    }
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub async fn insert_todo(conn: &mut MySqlConnection, todo: &Todo) -> Result<()> {
     sqlx::query!(
         r#"
-        INSERT INTO todos (id, title, description, status, user_id, created_at, updated_at)
-        VALUES (?, ?, ?, ?, ?, ?, ?)
+        INSERT INTO todos (id, title, description, status, user_id, due_date, recurrence_rule,
+            series_id, occurrence_index, workflow_status_id, version, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         "#,
         todo.id,
         todo.title,
         todo.description,
-        status_str,
+        todo.status.as_str(),
         todo.user_id,
+        todo.due_date,
+        todo.recurrence_rule,
+        todo.series_id,
+        todo.occurrence_index,
+        todo.workflow_status_id,
+        todo.version,
         todo.created_at,
         todo.updated_at
     )
-    .execute(pool)
+    .execute(&mut *conn)
+    .await?;
+
+    // The initial status opens the todo's timeline
+    let created = FieldChange::diff("status", None, Some(todo.status.as_str().to_string()));
+    record_changes(
+        &mut *conn,
+        &todo.id,
+        Some(&todo.user_id),
+        created.as_slice(),
+    )
     .await?;
+    record_change(conn, ChangeKind::Created, &todo.id).await?;
 
     Ok(())
 }
 
-pub async fn get_all_todos(pool: &MySqlPool, user_id: &Uuid) -> Result<Vec<Todo>> {
+// Active and archived todos together, for exports such as the calendar feed
+pub async fn get_todos_including_archived(pool: &MySqlPool, user_id: &Uuid) -> Result<Vec<Todo>> {
     let rows = sqlx::query_as!(
         TodoRow,
         r#"
-        SELECT id, title, description, status, user_id, created_at, updated_at
+        SELECT id, title, description, status, user_id, due_date, recurrence_rule, series_id,
+            occurrence_index, workflow_status_id, version, archived_at, deleted_at, created_at,
+            updated_at
         FROM todos
-        WHERE user_id = ?
+        WHERE user_id = ? AND deleted_at IS NULL
         ORDER BY created_at DESC
         "#,
         user_id.to_string()
@@ -81,7 +166,9 @@ pub async fn get_all_todos(pool: &MySqlPool, user_id: &Uuid) -> Result<Vec<Todo>
     .fetch_all(pool)
     .await?;
 
-    let todos = rows.into_iter().map(Todo::from).collect();
+    let mut todos: Vec<Todo> = rows.into_iter().map(Todo::from).collect();
+    attach_details(pool, &mut todos).await?;
+
     Ok(todos)
 }
 
@@ -89,8 +176,332 @@ pub async fn get_todo_by_id(pool: &MySqlPool, id: &Uuid) -> Result<Option<Todo>>
     let row = sqlx::query_as!(
         TodoRow,
         r#"
-        SELECT id, title, description, status, user_id, created_at, updated_at 
-        FROM todos 
+        SELECT id, title, description, status, user_id, due_date, recurrence_rule, series_id,
+            occurrence_index, workflow_status_id, version, archived_at, deleted_at, created_at,
+            updated_at
+        FROM todos
+        WHERE id = ? AND deleted_at IS NULL
+        "#,
+        id.to_string()
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    let Some(row) = row else {
+        return Ok(None);
+    };
+
+    let mut todo = Todo::from(row);
+    attach_details(pool, std::slice::from_mut(&mut todo)).await?;
+
+    Ok(Some(todo))
+}
+
+fn sort_expression(sort: TodoSortField) -> &'static str {
+    match sort {
+        TodoSortField::CreatedAt => "created_at",
+        TodoSortField::UpdatedAt => "updated_at",
+        TodoSortField::Title => "title",
+        // Compare ENUM positions rather than the labels
+        TodoSortField::Status => "(status + 0)",
+    }
+}
+
+// Escapes LIKE wildcards so user input only ever matches literally
+pub fn like_pattern(text: &str) -> String {
+    let escaped = text
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+fn push_filter(builder: &mut QueryBuilder<'_, MySql>, user_id: &Uuid, filter: &TodoFilter) {
+    builder
+        .push(" WHERE user_id = ")
+        .push_bind(user_id.to_string())
+        .push(" AND deleted_at IS NULL");
+
+    builder.push(if filter.archived {
+        " AND archived_at IS NOT NULL"
+    } else {
+        " AND archived_at IS NULL"
+    });
+
+    if !filter.statuses.is_empty() {
+        builder.push(" AND status IN (");
+        let mut separated = builder.separated(", ");
+        for status in &filter.statuses {
+            separated.push_bind(status.as_str());
+        }
+        builder.push(")");
+    }
+
+    if let Some(q) = &filter.q {
+        let pattern = like_pattern(q);
+        builder
+            .push(" AND (title LIKE ")
+            .push_bind(pattern.clone())
+            .push(" OR description LIKE ")
+            .push_bind(pattern)
+            .push(")");
+    }
+
+    if let Some(created_after) = filter.created_after {
+        builder.push(" AND created_at >= ").push_bind(created_after);
+    }
+    if let Some(created_before) = filter.created_before {
+        builder.push(" AND created_at < ").push_bind(created_before);
+    }
+    if let Some(updated_after) = filter.updated_after {
+        builder.push(" AND updated_at >= ").push_bind(updated_after);
+    }
+    if let Some(updated_before) = filter.updated_before {
+        builder.push(" AND updated_at < ").push_bind(updated_before);
+    }
+}
+
+// Keyset condition: rows strictly after the cursor in (sort key, id) order
+fn push_cursor(builder: &mut QueryBuilder<'_, MySql>, cursor: &TodoCursor) -> Result<()> {
+    let expression = sort_expression(cursor.sort);
+    let comparison = match cursor.order {
+        SortOrder::Asc => " > ",
+        SortOrder::Desc => " < ",
+    };
+
+    for round in 0..2 {
+        builder.push(if round == 0 { " AND (" } else { " OR (" });
+        builder.push(expression);
+        builder.push(if round == 0 { comparison } else { " = " });
+
+        match cursor.sort {
+            TodoSortField::CreatedAt | TodoSortField::UpdatedAt => {
+                builder.push_bind(DateTime::parse_from_rfc3339(&cursor.value)?.with_timezone(&Utc));
+            }
+            TodoSortField::Title => {
+                builder.push_bind(cursor.value.clone());
+            }
+            TodoSortField::Status => {
+                builder.push_bind(cursor.value.parse::<i64>()?);
+            }
+        }
+    }
+
+    builder
+        .push(" AND id")
+        .push(comparison)
+        .push_bind(cursor.id.clone())
+        .push("))");
+
+    Ok(())
+}
+
+// One page of a user's todos matching `filter`, using keyset pagination
+pub async fn list_todos(
+    pool: &MySqlPool,
+    user_id: &Uuid,
+    filter: &TodoFilter,
+    limit: u32,
+    cursor: Option<&TodoCursor>,
+) -> Result<TodoPage> {
+    let mut builder = QueryBuilder::new(format!("SELECT {} FROM todos", TODO_COLUMNS));
+    push_filter(&mut builder, user_id, filter);
+
+    if let Some(cursor) = cursor {
+        push_cursor(&mut builder, cursor)?;
+    }
+
+    let direction = match filter.order {
+        SortOrder::Asc => "ASC",
+        SortOrder::Desc => "DESC",
+    };
+    builder
+        .push(format!(
+            " ORDER BY {} {}, id {}",
+            sort_expression(filter.sort),
+            direction,
+            direction
+        ))
+        .push(" LIMIT ")
+        .push_bind(limit as i64 + 1);
+
+    let rows = builder.build_query_as::<TodoRow>().fetch_all(pool).await?;
+
+    let mut todos: Vec<Todo> = rows.into_iter().map(Todo::from).collect();
+    let has_more = todos.len() > limit as usize;
+    todos.truncate(limit as usize);
+    attach_details(pool, &mut todos).await?;
+
+    let next_cursor = todos
+        .last()
+        .filter(|_| has_more)
+        .map(|last| TodoCursor::after(last, filter).encode());
+
+    Ok(TodoPage {
+        items: todos,
+        next_cursor,
+    })
+}
+
+pub async fn count_todos(pool: &MySqlPool, user_id: &Uuid, filter: &TodoFilter) -> Result<i64> {
+    let mut builder = QueryBuilder::new("SELECT COUNT(*) FROM todos");
+    push_filter(&mut builder, user_id, filter);
+
+    let count = builder.build_query_scalar::<i64>().fetch_one(pool).await?;
+
+    Ok(count)
+}
+
+// Fills in the derived fields of the todos, reading only what concerns them
+async fn attach_details(pool: &MySqlPool, todos: &mut [Todo]) -> Result<()> {
+    let ids: Vec<String> = todos.iter().map(|todo| todo.id.clone()).collect();
+
+    let edges = get_dependencies_of(pool, &ids).await?;
+    attach_dependencies(todos, &edges);
+    let timestamps = get_status_timestamps(pool, &ids).await?;
+    attach_status_timestamps(todos, &timestamps);
+    let counts = get_comment_counts(pool, &ids).await?;
+    attach_comment_counts(todos, &counts);
+
+    Ok(())
+}
+
+// Ranked FULLTEXT search over a user's todos, archived ones included
+pub async fn search_todos_fulltext(
+    pool: &MySqlPool,
+    user_id: &Uuid,
+    q: &str,
+    mode: SearchMode,
+    limit: u32,
+) -> Result<Vec<(Todo, f64)>> {
+    let against = match mode {
+        SearchMode::Natural => "IN NATURAL LANGUAGE MODE",
+        SearchMode::Boolean => "IN BOOLEAN MODE",
+    };
+
+    let sql = format!(
+        r#"
+        SELECT {columns}, MATCH(title, description) AGAINST (? {against}) AS relevance
+        FROM todos
+        WHERE user_id = ? AND deleted_at IS NULL
+            AND MATCH(title, description) AGAINST (? {against})
+        ORDER BY relevance DESC, updated_at DESC
+        LIMIT ?
+        "#,
+        columns = TODO_COLUMNS,
+        against = against
+    );
+
+    let rows = sqlx::query_as::<_, ScoredTodoRow>(&sql)
+        .bind(q)
+        .bind(user_id.to_string())
+        .bind(q)
+        .bind(limit as i64)
+        .fetch_all(pool)
+        .await?;
+
+    let relevances: Vec<f64> = rows.iter().map(|row| row.relevance).collect();
+    let mut todos: Vec<Todo> = rows.into_iter().map(|row| Todo::from(row.todo)).collect();
+    attach_details(pool, &mut todos).await?;
+
+    Ok(todos.into_iter().zip(relevances).collect())
+}
+
+// LIKE-based fallback for databases without the FULLTEXT index, matching
+// todos the way FULLTEXT would (see SearchCriteria). Relevance counts the terms
+// found, title ones double.
+pub async fn search_todos_like(
+    pool: &MySqlPool,
+    user_id: &Uuid,
+    criteria: &SearchCriteria,
+    limit: u32,
+) -> Result<Vec<(Todo, f64)>> {
+    fn push_match(builder: &mut QueryBuilder<'_, MySql>, term: &str) {
+        let pattern = like_pattern(term);
+        builder
+            .push("(title LIKE ")
+            .push_bind(pattern.clone())
+            .push(" OR COALESCE(description, '') LIKE ")
+            .push_bind(pattern)
+            .push(")");
+    }
+
+    let mut builder = QueryBuilder::new(format!("SELECT {}, CAST(0", TODO_COLUMNS));
+    for term in criteria.required.iter().chain(&criteria.optional) {
+        let pattern = like_pattern(term);
+        builder
+            .push(" + (title LIKE ")
+            .push_bind(pattern.clone())
+            .push(") * 2 + (COALESCE(description, '') LIKE ")
+            .push_bind(pattern)
+            .push(")");
+    }
+    builder
+        .push(" AS DOUBLE) AS relevance FROM todos WHERE user_id = ")
+        .push_bind(user_id.to_string())
+        .push(" AND deleted_at IS NULL");
+
+    for term in &criteria.required {
+        builder.push(" AND ");
+        push_match(&mut builder, term);
+    }
+    for term in &criteria.excluded {
+        builder.push(" AND NOT ");
+        push_match(&mut builder, term);
+    }
+    if criteria.required.is_empty() {
+        builder.push(" AND (FALSE");
+        for term in &criteria.optional {
+            builder.push(" OR ");
+            push_match(&mut builder, term);
+        }
+        builder.push(")");
+    }
+
+    builder
+        .push(" ORDER BY relevance DESC, updated_at DESC LIMIT ")
+        .push_bind(limit as i64);
+
+    let rows = builder
+        .build_query_as::<ScoredTodoRow>()
+        .fetch_all(pool)
+        .await?;
+
+    let relevances: Vec<f64> = rows.iter().map(|row| row.relevance).collect();
+    let mut todos: Vec<Todo> = rows.into_iter().map(|row| Todo::from(row.todo)).collect();
+    attach_details(pool, &mut todos).await?;
+
+    Ok(todos.into_iter().zip(relevances).collect())
+}
+
+pub async fn get_trashed_todos(pool: &MySqlPool, user_id: &Uuid) -> Result<Vec<Todo>> {
+    let rows = sqlx::query_as!(
+        TodoRow,
+        r#"
+        SELECT id, title, description, status, user_id, due_date, recurrence_rule, series_id,
+            occurrence_index, workflow_status_id, version, archived_at, deleted_at, created_at,
+            updated_at
+        FROM todos
+        WHERE user_id = ? AND deleted_at IS NOT NULL
+        ORDER BY deleted_at DESC
+        "#,
+        user_id.to_string()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(Todo::from).collect())
+}
+
+// Looks a todo up whether or not it is in the trash
+pub async fn get_todo_by_id_with_trashed(pool: &MySqlPool, id: &Uuid) -> Result<Option<Todo>> {
+    let row = sqlx::query_as!(
+        TodoRow,
+        r#"
+        SELECT id, title, description, status, user_id, due_date, recurrence_rule, series_id,
+            occurrence_index, workflow_status_id, version, archived_at, deleted_at, created_at,
+            updated_at
+        FROM todos
         WHERE id = ?
         "#,
         id.to_string()
@@ -101,54 +512,838 @@ pub async fn get_todo_by_id(pool: &MySqlPool, id: &Uuid) -> Result<Option<Todo>>
     Ok(row.map(Todo::from))
 }
 
+// The given todos, trashed ones included, in no particular order
+pub async fn get_todos_by_ids_with_trashed(
+    pool: &MySqlPool,
+    todo_ids: &[String],
+) -> Result<Vec<Todo>> {
+    if todo_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut builder =
+        QueryBuilder::new(format!("SELECT {} FROM todos WHERE id IN (", TODO_COLUMNS));
+    let mut separated = builder.separated(", ");
+    for id in todo_ids {
+        separated.push_bind(id);
+    }
+    builder.push(")");
+
+    let rows = builder.build_query_as::<TodoRow>().fetch_all(pool).await?;
+
+    let mut todos: Vec<Todo> = rows.into_iter().map(Todo::from).collect();
+    attach_details(pool, &mut todos).await?;
+
+    Ok(todos)
+}
+
+// Applies `patch` to `todo` as it was read; fails with VersionConflict if the
+// todo has been written since
 pub async fn update_todo(
     pool: &MySqlPool,
-    id: &Uuid,
-    update_data: &UpdateTodoRequest,
+    todo: &Todo,
+    patch: &TodoPatch,
+    actor_id: &Uuid,
 ) -> Result<Option<Todo>> {
+    let workflow = get_default_workflow(pool, &todo.user_id).await?;
+
+    let mut tx = pool.begin().await?;
+    apply_update(&mut tx, todo, workflow.as_ref(), patch, actor_id).await?;
+    tx.commit().await?;
+
+    get_todo_by_id(pool, &Uuid::parse_str(&todo.id)?).await
+}
+
+// Writes an update of `todo` (as currently stored) and its history on `conn`.
+// With a custom workflow the status moves through it (and its transition rules);
+// todos.status then follows the category of the new custom status.
+async fn apply_update(
+    conn: &mut MySqlConnection,
+    todo: &Todo,
+    workflow: Option<&Workflow>,
+    patch: &TodoPatch,
+    actor_id: &Uuid,
+) -> Result<()> {
     let now = Utc::now();
 
-    let current_todo = get_todo_by_id(pool, id).await?;
+    let title = patch.title.apply(Some(&todo.title)).unwrap_or(&todo.title);
+    let description = patch.description.apply(todo.description.as_ref());
+    let due_date = patch.due_date.apply(todo.due_date.as_ref()).copied();
+    let recurrence_rule = patch.recurrence_rule.apply(todo.recurrence_rule.as_ref());
+    // A todo that starts recurring becomes the first occurrence of its own series
+    let series_id = todo
+        .series_id
+        .clone()
+        .or_else(|| recurrence_rule.map(|_| todo.id.clone()));
+
+    let (status, workflow_status_id) = match workflow {
+        Some(workflow) => match workflow.resolve(
+            workflow.current_status(todo.workflow_status_id.as_deref(), &todo.status),
+            patch.workflow_status.as_value().map(String::as_str),
+            patch.status.as_value(),
+        )? {
+            Some(target) => (target.category.to_todo_status(), Some(target.id.clone())),
+            None => (todo.status.clone(), todo.workflow_status_id.clone()),
+        },
+        None if !patch.workflow_status.is_absent() => {
+            return Err(WorkflowError::NoDefaultWorkflow.into());
+        }
+        None => (
+            patch.status.as_value().unwrap_or(&todo.status).clone(),
+            None,
+        ),
+    };
+
+    let rfc3339 = |date: Option<DateTime<Utc>>| date.map(|d| d.to_rfc3339());
+    let changes: Vec<FieldChange> = [
+        FieldChange::diff("title", Some(todo.title.clone()), Some(title.clone())),
+        FieldChange::diff(
+            "description",
+            todo.description.clone(),
+            description.cloned(),
+        ),
+        FieldChange::diff(
+            "status",
+            Some(todo.status.as_str().to_string()),
+            Some(status.as_str().to_string()),
+        ),
+        FieldChange::diff(
+            "workflow_status",
+            todo.workflow_status_id.clone(),
+            workflow_status_id.clone(),
+        ),
+        FieldChange::diff("due_date", rfc3339(todo.due_date), rfc3339(due_date)),
+        FieldChange::diff(
+            "recurrence_rule",
+            todo.recurrence_rule.clone(),
+            recurrence_rule.cloned(),
+        ),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+
+    let result = sqlx::query!(
+        r#"
+        UPDATE todos
+        SET title = ?, description = ?, status = ?, due_date = ?, recurrence_rule = ?,
+            series_id = ?, workflow_status_id = ?, version = version + 1, updated_at = ?
+        WHERE id = ? AND version = ?
+        "#,
+        title,
+        description,
+        status.as_str(),
+        due_date,
+        recurrence_rule,
+        series_id,
+        workflow_status_id,
+        now,
+        todo.id,
+        todo.version
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    // Someone else wrote the todo since it was read
+    if result.rows_affected() == 0 {
+        return Err(VersionConflict.into());
+    }
 
-    if let Some(todo) = current_todo {
-        let title = update_data.title.as_ref().unwrap_or(&todo.title);
-        let description = update_data
-            .description
-            .as_ref()
-            .or(todo.description.as_ref());
-        let status = update_data.status.as_ref().unwrap_or(&todo.status);
+    record_changes(&mut *conn, &todo.id, Some(&actor_id.to_string()), &changes).await?;
+    record_change(&mut *conn, ChangeKind::Updated, &todo.id).await?;
 
-        let status_str = match status {
-            TodoStatus::Pending => "pending",
-            TodoStatus::InProcess => "in_process",
-            TodoStatus::Completed => "completed",
+    // Completing an occurrence of a recurring todo schedules the next one
+    if status == TodoStatus::Completed && todo.status != TodoStatus::Completed {
+        let completed = Todo {
+            title: title.clone(),
+            description: description.cloned(),
+            due_date,
+            recurrence_rule: recurrence_rule.cloned(),
+            series_id,
+            ..todo.clone()
         };
 
+        if let Some(mut next) = completed.next_occurrence() {
+            next.workflow_status_id = workflow
+                .and_then(|w| w.initial_status(StatusCategory::Todo))
+                .map(|s| s.id.clone());
+
+            if !has_later_occurrence(&mut *conn, &completed).await? {
+                insert_todo(&mut *conn, &next).await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Guards against re-completing an occurrence spawning a duplicate of its successor.
+// A trashed successor doesn't count, or trashing it would end the series.
+async fn has_later_occurrence(conn: &mut MySqlConnection, todo: &Todo) -> Result<bool> {
+    let result = sqlx::query!(
+        r#"
+        SELECT id FROM todos
+        WHERE series_id = ? AND occurrence_index > ? AND deleted_at IS NULL
+        LIMIT 1
+        "#,
+        todo.series_id,
+        todo.occurrence_index
+    )
+    .fetch_optional(conn)
+    .await?;
+
+    Ok(result.is_some())
+}
+
+pub async fn reschedule_occurrence(
+    pool: &MySqlPool,
+    todo: &Todo,
+    due_date: DateTime<Utc>,
+    actor_id: &Uuid,
+) -> Result<Option<Todo>> {
+    let mut tx = pool.begin().await?;
+
+    let result = sqlx::query!(
+        r#"
+        UPDATE todos
+        SET due_date = ?, occurrence_index = ?, version = version + 1, updated_at = ?
+        WHERE id = ?
+        "#,
+        due_date,
+        todo.occurrence_index + 1,
+        Utc::now(),
+        todo.id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Ok(None);
+    }
+
+    let change = FieldChange::diff(
+        "due_date",
+        todo.due_date.map(|d| d.to_rfc3339()),
+        Some(due_date.to_rfc3339()),
+    );
+    record_changes(
+        &mut tx,
+        &todo.id,
+        Some(&actor_id.to_string()),
+        change.as_slice(),
+    )
+    .await?;
+    record_change(&mut tx, ChangeKind::Updated, &todo.id).await?;
+
+    tx.commit().await?;
+
+    get_todo_by_id(pool, &Uuid::parse_str(&todo.id)?).await
+}
+
+// Ends the series: every occurrence still carrying the rule loses it, and each
+// of them records the change in its history
+pub async fn stop_recurrence(
+    pool: &MySqlPool,
+    todo: &Todo,
+    actor_id: &Uuid,
+) -> Result<Option<Todo>> {
+    let series_id = todo.series_id.as_ref().unwrap_or(&todo.id);
+
+    let mut tx = pool.begin().await?;
+
+    let occurrences = sqlx::query!(
+        r#"
+        SELECT id, recurrence_rule AS `recurrence_rule!`
+        FROM todos
+        WHERE (series_id = ? OR id = ?) AND recurrence_rule IS NOT NULL
+        FOR UPDATE
+        "#,
+        series_id,
+        todo.id
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    let now = Utc::now();
+    for occurrence in &occurrences {
         sqlx::query!(
             r#"
             UPDATE todos
-            SET title = ?, description = ?, status = ?, updated_at = ?
+            SET recurrence_rule = NULL, version = version + 1, updated_at = ?
             WHERE id = ?
             "#,
-            title,
-            description,
-            status_str,
             now,
-            id.to_string()
+            occurrence.id
         )
-        .execute(pool)
+        .execute(&mut *tx)
         .await?;
 
-        return get_todo_by_id(pool, id).await;
+        let change = FieldChange::diff(
+            "recurrence_rule",
+            Some(occurrence.recurrence_rule.clone()),
+            None,
+        );
+        record_changes(
+            &mut tx,
+            &occurrence.id,
+            Some(&actor_id.to_string()),
+            change.as_slice(),
+        )
+        .await?;
+        record_change(&mut tx, ChangeKind::Updated, &occurrence.id).await?;
+    }
+
+    tx.commit().await?;
+
+    get_todo_by_id(pool, &Uuid::parse_str(&todo.id)?).await
+}
+
+pub async fn archive_todo(pool: &MySqlPool, todo: &Todo, actor_id: &Uuid) -> Result<Option<Todo>> {
+    let now = Utc::now();
+    set_archived_at(pool, todo, Some(now), actor_id).await
+}
+
+pub async fn unarchive_todo(
+    pool: &MySqlPool,
+    todo: &Todo,
+    actor_id: &Uuid,
+) -> Result<Option<Todo>> {
+    set_archived_at(pool, todo, None, actor_id).await
+}
+
+async fn set_archived_at(
+    pool: &MySqlPool,
+    todo: &Todo,
+    archived_at: Option<DateTime<Utc>>,
+    actor_id: &Uuid,
+) -> Result<Option<Todo>> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query!(
+        "UPDATE todos SET archived_at = ?, version = version + 1, updated_at = ? WHERE id = ?",
+        archived_at,
+        Utc::now(),
+        todo.id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let change = FieldChange::diff(
+        "archived_at",
+        todo.archived_at.map(|d| d.to_rfc3339()),
+        archived_at.map(|d| d.to_rfc3339()),
+    );
+    record_changes(
+        &mut tx,
+        &todo.id,
+        Some(&actor_id.to_string()),
+        change.as_slice(),
+    )
+    .await?;
+    record_change(&mut tx, ChangeKind::Updated, &todo.id).await?;
+
+    tx.commit().await?;
+
+    get_todo_by_id(pool, &Uuid::parse_str(&todo.id)?).await
+}
+
+// Archives every active completed todo of a user, returning how many were archived
+pub async fn archive_completed_todos(pool: &MySqlPool, user_id: &Uuid) -> Result<u64> {
+    let now = Utc::now();
+    let mut tx = pool.begin().await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO todo_events (todo_id, actor_id, field, old_value, new_value, created_at)
+        SELECT id, ?, 'archived_at', NULL, ?, ?
+        FROM todos
+        WHERE user_id = ? AND status = 'completed' AND archived_at IS NULL AND deleted_at IS NULL
+        "#,
+        user_id.to_string(),
+        now.to_rfc3339(),
+        now,
+        user_id.to_string()
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO todo_change_outbox (todo_id, user_id, kind, created_at)
+        SELECT id, user_id, ?, ?
+        FROM todos
+        WHERE user_id = ? AND status = 'completed' AND archived_at IS NULL AND deleted_at IS NULL
+        "#,
+        ChangeKind::Updated.as_str(),
+        now,
+        user_id.to_string()
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let result = sqlx::query!(
+        r#"
+        UPDATE todos
+        SET archived_at = ?, version = version + 1, updated_at = ?
+        WHERE user_id = ? AND status = 'completed' AND archived_at IS NULL AND deleted_at IS NULL
+        "#,
+        now,
+        now,
+        user_id.to_string()
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(result.rows_affected())
+}
+
+// Archives completed todos of users with auto-archiving enabled once they have
+// been completed for the user's configured number of days. The clock starts at
+// the last completion, so editing a completed todo doesn't reset it; todos
+// completed before history was kept fall back to updated_at.
+pub async fn auto_archive_completed_todos(pool: &MySqlPool) -> Result<u64> {
+    let now = Utc::now();
+    let mut tx = pool.begin().await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO todo_events (todo_id, actor_id, field, old_value, new_value, created_at)
+        SELECT t.id, NULL, 'archived_at', NULL, ?, ?
+        FROM todos t
+        JOIN user_settings s ON s.user_id = t.user_id
+        WHERE s.auto_archive_days IS NOT NULL
+            AND t.status = 'completed' AND t.archived_at IS NULL AND t.deleted_at IS NULL
+            AND COALESCE(
+                (SELECT MAX(e.created_at) FROM todo_events e
+                 WHERE e.todo_id = t.id AND e.field = 'status' AND e.new_value = 'completed'),
+                t.updated_at
+            ) < ? - INTERVAL s.auto_archive_days DAY
+        "#,
+        now.to_rfc3339(),
+        now,
+        now
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO todo_change_outbox (todo_id, user_id, kind, created_at)
+        SELECT t.id, t.user_id, ?, ?
+        FROM todos t
+        JOIN user_settings s ON s.user_id = t.user_id
+        WHERE s.auto_archive_days IS NOT NULL
+            AND t.status = 'completed' AND t.archived_at IS NULL AND t.deleted_at IS NULL
+            AND COALESCE(
+                (SELECT MAX(e.created_at) FROM todo_events e
+                 WHERE e.todo_id = t.id AND e.field = 'status' AND e.new_value = 'completed'),
+                t.updated_at
+            ) < ? - INTERVAL s.auto_archive_days DAY
+        "#,
+        ChangeKind::Updated.as_str(),
+        now,
+        now
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let result = sqlx::query!(
+        r#"
+        UPDATE todos t
+        JOIN user_settings s ON s.user_id = t.user_id
+        SET t.archived_at = ?, t.version = t.version + 1, t.updated_at = ?
+        WHERE s.auto_archive_days IS NOT NULL
+            AND t.status = 'completed' AND t.archived_at IS NULL AND t.deleted_at IS NULL
+            AND COALESCE(
+                (SELECT MAX(e.created_at) FROM todo_events e
+                 WHERE e.todo_id = t.id AND e.field = 'status' AND e.new_value = 'completed'),
+                t.updated_at
+            ) < ? - INTERVAL s.auto_archive_days DAY
+        "#,
+        now,
+        now,
+        now
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(result.rows_affected())
+}
+
+// Moves a todo to the trash; it can be restored until it is purged
+pub async fn delete_todo(pool: &MySqlPool, todo: &Todo, actor_id: &Uuid) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    trash_todo(&mut tx, todo, actor_id).await?;
+    tx.commit().await?;
+
+    Ok(())
+}
+
+async fn trash_todo(conn: &mut MySqlConnection, todo: &Todo, actor_id: &Uuid) -> Result<()> {
+    let now = Utc::now();
+
+    let result = sqlx::query!(
+        r#"
+        UPDATE todos SET deleted_at = ?, version = version + 1, updated_at = ?
+        WHERE id = ? AND version = ? AND deleted_at IS NULL
+        "#,
+        now,
+        now,
+        todo.id,
+        todo.version
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(VersionConflict.into());
+    }
+
+    let change = FieldChange::diff("deleted_at", None, Some(now.to_rfc3339()));
+    record_changes(
+        &mut *conn,
+        &todo.id,
+        Some(&actor_id.to_string()),
+        change.as_slice(),
+    )
+    .await?;
+    record_change(&mut *conn, ChangeKind::Deleted, &todo.id).await?;
+
+    Ok(())
+}
+
+// Runs a bulk request in one transaction. The todos are loaded and locked up
+// front, so every operation is computed from the row it is applied to. Each
+// item gets a savepoint so that in best-effort mode a failing item is rolled
+// back alone; in atomic mode the first failure rolls back everything and the
+// remaining items are not attempted. Outcomes follow the order of `ids`.
+pub async fn bulk_apply(
+    pool: &MySqlPool,
+    user_id: &Uuid,
+    ids: &[Uuid],
+    request: &BulkTodoRequest,
+    workflow: Option<&Workflow>,
+    actor_id: &Uuid,
+) -> Result<Vec<Result<()>>> {
+    let atomic = request.mode == BulkMode::AllOrNothing;
+    let ids: Vec<String> = ids.iter().map(Uuid::to_string).collect();
+    let mut tx = pool.begin().await?;
+
+    let mut builder = QueryBuilder::new(format!(
+        "SELECT {} FROM todos WHERE user_id = ",
+        TODO_COLUMNS
+    ));
+    builder
+        .push_bind(user_id.to_string())
+        .push(" AND deleted_at IS NULL AND id IN (");
+    let mut separated = builder.separated(", ");
+    for id in &ids {
+        separated.push_bind(id);
+    }
+    builder.push(") FOR UPDATE");
+
+    let mut todos: HashMap<String, Todo> = builder
+        .build_query_as::<TodoRow>()
+        .fetch_all(&mut *tx)
+        .await?
+        .into_iter()
+        .map(|row| (row.id.clone(), Todo::from(row)))
+        .collect();
+
+    let requested_status = request.action.requested_status(workflow);
+    let open_blockers = match requested_status {
+        Some(_) if !request.force => get_open_blockers_of(&mut tx, &ids).await?,
+        _ => Vec::new(),
+    };
+    let mut blockers_by_todo: HashMap<&str, Vec<&str>> = HashMap::new();
+    for edge in &open_blockers {
+        blockers_by_todo
+            .entry(edge.todo_id.as_str())
+            .or_default()
+            .push(edge.blocked_by_id.as_str());
+    }
+
+    let prepared: Vec<Result<(Todo, BulkOperation)>> = ids
+        .iter()
+        .map(|id| {
+            let todo = todos
+                .remove(id)
+                .ok_or_else(|| BulkItemError("Todo not found".to_string()))?;
+
+            if starts_work(&todo, requested_status.as_ref()) {
+                if let Some(blockers) = blockers_by_todo.get(id.as_str()) {
+                    let message = format!("Todo is blocked by {}", blockers.join(", "));
+                    return Err(BulkItemError(message).into());
+                }
+            }
+
+            let operation = request.action.operation_for(todo.description.as_deref());
+            Ok((todo, operation))
+        })
+        .collect();
+
+    if atomic && prepared.iter().any(|item| item.is_err()) {
+        tx.rollback().await?;
+        return Ok(prepared.into_iter().map(|item| item.map(|_| ())).collect());
+    }
+
+    let mut outcomes = Vec::with_capacity(prepared.len());
+    for item in prepared {
+        let (todo, operation) = match item {
+            Ok(item) => item,
+            Err(err) => {
+                outcomes.push(Err(err));
+                continue;
+            }
+        };
+
+        let mut savepoint = tx.begin().await?;
+
+        let outcome = match &operation {
+            BulkOperation::Update(patch) => {
+                apply_update(&mut savepoint, &todo, workflow, patch, actor_id).await
+            }
+            BulkOperation::Delete => trash_todo(&mut savepoint, &todo, actor_id).await,
+        };
+
+        let failed = outcome.is_err();
+        if failed {
+            savepoint.rollback().await?;
+        } else {
+            savepoint.commit().await?;
+        }
+        outcomes.push(outcome);
+
+        if failed && atomic {
+            tx.rollback().await?;
+            return Ok(outcomes);
+        }
     }
 
-    Ok(None)
+    tx.commit().await?;
+
+    Ok(outcomes)
+}
+
+pub async fn restore_todo(pool: &MySqlPool, todo: &Todo, actor_id: &Uuid) -> Result<Option<Todo>> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query!(
+        "UPDATE todos SET deleted_at = NULL, version = version + 1, updated_at = ? WHERE id = ?",
+        Utc::now(),
+        todo.id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let change = FieldChange::diff("deleted_at", todo.deleted_at.map(|d| d.to_rfc3339()), None);
+    record_changes(
+        &mut tx,
+        &todo.id,
+        Some(&actor_id.to_string()),
+        change.as_slice(),
+    )
+    .await?;
+    record_change(&mut tx, ChangeKind::Updated, &todo.id).await?;
+
+    tx.commit().await?;
+
+    get_todo_by_id(pool, &Uuid::parse_str(&todo.id)?).await
 }
 
-pub async fn delete_todo(pool: &MySqlPool, id: &Uuid) -> Result<bool> {
-    let result = sqlx::query!("DELETE FROM todos WHERE id = ?", id.to_string())
-        .execute(pool)
+// Permanent deletes leave a tombstone behind for delta sync. Clients already
+// dropped a trashed todo, so only purging a live one is relayed as a change.
+pub async fn purge_todo(pool: &MySqlPool, todo: &Todo) -> Result<bool> {
+    let mut tx = pool.begin().await?;
+
+    if todo.deleted_at.is_none() {
+        record_removal(&mut tx, todo).await?;
+    }
+
+    sqlx::query!(
+        r#"
+        INSERT IGNORE INTO todo_tombstones (todo_id, user_id, deleted_at)
+        SELECT id, user_id, ? FROM todos WHERE id = ?
+        "#,
+        Utc::now(),
+        todo.id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let result = sqlx::query!("DELETE FROM todos WHERE id = ?", todo.id)
+        .execute(&mut *tx)
         .await?;
 
+    tx.commit().await?;
+
     Ok(result.rows_affected() > 0)
 }
+
+pub async fn purge_trash(pool: &MySqlPool, deleted_before: DateTime<Utc>) -> Result<u64> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query!(
+        r#"
+        INSERT IGNORE INTO todo_tombstones (todo_id, user_id, deleted_at)
+        SELECT id, user_id, ? FROM todos WHERE deleted_at IS NOT NULL AND deleted_at < ?
+        "#,
+        Utc::now(),
+        deleted_before
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    let result = sqlx::query!(
+        "DELETE FROM todos WHERE deleted_at IS NOT NULL AND deleted_at < ?",
+        deleted_before
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(result.rows_affected())
+}
+
+pub async fn purge_tombstones(pool: &MySqlPool, deleted_before: DateTime<Utc>) -> Result<u64> {
+    let result = sqlx::query!(
+        "DELETE FROM todo_tombstones WHERE deleted_at < ?",
+        deleted_before
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
+
+// Position of the most recent change up to `horizon`, i.e. the token a client
+// holds once it has seen everything
+pub async fn get_latest_change_token(
+    pool: &MySqlPool,
+    user_id: &Uuid,
+    horizon: DateTime<Utc>,
+) -> Result<SyncToken> {
+    let todo = sqlx::query!(
+        r#"
+        SELECT id, updated_at AS `updated_at!`
+        FROM todos
+        WHERE user_id = ? AND updated_at <= ?
+        ORDER BY updated_at DESC, id DESC
+        LIMIT 1
+        "#,
+        user_id.to_string(),
+        horizon
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    let tombstone = sqlx::query!(
+        r#"
+        SELECT todo_id, deleted_at
+        FROM todo_tombstones
+        WHERE user_id = ? AND deleted_at <= ?
+        ORDER BY deleted_at DESC, todo_id DESC
+        LIMIT 1
+        "#,
+        user_id.to_string(),
+        horizon
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    let candidates = [
+        todo.map(|row| SyncToken {
+            changed_at: row.updated_at,
+            id: row.id,
+        }),
+        tombstone.map(|row| SyncToken {
+            changed_at: row.deleted_at,
+            id: row.todo_id,
+        }),
+    ];
+
+    Ok(candidates
+        .into_iter()
+        .flatten()
+        .max_by(|a, b| (a.changed_at, &a.id).cmp(&(b.changed_at, &b.id)))
+        .unwrap_or_else(SyncToken::start))
+}
+
+// Changes to a user's todos after `after` and up to `horizon`, in (time, id)
+// order: live and trashed todos by updated_at, purged ones by tombstone time.
+// Returns at most `limit` + 1 changes so callers can tell if more remain.
+pub async fn get_todo_changes(
+    pool: &MySqlPool,
+    user_id: &Uuid,
+    after: &SyncToken,
+    horizon: DateTime<Utc>,
+    limit: u32,
+) -> Result<Vec<SyncChange>> {
+    let sql = format!(
+        r#"
+        SELECT {}
+        FROM todos
+        WHERE user_id = ? AND updated_at <= ?
+            AND (updated_at > ? OR (updated_at = ? AND id > ?))
+        ORDER BY updated_at, id
+        LIMIT ?
+        "#,
+        TODO_COLUMNS
+    );
+    let rows = sqlx::query_as::<_, TodoRow>(&sql)
+        .bind(user_id.to_string())
+        .bind(horizon)
+        .bind(after.changed_at)
+        .bind(after.changed_at)
+        .bind(&after.id)
+        .bind(limit as i64 + 1)
+        .fetch_all(pool)
+        .await?;
+
+    let tombstones = sqlx::query!(
+        r#"
+        SELECT todo_id, deleted_at
+        FROM todo_tombstones
+        WHERE user_id = ? AND deleted_at <= ?
+            AND (deleted_at > ? OR (deleted_at = ? AND todo_id > ?))
+        ORDER BY deleted_at, todo_id
+        LIMIT ?
+        "#,
+        user_id.to_string(),
+        horizon,
+        after.changed_at,
+        after.changed_at,
+        after.id,
+        limit as i64 + 1
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut todos: Vec<Todo> = rows.into_iter().map(Todo::from).collect();
+    attach_details(pool, &mut todos).await?;
+
+    let mut changes: Vec<SyncChange> = todos
+        .into_iter()
+        .map(|todo| match todo.deleted_at {
+            Some(_) => SyncChange::Delete {
+                id: todo.id,
+                deleted_at: todo.updated_at,
+            },
+            None => SyncChange::Upsert { todo },
+        })
+        .chain(tombstones.into_iter().map(|row| SyncChange::Delete {
+            id: row.todo_id,
+            deleted_at: row.deleted_at,
+        }))
+        .collect();
+
+    changes.sort_by(|a, b| a.position().cmp(&b.position()));
+    changes.truncate(limit as usize + 1);
+
+    Ok(changes)
+}
@@ -155,11 +155,36 @@ pub async fn update_user(
     Ok(None)
 }
 
+// Erases the user and their personal data:
+// - todos are deleted, and with them their history and dependencies
+// - idempotency records scoped to the user are deleted
+// - todos take their comments with them, and deleting the user row cascades
+//   to settings, workflows, saved views, webhooks and their deliveries,
+//   comments the user wrote elsewhere, calendar feeds, CalDAV resources, sync
+//   tombstones, account exports and the erasure request; callers delete the
+//   stored export archives first
+// - history entries the user made on other users' todos keep the change but
+//   lose the actor (ON DELETE SET NULL)
+// - attachment files and rows are left to the attachment cleanup job, which
+//   picks them up once their todos are gone
 pub async fn delete_user(pool: &MySqlPool, id: &Uuid) -> Result<bool> {
-    let result = sqlx::query!("DELETE FROM users WHERE id = ?", id.to_string())
-        .execute(pool)
+    let mut tx = pool.begin().await?;
+    let user_id = id.to_string();
+
+    sqlx::query!("DELETE FROM idempotency_keys WHERE scope = ?", user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query!("DELETE FROM todos WHERE user_id = ?", user_id)
+        .execute(&mut *tx)
         .await?;
 
+    let result = sqlx::query!("DELETE FROM users WHERE id = ?", user_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+
     Ok(result.rows_affected() > 0)
 }
 
@@ -0,0 +1,38 @@
+use anyhow::Result;
+use sqlx::MySqlPool;
+use uuid::Uuid;
+
+use crate::models::user_settings_model::UserSettings;
+
+// Users without a settings row get the defaults
+pub async fn get_user_settings(pool: &MySqlPool, user_id: &Uuid) -> Result<UserSettings> {
+    let settings = sqlx::query_as!(
+        UserSettings,
+        "SELECT auto_archive_days FROM user_settings WHERE user_id = ?",
+        user_id.to_string()
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(settings.unwrap_or_default())
+}
+
+pub async fn save_user_settings(
+    pool: &MySqlPool,
+    user_id: &Uuid,
+    settings: &UserSettings,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO user_settings (user_id, auto_archive_days)
+        VALUES (?, ?)
+        ON DUPLICATE KEY UPDATE auto_archive_days = VALUES(auto_archive_days)
+        "#,
+        user_id.to_string(),
+        settings.auto_archive_days
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
@@ -0,0 +1,347 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Utc};
+use sqlx::{MySqlConnection, MySqlPool};
+use uuid::Uuid;
+
+use crate::models::webhook_model::{
+    AttemptOutcome, DeliveryAttempt, DeliveryStatus, Webhook, WebhookDelivery, WebhookEvent,
+};
+
+#[derive(sqlx::FromRow)]
+struct WebhookRow {
+    id: String,
+    user_id: String,
+    url: String,
+    secret: String,
+    events: String,
+    active: bool,
+    created_at: Option<DateTime<Utc>>,
+    updated_at: Option<DateTime<Utc>>,
+}
+
+impl TryFrom<WebhookRow> for Webhook {
+    type Error = anyhow::Error;
+
+    fn try_from(row: WebhookRow) -> Result<Self> {
+        let now = Utc::now();
+
+        Ok(Webhook {
+            id: row.id,
+            user_id: row.user_id,
+            url: row.url,
+            secret: row.secret,
+            events: serde_json::from_str(&row.events)?,
+            active: row.active,
+            created_at: row.created_at.unwrap_or(now),
+            updated_at: row.updated_at.unwrap_or(now),
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct DeliveryRow {
+    id: String,
+    webhook_id: String,
+    event_type: String,
+    payload: String,
+    status: String,
+    attempts: i32,
+    next_attempt_at: DateTime<Utc>,
+    last_response_code: Option<i32>,
+    last_error: Option<String>,
+    delivered_at: Option<DateTime<Utc>>,
+    created_at: Option<DateTime<Utc>>,
+}
+
+impl From<DeliveryRow> for WebhookDelivery {
+    fn from(row: DeliveryRow) -> Self {
+        WebhookDelivery {
+            id: row.id,
+            webhook_id: row.webhook_id,
+            event_type: WebhookEvent::from_db(&row.event_type),
+            payload: row.payload,
+            status: DeliveryStatus::from_db(&row.status),
+            attempts: row.attempts,
+            next_attempt_at: row.next_attempt_at,
+            last_response_code: row.last_response_code,
+            last_error: row.last_error,
+            delivered_at: row.delivered_at,
+            created_at: row.created_at.unwrap_or_else(Utc::now),
+        }
+    }
+}
+
+pub async fn create_webhook(pool: &MySqlPool, webhook: &Webhook) -> Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO webhooks (id, user_id, url, secret, events, active, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+        webhook.id,
+        webhook.user_id,
+        webhook.url,
+        webhook.secret,
+        serde_json::to_string(&webhook.events)?,
+        webhook.active,
+        webhook.created_at,
+        webhook.updated_at
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_webhooks_by_user(pool: &MySqlPool, user_id: &Uuid) -> Result<Vec<Webhook>> {
+    let rows = sqlx::query_as!(
+        WebhookRow,
+        r#"
+        SELECT id, user_id, url, secret, events, active AS `active: bool`, created_at, updated_at
+        FROM webhooks
+        WHERE user_id = ?
+        ORDER BY created_at
+        "#,
+        user_id.to_string()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    rows.into_iter().map(Webhook::try_from).collect()
+}
+
+pub async fn get_webhook_by_id(pool: &MySqlPool, id: &Uuid) -> Result<Option<Webhook>> {
+    let row = sqlx::query_as!(
+        WebhookRow,
+        r#"
+        SELECT id, user_id, url, secret, events, active AS `active: bool`, created_at, updated_at
+        FROM webhooks
+        WHERE id = ?
+        "#,
+        id.to_string()
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    row.map(Webhook::try_from).transpose()
+}
+
+pub async fn update_webhook(pool: &MySqlPool, webhook: &Webhook) -> Result<Option<Webhook>> {
+    sqlx::query!(
+        r#"
+        UPDATE webhooks
+        SET url = ?, secret = ?, events = ?, active = ?, updated_at = ?
+        WHERE id = ?
+        "#,
+        webhook.url,
+        webhook.secret,
+        serde_json::to_string(&webhook.events)?,
+        webhook.active,
+        Utc::now(),
+        webhook.id
+    )
+    .execute(pool)
+    .await?;
+
+    get_webhook_by_id(pool, &Uuid::parse_str(&webhook.id)?).await
+}
+
+pub async fn delete_webhook(pool: &MySqlPool, id: &Uuid) -> Result<bool> {
+    let result = sqlx::query!("DELETE FROM webhooks WHERE id = ?", id.to_string())
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn enqueue_delivery(
+    conn: &mut MySqlConnection,
+    delivery: &WebhookDelivery,
+) -> Result<()> {
+    sqlx::query!(
+        r#"
+        INSERT INTO webhook_deliveries
+            (id, webhook_id, event_type, payload, status, attempts, next_attempt_at, created_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        "#,
+        delivery.id,
+        delivery.webhook_id,
+        delivery.event_type.as_str(),
+        delivery.payload,
+        delivery.status.as_str(),
+        delivery.attempts,
+        delivery.next_attempt_at,
+        delivery.created_at
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+// Takes up to `limit` due deliveries and pushes their next attempt out by
+// `lease`, so other workers leave them alone while they are being sent. If
+// the worker dies the lease runs out and the delivery is retried.
+pub async fn claim_due_deliveries(
+    pool: &MySqlPool,
+    limit: i64,
+    lease: Duration,
+) -> Result<Vec<WebhookDelivery>> {
+    let mut tx = pool.begin().await?;
+    let now = Utc::now();
+
+    let rows = sqlx::query_as!(
+        DeliveryRow,
+        r#"
+        SELECT id, webhook_id, event_type, payload, status, attempts, next_attempt_at,
+            last_response_code, last_error, delivered_at, created_at
+        FROM webhook_deliveries
+        WHERE status = 'pending' AND next_attempt_at <= ?
+        ORDER BY next_attempt_at
+        LIMIT ?
+        FOR UPDATE SKIP LOCKED
+        "#,
+        now,
+        limit
+    )
+    .fetch_all(&mut *tx)
+    .await?;
+
+    for row in &rows {
+        sqlx::query!(
+            "UPDATE webhook_deliveries SET next_attempt_at = ? WHERE id = ?",
+            now + lease,
+            row.id
+        )
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(rows.into_iter().map(WebhookDelivery::from).collect())
+}
+
+// Stores the delivery's new state together with the attempt that led to it
+pub async fn record_delivery_attempt(
+    pool: &MySqlPool,
+    delivery: &WebhookDelivery,
+    outcome: &AttemptOutcome,
+    attempted_at: DateTime<Utc>,
+) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO webhook_delivery_attempts
+            (delivery_id, attempt, response_code, error, duration_ms, attempted_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+        delivery.id,
+        delivery.attempts,
+        outcome.response_code,
+        outcome.error,
+        outcome.duration_ms,
+        attempted_at
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE webhook_deliveries
+        SET status = ?, attempts = ?, next_attempt_at = ?, last_response_code = ?,
+            last_error = ?, delivered_at = ?
+        WHERE id = ?
+        "#,
+        delivery.status.as_str(),
+        delivery.attempts,
+        delivery.next_attempt_at,
+        delivery.last_response_code,
+        delivery.last_error,
+        delivery.delivered_at,
+        delivery.id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub async fn get_deliveries_by_webhook(
+    pool: &MySqlPool,
+    webhook_id: &Uuid,
+    limit: i64,
+) -> Result<Vec<WebhookDelivery>> {
+    let rows = sqlx::query_as!(
+        DeliveryRow,
+        r#"
+        SELECT id, webhook_id, event_type, payload, status, attempts, next_attempt_at,
+            last_response_code, last_error, delivered_at, created_at
+        FROM webhook_deliveries
+        WHERE webhook_id = ?
+        ORDER BY created_at DESC
+        LIMIT ?
+        "#,
+        webhook_id.to_string(),
+        limit
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows.into_iter().map(WebhookDelivery::from).collect())
+}
+
+pub async fn get_delivery_by_id(pool: &MySqlPool, id: &Uuid) -> Result<Option<WebhookDelivery>> {
+    let row = sqlx::query_as!(
+        DeliveryRow,
+        r#"
+        SELECT id, webhook_id, event_type, payload, status, attempts, next_attempt_at,
+            last_response_code, last_error, delivered_at, created_at
+        FROM webhook_deliveries
+        WHERE id = ?
+        "#,
+        id.to_string()
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(WebhookDelivery::from))
+}
+
+pub async fn get_delivery_attempts(
+    pool: &MySqlPool,
+    delivery_id: &Uuid,
+) -> Result<Vec<DeliveryAttempt>> {
+    let attempts = sqlx::query_as!(
+        DeliveryAttempt,
+        r#"
+        SELECT attempt, response_code, error, duration_ms, attempted_at
+        FROM webhook_delivery_attempts
+        WHERE delivery_id = ?
+        ORDER BY attempt
+        "#,
+        delivery_id.to_string()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(attempts)
+}
+
+// Deliveries still queued for a webhook that was switched off are given up
+pub async fn cancel_pending_deliveries(pool: &MySqlPool, webhook_id: &str) -> Result<u64> {
+    let result = sqlx::query!(
+        r#"
+        UPDATE webhook_deliveries
+        SET status = 'failed', last_error = 'Webhook was deactivated'
+        WHERE webhook_id = ? AND status = 'pending'
+        "#,
+        webhook_id
+    )
+    .execute(pool)
+    .await?;
+
+    Ok(result.rows_affected())
+}
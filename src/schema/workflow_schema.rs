@@ -0,0 +1,334 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::{MySqlConnection, MySqlPool};
+use uuid::Uuid;
+
+use crate::{
+    models::{
+        change_event_model::ChangeKind,
+        todo_event_model::FieldChange,
+        workflow_model::{StatusCategory, Workflow, WorkflowStatus, WorkflowTransition},
+    },
+    schema::{change_outbox_schema::record_change, todo_event_schema::record_changes},
+};
+
+#[derive(sqlx::FromRow)]
+struct WorkflowRow {
+    id: String,
+    user_id: String,
+    name: String,
+    is_default: bool,
+    created_at: Option<DateTime<Utc>>,
+    updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(sqlx::FromRow)]
+struct WorkflowStatusRow {
+    id: String,
+    status_key: String,
+    name: String,
+    category: String,
+    position: i32,
+}
+
+impl From<WorkflowStatusRow> for WorkflowStatus {
+    fn from(row: WorkflowStatusRow) -> Self {
+        WorkflowStatus {
+            id: row.id,
+            key: row.status_key,
+            name: row.name,
+            category: StatusCategory::from_db(&row.category),
+            position: row.position,
+        }
+    }
+}
+
+async fn load_workflow(pool: &MySqlPool, row: WorkflowRow) -> Result<Workflow> {
+    let statuses = sqlx::query_as!(
+        WorkflowStatusRow,
+        r#"
+        SELECT id, status_key, name, category, position
+        FROM workflow_statuses
+        WHERE workflow_id = ?
+        ORDER BY position
+        "#,
+        row.id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let transitions = sqlx::query_as!(
+        WorkflowTransition,
+        r#"
+        SELECT from_key AS `from`, to_key AS `to`
+        FROM workflow_transitions
+        WHERE workflow_id = ?
+        "#,
+        row.id
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let now = Utc::now();
+
+    Ok(Workflow {
+        id: row.id,
+        user_id: row.user_id,
+        name: row.name,
+        is_default: row.is_default,
+        statuses: statuses.into_iter().map(WorkflowStatus::from).collect(),
+        transitions,
+        created_at: row.created_at.unwrap_or(now),
+        updated_at: row.updated_at.unwrap_or(now),
+    })
+}
+
+// todos.status mirrors the category of a todo's workflow status, so when a
+// status changes category its todos move along, as a new version of each
+async fn move_todos_to_category(
+    conn: &mut MySqlConnection,
+    status: &WorkflowStatus,
+    actor_id: &str,
+) -> Result<()> {
+    let todo_status = status.category.to_todo_status();
+
+    let todos = sqlx::query!(
+        r#"
+        SELECT id, status AS `status: String`
+        FROM todos
+        WHERE workflow_status_id = ? AND status <> ?
+        FOR UPDATE
+        "#,
+        status.id,
+        todo_status.as_str()
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for todo in todos {
+        sqlx::query!(
+            "UPDATE todos SET status = ?, version = version + 1, updated_at = ? WHERE id = ?",
+            todo_status.as_str(),
+            Utc::now(),
+            todo.id
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        let change = FieldChange {
+            field: "status",
+            old_value: Some(todo.status),
+            new_value: Some(todo_status.as_str().to_string()),
+        };
+        record_changes(&mut *conn, &todo.id, Some(actor_id), &[change]).await?;
+        record_change(&mut *conn, ChangeKind::Updated, &todo.id).await?;
+    }
+
+    Ok(())
+}
+
+// Writes the status list and transitions of a workflow, replacing any existing ones.
+// Statuses are upserted by id so todos referencing a kept status aren't detached.
+async fn save_statuses(conn: &mut MySqlConnection, workflow: &Workflow) -> Result<()> {
+    let status_ids: Vec<&str> = workflow.statuses.iter().map(|s| s.id.as_str()).collect();
+
+    let existing = sqlx::query!(
+        "SELECT id, category FROM workflow_statuses WHERE workflow_id = ?",
+        workflow.id
+    )
+    .fetch_all(&mut *conn)
+    .await?;
+
+    for row in &existing {
+        if !status_ids.contains(&row.id.as_str()) {
+            sqlx::query!("DELETE FROM workflow_statuses WHERE id = ?", row.id)
+                .execute(&mut *conn)
+                .await?;
+        }
+    }
+
+    for status in &workflow.statuses {
+        let recategorized = existing.iter().any(|row| {
+            row.id == status.id && StatusCategory::from_db(&row.category) != status.category
+        });
+
+        sqlx::query!(
+            r#"
+            INSERT INTO workflow_statuses (id, workflow_id, status_key, name, category, position)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON DUPLICATE KEY UPDATE name = VALUES(name), category = VALUES(category),
+                position = VALUES(position)
+            "#,
+            status.id,
+            workflow.id,
+            status.key,
+            status.name,
+            status.category.as_str(),
+            status.position
+        )
+        .execute(&mut *conn)
+        .await?;
+
+        if recategorized {
+            move_todos_to_category(&mut *conn, status, &workflow.user_id).await?;
+        }
+    }
+
+    sqlx::query!(
+        "DELETE FROM workflow_transitions WHERE workflow_id = ?",
+        workflow.id
+    )
+    .execute(&mut *conn)
+    .await?;
+
+    for transition in &workflow.transitions {
+        sqlx::query!(
+            "INSERT IGNORE INTO workflow_transitions (workflow_id, from_key, to_key) VALUES (?, ?, ?)",
+            workflow.id,
+            transition.from,
+            transition.to
+        )
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    Ok(())
+}
+
+// A user has at most one default workflow
+async fn clear_default(conn: &mut MySqlConnection, user_id: &str, keep_id: &str) -> Result<()> {
+    sqlx::query!(
+        "UPDATE workflows SET is_default = FALSE WHERE user_id = ? AND id <> ?",
+        user_id,
+        keep_id
+    )
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn create_workflow(pool: &MySqlPool, workflow: &Workflow) -> Result<()> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query!(
+        r#"
+        INSERT INTO workflows (id, user_id, name, is_default, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        "#,
+        workflow.id,
+        workflow.user_id,
+        workflow.name,
+        workflow.is_default,
+        workflow.created_at,
+        workflow.updated_at
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    save_statuses(&mut tx, workflow).await?;
+
+    if workflow.is_default {
+        clear_default(&mut tx, &workflow.user_id, &workflow.id).await?;
+    }
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+pub async fn get_workflows_by_user(pool: &MySqlPool, user_id: &Uuid) -> Result<Vec<Workflow>> {
+    let rows = sqlx::query_as!(
+        WorkflowRow,
+        r#"
+        SELECT id, user_id, name, is_default as `is_default: bool`, created_at, updated_at
+        FROM workflows
+        WHERE user_id = ?
+        ORDER BY created_at
+        "#,
+        user_id.to_string()
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut workflows = Vec::with_capacity(rows.len());
+    for row in rows {
+        workflows.push(load_workflow(pool, row).await?);
+    }
+
+    Ok(workflows)
+}
+
+pub async fn get_workflow_by_id(pool: &MySqlPool, id: &Uuid) -> Result<Option<Workflow>> {
+    let row = sqlx::query_as!(
+        WorkflowRow,
+        r#"
+        SELECT id, user_id, name, is_default as `is_default: bool`, created_at, updated_at
+        FROM workflows
+        WHERE id = ?
+        "#,
+        id.to_string()
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    match row {
+        Some(row) => Ok(Some(load_workflow(pool, row).await?)),
+        None => Ok(None),
+    }
+}
+
+pub async fn get_default_workflow(pool: &MySqlPool, user_id: &str) -> Result<Option<Workflow>> {
+    let row = sqlx::query_as!(
+        WorkflowRow,
+        r#"
+        SELECT id, user_id, name, is_default as `is_default: bool`, created_at, updated_at
+        FROM workflows
+        WHERE user_id = ? AND is_default = TRUE
+        "#,
+        user_id
+    )
+    .fetch_optional(pool)
+    .await?;
+
+    match row {
+        Some(row) => Ok(Some(load_workflow(pool, row).await?)),
+        None => Ok(None),
+    }
+}
+
+pub async fn update_workflow(pool: &MySqlPool, workflow: &Workflow) -> Result<Option<Workflow>> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query!(
+        r#"
+        UPDATE workflows
+        SET name = ?, is_default = ?, updated_at = ?
+        WHERE id = ?
+        "#,
+        workflow.name,
+        workflow.is_default,
+        Utc::now(),
+        workflow.id
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    save_statuses(&mut tx, workflow).await?;
+
+    if workflow.is_default {
+        clear_default(&mut tx, &workflow.user_id, &workflow.id).await?;
+    }
+
+    tx.commit().await?;
+
+    get_workflow_by_id(pool, &Uuid::parse_str(&workflow.id)?).await
+}
+
+pub async fn delete_workflow(pool: &MySqlPool, id: &Uuid) -> Result<bool> {
+    let result = sqlx::query!("DELETE FROM workflows WHERE id = ?", id.to_string())
+        .execute(pool)
+        .await?;
+
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,80 @@
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+
+use actix_web::web::Bytes;
+use anyhow::{Result, bail};
+use async_trait::async_trait;
+use tokio::fs;
+
+use crate::storage::{AttachmentStorage, ByteStream, file_stream};
+
+// Stores each attachment as a file below `root`
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        LocalStorage { root: root.into() }
+    }
+
+    fn path_for(&self, key: &str) -> Result<PathBuf> {
+        if key
+            .split('/')
+            .any(|part| part.is_empty() || part == "." || part == "..")
+        {
+            bail!("Invalid storage key '{}'", key);
+        }
+        Ok(self.root.join(key))
+    }
+}
+
+#[async_trait]
+impl AttachmentStorage for LocalStorage {
+    // Written to a temporary file first so a crash never leaves a partial file
+    async fn put(&self, key: &str, _content_type: &str, data: Bytes) -> Result<()> {
+        let path = self.path_for(key)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let temp_path = path.with_extension("partial");
+        fs::write(&temp_path, &data).await?;
+        fs::rename(&temp_path, &path).await?;
+
+        Ok(())
+    }
+
+    async fn put_file(&self, key: &str, _content_type: &str, source: &Path) -> Result<()> {
+        let path = self.path_for(key)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+
+        let temp_path = path.with_extension("partial");
+        fs::copy(source, &temp_path).await?;
+        fs::rename(&temp_path, &path).await?;
+
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<ByteStream>> {
+        let file = match fs::File::open(self.path_for(key)?).await {
+            Ok(file) => file,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+
+        Ok(Some(file_stream(file)))
+    }
+
+    async fn delete(&self, key: &str) -> Result<()> {
+        match fs::remove_file(self.path_for(key)?).await {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
@@ -0,0 +1,48 @@
+pub mod local_storage;
+pub mod s3_storage;
+
+use std::{path::Path, pin::Pin};
+
+use actix_web::web::Bytes;
+use anyhow::Result;
+use async_trait::async_trait;
+use futures_util::{Stream, stream};
+use tokio::{fs::File, io::AsyncReadExt};
+
+pub type ByteStream = Pin<Box<dyn Stream<Item = Result<Bytes, std::io::Error>> + Send>>;
+
+const READ_CHUNK_BYTES: usize = 64 * 1024;
+
+// Where attachment contents and account export archives live. Keys are
+// generated by the server and only contain [0-9a-z-/].
+#[async_trait]
+pub trait AttachmentStorage: Send + Sync {
+    async fn put(&self, key: &str, content_type: &str, data: Bytes) -> Result<()>;
+
+    // Like `put`, reading the contents from a file as they're sent
+    async fn put_file(&self, key: &str, content_type: &str, path: &Path) -> Result<()>;
+
+    // None when nothing is stored under the key
+    async fn get(&self, key: &str) -> Result<Option<ByteStream>>;
+
+    // Deleting a key that doesn't exist is not an error
+    async fn delete(&self, key: &str) -> Result<()>;
+}
+
+// Reads the file in chunks, ending the stream at the first error
+pub fn file_stream(file: File) -> ByteStream {
+    let chunks = stream::unfold(Some(file), |file| async move {
+        let mut file = file?;
+        let mut buffer = vec![0; READ_CHUNK_BYTES];
+        match file.read(&mut buffer).await {
+            Ok(0) => None,
+            Ok(read) => {
+                buffer.truncate(read);
+                Some((Ok(Bytes::from(buffer)), Some(file)))
+            }
+            Err(err) => Some((Err(err), None)),
+        }
+    });
+
+    Box::pin(chunks)
+}
@@ -0,0 +1,232 @@
+use std::{collections::BTreeMap, fs::File, io::Write, path::Path};
+
+use actix_web::{
+    rt::task::{self, JoinHandle},
+    web::Bytes,
+};
+use anyhow::{Result, anyhow};
+use chrono::Utc;
+use futures_util::TryStreamExt;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use sqlx::MySqlPool;
+use tokio::sync::mpsc;
+use uuid::Uuid;
+use zip::{CompressionMethod, ZipWriter, write::SimpleFileOptions};
+
+use crate::{
+    schema::{
+        account_schema::get_account_exports_by_user,
+        attachment_schema::get_attachments_by_user,
+        comment_schema::get_comments_by_author,
+        saved_view_schema::get_saved_views_by_user,
+        todo_event_schema::get_todo_events,
+        todo_schema::{get_todos_including_archived, get_trashed_todos},
+        user_schema::get_user_by_id,
+        user_settings_schema::get_user_settings,
+        webhook_schema::get_webhooks_by_user,
+        workflow_schema::get_workflows_by_user,
+    },
+    storage::{AttachmentStorage, ByteStream},
+};
+
+fn download_mac(secret: &str, export_id: &str, expires: i64) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(b"account-export.");
+    mac.update(export_id.as_bytes());
+    mac.update(b".");
+    mac.update(expires.to_string().as_bytes());
+    mac
+}
+
+// The link carries its expiry and a signature over it, so it works without
+// authentication but can't be extended or pointed at another export
+pub fn download_link(secret: &str, export_id: &str, expires: i64) -> String {
+    let signature = hex::encode(
+        download_mac(secret, export_id, expires)
+            .finalize()
+            .into_bytes(),
+    );
+    format!(
+        "/api/v1/exports/{}/download?expires={}&signature={}",
+        export_id, expires, signature
+    )
+}
+
+pub fn verify_download_link(secret: &str, export_id: &str, expires: i64, signature: &str) -> bool {
+    let Ok(signature) = hex::decode(signature) else {
+        return false;
+    };
+    expires > Utc::now().timestamp()
+        && download_mac(secret, export_id, expires)
+            .verify_slice(&signature)
+            .is_ok()
+}
+
+enum ArchiveEntry {
+    File {
+        name: String,
+        options: SimpleFileOptions,
+    },
+    Data(Bytes),
+}
+
+// Hands entries to a ZipWriter on a blocking thread, which writes them to the
+// file as they arrive, so the archive is never held in memory
+struct ArchiveWriter {
+    sender: mpsc::Sender<ArchiveEntry>,
+    writer: JoinHandle<Result<u64>>,
+}
+
+impl ArchiveWriter {
+    fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path)?;
+        let (sender, mut receiver) = mpsc::channel(16);
+
+        let writer = task::spawn_blocking(move || {
+            let mut zip = ZipWriter::new(file);
+            while let Some(entry) = receiver.blocking_recv() {
+                match entry {
+                    ArchiveEntry::File { name, options } => zip.start_file(name, options)?,
+                    ArchiveEntry::Data(data) => zip.write_all(&data)?,
+                }
+            }
+            Ok(zip.finish()?.metadata()?.len())
+        });
+
+        Ok(ArchiveWriter { sender, writer })
+    }
+
+    async fn send(&self, entry: ArchiveEntry) -> Result<()> {
+        self.sender
+            .send(entry)
+            .await
+            .map_err(|_| anyhow!("Archive writer stopped"))
+    }
+
+    async fn add_json<T: Serialize + ?Sized>(&self, name: &str, value: &T) -> Result<()> {
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        self.send(ArchiveEntry::File {
+            name: name.to_string(),
+            options,
+        })
+        .await?;
+        self.send(ArchiveEntry::Data(serde_json::to_vec_pretty(value)?.into()))
+            .await
+    }
+
+    // Files are mostly compressed formats already, so they're stored as is
+    async fn add_stream(&self, name: String, mut data: ByteStream) -> Result<()> {
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+        self.send(ArchiveEntry::File { name, options }).await?;
+        while let Some(chunk) = data.try_next().await? {
+            self.send(ArchiveEntry::Data(chunk)).await?;
+        }
+        Ok(())
+    }
+
+    // The size of the finished archive
+    async fn finish(self) -> Result<u64> {
+        drop(self.sender);
+        self.writer.await?
+    }
+}
+
+async fn add_account_data(
+    archive: &ArchiveWriter,
+    pool: &MySqlPool,
+    storage: &dyn AttachmentStorage,
+    user_id: &Uuid,
+) -> Result<()> {
+    let user = get_user_by_id(pool, user_id)
+        .await?
+        .ok_or_else(|| anyhow!("User {} not found", user_id))?;
+
+    let mut todos = get_todos_including_archived(pool, user_id).await?;
+    todos.extend(get_trashed_todos(pool, user_id).await?);
+
+    let mut history = BTreeMap::new();
+    for todo in &todos {
+        let events = get_todo_events(pool, &Uuid::parse_str(&todo.id)?).await?;
+        history.insert(todo.id.clone(), events);
+    }
+
+    archive
+        .add_json("profile.json", &user.to_response())
+        .await?;
+    archive
+        .add_json("settings.json", &get_user_settings(pool, user_id).await?)
+        .await?;
+    archive.add_json("todos.json", &todos).await?;
+    archive.add_json("todo_history.json", &history).await?;
+    archive
+        .add_json(
+            "comments.json",
+            &get_comments_by_author(pool, user_id).await?,
+        )
+        .await?;
+    archive
+        .add_json(
+            "workflows.json",
+            &get_workflows_by_user(pool, user_id).await?,
+        )
+        .await?;
+    archive
+        .add_json(
+            "saved_views.json",
+            &get_saved_views_by_user(pool, user_id).await?,
+        )
+        .await?;
+    archive
+        .add_json("webhooks.json", &get_webhooks_by_user(pool, user_id).await?)
+        .await?;
+
+    let attachments = get_attachments_by_user(pool, user_id).await?;
+    archive.add_json("attachments.json", &attachments).await?;
+    for attachment in &attachments {
+        match storage.get(&attachment.storage_key).await? {
+            Some(data) => {
+                let name = format!("attachments/{}/{}", attachment.id, attachment.file_name);
+                archive.add_stream(name, data).await?;
+            }
+            None => log::error!("Attachment {} has no stored file", attachment.id),
+        }
+    }
+
+    Ok(())
+}
+
+// Everything stored about the user, one JSON file per kind of data plus the
+// attachment files, written to `path`. Returns the archive's size.
+pub async fn build_account_archive(
+    pool: &MySqlPool,
+    storage: &dyn AttachmentStorage,
+    user_id: &Uuid,
+    path: &Path,
+) -> Result<u64> {
+    let archive = ArchiveWriter::create(path)?;
+    let added = add_account_data(&archive, pool, storage, user_id).await;
+
+    // A failed write stops the writer, which then has the more useful error
+    let size = archive.finish().await?;
+    added?;
+
+    Ok(size)
+}
+
+// Export rows go with the user, so their archives are deleted before the user
+pub async fn delete_export_archives(
+    pool: &MySqlPool,
+    storage: &dyn AttachmentStorage,
+    user_id: &Uuid,
+) -> Result<()> {
+    for export in get_account_exports_by_user(pool, user_id).await? {
+        if let Some(key) = &export.storage_key {
+            storage.delete(key).await?;
+        }
+    }
+
+    Ok(())
+}
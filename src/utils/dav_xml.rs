@@ -0,0 +1,374 @@
+use quick_xml::{
+    NsReader,
+    escape::escape,
+    events::Event,
+    name::{Namespace, ResolveResult},
+};
+
+use crate::models::caldav_model::{
+    CALDAV_NS, CALENDARSERVER_NS, DAV_NS, DavRequest, DavRequestKind, PropName,
+};
+
+// WebDAV request parsing and multistatus rendering, covering what the CalDAV
+// endpoints need
+
+fn namespace_of(resolved: &ResolveResult) -> String {
+    match resolved {
+        ResolveResult::Bound(Namespace(ns)) => String::from_utf8_lossy(ns).into_owned(),
+        _ => String::new(),
+    }
+}
+
+fn predefined_entity(name: &str) -> Option<char> {
+    match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        _ => None,
+    }
+}
+
+pub fn parse_dav_request(body: &[u8]) -> Result<DavRequest, String> {
+    let text = std::str::from_utf8(body).map_err(|_| "Request body must be UTF-8".to_string())?;
+    let mut reader = NsReader::from_str(text);
+    reader.config_mut().trim_text(true);
+
+    let mut kind = None;
+    let mut props: Option<Vec<PropName>> = Some(Vec::new());
+    let mut hrefs = Vec::new();
+    let mut sync_token = None;
+    let mut stack: Vec<PropName> = Vec::new();
+    let mut text_buf = String::new();
+
+    loop {
+        let (resolved, event) = reader
+            .read_resolved_event()
+            .map_err(|err| format!("Invalid XML: {}", err))?;
+
+        match event {
+            Event::Start(ref e) | Event::Empty(ref e) => {
+                let name = PropName {
+                    namespace: namespace_of(&resolved),
+                    name: String::from_utf8_lossy(e.local_name().as_ref()).into_owned(),
+                };
+
+                if kind.is_none() {
+                    kind = Some(match (name.namespace.as_str(), name.name.as_str()) {
+                        (DAV_NS, "propfind") => DavRequestKind::Propfind,
+                        (DAV_NS, "sync-collection") => DavRequestKind::SyncCollection,
+                        (CALDAV_NS, "calendar-query") => DavRequestKind::CalendarQuery,
+                        (CALDAV_NS, "calendar-multiget") => DavRequestKind::CalendarMultiget,
+                        (_, other) => DavRequestKind::Other(other.to_string()),
+                    });
+                }
+
+                let parent = stack.last();
+                if name.is(DAV_NS, "allprop") {
+                    props = None;
+                } else if parent.is_some_and(|p| p.is(DAV_NS, "prop")) && stack.len() == 2 {
+                    // Only the top-level <prop> lists requested properties
+                    if let Some(props) = props.as_mut() {
+                        props.push(name.clone());
+                    }
+                }
+
+                text_buf.clear();
+                if matches!(event, Event::Start(_)) {
+                    stack.push(name);
+                }
+            }
+            Event::Text(e) => {
+                text_buf.push_str(&e.decode().map_err(|err| format!("Invalid XML: {}", err))?)
+            }
+            Event::GeneralRef(e) => {
+                let name = e.decode().map_err(|err| format!("Invalid XML: {}", err))?;
+                match e.resolve_char_ref().ok().flatten() {
+                    Some(c) => text_buf.push(c),
+                    None => text_buf.extend(predefined_entity(&name)),
+                }
+            }
+            Event::End(_) => {
+                if let Some(name) = stack.pop() {
+                    // hrefs and sync-token are direct children of the report
+                    if stack.len() == 1 && name.is(DAV_NS, "href") {
+                        hrefs.push(text_buf.trim().to_string());
+                    } else if stack.len() == 1 && name.is(DAV_NS, "sync-token") {
+                        sync_token = Some(text_buf.trim().to_string());
+                    }
+                }
+                text_buf.clear();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    match kind {
+        Some(kind) => Ok(DavRequest {
+            kind,
+            props,
+            hrefs,
+            sync_token,
+        }),
+        None => Ok(DavRequest::empty_propfind()),
+    }
+}
+
+fn qualified_name(prop: &PropName) -> (String, Option<String>) {
+    match prop.namespace.as_str() {
+        DAV_NS => (format!("d:{}", prop.name), None),
+        CALDAV_NS => (format!("c:{}", prop.name), None),
+        CALENDARSERVER_NS => (format!("cs:{}", prop.name), None),
+        "" => (prop.name.clone(), None),
+        ns => (
+            format!("x:{}", prop.name),
+            Some(format!(" xmlns:x=\"{}\"", escape(ns))),
+        ),
+    }
+}
+
+// One <response> of a multistatus: properties found with their XML content,
+// properties the resource doesn't have, or a status for the whole resource
+// with the DAV: precondition that explains it
+pub struct DavResponse {
+    pub href: String,
+    pub found: Vec<(PropName, String)>,
+    pub not_found: Vec<PropName>,
+    pub status: Option<&'static str>,
+    pub error: Option<&'static str>,
+}
+
+impl DavResponse {
+    pub fn new(href: String) -> Self {
+        DavResponse {
+            href,
+            found: Vec::new(),
+            not_found: Vec::new(),
+            status: None,
+            error: None,
+        }
+    }
+
+    pub fn with_status(href: String, status: &'static str) -> Self {
+        DavResponse {
+            status: Some(status),
+            ..DavResponse::new(href)
+        }
+    }
+
+    fn write(&self, out: &mut String) {
+        out.push_str("<d:response><d:href>");
+        out.push_str(&escape(self.href.as_str()));
+        out.push_str("</d:href>");
+
+        if let Some(status) = self.status {
+            out.push_str(&format!("<d:status>HTTP/1.1 {}</d:status>", status));
+        }
+
+        if let Some(error) = self.error {
+            out.push_str(&format!("<d:error><d:{}/></d:error>", error));
+        }
+
+        if !self.found.is_empty() {
+            out.push_str("<d:propstat><d:prop>");
+            for (prop, value) in &self.found {
+                let (name, xmlns) = qualified_name(prop);
+                let xmlns = xmlns.unwrap_or_default();
+                if value.is_empty() {
+                    out.push_str(&format!("<{}{}/>", name, xmlns));
+                } else {
+                    out.push_str(&format!("<{}{}>{}</{}>", name, xmlns, value, name));
+                }
+            }
+            out.push_str("</d:prop><d:status>HTTP/1.1 200 OK</d:status></d:propstat>");
+        }
+
+        if !self.not_found.is_empty() {
+            out.push_str("<d:propstat><d:prop>");
+            for prop in &self.not_found {
+                let (name, xmlns) = qualified_name(prop);
+                out.push_str(&format!("<{}{}/>", name, xmlns.unwrap_or_default()));
+            }
+            out.push_str("</d:prop><d:status>HTTP/1.1 404 Not Found</d:status></d:propstat>");
+        }
+
+        out.push_str("</d:response>");
+    }
+}
+
+pub fn multistatus(responses: &[DavResponse], sync_token: Option<&str>) -> String {
+    let mut out = String::from(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<d:multistatus xmlns:d=\"DAV:\" \
+         xmlns:c=\"urn:ietf:params:xml:ns:caldav\" xmlns:cs=\"http://calendarserver.org/ns/\">",
+    );
+    for response in responses {
+        response.write(&mut out);
+    }
+    if let Some(token) = sync_token {
+        out.push_str("<d:sync-token>");
+        out.push_str(&escape(token));
+        out.push_str("</d:sync-token>");
+    }
+    out.push_str("</d:multistatus>");
+    out
+}
+
+// Error body carrying a precondition, e.g. DAV:valid-sync-token
+pub fn precondition_error(namespace_prefix: &str, name: &str) -> String {
+    format!(
+        "<?xml version=\"1.0\" encoding=\"utf-8\"?>\n<d:error xmlns:d=\"DAV:\" \
+         xmlns:c=\"urn:ietf:params:xml:ns:caldav\"><{}:{}/></d:error>",
+        namespace_prefix, name
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_properties_a_propfind_asks_for() {
+        let body = br#"<?xml version="1.0" encoding="utf-8"?>
+            <d:propfind xmlns:d="DAV:" xmlns:cs="http://calendarserver.org/ns/">
+              <d:prop>
+                <d:displayname/>
+                <cs:getctag/>
+                <d:resourcetype></d:resourcetype>
+              </d:prop>
+            </d:propfind>"#;
+        let request = parse_dav_request(body).unwrap();
+
+        assert_eq!(request.kind, DavRequestKind::Propfind);
+        assert_eq!(
+            request.props,
+            Some(vec![
+                PropName::new(DAV_NS, "displayname"),
+                PropName::new(CALENDARSERVER_NS, "getctag"),
+                PropName::new(DAV_NS, "resourcetype"),
+            ])
+        );
+    }
+
+    #[test]
+    fn treats_allprop_and_empty_bodies_as_every_property() {
+        let allprop = parse_dav_request(br#"<propfind xmlns="DAV:"><allprop/></propfind>"#);
+        let empty = parse_dav_request(b"");
+
+        assert_eq!(allprop.unwrap().props, None);
+        let empty = empty.unwrap();
+        assert_eq!(empty.kind, DavRequestKind::Propfind);
+        assert_eq!(empty.props, None);
+    }
+
+    #[test]
+    fn parses_multiget_hrefs_but_not_filter_properties() {
+        let body = br#"<c:calendar-multiget xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+              <d:prop><d:getetag/><c:calendar-data/></d:prop>
+              <d:href>/dav/calendars/u1/todos/a.ics</d:href>
+              <d:href> /dav/calendars/u1/todos/b&amp;c%20d.ics </d:href>
+            </c:calendar-multiget>"#;
+        let request = parse_dav_request(body).unwrap();
+
+        assert_eq!(request.kind, DavRequestKind::CalendarMultiget);
+        assert_eq!(
+            request.props,
+            Some(vec![
+                PropName::new(DAV_NS, "getetag"),
+                PropName::new(CALDAV_NS, "calendar-data"),
+            ])
+        );
+        assert_eq!(
+            request.hrefs,
+            vec![
+                "/dav/calendars/u1/todos/a.ics",
+                "/dav/calendars/u1/todos/b&c%20d.ics"
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_calendar_queries_and_sync_tokens() {
+        let query = br#"<c:calendar-query xmlns:d="DAV:" xmlns:c="urn:ietf:params:xml:ns:caldav">
+              <d:prop><d:getetag/></d:prop>
+              <c:filter><c:comp-filter name="VCALENDAR"><c:comp-filter name="VTODO"/></c:comp-filter></c:filter>
+            </c:calendar-query>"#;
+        let sync = br#"<d:sync-collection xmlns:d="DAV:">
+              <d:sync-token>urn:todo-app:sync:abc</d:sync-token>
+              <d:sync-level>1</d:sync-level>
+              <d:prop><d:getetag/></d:prop>
+            </d:sync-collection>"#;
+
+        let query = parse_dav_request(query).unwrap();
+        assert_eq!(query.kind, DavRequestKind::CalendarQuery);
+        assert_eq!(query.props, Some(vec![PropName::new(DAV_NS, "getetag")]));
+
+        let sync = parse_dav_request(sync).unwrap();
+        assert_eq!(sync.kind, DavRequestKind::SyncCollection);
+        assert_eq!(sync.sync_token.as_deref(), Some("urn:todo-app:sync:abc"));
+        assert_eq!(sync.props, Some(vec![PropName::new(DAV_NS, "getetag")]));
+    }
+
+    #[test]
+    fn reports_unknown_reports_and_invalid_bodies() {
+        let other = parse_dav_request(br#"<d:expand-property xmlns:d="DAV:"/>"#).unwrap();
+
+        assert_eq!(
+            other.kind,
+            DavRequestKind::Other("expand-property".to_string())
+        );
+        assert!(parse_dav_request(br#"<d:propfind xmlns:d="DAV:"><d:prop></d:propfind>"#).is_err());
+        assert!(parse_dav_request(&[0xff, 0xfe]).is_err());
+    }
+
+    #[test]
+    fn renders_found_and_missing_properties() {
+        let mut response = DavResponse::new("/dav/calendars/u1/todos/a&b.ics".to_string());
+        response
+            .found
+            .push((PropName::new(DAV_NS, "getetag"), "\"3\"".to_string()));
+        response
+            .found
+            .push((PropName::new(DAV_NS, "resourcetype"), String::new()));
+        response
+            .not_found
+            .push(PropName::new("http://apple.com/ns/ical/", "calendar-color"));
+
+        let xml = multistatus(&[response], Some("urn:todo-app:sync:abc"));
+
+        assert!(xml.contains("<d:href>/dav/calendars/u1/todos/a&amp;b.ics</d:href>"));
+        assert!(xml.contains(
+            "<d:propstat><d:prop><d:getetag>\"3\"</d:getetag><d:resourcetype/></d:prop>\
+             <d:status>HTTP/1.1 200 OK</d:status></d:propstat>"
+        ));
+        assert!(xml.contains(
+            "<d:propstat><d:prop><x:calendar-color xmlns:x=\"http://apple.com/ns/ical/\"/>\
+             </d:prop><d:status>HTTP/1.1 404 Not Found</d:status></d:propstat>"
+        ));
+        assert!(
+            xml.ends_with("<d:sync-token>urn:todo-app:sync:abc</d:sync-token></d:multistatus>")
+        );
+    }
+
+    #[test]
+    fn renders_a_status_with_its_precondition() {
+        let response = DavResponse {
+            error: Some("number-of-matches-within-limits"),
+            ..DavResponse::with_status(
+                "/dav/calendars/u1/todos/".to_string(),
+                "507 Insufficient Storage",
+            )
+        };
+
+        assert!(multistatus(&[response], None).contains(
+            "<d:response><d:href>/dav/calendars/u1/todos/</d:href>\
+             <d:status>HTTP/1.1 507 Insufficient Storage</d:status>\
+             <d:error><d:number-of-matches-within-limits/></d:error></d:response>"
+        ));
+        assert!(
+            precondition_error("d", "valid-sync-token")
+                .ends_with("<d:valid-sync-token/></d:error>")
+        );
+    }
+}
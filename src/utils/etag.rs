@@ -0,0 +1,38 @@
+use actix_web::{
+    HttpRequest,
+    http::header::{self, EntityTag, Header, IfMatch, IfNoneMatch},
+};
+
+// Todos are tagged with their version, so the tag changes on every write
+pub fn entity_tag(version: i32) -> EntityTag {
+    EntityTag::new_strong(version.to_string())
+}
+
+// Result of the If-Match precondition for a resource at `version`, or None
+// when the request doesn't carry one
+pub fn if_match(req: &HttpRequest, version: i32) -> Option<bool> {
+    if !req.headers().contains_key(header::IF_MATCH) {
+        return None;
+    }
+
+    let tag = entity_tag(version);
+    match IfMatch::parse(req) {
+        Ok(IfMatch::Any) => Some(true),
+        Ok(IfMatch::Items(tags)) => Some(tags.iter().any(|t| t.strong_eq(&tag))),
+        Err(_) => Some(false),
+    }
+}
+
+// Whether If-None-Match says the client already has `version`
+pub fn not_modified(req: &HttpRequest, version: i32) -> bool {
+    if !req.headers().contains_key(header::IF_NONE_MATCH) {
+        return false;
+    }
+
+    let tag = entity_tag(version);
+    match IfNoneMatch::parse(req) {
+        Ok(IfNoneMatch::Any) => true,
+        Ok(IfNoneMatch::Items(tags)) => tags.iter().any(|t| t.weak_eq(&tag)),
+        Err(_) => false,
+    }
+}
@@ -0,0 +1,168 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicU64, Ordering},
+    },
+};
+
+use chrono::Utc;
+use tokio::sync::broadcast;
+
+use crate::models::{
+    change_event_model::{ChangeEvent, ChangeKind},
+    todo_model::Todo,
+};
+
+const CHANNEL_CAPACITY: usize = 1024;
+const REPLAY_LOG_SIZE: usize = 1000;
+
+// In-process fan-out of todo changes to connected clients. The most recent
+// events are kept so reconnecting clients can resume where they left off.
+pub struct EventHub {
+    sender: broadcast::Sender<Arc<ChangeEvent>>,
+    log: Mutex<VecDeque<Arc<ChangeEvent>>>,
+    next_id: AtomicU64,
+}
+
+impl EventHub {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+
+        // Starting from the clock keeps ids increasing across restarts, so an
+        // id from a previous run reads as too old rather than as the future
+        let first_id = Utc::now().timestamp_millis() as u64 * 1000;
+
+        EventHub {
+            sender,
+            log: Mutex::new(VecDeque::with_capacity(REPLAY_LOG_SIZE)),
+            next_id: AtomicU64::new(first_id),
+        }
+    }
+
+    pub fn publish(&self, kind: ChangeKind, todo: &Todo) {
+        // The id is taken under the lock so the log stays in id order
+        let mut log = self.log.lock().unwrap_or_else(|e| e.into_inner());
+        let event = Arc::new(ChangeEvent {
+            id: self.next_id.fetch_add(1, Ordering::Relaxed),
+            kind,
+            todo_id: todo.id.clone(),
+            user_id: todo.user_id.clone(),
+            todo: todo.clone(),
+            occurred_at: Utc::now(),
+        });
+
+        if log.len() == REPLAY_LOG_SIZE {
+            log.pop_front();
+        }
+        log.push_back(Arc::clone(&event));
+
+        // Sending only fails when nobody is listening
+        let _ = self.sender.send(event);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<ChangeEvent>> {
+        self.sender.subscribe()
+    }
+
+    // Events after `last_id` visible to the user, or None when some of them
+    // have already dropped out of the log
+    pub fn replay_since(&self, last_id: u64, user_id: &str) -> Option<Vec<Arc<ChangeEvent>>> {
+        let log = self.log.lock().unwrap_or_else(|e| e.into_inner());
+
+        let oldest_id = log
+            .front()
+            .map(|event| event.id)
+            .unwrap_or_else(|| self.next_id.load(Ordering::Relaxed));
+        // last_id comes from the client, so it may be anything
+        if last_id.saturating_add(1) < oldest_id || last_id >= self.next_id.load(Ordering::Relaxed)
+        {
+            return None;
+        }
+
+        Some(
+            log.iter()
+                .filter(|event| event.id > last_id && event.is_visible_to(user_id))
+                .cloned()
+                .collect(),
+        )
+    }
+}
+
+impl Default for EventHub {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todo(user_id: &str) -> Todo {
+        Todo::new(
+            "Todo".to_string(),
+            None,
+            None,
+            None,
+            None,
+            user_id.to_string(),
+        )
+    }
+
+    fn ids(events: &[Arc<ChangeEvent>]) -> Vec<u64> {
+        events.iter().map(|event| event.id).collect()
+    }
+
+    #[test]
+    fn replays_the_users_events_after_the_last_seen_one() {
+        let hub = EventHub::new();
+        let mut receiver = hub.subscribe();
+        hub.publish(ChangeKind::Created, &todo("alice"));
+        hub.publish(ChangeKind::Created, &todo("bob"));
+        hub.publish(ChangeKind::Updated, &todo("alice"));
+
+        let published: Vec<_> = (0..3).map(|_| receiver.try_recv().unwrap()).collect();
+        let first = published[0].id;
+        assert_eq!(ids(&published), vec![first, first + 1, first + 2]);
+
+        let replayed = hub.replay_since(first, "alice").unwrap();
+        assert_eq!(ids(&replayed), vec![first + 2]);
+        assert_eq!(replayed[0].kind, ChangeKind::Updated);
+
+        // Caught up, or resuming from just before the first event
+        assert!(hub.replay_since(first + 2, "alice").unwrap().is_empty());
+        assert_eq!(
+            ids(&hub.replay_since(first - 1, "alice").unwrap()),
+            vec![first, first + 2]
+        );
+    }
+
+    #[test]
+    fn rejects_ids_it_has_not_handed_out() {
+        let hub = EventHub::new();
+        hub.publish(ChangeKind::Created, &todo("alice"));
+        let next = hub.next_id.load(Ordering::Relaxed);
+
+        assert!(hub.replay_since(next, "alice").is_none());
+        assert!(hub.replay_since(u64::MAX, "alice").is_none());
+        assert!(hub.replay_since(0, "alice").is_none());
+    }
+
+    #[test]
+    fn reports_events_evicted_from_the_log() {
+        let hub = EventHub::new();
+        let first = hub.next_id.load(Ordering::Relaxed);
+        for _ in 0..REPLAY_LOG_SIZE + 5 {
+            hub.publish(ChangeKind::Updated, &todo("alice"));
+        }
+
+        // The first five events are gone, so resuming before them would miss some
+        assert!(hub.replay_since(first, "alice").is_none());
+        assert!(hub.replay_since(first + 3, "alice").is_none());
+
+        let replayed = hub.replay_since(first + 4, "alice").unwrap();
+        assert_eq!(replayed.len(), REPLAY_LOG_SIZE);
+        assert_eq!(replayed[0].id, first + 5);
+    }
+}
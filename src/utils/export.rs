@@ -0,0 +1,293 @@
+use chrono::{DateTime, Utc};
+
+use crate::models::{
+    export_model::ExportFormat,
+    todo_model::{Todo, TodoStatus},
+};
+
+// Renders todos one at a time so an export can be streamed page by page
+
+const CSV_COLUMNS: &[&str] = &[
+    "id",
+    "title",
+    "description",
+    "status",
+    "due_date",
+    "recurrence_rule",
+    "created_at",
+    "updated_at",
+    "completed_at",
+];
+
+pub struct ExportRenderer {
+    format: ExportFormat,
+    written: usize,
+    // Markdown: the status whose section is open
+    section: Option<TodoStatus>,
+}
+
+fn timestamp(value: Option<DateTime<Utc>>) -> String {
+    value.map(|v| v.to_rfc3339()).unwrap_or_default()
+}
+
+fn csv_record(fields: &[&str]) -> String {
+    let mut writer = csv::WriterBuilder::new()
+        .terminator(csv::Terminator::CRLF)
+        .from_writer(Vec::new());
+    // Writing to a Vec can't fail
+    let _ = writer.write_record(fields);
+    let bytes = writer.into_inner().unwrap_or_default();
+    String::from_utf8(bytes).unwrap_or_default()
+}
+
+fn section_title(status: &TodoStatus) -> &'static str {
+    match status {
+        TodoStatus::Pending => "Pending",
+        TodoStatus::InProcess => "In progress",
+        TodoStatus::Completed => "Completed",
+    }
+}
+
+// Keeps todo text from being read as Markdown structure
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(
+            c,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|'
+        ) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn render_markdown_item(todo: &Todo) -> String {
+    let checkbox = if todo.status == TodoStatus::Completed {
+        "[x]"
+    } else {
+        "[ ]"
+    };
+    let mut item = format!("- {} {}", checkbox, escape_markdown(&todo.title));
+    if let Some(due_date) = todo.due_date {
+        item.push_str(&format!(" (due {})", due_date.format("%Y-%m-%d")));
+    }
+    item.push('\n');
+
+    // Indented lines continue the list item
+    if let Some(description) = todo.description.as_deref().filter(|d| !d.trim().is_empty()) {
+        for line in description.lines() {
+            item.push_str("  ");
+            item.push_str(&escape_markdown(line));
+            item.push('\n');
+        }
+    }
+    item
+}
+
+// todo.txt has no in-progress state, so it is kept as a status: tag
+fn render_todo_txt_line(todo: &Todo) -> String {
+    let mut parts = Vec::new();
+    if todo.status == TodoStatus::Completed {
+        parts.push("x".to_string());
+        if let Some(completed_at) = todo.completed_at {
+            parts.push(completed_at.format("%Y-%m-%d").to_string());
+        }
+    }
+    parts.push(todo.created_at.format("%Y-%m-%d").to_string());
+    parts.push(todo.title.split_whitespace().collect::<Vec<_>>().join(" "));
+    if let Some(due_date) = todo.due_date {
+        parts.push(format!("due:{}", due_date.format("%Y-%m-%d")));
+    }
+    if todo.status == TodoStatus::InProcess {
+        parts.push(format!("status:{}", todo.status.as_str()));
+    }
+
+    format!("{}\n", parts.join(" "))
+}
+
+impl ExportRenderer {
+    pub fn new(format: ExportFormat) -> Self {
+        ExportRenderer {
+            format,
+            written: 0,
+            section: None,
+        }
+    }
+
+    pub fn begin(&self) -> String {
+        match self.format {
+            ExportFormat::Csv => csv_record(CSV_COLUMNS),
+            ExportFormat::Json => "[".to_string(),
+            ExportFormat::Markdown => "# Todos\n".to_string(),
+            ExportFormat::Ndjson | ExportFormat::TodoTxt => String::new(),
+        }
+    }
+
+    pub fn render(&mut self, todo: &Todo) -> String {
+        let rendered = match self.format {
+            ExportFormat::Csv => csv_record(&[
+                &todo.id,
+                &todo.title,
+                todo.description.as_deref().unwrap_or_default(),
+                todo.status.as_str(),
+                &timestamp(todo.due_date),
+                todo.recurrence_rule.as_deref().unwrap_or_default(),
+                &todo.created_at.to_rfc3339(),
+                &todo.updated_at.to_rfc3339(),
+                &timestamp(todo.completed_at),
+            ]),
+            ExportFormat::Json => {
+                let separator = if self.written == 0 { "" } else { "," };
+                format!(
+                    "{}{}",
+                    separator,
+                    serde_json::to_string(todo).unwrap_or_default()
+                )
+            }
+            ExportFormat::Ndjson => {
+                format!("{}\n", serde_json::to_string(todo).unwrap_or_default())
+            }
+            ExportFormat::Markdown => {
+                let mut rendered = String::new();
+                if self.section.as_ref() != Some(&todo.status) {
+                    rendered.push_str(&format!("\n## {}\n\n", section_title(&todo.status)));
+                    self.section = Some(todo.status.clone());
+                }
+                rendered.push_str(&render_markdown_item(todo));
+                rendered
+            }
+            ExportFormat::TodoTxt => render_todo_txt_line(todo),
+        };
+
+        self.written += 1;
+        rendered
+    }
+
+    pub fn finish(&self) -> String {
+        match self.format {
+            ExportFormat::Json => "]".to_string(),
+            ExportFormat::Markdown if self.written == 0 => "\nNo todos.\n".to_string(),
+            _ => String::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value).unwrap().to_utc()
+    }
+
+    fn todo(title: &str, status: TodoStatus) -> Todo {
+        let mut todo = Todo::new(
+            title.to_string(),
+            None,
+            Some(status),
+            None,
+            None,
+            "user-1".to_string(),
+        )
+        .with_id(format!("id-{}", title.len()));
+        todo.created_at = utc("2026-10-01T08:00:00Z");
+        todo.updated_at = utc("2026-10-02T08:00:00Z");
+        todo
+    }
+
+    fn export(format: ExportFormat, todos: &[Todo]) -> String {
+        let mut renderer = ExportRenderer::new(format);
+        let mut out = renderer.begin();
+        for todo in todos {
+            out.push_str(&renderer.render(todo));
+        }
+        out.push_str(&renderer.finish());
+        out
+    }
+
+    #[test]
+    fn renders_csv_with_quoted_fields() {
+        let mut todo = todo("Buy milk, eggs", TodoStatus::Completed);
+        todo.description = Some("Two \"large\"\nboxes".to_string());
+        todo.due_date = Some(utc("2026-10-20T17:00:00Z"));
+        todo.completed_at = Some(utc("2026-10-03T09:00:00Z"));
+
+        assert_eq!(
+            export(ExportFormat::Csv, &[todo]),
+            "id,title,description,status,due_date,recurrence_rule,created_at,updated_at,completed_at\r\n\
+             id-14,\"Buy milk, eggs\",\"Two \"\"large\"\"\nboxes\",completed,2026-10-20T17:00:00+00:00,,\
+             2026-10-01T08:00:00+00:00,2026-10-02T08:00:00+00:00,2026-10-03T09:00:00+00:00\r\n"
+        );
+    }
+
+    #[test]
+    fn renders_json_arrays_and_ndjson_lines() {
+        let todos = [
+            todo("One", TodoStatus::Pending),
+            todo("Three", TodoStatus::Pending),
+        ];
+
+        let array: serde_json::Value =
+            serde_json::from_str(&export(ExportFormat::Json, &todos)).unwrap();
+        assert_eq!(array[0]["title"], "One");
+        assert_eq!(array[1]["title"], "Three");
+        assert_eq!(export(ExportFormat::Json, &[]), "[]");
+
+        let ndjson = export(ExportFormat::Ndjson, &todos);
+        let lines = ndjson.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        for (line, todo) in lines.iter().zip(&todos) {
+            let value: serde_json::Value = serde_json::from_str(line).unwrap();
+            assert_eq!(value["id"], todo.id.as_str());
+        }
+    }
+
+    #[test]
+    fn renders_markdown_sections_per_status() {
+        let mut plan = todo("Plan *party*", TodoStatus::Pending);
+        plan.due_date = Some(utc("2026-10-31T18:00:00Z"));
+        plan.description = Some("Guests:\n# not a heading".to_string());
+        let todos = [
+            plan,
+            todo("Invite [friends]", TodoStatus::Pending),
+            todo("Bake", TodoStatus::InProcess),
+            todo("Book venue", TodoStatus::Completed),
+        ];
+
+        assert_eq!(
+            export(ExportFormat::Markdown, &todos),
+            "# Todos\n\
+             \n## Pending\n\n\
+             - [ ] Plan \\*party\\* (due 2026-10-31)\n  Guests:\n  \\# not a heading\n\
+             - [ ] Invite \\[friends\\]\n\
+             \n## In progress\n\n\
+             - [ ] Bake\n\
+             \n## Completed\n\n\
+             - [x] Book venue\n"
+        );
+        assert_eq!(
+            export(ExportFormat::Markdown, &[]),
+            "# Todos\n\nNo todos.\n"
+        );
+    }
+
+    #[test]
+    fn renders_todo_txt_lines() {
+        let mut done = todo("File  taxes", TodoStatus::Completed);
+        done.completed_at = Some(utc("2026-10-05T10:00:00Z"));
+        let mut started = todo("Paint\tfence", TodoStatus::InProcess);
+        started.due_date = Some(utc("2026-10-25T00:00:00Z"));
+
+        assert_eq!(
+            export(
+                ExportFormat::TodoTxt,
+                &[done, started, todo("Rest", TodoStatus::Pending)]
+            ),
+            "x 2026-10-05 2026-10-01 File taxes\n\
+             2026-10-01 Paint fence due:2026-10-25 status:in_process\n\
+             2026-10-01 Rest\n"
+        );
+    }
+}
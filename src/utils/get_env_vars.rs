@@ -1,5 +1,18 @@
 use std::env;
 
+use anyhow::{Result, anyhow};
+
 pub fn get_env_var(key: &str) -> String {
     env::var(key).expect("Key not found in .env file")
 }
+
+// For optional settings that fall back to a default when unset
+pub fn get_env_var_or(key: &str, default: &str) -> String {
+    env::var(key).unwrap_or_else(|_| default.to_string())
+}
+
+// For settings only some configurations need, reported as an error instead
+// of a panic when missing
+pub fn require_env_var(key: &str) -> Result<String> {
+    env::var(key).map_err(|_| anyhow!("{} is not set", key))
+}
@@ -0,0 +1,447 @@
+use std::fmt;
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+use crate::models::{
+    caldav_model::VTodoData,
+    todo_model::{Todo, TodoStatus},
+};
+
+// RFC 5545 rendering and parsing of todos as VTODO components
+
+const MAX_LINE_OCTETS: usize = 75;
+const PRODID: &str = "-//todo-app//Todo Feed//EN";
+
+#[derive(Debug, PartialEq)]
+pub enum VTodoError {
+    Invalid(String),
+    // A TZID that isn't an IANA time zone name, such as a Windows zone name
+    // defined by the object's own VTIMEZONE
+    UnknownTimeZone(String),
+}
+
+impl fmt::Display for VTodoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VTodoError::Invalid(message) => write!(f, "{}", message),
+            VTodoError::UnknownTimeZone(tzid) => write!(f, "Unsupported time zone '{}'", tzid),
+        }
+    }
+}
+
+// TEXT values escape backslashes, semicolons, commas and line breaks
+pub fn escape_text(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            ';' => escaped.push_str("\\;"),
+            ',' => escaped.push_str("\\,"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// Lines longer than 75 octets are split with CRLF followed by a space, never
+// inside a multi-byte character
+pub fn fold_line(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len() + line.len() / MAX_LINE_OCTETS * 3);
+    let mut line_octets = 0;
+
+    for c in line.chars() {
+        if line_octets + c.len_utf8() > MAX_LINE_OCTETS {
+            folded.push_str("\r\n ");
+            line_octets = 1;
+        }
+        folded.push(c);
+        line_octets += c.len_utf8();
+    }
+
+    folded.push_str("\r\n");
+    folded
+}
+
+fn format_datetime(value: DateTime<Utc>) -> String {
+    value.format("%Y%m%dT%H%M%SZ").to_string()
+}
+
+fn status_value(status: &TodoStatus) -> &'static str {
+    match status {
+        TodoStatus::Pending => "NEEDS-ACTION",
+        TodoStatus::InProcess => "IN-PROCESS",
+        TodoStatus::Completed => "COMPLETED",
+    }
+}
+
+fn push_property(out: &mut String, name: &str, value: &str) {
+    out.push_str(&fold_line(&format!("{}:{}", name, value)));
+}
+
+// Occurrences of a recurring todo are stored as separate todos, so each one is
+// rendered on its own rather than with an RRULE
+fn push_todo(out: &mut String, todo: &Todo, uid: &str, now: DateTime<Utc>) {
+    push_property(out, "BEGIN", "VTODO");
+    push_property(out, "UID", &escape_text(uid));
+    push_property(out, "DTSTAMP", &format_datetime(now));
+    push_property(out, "CREATED", &format_datetime(todo.created_at));
+    push_property(out, "LAST-MODIFIED", &format_datetime(todo.updated_at));
+    push_property(out, "SEQUENCE", &(todo.version - 1).max(0).to_string());
+    push_property(out, "SUMMARY", &escape_text(&todo.title));
+    if let Some(description) = todo.description.as_deref().filter(|d| !d.is_empty()) {
+        push_property(out, "DESCRIPTION", &escape_text(description));
+    }
+    push_property(out, "STATUS", status_value(&todo.status));
+    if let Some(due_date) = todo.due_date {
+        push_property(out, "DUE", &format_datetime(due_date));
+    }
+    if let Some(completed_at) = todo.completed_at {
+        push_property(out, "COMPLETED", &format_datetime(completed_at));
+    }
+    push_property(out, "END", "VTODO");
+}
+
+fn push_calendar_header(out: &mut String) {
+    push_property(out, "BEGIN", "VCALENDAR");
+    push_property(out, "VERSION", "2.0");
+    push_property(out, "PRODID", PRODID);
+    push_property(out, "CALSCALE", "GREGORIAN");
+}
+
+pub fn render_calendar(name: &str, todos: &[Todo], now: DateTime<Utc>) -> String {
+    let mut out = String::new();
+
+    push_calendar_header(&mut out);
+    push_property(&mut out, "X-WR-CALNAME", &escape_text(name));
+    for todo in todos {
+        push_todo(&mut out, todo, &todo.id, now);
+    }
+    push_property(&mut out, "END", "VCALENDAR");
+
+    out
+}
+
+// A single todo as a calendar object resource, as served over CalDAV
+pub fn render_todo_resource(todo: &Todo, uid: &str, now: DateTime<Utc>) -> String {
+    let mut out = String::new();
+
+    push_calendar_header(&mut out);
+    push_todo(&mut out, todo, uid, now);
+    push_property(&mut out, "END", "VCALENDAR");
+
+    out
+}
+
+pub fn unescape_text(value: &str) -> String {
+    let mut unescaped = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match (c, chars.clone().next()) {
+            ('\\', Some('n' | 'N')) => {
+                unescaped.push('\n');
+                chars.next();
+            }
+            ('\\', Some(escaped @ ('\\' | ';' | ','))) => {
+                unescaped.push(escaped);
+                chars.next();
+            }
+            (c, _) => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+// Joins folded lines back together
+fn unfold_lines(text: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        match line.strip_prefix([' ', '\t']) {
+            Some(continuation) if !lines.is_empty() => {
+                if let Some(last) = lines.last_mut() {
+                    last.push_str(continuation);
+                }
+            }
+            _ => lines.push(line.to_string()),
+        }
+    }
+    lines
+}
+
+// Splits "NAME;PARAM=x:value" into the upper-cased name, its parameters and
+// the value. Colons inside quoted parameter values don't end the name part.
+fn split_property(line: &str) -> Option<(String, &str, &str)> {
+    let mut in_quotes = false;
+    let colon = line.char_indices().find_map(|(i, c)| match c {
+        '"' => {
+            in_quotes = !in_quotes;
+            None
+        }
+        ':' if !in_quotes => Some(i),
+        _ => None,
+    })?;
+
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+    let (name, params) = head.split_once(';').unwrap_or((head, ""));
+    Some((name.to_ascii_uppercase(), params, value))
+}
+
+fn param_value<'a>(params: &'a str, name: &str) -> Option<&'a str> {
+    params.split(';').find_map(|param| {
+        let (key, value) = param.split_once('=')?;
+        key.eq_ignore_ascii_case(name)
+            .then(|| value.trim_matches('"'))
+    })
+}
+
+// UTC ("Z") and floating date-times are taken as UTC. Local times with a TZID
+// are converted from that zone; at a DST overlap the earlier instant wins.
+fn parse_datetime(params: &str, value: &str) -> Result<DateTime<Utc>, VTodoError> {
+    let invalid = || VTodoError::Invalid(format!("Invalid date-time '{}'", value));
+
+    if param_value(params, "VALUE").is_some_and(|v| v.eq_ignore_ascii_case("DATE"))
+        && !value.contains('T')
+    {
+        return NaiveDate::parse_from_str(value, "%Y%m%d")
+            .map(|date| date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc())
+            .map_err(|_| invalid());
+    }
+
+    let is_utc = value.ends_with('Z');
+    let local = value.trim_end_matches('Z');
+    let datetime = NaiveDateTime::parse_from_str(local, "%Y%m%dT%H%M%S")
+        .or_else(|_| {
+            NaiveDate::parse_from_str(local, "%Y%m%d")
+                .map(|date| date.and_hms_opt(0, 0, 0).unwrap_or_default())
+        })
+        .map_err(|_| invalid())?;
+
+    match param_value(params, "TZID").filter(|_| !is_utc) {
+        Some(tzid) => {
+            let tz: Tz = tzid
+                .parse()
+                .map_err(|_| VTodoError::UnknownTimeZone(tzid.to_string()))?;
+            tz.from_local_datetime(&datetime)
+                .earliest()
+                .map(|local| local.with_timezone(&Utc))
+                // The time falls in a DST gap and never happened there
+                .ok_or_else(invalid)
+        }
+        None => Ok(datetime.and_utc()),
+    }
+}
+
+fn parse_status(value: &str) -> Option<TodoStatus> {
+    match value.to_ascii_uppercase().as_str() {
+        "NEEDS-ACTION" => Some(TodoStatus::Pending),
+        "IN-PROCESS" => Some(TodoStatus::InProcess),
+        "COMPLETED" | "CANCELLED" => Some(TodoStatus::Completed),
+        _ => None,
+    }
+}
+
+// Reads the first VTODO of a calendar object. Properties of nested
+// components such as VALARM are skipped.
+pub fn parse_vtodo(text: &str) -> Result<VTodoData, VTodoError> {
+    let mut data = VTodoData::default();
+    let mut components: Vec<String> = Vec::new();
+    let mut found = false;
+
+    for line in unfold_lines(text) {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Some((name, params, value)) = split_property(&line) else {
+            return Err(VTodoError::Invalid(format!("Malformed line '{}'", line)));
+        };
+
+        match name.as_str() {
+            "BEGIN" => {
+                components.push(value.to_ascii_uppercase());
+                continue;
+            }
+            "END" => {
+                if components.pop().as_deref() == Some("VTODO") && !found {
+                    found = true;
+                }
+                continue;
+            }
+            _ => {}
+        }
+
+        if found || components.last().map(String::as_str) != Some("VTODO") {
+            continue;
+        }
+
+        match name.as_str() {
+            "UID" => data.uid = Some(unescape_text(value)),
+            "SUMMARY" => data.summary = Some(unescape_text(value)),
+            "DESCRIPTION" => data.description = Some(unescape_text(value)),
+            "STATUS" => data.status = parse_status(value),
+            "DUE" => data.due = Some(parse_datetime(params, value)?),
+            // Some clients only mark completion with the COMPLETED timestamp
+            "COMPLETED" if data.status.is_none() => data.status = Some(TodoStatus::Completed),
+            _ => {}
+        }
+    }
+
+    if found {
+        Ok(data)
+    } else {
+        Err(VTodoError::Invalid(
+            "Only VTODO components are supported".to_string(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vcalendar(vtodo_lines: &[&str]) -> String {
+        let mut lines = vec!["BEGIN:VCALENDAR", "VERSION:2.0", "BEGIN:VTODO"];
+        lines.extend_from_slice(vtodo_lines);
+        lines.extend_from_slice(&["END:VTODO", "END:VCALENDAR", ""]);
+        lines.join("\r\n")
+    }
+
+    fn due(line: &str) -> Result<Option<DateTime<Utc>>, VTodoError> {
+        parse_vtodo(&vcalendar(&["UID:1", line])).map(|data| data.due)
+    }
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value).unwrap().to_utc()
+    }
+
+    #[test]
+    fn escapes_and_unescapes_text() {
+        let text = "Milk, eggs; flour\\sugar\r\nand butter";
+        let escaped = escape_text(text);
+
+        assert_eq!(escaped, r"Milk\, eggs\; flour\\sugar\nand butter");
+        assert_eq!(
+            unescape_text(&escaped),
+            "Milk, eggs; flour\\sugar\nand butter"
+        );
+    }
+
+    #[test]
+    fn leaves_short_lines_alone() {
+        assert_eq!(fold_line("SUMMARY:Short"), "SUMMARY:Short\r\n");
+    }
+
+    #[test]
+    fn folds_long_lines_at_75_octets() {
+        let line = format!("DESCRIPTION:{}", "x".repeat(100));
+        let folded = fold_line(&line);
+        let physical: Vec<&str> = folded.trim_end_matches("\r\n").split("\r\n").collect();
+
+        assert_eq!(physical.len(), 2);
+        assert_eq!(physical[0].len(), 75);
+        assert!(physical[1].starts_with(' '));
+        assert_eq!(unfold_lines(&folded)[0], line);
+    }
+
+    #[test]
+    fn never_folds_inside_a_character() {
+        let line = format!("SUMMARY:{}", "é".repeat(80));
+        let folded = fold_line(&line);
+
+        for physical in folded.trim_end_matches("\r\n").split("\r\n") {
+            assert!(physical.len() <= MAX_LINE_OCTETS);
+        }
+        assert_eq!(unfold_lines(&folded)[0], line);
+    }
+
+    #[test]
+    fn parses_a_vtodo() {
+        let text = vcalendar(&[
+            "UID:abc@example.com",
+            "SUMMARY:Buy milk\\, eggs",
+            "DESCRIPTION:From the shop on the",
+            "  corner\\nbefore noon",
+            "STATUS:IN-PROCESS",
+            "DUE:20261031T170000Z",
+            "BEGIN:VALARM",
+            "ACTION:DISPLAY",
+            "DESCRIPTION:Reminder",
+            "END:VALARM",
+        ]);
+        let data = parse_vtodo(&text).unwrap();
+
+        assert_eq!(data.uid.as_deref(), Some("abc@example.com"));
+        assert_eq!(data.summary.as_deref(), Some("Buy milk, eggs"));
+        assert_eq!(
+            data.description.as_deref(),
+            Some("From the shop on the corner\nbefore noon")
+        );
+        assert_eq!(data.status, Some(TodoStatus::InProcess));
+        assert_eq!(data.due, Some(utc("2026-10-31T17:00:00Z")));
+    }
+
+    #[test]
+    fn a_completed_timestamp_marks_the_todo_completed() {
+        let data = parse_vtodo(&vcalendar(&["UID:1", "COMPLETED:20261018T090000Z"])).unwrap();
+
+        assert_eq!(data.status, Some(TodoStatus::Completed));
+    }
+
+    #[test]
+    fn converts_due_times_from_their_time_zone() {
+        assert_eq!(
+            due("DUE;TZID=Europe/Berlin:20261018T120000"),
+            Ok(Some(utc("2026-10-18T10:00:00Z")))
+        );
+        assert_eq!(
+            due("DUE;TZID=\"America/New_York\":20260115T090000"),
+            Ok(Some(utc("2026-01-15T14:00:00Z")))
+        );
+        // Floating times and dates are taken as UTC
+        assert_eq!(
+            due("DUE:20261018T120000"),
+            Ok(Some(utc("2026-10-18T12:00:00Z")))
+        );
+        assert_eq!(
+            due("DUE;VALUE=DATE:20261031"),
+            Ok(Some(utc("2026-10-31T00:00:00Z")))
+        );
+    }
+
+    #[test]
+    fn handles_daylight_saving_transitions() {
+        // 02:30 happens twice as clocks go back; the first one counts
+        assert_eq!(
+            due("DUE;TZID=Europe/Berlin:20261025T023000"),
+            Ok(Some(utc("2026-10-25T00:30:00Z")))
+        );
+        // 02:30 never happens as clocks go forward
+        assert!(matches!(
+            due("DUE;TZID=Europe/Berlin:20260329T023000"),
+            Err(VTodoError::Invalid(_))
+        ));
+    }
+
+    #[test]
+    fn reports_unknown_time_zones() {
+        assert_eq!(
+            due("DUE;TZID=W. Europe Standard Time:20261018T120000"),
+            Err(VTodoError::UnknownTimeZone(
+                "W. Europe Standard Time".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn rejects_objects_without_a_vtodo() {
+        let event = "BEGIN:VCALENDAR\r\nBEGIN:VEVENT\r\nUID:1\r\nEND:VEVENT\r\nEND:VCALENDAR\r\n";
+
+        assert!(matches!(parse_vtodo(event), Err(VTodoError::Invalid(_))));
+        assert!(matches!(
+            parse_vtodo(&vcalendar(&["not a property"])),
+            Err(VTodoError::Invalid(_))
+        ));
+    }
+}
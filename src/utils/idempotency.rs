@@ -0,0 +1,601 @@
+use std::{future::Future, pin::pin, time::Duration as StdDuration};
+
+use actix_web::{
+    HttpRequest, HttpResponse,
+    body::{self, BoxBody},
+    http::{StatusCode, header},
+    rt,
+    web::Bytes,
+};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use sqlx::MySqlPool;
+
+use crate::{
+    models::idempotency_model::IdempotencyRecord,
+    schema::idempotency_schema::{
+        get_idempotency_record, refresh_idempotency_key, release_idempotency_key,
+        reserve_idempotency_key, save_idempotent_response,
+    },
+};
+
+const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+pub const REPLAYED_HEADER: &str = "Idempotent-Replayed";
+const MAX_KEY_LENGTH: usize = 255;
+const KEY_TTL_HOURS: i64 = 24;
+// How long the client waits for the response. A request that takes longer
+// keeps running with its key reserved, and a retry gets its response once it
+// is stored.
+const RESPONSE_TIMEOUT: StdDuration = StdDuration::from_secs(30);
+// A running request refreshes its reservation this often, so one that goes
+// unrefreshed for STALE_AFTER_SECONDS died with its process and can be
+// taken over
+const HEARTBEAT_INTERVAL: StdDuration = StdDuration::from_secs(10);
+const STALE_AFTER_SECONDS: i64 = 60;
+
+// Where keys and their responses are kept: the database, or memory in tests
+#[async_trait]
+pub trait IdempotencyStore: Clone + 'static {
+    async fn get(&self, scope: &str, key: &str) -> Result<Option<IdempotencyRecord>>;
+
+    // Returns false if another request holds the key
+    async fn reserve(
+        &self,
+        scope: &str,
+        key: &str,
+        request_hash: &str,
+        expires_at: DateTime<Utc>,
+        stale_before: DateTime<Utc>,
+    ) -> Result<bool>;
+
+    async fn refresh(&self, scope: &str, key: &str) -> Result<()>;
+
+    async fn save(
+        &self,
+        scope: &str,
+        key: &str,
+        status_code: i32,
+        headers: &str,
+        body: &[u8],
+    ) -> Result<()>;
+
+    async fn release(&self, scope: &str, key: &str) -> Result<()>;
+}
+
+#[async_trait]
+impl IdempotencyStore for MySqlPool {
+    async fn get(&self, scope: &str, key: &str) -> Result<Option<IdempotencyRecord>> {
+        get_idempotency_record(self, scope, key).await
+    }
+
+    async fn reserve(
+        &self,
+        scope: &str,
+        key: &str,
+        request_hash: &str,
+        expires_at: DateTime<Utc>,
+        stale_before: DateTime<Utc>,
+    ) -> Result<bool> {
+        reserve_idempotency_key(self, scope, key, request_hash, expires_at, stale_before).await
+    }
+
+    async fn refresh(&self, scope: &str, key: &str) -> Result<()> {
+        refresh_idempotency_key(self, scope, key).await
+    }
+
+    async fn save(
+        &self,
+        scope: &str,
+        key: &str,
+        status_code: i32,
+        headers: &str,
+        body: &[u8],
+    ) -> Result<()> {
+        save_idempotent_response(self, scope, key, status_code, headers, body).await
+    }
+
+    async fn release(&self, scope: &str, key: &str) -> Result<()> {
+        release_idempotency_key(self, scope, key).await
+    }
+}
+
+// A response with its body read, so it can be both stored and sent
+struct BufferedResponse {
+    status: StatusCode,
+    headers: Vec<(String, String)>,
+    body: Bytes,
+}
+
+impl BufferedResponse {
+    async fn read(response: HttpResponse) -> Self {
+        let status = response.status();
+        let headers = response
+            .headers()
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+
+        match body::to_bytes(response.into_body()).await {
+            Ok(body) => BufferedResponse {
+                status,
+                headers,
+                body,
+            },
+            Err(_) => {
+                log::error!("Could not buffer response for idempotency key");
+                BufferedResponse {
+                    status: StatusCode::INTERNAL_SERVER_ERROR,
+                    headers: vec![(
+                        header::CONTENT_TYPE.to_string(),
+                        "application/json".to_string(),
+                    )],
+                    body: Bytes::from_static(b"\"Error storing response\""),
+                }
+            }
+        }
+    }
+
+    fn from_record(record: IdempotencyRecord) -> Self {
+        let status = record
+            .status_code
+            .and_then(|code| u16::try_from(code).ok())
+            .and_then(|code| StatusCode::from_u16(code).ok())
+            .unwrap_or(StatusCode::OK);
+        let headers = record
+            .response_headers
+            .and_then(|h| serde_json::from_str(&h).ok())
+            .unwrap_or_default();
+
+        BufferedResponse {
+            status,
+            headers,
+            body: Bytes::from(record.response_body.unwrap_or_default()),
+        }
+    }
+
+    fn into_response(self, replayed: bool) -> HttpResponse {
+        let mut response = HttpResponse::build(self.status);
+        for (name, value) in self.headers {
+            response.append_header((name, value));
+        }
+        if replayed {
+            response.insert_header((REPLAYED_HEADER, "true"));
+        }
+        response.body(BoxBody::new(self.body))
+    }
+}
+
+// Identifies a request by method, path, query, preconditions and body, so a
+// reused key can be told apart from a genuine retry
+fn request_hash(req: &HttpRequest, body: &impl Serialize) -> String {
+    let if_match = req
+        .headers()
+        .get(header::IF_MATCH)
+        .map(|value| value.as_bytes())
+        .unwrap_or_default();
+
+    let mut hasher = Sha256::new();
+    hasher.update(req.method().as_str());
+    hasher.update(b" ");
+    hasher.update(req.path());
+    hasher.update(b"?");
+    hasher.update(req.query_string());
+    hasher.update(b"\n");
+    hasher.update(if_match);
+    hasher.update(b"\n");
+    hasher.update(serde_json::to_vec(body).unwrap_or_default());
+    format!("{:x}", hasher.finalize())
+}
+
+// Runs the handler of a request that holds a key to the end, whether or not
+// the client is still waiting, and stores its response for retries. Server
+// errors aren't final, so their key is released for the client to retry.
+async fn run_reserved<S: IdempotencyStore>(
+    store: S,
+    scope: String,
+    key: String,
+    handler: impl Future<Output = HttpResponse>,
+) -> BufferedResponse {
+    let mut handler = pin!(handler);
+    let mut heartbeat = rt::time::interval(HEARTBEAT_INTERVAL);
+    // The first tick completes immediately; the key was just reserved
+    heartbeat.tick().await;
+
+    let response = loop {
+        tokio::select! {
+            response = &mut handler => break response,
+            _ = heartbeat.tick() => {
+                if let Err(err) = store.refresh(&scope, &key).await {
+                    log::error!("Refresh idempotency key error: {}", err);
+                }
+            }
+        }
+    };
+    let response = BufferedResponse::read(response).await;
+
+    if response.status.is_server_error() {
+        if let Err(err) = store.release(&scope, &key).await {
+            log::error!("Release idempotency key error: {}", err);
+        }
+    } else if let Err(err) = store
+        .save(
+            &scope,
+            &key,
+            response.status.as_u16() as i32,
+            &serde_json::to_string(&response.headers).unwrap_or_default(),
+            &response.body,
+        )
+        .await
+    {
+        log::error!("Save idempotent response error: {}", err);
+    }
+
+    response
+}
+
+// Runs `handler` at most once per Idempotency-Key within `scope` (the user, or
+// "anonymous" for registration). The first response is stored for 24 hours
+// and replayed for retries; the same key with a different request is a
+// conflict. Requests without the header run as usual. Responses are stored as
+// sent, so handlers must not put credentials in them.
+pub async fn idempotent(
+    req: &HttpRequest,
+    pool: &MySqlPool,
+    scope: &str,
+    body: &impl Serialize,
+    handler: impl Future<Output = HttpResponse> + 'static,
+) -> HttpResponse {
+    run_idempotent(req, pool.clone(), scope, body, handler, RESPONSE_TIMEOUT).await
+}
+
+async fn run_idempotent<S: IdempotencyStore>(
+    req: &HttpRequest,
+    store: S,
+    scope: &str,
+    body: &impl Serialize,
+    handler: impl Future<Output = HttpResponse> + 'static,
+    response_timeout: StdDuration,
+) -> HttpResponse {
+    let Some(key) = req.headers().get(IDEMPOTENCY_KEY_HEADER) else {
+        return handler.await;
+    };
+    let key = match key.to_str() {
+        Ok(key) if !key.is_empty() && key.len() <= MAX_KEY_LENGTH => key.to_string(),
+        _ => {
+            return HttpResponse::BadRequest().json(format!(
+                "{} must be 1 to {} visible ASCII characters",
+                IDEMPOTENCY_KEY_HEADER, MAX_KEY_LENGTH
+            ));
+        }
+    };
+
+    let hash = request_hash(req, body);
+
+    match store.get(scope, &key).await {
+        Ok(Some(record)) if record.request_hash != hash => {
+            return HttpResponse::Conflict()
+                .json("Idempotency-Key was already used for a different request");
+        }
+        Ok(Some(record)) if record.is_complete() => {
+            return BufferedResponse::from_record(record).into_response(true);
+        }
+        Ok(_) => {}
+        Err(err) => {
+            log::error!("Get idempotency key error: {}", err);
+            return HttpResponse::InternalServerError().json("Error checking idempotency key");
+        }
+    }
+
+    let now = Utc::now();
+    match store
+        .reserve(
+            scope,
+            &key,
+            &hash,
+            now + Duration::hours(KEY_TTL_HOURS),
+            now - Duration::seconds(STALE_AFTER_SECONDS),
+        )
+        .await
+    {
+        Ok(true) => {}
+        Ok(false) => {
+            return HttpResponse::Conflict()
+                .json("A request with this Idempotency-Key is still being processed");
+        }
+        Err(err) => {
+            log::error!("Reserve idempotency key error: {}", err);
+            return HttpResponse::InternalServerError().json("Error checking idempotency key");
+        }
+    }
+
+    // The handler runs in its own task so that it finishes, and its response
+    // is stored, even if the client stops waiting
+    let task = rt::spawn(run_reserved(store, scope.to_string(), key, handler));
+    match rt::time::timeout(response_timeout, task).await {
+        Ok(Ok(response)) => response.into_response(false),
+        Ok(Err(err)) => {
+            log::error!("Idempotent request task error: {}", err);
+            HttpResponse::InternalServerError().json("Error processing request")
+        }
+        Err(_) => HttpResponse::ServiceUnavailable()
+            .insert_header((header::RETRY_AFTER, HEARTBEAT_INTERVAL.as_secs().to_string()))
+            .json("The request is still being processed; retry with the same Idempotency-Key for its response"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::HashMap,
+        sync::{
+            Arc, Mutex,
+            atomic::{AtomicUsize, Ordering},
+        },
+    };
+
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct MemoryStore {
+        records: Arc<Mutex<HashMap<(String, String), IdempotencyRecord>>>,
+    }
+
+    impl MemoryStore {
+        fn record(&self, key: &str) -> Option<(bool, Option<Vec<u8>>)> {
+            let records = self.records.lock().unwrap();
+            let record = records.get(&("user-1".to_string(), key.to_string()))?;
+            Some((record.is_complete(), record.response_body.clone()))
+        }
+    }
+
+    #[async_trait]
+    impl IdempotencyStore for MemoryStore {
+        async fn get(&self, scope: &str, key: &str) -> Result<Option<IdempotencyRecord>> {
+            let records = self.records.lock().unwrap();
+            Ok(records
+                .get(&(scope.to_string(), key.to_string()))
+                .map(|record| IdempotencyRecord {
+                    request_hash: record.request_hash.clone(),
+                    status_code: record.status_code,
+                    response_headers: record.response_headers.clone(),
+                    response_body: record.response_body.clone(),
+                }))
+        }
+
+        async fn reserve(
+            &self,
+            scope: &str,
+            key: &str,
+            request_hash: &str,
+            _expires_at: DateTime<Utc>,
+            _stale_before: DateTime<Utc>,
+        ) -> Result<bool> {
+            let mut records = self.records.lock().unwrap();
+            let id = (scope.to_string(), key.to_string());
+            if records.contains_key(&id) {
+                return Ok(false);
+            }
+            records.insert(
+                id,
+                IdempotencyRecord {
+                    request_hash: request_hash.to_string(),
+                    status_code: None,
+                    response_headers: None,
+                    response_body: None,
+                },
+            );
+            Ok(true)
+        }
+
+        async fn refresh(&self, _scope: &str, _key: &str) -> Result<()> {
+            Ok(())
+        }
+
+        async fn save(
+            &self,
+            scope: &str,
+            key: &str,
+            status_code: i32,
+            headers: &str,
+            body: &[u8],
+        ) -> Result<()> {
+            let mut records = self.records.lock().unwrap();
+            if let Some(record) = records.get_mut(&(scope.to_string(), key.to_string())) {
+                record.status_code = Some(status_code);
+                record.response_headers = Some(headers.to_string());
+                record.response_body = Some(body.to_vec());
+            }
+            Ok(())
+        }
+
+        async fn release(&self, scope: &str, key: &str) -> Result<()> {
+            let mut records = self.records.lock().unwrap();
+            records.remove(&(scope.to_string(), key.to_string()));
+            Ok(())
+        }
+    }
+
+    fn request(key: &str) -> HttpRequest {
+        TestRequest::post()
+            .uri("/todos")
+            .insert_header((IDEMPOTENCY_KEY_HEADER, key))
+            .to_http_request()
+    }
+
+    // A handler that counts its runs and responds after `delay`
+    fn handler(
+        runs: &Arc<AtomicUsize>,
+        status: StatusCode,
+        delay: StdDuration,
+    ) -> impl Future<Output = HttpResponse> + 'static {
+        let runs = runs.clone();
+        async move {
+            rt::time::sleep(delay).await;
+            let run = runs.fetch_add(1, Ordering::SeqCst) + 1;
+            HttpResponse::build(status).json(run)
+        }
+    }
+
+    async fn send(
+        store: &MemoryStore,
+        key: &str,
+        body: &str,
+        handler: impl Future<Output = HttpResponse> + 'static,
+        timeout: StdDuration,
+    ) -> (StatusCode, bool, Bytes) {
+        let response = run_idempotent(
+            &request(key),
+            store.clone(),
+            "user-1",
+            &body,
+            handler,
+            timeout,
+        )
+        .await;
+        let replayed = response.headers().contains_key(REPLAYED_HEADER);
+        let status = response.status();
+        (
+            status,
+            replayed,
+            body::to_bytes(response.into_body()).await.unwrap(),
+        )
+    }
+
+    const WAIT: StdDuration = StdDuration::from_secs(5);
+
+    #[actix_web::test]
+    async fn replays_the_first_response() {
+        let store = MemoryStore::default();
+        let runs = Arc::new(AtomicUsize::new(0));
+        let created = || handler(&runs, StatusCode::CREATED, StdDuration::ZERO);
+
+        let first = send(&store, "k1", "a", created(), WAIT).await;
+        let retry = send(&store, "k1", "a", created(), WAIT).await;
+
+        assert_eq!(first, (StatusCode::CREATED, false, Bytes::from("1")));
+        assert_eq!(retry, (StatusCode::CREATED, true, Bytes::from("1")));
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[actix_web::test]
+    async fn rejects_a_reused_key_and_a_key_in_use() {
+        let store = MemoryStore::default();
+        let runs = Arc::new(AtomicUsize::new(0));
+        let ok = || handler(&runs, StatusCode::OK, StdDuration::ZERO);
+
+        send(&store, "k1", "a", ok(), WAIT).await;
+        let reused = send(&store, "k1", "b", ok(), WAIT).await;
+        assert_eq!(reused.0, StatusCode::CONFLICT);
+
+        store
+            .reserve(
+                "user-1",
+                "k2",
+                &request_hash(&request("k2"), &"a"),
+                Utc::now(),
+                Utc::now(),
+            )
+            .await
+            .unwrap();
+        let in_use = send(&store, "k2", "a", ok(), WAIT).await;
+        assert_eq!(in_use.0, StatusCode::CONFLICT);
+
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[actix_web::test]
+    async fn keeps_the_key_of_a_slow_request_and_stores_its_response() {
+        let store = MemoryStore::default();
+        let runs = Arc::new(AtomicUsize::new(0));
+        let slow = handler(&runs, StatusCode::CREATED, StdDuration::from_millis(200));
+
+        let timed_out = send(&store, "k1", "a", slow, StdDuration::from_millis(20)).await;
+        assert_eq!(timed_out.0, StatusCode::SERVICE_UNAVAILABLE);
+        // Still reserved, so a retry can't run the handler a second time
+        assert_eq!(store.record("k1"), Some((false, None)));
+        let retry = send(
+            &store,
+            "k1",
+            "a",
+            handler(&runs, StatusCode::CREATED, StdDuration::ZERO),
+            WAIT,
+        )
+        .await;
+        assert_eq!(retry.0, StatusCode::CONFLICT);
+
+        rt::time::sleep(StdDuration::from_millis(400)).await;
+        let replayed = send(
+            &store,
+            "k1",
+            "a",
+            handler(&runs, StatusCode::CREATED, StdDuration::ZERO),
+            WAIT,
+        )
+        .await;
+        assert_eq!(replayed, (StatusCode::CREATED, true, Bytes::from("1")));
+        assert_eq!(runs.load(Ordering::SeqCst), 1);
+    }
+
+    #[actix_web::test]
+    async fn releases_the_key_after_a_server_error() {
+        let store = MemoryStore::default();
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        let failed = send(
+            &store,
+            "k1",
+            "a",
+            handler(&runs, StatusCode::INTERNAL_SERVER_ERROR, StdDuration::ZERO),
+            WAIT,
+        )
+        .await;
+        assert_eq!(failed.0, StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(store.record("k1"), None);
+
+        let retried = send(
+            &store,
+            "k1",
+            "a",
+            handler(&runs, StatusCode::OK, StdDuration::ZERO),
+            WAIT,
+        )
+        .await;
+        assert_eq!(retried, (StatusCode::OK, false, Bytes::from("2")));
+    }
+
+    #[actix_web::test]
+    async fn runs_requests_without_a_key_as_usual() {
+        let store = MemoryStore::default();
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        for _ in 0..2 {
+            let response = run_idempotent(
+                &TestRequest::post().to_http_request(),
+                store.clone(),
+                "user-1",
+                &"a",
+                handler(&runs, StatusCode::CREATED, StdDuration::ZERO),
+                WAIT,
+            )
+            .await;
+            assert_eq!(response.status(), StatusCode::CREATED);
+        }
+        assert_eq!(runs.load(Ordering::SeqCst), 2);
+
+        let too_long = "k".repeat(MAX_KEY_LENGTH + 1);
+        let invalid = send(
+            &store,
+            &too_long,
+            "a",
+            handler(&runs, StatusCode::CREATED, StdDuration::ZERO),
+            WAIT,
+        )
+        .await;
+        assert_eq!(invalid.0, StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,811 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
+use serde_json::{Map, Value};
+
+use crate::models::{
+    import_model::{FieldMapping, ImportFormat, ImportedTodo, map_status},
+    todo_model::TodoStatus,
+};
+
+// Parsing of the supported import formats into todos. A file that can't be
+// read at all is an error; a row that can't be mapped is reported on its own.
+
+// Row number in the source and what it mapped to
+pub type ParsedRow = (usize, Result<ImportedTodo, String>);
+
+const TITLE_COLUMNS: &[&str] = &["title", "name", "task", "content", "summary", "subject"];
+const DESCRIPTION_COLUMNS: &[&str] = &["description", "notes", "note", "details", "desc"];
+const STATUS_COLUMNS: &[&str] = &["status", "state", "done", "completed"];
+const DUE_DATE_COLUMNS: &[&str] = &["due_date", "due date", "due", "deadline"];
+
+// Bookkeeping fields of our own todo JSON, which a new todo gets fresh
+const SYSTEM_FIELDS: &[&str] = &[
+    "id",
+    "user_id",
+    "series_id",
+    "occurrence_index",
+    "workflow_status_id",
+    "version",
+    "blocked_by",
+    "blocking",
+    "comment_count",
+    "started_at",
+    "completed_at",
+    "archived_at",
+    "deleted_at",
+    "created_at",
+    "updated_at",
+];
+
+// Picks the format from the file name, then from the shape of the content
+pub fn detect_format(filename: Option<&str>, content: &str) -> Option<ImportFormat> {
+    let extension = filename
+        .and_then(|name| name.rsplit_once('.'))
+        .map(|(_, extension)| extension.to_lowercase());
+
+    match extension.as_deref() {
+        Some("csv") => return Some(ImportFormat::Csv),
+        Some("txt") => return Some(ImportFormat::TodoTxt),
+        _ => {}
+    }
+
+    let Ok(value) = serde_json::from_str::<Value>(content) else {
+        return match extension.as_deref() {
+            Some("json") => None,
+            _ if content.trim_start().starts_with(['{', '[']) => None,
+            _ => Some(ImportFormat::TodoTxt),
+        };
+    };
+
+    if value.get("cards").is_some() && value.get("lists").is_some() {
+        return Some(ImportFormat::Trello);
+    }
+
+    let first = json_items(&value).and_then(|items| items.first());
+    match first {
+        Some(item) if item.get("content").is_some() => Some(ImportFormat::Todoist),
+        _ => Some(ImportFormat::Json),
+    }
+}
+
+pub fn parse_import(
+    format: ImportFormat,
+    content: &str,
+    mapping: &FieldMapping,
+) -> Result<Vec<ParsedRow>, String> {
+    match format {
+        ImportFormat::Csv => parse_csv(content, mapping),
+        ImportFormat::Json => parse_json(content),
+        ImportFormat::TodoTxt => Ok(parse_todo_txt(content)),
+        ImportFormat::Todoist => parse_todoist(content),
+        ImportFormat::Trello => parse_trello(content),
+    }
+}
+
+// Accepts RFC 3339 timestamps, and dates or local times taken as UTC
+pub fn parse_due_date(value: &str) -> Result<DateTime<Utc>, String> {
+    let value = value.trim();
+
+    if let Ok(parsed) = DateTime::parse_from_rfc3339(value) {
+        return Ok(parsed.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(parsed) = NaiveDateTime::parse_from_str(value, format) {
+            return Ok(parsed.and_utc());
+        }
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc());
+    }
+
+    Err(format!("Invalid due date '{}'", value))
+}
+
+// A status we don't know becomes pending, with the original value kept
+fn status_or_extra(
+    value: Option<&str>,
+    label: &str,
+    extra: &mut Vec<(String, String)>,
+) -> TodoStatus {
+    let Some(value) = value else {
+        return TodoStatus::default();
+    };
+    match map_status(value) {
+        Some(status) => status,
+        None => {
+            extra.push((label.to_string(), value.to_string()));
+            TodoStatus::default()
+        }
+    }
+}
+
+fn optional_due_date(value: Option<&str>) -> Result<Option<DateTime<Utc>>, String> {
+    value
+        .filter(|v| !v.trim().is_empty())
+        .map(parse_due_date)
+        .transpose()
+}
+
+fn find_column(
+    headers: &[String],
+    mapped: Option<&String>,
+    candidates: &[&str],
+) -> Result<Option<usize>, String> {
+    if let Some(name) = mapped {
+        return headers
+            .iter()
+            .position(|h| h.eq_ignore_ascii_case(name.trim()))
+            .map(Some)
+            .ok_or_else(|| format!("Column '{}' not found", name));
+    }
+
+    Ok(candidates.iter().find_map(|candidate| {
+        headers
+            .iter()
+            .position(|h| h.eq_ignore_ascii_case(candidate))
+    }))
+}
+
+fn parse_csv(content: &str, mapping: &FieldMapping) -> Result<Vec<ParsedRow>, String> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(content.trim_start_matches('\u{feff}').as_bytes());
+
+    let headers = reader
+        .headers()
+        .map_err(|err| format!("Invalid CSV header: {}", err))?
+        .iter()
+        .map(str::to_string)
+        .collect::<Vec<_>>();
+
+    let title = find_column(&headers, mapping.title.as_ref(), TITLE_COLUMNS)?
+        .ok_or("No title column; name one in the mapping")?;
+    let description = find_column(&headers, mapping.description.as_ref(), DESCRIPTION_COLUMNS)?;
+    let status = find_column(&headers, mapping.status.as_ref(), STATUS_COLUMNS)?;
+    let due_date = find_column(&headers, mapping.due_date.as_ref(), DUE_DATE_COLUMNS)?;
+    let mapped = [Some(title), description, status, due_date];
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(err) => {
+                let line = err.position().map_or(0, |p| p.line() as usize);
+                rows.push((line, Err(format!("Invalid CSV row: {}", err))));
+                continue;
+            }
+        };
+        if record.iter().all(str::is_empty) {
+            continue;
+        }
+
+        let line = record.position().map_or(0, |p| p.line() as usize);
+        let field = |index: Option<usize>| index.and_then(|i| record.get(i));
+
+        let mut extra = headers
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !mapped.contains(&Some(*i)))
+            .filter_map(|(i, header)| Some((header.clone(), record.get(i)?.to_string())))
+            .collect::<Vec<_>>();
+        let status_label = status.map_or("Status", |i| headers[i].as_str());
+        let todo_status = status_or_extra(field(status), status_label, &mut extra);
+
+        let todo = optional_due_date(field(due_date)).and_then(|due_date| {
+            ImportedTodo::new(
+                field(Some(title)).unwrap_or_default(),
+                field(description),
+                todo_status,
+                due_date,
+                &extra,
+            )
+        });
+        rows.push((line, todo));
+    }
+
+    Ok(rows)
+}
+
+fn parse_json_value(content: &str) -> Result<Value, String> {
+    serde_json::from_str(content.trim_start_matches('\u{feff}'))
+        .map_err(|err| format!("Invalid JSON: {}", err))
+}
+
+// The todo list of a JSON document: a bare array or the array under one of
+// the keys exports commonly use
+fn json_items(value: &Value) -> Option<&Vec<Value>> {
+    match value {
+        Value::Array(items) => Some(items),
+        Value::Object(object) => ["items", "todos", "tasks"]
+            .iter()
+            .find_map(|key| object.get(*key)?.as_array()),
+        _ => None,
+    }
+}
+
+// Renders a JSON value for the description: scalars as-is, lists of scalars
+// comma separated, anything else as compact JSON
+fn display_value(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(s) => Some(s.clone()),
+        Value::Bool(_) | Value::Number(_) => Some(value.to_string()),
+        Value::Array(items) if items.iter().all(|i| !i.is_object() && !i.is_array()) => {
+            let items = items.iter().filter_map(display_value).collect::<Vec<_>>();
+            (!items.is_empty()).then(|| items.join(", "))
+        }
+        _ => Some(value.to_string()),
+    }
+}
+
+fn str_field<'a>(object: &'a Map<String, Value>, key: &str) -> Option<&'a str> {
+    object.get(key).and_then(Value::as_str)
+}
+
+fn truthy(value: Option<&Value>) -> bool {
+    match value {
+        Some(Value::Bool(b)) => *b,
+        Some(Value::Number(n)) => n.as_i64().is_some_and(|n| n != 0),
+        _ => false,
+    }
+}
+
+fn parse_json(content: &str) -> Result<Vec<ParsedRow>, String> {
+    let value = parse_json_value(content)?;
+    let items = json_items(&value).ok_or("Expected an array of todos")?;
+
+    Ok(items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| (index + 1, parse_json_todo(item)))
+        .collect())
+}
+
+fn parse_json_todo(item: &Value) -> Result<ImportedTodo, String> {
+    let object = item.as_object().ok_or("Expected a todo object")?;
+    let title = str_field(object, "title").ok_or("Title is required")?;
+
+    let mut extra = object
+        .iter()
+        .filter(|(key, _)| {
+            !matches!(
+                key.as_str(),
+                "title" | "description" | "status" | "due_date" | "recurrence_rule"
+            ) && !SYSTEM_FIELDS.contains(&key.as_str())
+        })
+        .filter_map(|(key, value)| Some((key.clone(), display_value(value)?)))
+        .collect::<Vec<_>>();
+    let status = status_or_extra(str_field(object, "status"), "status", &mut extra);
+    let due_date = optional_due_date(str_field(object, "due_date"))?;
+
+    let mut todo = ImportedTodo::new(
+        title,
+        str_field(object, "description"),
+        status,
+        due_date,
+        &extra,
+    )?;
+    todo.recurrence_rule = str_field(object, "recurrence_rule").map(str::to_string);
+    Ok(todo)
+}
+
+// todo.txt: `x` marks completion, followed by the completion and creation
+// dates; open tasks may start with a (A) priority and a creation date.
+// key:value tags other than due: are kept in the description.
+fn parse_todo_txt(content: &str) -> Vec<ParsedRow> {
+    content
+        .trim_start_matches('\u{feff}')
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| (index + 1, parse_todo_txt_line(line.trim())))
+        .collect()
+}
+
+fn is_date(word: &str) -> bool {
+    NaiveDate::parse_from_str(word, "%Y-%m-%d").is_ok()
+}
+
+fn parse_todo_txt_line(line: &str) -> Result<ImportedTodo, String> {
+    let mut words = line.split_whitespace().peekable();
+    let mut extra = Vec::new();
+
+    let completed = words.next_if_eq(&"x").is_some();
+    if !completed {
+        if let Some(priority) = words.next_if(|w| {
+            w.len() == 3
+                && w.starts_with('(')
+                && w.ends_with(')')
+                && w.as_bytes()[1].is_ascii_uppercase()
+        }) {
+            extra.push(("Priority".to_string(), priority[1..2].to_string()));
+        }
+    }
+    if completed {
+        if let Some(date) = words.next_if(|w| is_date(w)) {
+            extra.push(("Completed".to_string(), date.to_string()));
+        }
+    }
+    if let Some(date) = words.next_if(|w| is_date(w)) {
+        extra.push(("Created".to_string(), date.to_string()));
+    }
+
+    let mut title = Vec::new();
+    let mut due_date = None;
+    for word in words {
+        // Anything with a scheme such as http:// is text, not a tag
+        let tag = word
+            .split_once(':')
+            .filter(|(key, value)| !key.is_empty() && !value.is_empty() && !value.starts_with('/'));
+        match tag {
+            Some(("due", value)) => due_date = Some(parse_due_date(value)?),
+            Some((key, value)) => extra.push((key.to_string(), value.to_string())),
+            None => title.push(word),
+        }
+    }
+
+    let status = if completed {
+        TodoStatus::Completed
+    } else {
+        TodoStatus::Pending
+    };
+    ImportedTodo::new(&title.join(" "), None, status, due_date, &extra)
+}
+
+// Todoist JSON: the REST API's task array or a sync/backup document with
+// `items` and `projects`
+fn parse_todoist(content: &str) -> Result<Vec<ParsedRow>, String> {
+    let value = parse_json_value(content)?;
+    let items = json_items(&value).ok_or("Expected Todoist tasks or items")?;
+
+    let names = |key: &str| -> HashMap<String, String> {
+        value
+            .get(key)
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| {
+                let id = display_value(entry.get("id")?)?;
+                let name = entry.get("name")?.as_str()?.to_string();
+                Some((id, name))
+            })
+            .collect()
+    };
+    let projects = names("projects");
+    let sections = names("sections");
+
+    Ok(items
+        .iter()
+        .enumerate()
+        .map(|(index, item)| (index + 1, parse_todoist_task(item, &projects, &sections)))
+        .collect())
+}
+
+fn parse_todoist_task(
+    item: &Value,
+    projects: &HashMap<String, String>,
+    sections: &HashMap<String, String>,
+) -> Result<ImportedTodo, String> {
+    let object = item.as_object().ok_or("Expected a task object")?;
+    let title = str_field(object, "content").ok_or("Task content is required")?;
+    let mut extra = Vec::new();
+
+    let lookup = |key: &str, names: &HashMap<String, String>| {
+        let id = display_value(object.get(key)?)?;
+        Some(names.get(&id).cloned().unwrap_or(id))
+    };
+    if let Some(project) = lookup("project_id", projects) {
+        extra.push(("Project".to_string(), project));
+    }
+    if let Some(section) = lookup("section_id", sections) {
+        extra.push(("Section".to_string(), section));
+    }
+    // Todoist's priority 4 is what its apps show as p1
+    if let Some(priority) = object.get("priority").and_then(Value::as_i64) {
+        if priority > 1 {
+            extra.push(("Priority".to_string(), format!("p{}", 5 - priority.min(4))));
+        }
+    }
+    if let Some(labels) = object.get("labels").and_then(display_value) {
+        extra.push(("Labels".to_string(), labels));
+    }
+
+    let due = object.get("due").and_then(Value::as_object);
+    let due_date =
+        optional_due_date(due.and_then(|d| str_field(d, "datetime").or(str_field(d, "date"))))?;
+    if let Some(due) = due.filter(|d| truthy(d.get("is_recurring"))) {
+        if let Some(rule) = str_field(due, "string") {
+            extra.push(("Repeats".to_string(), rule.to_string()));
+        }
+    }
+    if let Some(url) = str_field(object, "url") {
+        extra.push(("URL".to_string(), url.to_string()));
+    }
+
+    let completed = ["checked", "is_completed", "completed"]
+        .iter()
+        .any(|key| truthy(object.get(*key)));
+    let status = if completed {
+        TodoStatus::Completed
+    } else {
+        TodoStatus::Pending
+    };
+
+    ImportedTodo::new(
+        title,
+        str_field(object, "description"),
+        status,
+        due_date,
+        &extra,
+    )
+}
+
+// Trello board export: each card becomes a todo. The list decides the status
+// when its name is one we recognise, such as "Doing" or "Done".
+fn parse_trello(content: &str) -> Result<Vec<ParsedRow>, String> {
+    let value = parse_json_value(content)?;
+    let cards = value
+        .get("cards")
+        .and_then(Value::as_array)
+        .ok_or("Expected a Trello board export")?;
+
+    let lists = value
+        .get("lists")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|list| Some((list.get("id")?.as_str()?, list.get("name")?.as_str()?)))
+        .collect::<HashMap<_, _>>();
+
+    let mut checklists: HashMap<&str, Vec<String>> = HashMap::new();
+    for checklist in value
+        .get("checklists")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+    {
+        let (Some(card_id), Some(name)) = (
+            checklist.get("idCard").and_then(Value::as_str),
+            checklist.get("name").and_then(Value::as_str),
+        ) else {
+            continue;
+        };
+        let items = checklist
+            .get("checkItems")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|item| {
+                let done = item.get("state").and_then(Value::as_str) == Some("complete");
+                let name = item.get("name")?.as_str()?;
+                Some(format!("[{}] {}", if done { "x" } else { " " }, name))
+            })
+            .collect::<Vec<_>>();
+        checklists
+            .entry(card_id)
+            .or_default()
+            .push(format!("{} ({})", name, items.join("; ")));
+    }
+
+    Ok(cards
+        .iter()
+        .enumerate()
+        .map(|(index, card)| (index + 1, parse_trello_card(card, &lists, &checklists)))
+        .collect())
+}
+
+fn parse_trello_card(
+    card: &Value,
+    lists: &HashMap<&str, &str>,
+    checklists: &HashMap<&str, Vec<String>>,
+) -> Result<ImportedTodo, String> {
+    let object = card.as_object().ok_or("Expected a card object")?;
+    let title = str_field(object, "name").ok_or("Card name is required")?;
+    let mut extra = Vec::new();
+
+    let list = str_field(object, "idList").and_then(|id| lists.get(id).copied());
+    if let Some(list) = list {
+        extra.push(("List".to_string(), list.to_string()));
+    }
+
+    let labels = object
+        .get("labels")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(|label| {
+            let name = label
+                .get("name")
+                .and_then(Value::as_str)
+                .filter(|n| !n.is_empty());
+            name.or_else(|| label.get("color")?.as_str())
+        })
+        .collect::<HashSet<_>>();
+    if !labels.is_empty() {
+        let mut labels = labels.into_iter().collect::<Vec<_>>();
+        labels.sort_unstable();
+        extra.push(("Labels".to_string(), labels.join(", ")));
+    }
+
+    if let Some(id) = str_field(object, "id") {
+        for checklist in checklists.get(id).into_iter().flatten() {
+            extra.push(("Checklist".to_string(), checklist.clone()));
+        }
+    }
+    if truthy(object.get("closed")) {
+        extra.push(("Archived in Trello".to_string(), "yes".to_string()));
+    }
+    if let Some(url) = str_field(object, "shortUrl").or(str_field(object, "url")) {
+        extra.push(("URL".to_string(), url.to_string()));
+    }
+
+    let status = if truthy(object.get("dueComplete")) {
+        TodoStatus::Completed
+    } else {
+        list.and_then(map_status).unwrap_or_default()
+    };
+    let due_date = optional_due_date(str_field(object, "due"))?;
+
+    ImportedTodo::new(title, str_field(object, "desc"), status, due_date, &extra)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn todos(rows: Vec<ParsedRow>) -> Vec<ImportedTodo> {
+        rows.into_iter()
+            .map(|(row, todo)| todo.unwrap_or_else(|err| panic!("row {}: {}", row, err)))
+            .collect()
+    }
+
+    fn error(row: &ParsedRow) -> &str {
+        row.1.as_ref().unwrap_err()
+    }
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value).unwrap().to_utc()
+    }
+
+    #[test]
+    fn detects_formats() {
+        assert_eq!(
+            detect_format(Some("tasks.CSV"), "title\nA"),
+            Some(ImportFormat::Csv)
+        );
+        assert_eq!(
+            detect_format(Some("todo.txt"), "[1]"),
+            Some(ImportFormat::TodoTxt)
+        );
+        assert_eq!(
+            detect_format(None, r#"{"cards": [], "lists": []}"#),
+            Some(ImportFormat::Trello)
+        );
+        assert_eq!(
+            detect_format(Some("export.json"), r#"{"items": [{"content": "A"}]}"#),
+            Some(ImportFormat::Todoist)
+        );
+        assert_eq!(
+            detect_format(None, r#"[{"title": "A"}]"#),
+            Some(ImportFormat::Json)
+        );
+        assert_eq!(
+            detect_format(None, "(A) Call mum due:2026-10-20"),
+            Some(ImportFormat::TodoTxt)
+        );
+        assert_eq!(detect_format(Some("export.json"), "not json"), None);
+        assert_eq!(detect_format(None, r#"[{"title": "#), None);
+    }
+
+    #[test]
+    fn parses_due_dates() {
+        assert_eq!(
+            parse_due_date("2026-10-20T09:30:00+02:00"),
+            Ok(utc("2026-10-20T07:30:00Z"))
+        );
+        assert_eq!(
+            parse_due_date("2026-10-20 09:30"),
+            Ok(utc("2026-10-20T09:30:00Z"))
+        );
+        assert_eq!(
+            parse_due_date(" 2026-10-20 "),
+            Ok(utc("2026-10-20T00:00:00Z"))
+        );
+        assert_eq!(
+            parse_due_date("20/10/2026"),
+            Err("Invalid due date '20/10/2026'".to_string())
+        );
+    }
+
+    #[test]
+    fn parses_csv_rows() {
+        let content = "\u{feff}Name,Notes,State,Deadline,Priority\n\
+                       Buy milk,Two litres,Doing,2026-10-20,High\n\
+                       Call Bob,,someday,,\n\
+                       ,,,,\n\
+                       Pay rent,,,next week,\n\
+                       ,Orphan note,,,\n";
+        let rows = parse_csv(content, &FieldMapping::default()).unwrap();
+
+        assert_eq!(
+            rows.iter().map(|(row, _)| *row).collect::<Vec<_>>(),
+            vec![2, 3, 5, 6]
+        );
+
+        let milk = rows[0].1.as_ref().unwrap();
+        assert_eq!(milk.title, "Buy milk");
+        assert_eq!(
+            milk.description.as_deref(),
+            Some("Two litres\n\nPriority: High")
+        );
+        assert_eq!(milk.status, TodoStatus::InProcess);
+        assert_eq!(milk.due_date, Some(utc("2026-10-20T00:00:00Z")));
+
+        let bob = rows[1].1.as_ref().unwrap();
+        assert_eq!(bob.status, TodoStatus::Pending);
+        assert_eq!(bob.description.as_deref(), Some("State: someday"));
+
+        assert_eq!(error(&rows[2]), "Invalid due date 'next week'");
+        assert_eq!(error(&rows[3]), "Title is required");
+    }
+
+    #[test]
+    fn uses_the_csv_field_mapping() {
+        let content = "Task,Subject\nIgnored,Renew passport\n";
+        let mapping = FieldMapping {
+            title: Some("subject".to_string()),
+            ..FieldMapping::default()
+        };
+        let rows = todos(parse_csv(content, &mapping).unwrap());
+
+        assert_eq!(rows[0].title, "Renew passport");
+        assert_eq!(rows[0].description.as_deref(), Some("Task: Ignored"));
+
+        let missing = FieldMapping {
+            due_date: Some("When".to_string()),
+            ..FieldMapping::default()
+        };
+        assert_eq!(
+            parse_csv(content, &missing).unwrap_err(),
+            "Column 'When' not found"
+        );
+        assert!(parse_csv("Notes\nA\n", &FieldMapping::default()).is_err());
+    }
+
+    #[test]
+    fn parses_our_own_json() {
+        let content = r#"{"todos": [
+            {
+                "id": "5f0c", "version": 3, "created_at": "2026-01-01T00:00:00Z",
+                "title": "Water plants", "description": "Balcony",
+                "status": "blocked", "due_date": "2026-10-21T08:00:00Z",
+                "recurrence_rule": "FREQ=WEEKLY", "tags": ["home", "garden"]
+            },
+            {"description": "No title"},
+            "not an object"
+        ]}"#;
+        let rows = parse_json(content).unwrap();
+
+        let plants = rows[0].1.as_ref().unwrap();
+        assert_eq!(plants.title, "Water plants");
+        assert_eq!(
+            plants.description.as_deref(),
+            Some("Balcony\n\ntags: home, garden\nstatus: blocked")
+        );
+        assert_eq!(plants.status, TodoStatus::Pending);
+        assert_eq!(plants.due_date, Some(utc("2026-10-21T08:00:00Z")));
+        assert_eq!(plants.recurrence_rule.as_deref(), Some("FREQ=WEEKLY"));
+
+        assert_eq!((rows[1].0, error(&rows[1])), (2, "Title is required"));
+        assert_eq!((rows[2].0, error(&rows[2])), (3, "Expected a todo object"));
+        assert!(parse_json(r#"{"title": "A"}"#).is_err());
+    }
+
+    #[test]
+    fn parses_todo_txt() {
+        let content = "x 2026-10-17 2026-10-01 Pay rent due:2026-10-20 +home @desk\n\
+                       \n\
+                       (A) 2026-10-02 Read https://example.com/docs pri:x\n\
+                       Call mum due:soon\n";
+        let rows = parse_todo_txt(content);
+
+        assert_eq!(
+            rows.iter().map(|(row, _)| *row).collect::<Vec<_>>(),
+            vec![1, 3, 4]
+        );
+
+        let rent = rows[0].1.as_ref().unwrap();
+        assert_eq!(rent.title, "Pay rent +home @desk");
+        assert_eq!(rent.status, TodoStatus::Completed);
+        assert_eq!(rent.due_date, Some(utc("2026-10-20T00:00:00Z")));
+        assert_eq!(
+            rent.description.as_deref(),
+            Some("Completed: 2026-10-17\nCreated: 2026-10-01")
+        );
+
+        let read = rows[1].1.as_ref().unwrap();
+        assert_eq!(read.title, "Read https://example.com/docs");
+        assert_eq!(read.status, TodoStatus::Pending);
+        assert_eq!(
+            read.description.as_deref(),
+            Some("Priority: A\nCreated: 2026-10-02\npri: x")
+        );
+
+        assert_eq!(error(&rows[2]), "Invalid due date 'soon'");
+    }
+
+    #[test]
+    fn parses_todoist_backups() {
+        let content = r#"{
+            "projects": [{"id": "220", "name": "Errands"}],
+            "items": [
+                {
+                    "content": "Pick up parcel", "project_id": "220", "priority": 4,
+                    "labels": ["post", "town"], "checked": true,
+                    "due": {"date": "2026-10-22", "is_recurring": true, "string": "every thu"}
+                },
+                {"content": "Unfiled", "project_id": 999, "priority": 1},
+                {"project_id": "220"}
+            ]
+        }"#;
+        let rows = parse_todoist(content).unwrap();
+
+        let parcel = rows[0].1.as_ref().unwrap();
+        assert_eq!(parcel.title, "Pick up parcel");
+        assert_eq!(parcel.status, TodoStatus::Completed);
+        assert_eq!(parcel.due_date, Some(utc("2026-10-22T00:00:00Z")));
+        assert_eq!(
+            parcel.description.as_deref(),
+            Some("Project: Errands\nPriority: p1\nLabels: post, town\nRepeats: every thu")
+        );
+
+        let unfiled = rows[1].1.as_ref().unwrap();
+        assert_eq!(unfiled.status, TodoStatus::Pending);
+        assert_eq!(unfiled.description.as_deref(), Some("Project: 999"));
+
+        assert_eq!(error(&rows[2]), "Task content is required");
+    }
+
+    #[test]
+    fn parses_trello_boards() {
+        let content = r#"{
+            "lists": [{"id": "l1", "name": "Doing"}, {"id": "l2", "name": "Ideas"}],
+            "checklists": [{
+                "idCard": "c1", "name": "Steps",
+                "checkItems": [
+                    {"name": "Sand", "state": "complete"},
+                    {"name": "Paint", "state": "incomplete"}
+                ]
+            }],
+            "cards": [
+                {
+                    "id": "c1", "name": "Fix fence", "desc": "Back garden", "idList": "l1",
+                    "labels": [{"name": "", "color": "red"}, {"name": "Home"}, {"name": "Home"}],
+                    "due": "2026-10-24T12:00:00.000Z", "shortUrl": "https://trello.com/c/abc"
+                },
+                {"id": "c2", "name": "Shed", "idList": "l2", "closed": true, "dueComplete": true}
+            ]
+        }"#;
+        let rows = todos(parse_trello(content).unwrap());
+
+        assert_eq!(rows[0].title, "Fix fence");
+        assert_eq!(rows[0].status, TodoStatus::InProcess);
+        assert_eq!(rows[0].due_date, Some(utc("2026-10-24T12:00:00Z")));
+        assert_eq!(
+            rows[0].description.as_deref(),
+            Some(
+                "Back garden\n\nList: Doing\nLabels: Home, red\n\
+                 Checklist: Steps ([x] Sand; [ ] Paint)\nURL: https://trello.com/c/abc"
+            )
+        );
+
+        assert_eq!(rows[1].status, TodoStatus::Completed);
+        assert_eq!(
+            rows[1].description.as_deref(),
+            Some("List: Ideas\nArchived in Trello: yes")
+        );
+        assert!(parse_trello(r#"{"lists": []}"#).is_err());
+    }
+}
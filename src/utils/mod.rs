@@ -1 +1,13 @@
+pub mod account_export;
+pub mod dav_xml;
+pub mod etag;
+pub mod event_hub;
+pub mod export;
 pub mod get_env_vars;
+pub mod ical;
+pub mod idempotency;
+pub mod import;
+pub mod patch;
+pub mod recurrence;
+pub mod webhooks;
+pub mod ws_tickets;
@@ -0,0 +1,104 @@
+use serde::{Deserialize, Deserializer};
+
+// A field of a JSON Merge Patch (RFC 7396) body: left out, explicitly null, or
+// set. Fields of this type need `#[serde(default)]` so absence is detected.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum Patch<T> {
+    #[default]
+    Absent,
+    Null,
+    Value(T),
+}
+
+impl<T> Patch<T> {
+    pub fn is_absent(&self) -> bool {
+        matches!(self, Patch::Absent)
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, Patch::Null)
+    }
+
+    pub fn as_value(&self) -> Option<&T> {
+        match self {
+            Patch::Value(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    // The field's value after applying the patch to `current`
+    pub fn apply<'a>(&'a self, current: Option<&'a T>) -> Option<&'a T> {
+        match self {
+            Patch::Absent => current,
+            Patch::Null => None,
+            Patch::Value(value) => Some(value),
+        }
+    }
+}
+
+impl<T> From<Option<T>> for Patch<T> {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => Patch::Value(value),
+            None => Patch::Null,
+        }
+    }
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Patch<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Option::<T>::deserialize(deserializer).map(Patch::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize)]
+    struct TodoPatch {
+        #[serde(default)]
+        description: Patch<String>,
+        #[serde(default)]
+        due_date: Patch<String>,
+    }
+
+    fn parse(body: &str) -> TodoPatch {
+        serde_json::from_str(body).unwrap()
+    }
+
+    #[test]
+    fn tells_absent_null_and_set_fields_apart() {
+        let patch = parse(r#"{"description": null, "due_date": "2026-03-01"}"#);
+        assert!(patch.description.is_null());
+        assert_eq!(
+            patch.due_date.as_value().map(String::as_str),
+            Some("2026-03-01")
+        );
+
+        let patch = parse("{}");
+        assert!(patch.description.is_absent());
+        assert!(patch.due_date.is_absent());
+    }
+
+    #[test]
+    fn applies_to_the_current_value() {
+        let current = "Buy milk".to_string();
+        let replacement = "Buy oat milk".to_string();
+
+        assert_eq!(Patch::Absent.apply(Some(&current)), Some(&current));
+        assert_eq!(Patch::<String>::Absent.apply(None), None);
+        assert_eq!(Patch::Null.apply(Some(&current)), None);
+        assert_eq!(
+            Patch::Value(replacement.clone()).apply(Some(&current)),
+            Some(&replacement)
+        );
+    }
+
+    #[test]
+    fn a_wrongly_typed_value_is_an_error() {
+        assert!(serde_json::from_str::<TodoPatch>(r#"{"description": 5}"#).is_err());
+    }
+}
@@ -0,0 +1,469 @@
+use std::{fmt, str::FromStr};
+
+use chrono::{DateTime, Datelike, Days, NaiveDate, NaiveTime, Utc, Weekday};
+
+// Supported subset of RFC 5545 RRULE: FREQ, INTERVAL, BYDAY, COUNT and UNTIL
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByDay {
+    pub ordinal: Option<i8>, // e.g. 2 for "2TU", -1 for "-1FR" (MONTHLY only)
+    pub weekday: Weekday,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecurrenceRule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub by_day: Vec<ByDay>,
+    pub count: Option<u32>,
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl RecurrenceRule {
+    // Due date of the occurrence following `current`, which is occurrence number
+    // `occurrence_index` (1-based) of the series. None once the series is exhausted.
+    pub fn next_after(
+        &self,
+        current: DateTime<Utc>,
+        occurrence_index: u32,
+    ) -> Option<DateTime<Utc>> {
+        if let Some(count) = self.count {
+            if occurrence_index >= count {
+                return None;
+            }
+        }
+
+        let date = current.date_naive();
+        let next_date = match self.frequency {
+            Frequency::Daily => self.next_daily(date),
+            Frequency::Weekly => self.next_weekly(date),
+            Frequency::Monthly => self.next_monthly(date),
+            Frequency::Yearly => self.next_yearly(date),
+        }?;
+        let next = next_date.and_time(current.time()).and_utc();
+
+        match self.until {
+            Some(until) if next > until => None,
+            _ => Some(next),
+        }
+    }
+
+    fn matches_weekday(&self, date: NaiveDate) -> bool {
+        self.by_day.iter().any(|d| d.weekday == date.weekday())
+    }
+
+    fn next_daily(&self, date: NaiveDate) -> Option<NaiveDate> {
+        let step = Days::new(self.interval as u64);
+        let mut candidate = date.checked_add_days(step)?;
+
+        if self.by_day.is_empty() {
+            return Some(candidate);
+        }
+
+        // Stepping by INTERVAL days cycles through at most 7 weekdays
+        for _ in 0..7 {
+            if self.matches_weekday(candidate) {
+                return Some(candidate);
+            }
+            candidate = candidate.checked_add_days(step)?;
+        }
+
+        None
+    }
+
+    fn next_weekly(&self, date: NaiveDate) -> Option<NaiveDate> {
+        if self.by_day.is_empty() {
+            return date.checked_add_days(Days::new(7 * self.interval as u64));
+        }
+
+        // Weeks start on Monday (the RFC 5545 default WKST); only every INTERVAL-th
+        // week counted from the current occurrence's week is eligible.
+        let week_start = date.week(Weekday::Mon).first_day();
+        for offset in 1..=(7 * self.interval as u64 + 7) {
+            let candidate = date.checked_add_days(Days::new(offset))?;
+            let weeks = (candidate.week(Weekday::Mon).first_day() - week_start).num_weeks();
+
+            if weeks % self.interval as i64 == 0 && self.matches_weekday(candidate) {
+                return Some(candidate);
+            }
+        }
+
+        None
+    }
+
+    fn next_monthly(&self, date: NaiveDate) -> Option<NaiveDate> {
+        if self.by_day.is_empty() {
+            // Months without the occurrence's day (e.g. the 31st) are skipped, as per RFC 5545
+            return (1..=48).find_map(|k| {
+                let (year, month) = add_months(date.year(), date.month(), k * self.interval);
+                NaiveDate::from_ymd_opt(year, month, date.day())
+            });
+        }
+
+        // The current month may still contain a later matching day
+        (0..=48).find_map(|k| {
+            let (year, month) = add_months(date.year(), date.month(), k * self.interval);
+            self.monthly_candidates(year, month)
+                .into_iter()
+                .find(|candidate| *candidate > date)
+        })
+    }
+
+    fn monthly_candidates(&self, year: i32, month: u32) -> Vec<NaiveDate> {
+        let mut dates: Vec<NaiveDate> = self
+            .by_day
+            .iter()
+            .flat_map(|by_day| {
+                let all: Vec<NaiveDate> = (1..=31)
+                    .filter_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+                    .filter(|d| d.weekday() == by_day.weekday)
+                    .collect();
+
+                match by_day.ordinal {
+                    None => all,
+                    Some(n) if n > 0 => all.get(n as usize - 1).copied().into_iter().collect(),
+                    Some(n) => all
+                        .len()
+                        .checked_sub(n.unsigned_abs() as usize)
+                        .and_then(|i| all.get(i).copied())
+                        .into_iter()
+                        .collect(),
+                }
+            })
+            .collect();
+
+        dates.sort();
+        dates.dedup();
+        dates
+    }
+
+    fn next_yearly(&self, date: NaiveDate) -> Option<NaiveDate> {
+        // Feb 29 only recurs in leap years
+        (1..=8).find_map(|k| {
+            let year = date.year() + (k * self.interval) as i32;
+            NaiveDate::from_ymd_opt(year, date.month(), date.day())
+        })
+    }
+}
+
+fn add_months(year: i32, month: u32, months: u32) -> (i32, u32) {
+    let zero_based = month - 1 + months;
+    (year + (zero_based / 12) as i32, zero_based % 12 + 1)
+}
+
+fn parse_weekday(s: &str) -> Option<Weekday> {
+    match s {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+fn weekday_code(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Mon => "MO",
+        Weekday::Tue => "TU",
+        Weekday::Wed => "WE",
+        Weekday::Thu => "TH",
+        Weekday::Fri => "FR",
+        Weekday::Sat => "SA",
+        Weekday::Sun => "SU",
+    }
+}
+
+fn parse_by_day(value: &str) -> Result<ByDay, String> {
+    // Splitting off the day code by byte offset needs ASCII input
+    if !value.is_ascii() {
+        return Err(format!("Invalid BYDAY value '{}'", value));
+    }
+    let split = value.len().saturating_sub(2);
+    let (ordinal, day) = value.split_at(split);
+    let weekday = parse_weekday(day).ok_or_else(|| format!("Invalid BYDAY value '{}'", value))?;
+
+    let ordinal = if ordinal.is_empty() {
+        None
+    } else {
+        match ordinal.parse::<i8>() {
+            Ok(n) if n != 0 && (-5..=5).contains(&n) => Some(n),
+            _ => return Err(format!("Invalid BYDAY ordinal in '{}'", value)),
+        }
+    };
+
+    Ok(ByDay { ordinal, weekday })
+}
+
+fn parse_until(value: &str) -> Result<DateTime<Utc>, String> {
+    let invalid = || format!("Invalid UNTIL value '{}'", value);
+
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y%m%d") {
+        // A date-only UNTIL includes occurrences on that day
+        return Ok(date
+            .and_time(NaiveTime::from_hms_opt(23, 59, 59).unwrap())
+            .and_utc());
+    }
+
+    let value = value.strip_suffix('Z').unwrap_or(value);
+    chrono::NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .map(|dt| dt.and_utc())
+        .map_err(|_| invalid())
+}
+
+impl FromStr for RecurrenceRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = s.strip_prefix("RRULE:").unwrap_or(s);
+
+        let mut frequency = None;
+        let mut interval = 1;
+        let mut by_day = Vec::new();
+        let mut count = None;
+        let mut until = None;
+
+        for part in s.split(';').filter(|p| !p.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("Invalid RRULE part '{}'", part))?;
+            let value = value.to_uppercase();
+
+            match key.to_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = Some(match value.as_str() {
+                        "DAILY" => Frequency::Daily,
+                        "WEEKLY" => Frequency::Weekly,
+                        "MONTHLY" => Frequency::Monthly,
+                        "YEARLY" => Frequency::Yearly,
+                        _ => return Err(format!("Unsupported FREQ '{}'", value)),
+                    })
+                }
+                "INTERVAL" => {
+                    interval = match value.parse::<u32>() {
+                        Ok(n) if n > 0 => n,
+                        _ => return Err(format!("Invalid INTERVAL '{}'", value)),
+                    }
+                }
+                "BYDAY" => {
+                    by_day = value
+                        .split(',')
+                        .map(parse_by_day)
+                        .collect::<Result<Vec<_>, _>>()?;
+                }
+                "COUNT" => {
+                    count = match value.parse::<u32>() {
+                        Ok(n) if n > 0 => Some(n),
+                        _ => return Err(format!("Invalid COUNT '{}'", value)),
+                    }
+                }
+                "UNTIL" => until = Some(parse_until(&value)?),
+                other => return Err(format!("Unsupported RRULE part '{}'", other)),
+            }
+        }
+
+        let frequency = frequency.ok_or("RRULE requires FREQ")?;
+
+        if count.is_some() && until.is_some() {
+            return Err("COUNT and UNTIL cannot be combined".to_string());
+        }
+        if frequency == Frequency::Yearly && !by_day.is_empty() {
+            return Err("BYDAY is not supported with FREQ=YEARLY".to_string());
+        }
+        if frequency != Frequency::Monthly && by_day.iter().any(|d| d.ordinal.is_some()) {
+            return Err("BYDAY ordinals are only supported with FREQ=MONTHLY".to_string());
+        }
+
+        Ok(RecurrenceRule {
+            frequency,
+            interval,
+            by_day,
+            count,
+            until,
+        })
+    }
+}
+
+impl fmt::Display for RecurrenceRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let frequency = match self.frequency {
+            Frequency::Daily => "DAILY",
+            Frequency::Weekly => "WEEKLY",
+            Frequency::Monthly => "MONTHLY",
+            Frequency::Yearly => "YEARLY",
+        };
+        write!(f, "FREQ={}", frequency)?;
+
+        if self.interval != 1 {
+            write!(f, ";INTERVAL={}", self.interval)?;
+        }
+        if !self.by_day.is_empty() {
+            let days: Vec<String> = self
+                .by_day
+                .iter()
+                .map(|d| match d.ordinal {
+                    Some(n) => format!("{}{}", n, weekday_code(d.weekday)),
+                    None => weekday_code(d.weekday).to_string(),
+                })
+                .collect();
+            write!(f, ";BYDAY={}", days.join(","))?;
+        }
+        if let Some(count) = self.count {
+            write!(f, ";COUNT={}", count)?;
+        }
+        if let Some(until) = self.until {
+            write!(f, ";UNTIL={}", until.format("%Y%m%dT%H%M%SZ"))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn rule(value: &str) -> RecurrenceRule {
+        value.parse().unwrap()
+    }
+
+    fn at(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, 9, 30, 0).unwrap()
+    }
+
+    #[test]
+    fn parses_and_normalizes_rules() {
+        let parsed = rule("RRULE:freq=weekly;INTERVAL=2;BYDAY=mo,we;COUNT=5");
+
+        assert_eq!(parsed.frequency, Frequency::Weekly);
+        assert_eq!(parsed.interval, 2);
+        assert_eq!(parsed.count, Some(5));
+        assert_eq!(
+            parsed.to_string(),
+            "FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE;COUNT=5"
+        );
+        assert_eq!(
+            rule("FREQ=MONTHLY;BYDAY=-1FR;UNTIL=20261231").to_string(),
+            "FREQ=MONTHLY;BYDAY=-1FR;UNTIL=20261231T235959Z"
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_rules() {
+        for value in [
+            "INTERVAL=2",
+            "FREQ=HOURLY",
+            "FREQ=DAILY;INTERVAL=0",
+            "FREQ=DAILY;COUNT=3;UNTIL=20261231",
+            "FREQ=WEEKLY;BYDAY=2TU",
+            "FREQ=YEARLY;BYDAY=MO",
+            "FREQ=MONTHLY;BYDAY=6MO",
+            "FREQ=DAILY;BYSETPOS=1",
+        ] {
+            assert!(value.parse::<RecurrenceRule>().is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn rejects_non_ascii_by_day_values() {
+        for value in [
+            "FREQ=WEEKLY;BYDAY=éO",
+            "FREQ=MONTHLY;BYDAY=1ΜΟ",
+            "FREQ=WEEKLY;BYDAY=é",
+        ] {
+            assert!(value.parse::<RecurrenceRule>().is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn daily_steps_by_interval_and_keeps_the_time() {
+        assert_eq!(
+            rule("FREQ=DAILY;INTERVAL=3").next_after(at(2026, 3, 1), 1),
+            Some(at(2026, 3, 4))
+        );
+    }
+
+    #[test]
+    fn daily_with_by_day_skips_other_weekdays() {
+        // Friday to Monday
+        assert_eq!(
+            rule("FREQ=DAILY;BYDAY=MO,FR").next_after(at(2026, 3, 6), 1),
+            Some(at(2026, 3, 9))
+        );
+    }
+
+    #[test]
+    fn weekly_with_interval_only_uses_every_other_week() {
+        let biweekly = rule("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,WE");
+
+        // Monday to Wednesday of the same week, then on to Monday two weeks on
+        assert_eq!(biweekly.next_after(at(2026, 3, 2), 1), Some(at(2026, 3, 4)));
+        assert_eq!(
+            biweekly.next_after(at(2026, 3, 4), 2),
+            Some(at(2026, 3, 16))
+        );
+    }
+
+    #[test]
+    fn monthly_skips_months_without_the_day() {
+        assert_eq!(
+            rule("FREQ=MONTHLY").next_after(at(2026, 1, 31), 1),
+            Some(at(2026, 3, 31))
+        );
+    }
+
+    #[test]
+    fn monthly_by_day_ordinals() {
+        assert_eq!(
+            rule("FREQ=MONTHLY;BYDAY=2TU").next_after(at(2026, 3, 10), 1),
+            Some(at(2026, 4, 14))
+        );
+        assert_eq!(
+            rule("FREQ=MONTHLY;BYDAY=-1FR").next_after(at(2026, 3, 27), 1),
+            Some(at(2026, 4, 24))
+        );
+    }
+
+    #[test]
+    fn yearly_on_leap_day_waits_for_the_next_leap_year() {
+        assert_eq!(
+            rule("FREQ=YEARLY").next_after(at(2024, 2, 29), 1),
+            Some(at(2028, 2, 29))
+        );
+    }
+
+    #[test]
+    fn count_ends_the_series() {
+        let three_times = rule("FREQ=DAILY;COUNT=3");
+
+        assert_eq!(
+            three_times.next_after(at(2026, 3, 2), 2),
+            Some(at(2026, 3, 3))
+        );
+        assert_eq!(three_times.next_after(at(2026, 3, 3), 3), None);
+    }
+
+    #[test]
+    fn date_only_until_includes_that_day() {
+        let until_7th = rule("FREQ=DAILY;UNTIL=20260307");
+
+        assert_eq!(
+            until_7th.next_after(at(2026, 3, 6), 1),
+            Some(at(2026, 3, 7))
+        );
+        assert_eq!(until_7th.next_after(at(2026, 3, 7), 2), None);
+    }
+}
@@ -0,0 +1,399 @@
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use reqwest::{
+    Url,
+    dns::{Addrs, Name, Resolve, Resolving},
+    redirect::Policy,
+};
+use sha2::Sha256;
+use sqlx::MySqlPool;
+
+use crate::{
+    models::webhook_model::{AttemptOutcome, Webhook, WebhookDelivery, WebhookTargets},
+    schema::webhook_schema::record_delivery_attempt,
+};
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+// Whether the address is reachable from the internet at large, as opposed to
+// loopback, private networks, link-local (which includes cloud metadata
+// endpoints) and the like
+pub fn is_public_address(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || a == 0
+                // Carrier-grade NAT, 100.64.0.0/10
+                || (a == 100 && (64..128).contains(&b)))
+        }
+        IpAddr::V6(ip) => {
+            if let Some(mapped) = ip.to_ipv4_mapped() {
+                return is_public_address(IpAddr::V4(mapped));
+            }
+            let first = ip.segments()[0];
+            !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // Unique local, fc00::/7
+                || (first & 0xfe00) == 0xfc00
+                // Link-local, fe80::/10
+                || (first & 0xffc0) == 0xfe80)
+        }
+    }
+}
+
+// Checks that a webhook url is an absolute http(s) URL and, unless any address
+// is allowed, that its host only resolves to public addresses. Plain http is
+// allowed so webhooks can be tried against a local receiver.
+pub async fn validate_webhook_url(url: &str, targets: WebhookTargets) -> Result<(), String> {
+    let parsed = match Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") && parsed.has_host() => parsed,
+        _ => return Err("Webhook url must be an absolute http or https URL".to_string()),
+    };
+
+    if targets == WebhookTargets::AnyAddress {
+        return Ok(());
+    }
+
+    let host = parsed.host_str().unwrap_or_default();
+    let port = parsed.port_or_known_default().unwrap_or(80);
+    // IPv6 literals come bracketed
+    let addresses: Vec<IpAddr> = match host.trim_matches(['[', ']']).parse::<IpAddr>() {
+        Ok(ip) => vec![ip],
+        Err(_) => match tokio::net::lookup_host((host, port)).await {
+            Ok(addresses) => addresses.map(|address| address.ip()).collect(),
+            Err(_) => return Err("Webhook url host could not be resolved".to_string()),
+        },
+    };
+
+    if addresses.is_empty() || !addresses.into_iter().all(is_public_address) {
+        return Err("Webhook url must point to a public address".to_string());
+    }
+    Ok(())
+}
+
+// Resolves hosts like the system resolver but drops non-public addresses. A
+// host that passed validate_webhook_url could resolve elsewhere by the time a
+// delivery is sent, so the check is repeated for every connection.
+struct PublicAddressResolver;
+
+impl Resolve for PublicAddressResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        Box::pin(async move {
+            let addresses: Vec<SocketAddr> = tokio::net::lookup_host((name.as_str(), 0))
+                .await?
+                .filter(|address| is_public_address(address.ip()))
+                .collect();
+
+            if addresses.is_empty() {
+                return Err(format!("{} has no public address", name.as_str()).into());
+            }
+            Ok(Box::new(addresses.into_iter()) as Addrs)
+        })
+    }
+}
+
+// Client for sending deliveries. Redirects aren't followed, since they could
+// lead anywhere.
+pub fn build_webhook_client(targets: WebhookTargets) -> reqwest::Result<reqwest::Client> {
+    let builder = reqwest::Client::builder()
+        .timeout(WEBHOOK_TIMEOUT)
+        .redirect(Policy::none());
+
+    match targets {
+        WebhookTargets::PublicOnly => builder.dns_resolver(Arc::new(PublicAddressResolver)),
+        WebhookTargets::AnyAddress => builder,
+    }
+    .build()
+}
+
+// HMAC-SHA256 over "<timestamp>.<body>", hex encoded. Signing the timestamp
+// lets receivers reject replayed requests.
+pub fn sign_payload(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+async fn post_delivery(
+    client: &reqwest::Client,
+    webhook: &Webhook,
+    delivery: &WebhookDelivery,
+) -> AttemptOutcome {
+    let timestamp = Utc::now().timestamp();
+    let signature = sign_payload(&webhook.secret, timestamp, &delivery.payload);
+    let started = Instant::now();
+
+    let result = client
+        .post(&webhook.url)
+        .header("Content-Type", "application/json")
+        .header("User-Agent", "todo-app-webhooks/1")
+        .header("X-Webhook-Id", &webhook.id)
+        .header("X-Webhook-Delivery", &delivery.id)
+        .header("X-Webhook-Event", delivery.event_type.as_str())
+        .header("X-Webhook-Timestamp", timestamp.to_string())
+        .header("X-Signature", format!("sha256={}", signature))
+        .body(delivery.payload.clone())
+        .send()
+        .await;
+
+    // The receiver's response body isn't kept; it's theirs and may say
+    // anything, so the status code has to do
+    let (response_code, error) = match result {
+        Ok(response) if response.status().is_success() => (Some(response.status().as_u16()), None),
+        Ok(response) => {
+            let status = response.status();
+            (
+                Some(status.as_u16()),
+                Some(format!("Receiver responded with {}", status)),
+            )
+        }
+        Err(err) => (None, Some(err.to_string())),
+    };
+
+    AttemptOutcome {
+        response_code: response_code.map(i32::from),
+        error,
+        duration_ms: started.elapsed().as_millis().min(i32::MAX as u128) as i32,
+    }
+}
+
+// Makes one attempt at a delivery and records how it went
+pub async fn attempt_delivery(
+    pool: &MySqlPool,
+    client: &reqwest::Client,
+    webhook: &Webhook,
+    delivery: &mut WebhookDelivery,
+) -> Result<()> {
+    let attempted_at = Utc::now();
+    let outcome = post_delivery(client, webhook, delivery).await;
+
+    delivery.record(&outcome, Utc::now());
+    record_delivery_attempt(pool, delivery, &outcome, attempted_at).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use actix_web::rt::{self, task::JoinHandle};
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+    use crate::models::webhook_model::{DeliveryStatus, WebhookEvent};
+
+    #[test]
+    fn signs_timestamp_and_body() {
+        assert_eq!(
+            sign_payload("whsec_test", 1760000000, r#"{"id":"1"}"#),
+            "4f409249923e3c71e7fbc42757a3525f09cc51e7a8eccd12a1e26692e08d946e"
+        );
+    }
+
+    #[test]
+    fn classifies_addresses() {
+        for public in ["93.184.216.34", "2606:2800:220:1:248:1893:25c8:1946"] {
+            assert!(is_public_address(public.parse().unwrap()), "{}", public);
+        }
+        for private in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.16.0.1",
+            "192.168.1.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "::1",
+            "fd00::1",
+            "fe80::1",
+            "::ffff:127.0.0.1",
+        ] {
+            assert!(!is_public_address(private.parse().unwrap()), "{}", private);
+        }
+    }
+
+    #[actix_web::test]
+    async fn validates_urls_without_resolving_ip_literals() {
+        let public_only = WebhookTargets::PublicOnly;
+
+        assert!(
+            validate_webhook_url("https://93.184.216.34/hooks", public_only)
+                .await
+                .is_ok()
+        );
+        assert!(
+            validate_webhook_url("http://169.254.169.254/latest", public_only)
+                .await
+                .is_err()
+        );
+        assert!(
+            validate_webhook_url("http://[::1]:8080/", public_only)
+                .await
+                .is_err()
+        );
+        assert!(
+            validate_webhook_url("http://127.0.0.1:9000/", WebhookTargets::AnyAddress)
+                .await
+                .is_ok()
+        );
+        assert!(
+            validate_webhook_url("ftp://93.184.216.34/", WebhookTargets::AnyAddress)
+                .await
+                .is_err()
+        );
+    }
+
+    // Accepts one connection, answers it with `response` and returns the raw
+    // request it read
+    async fn receive_once(response: &'static str) -> (u16, JoinHandle<String>) {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let receiver = rt::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut request = Vec::new();
+            let mut buffer = [0; 4096];
+            loop {
+                let read = socket.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..read]);
+                let text = String::from_utf8_lossy(&request);
+                if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                    let length = header(head, "content-length")
+                        .and_then(|value| value.parse::<usize>().ok())
+                        .unwrap_or(0);
+                    if body.len() >= length {
+                        break;
+                    }
+                }
+                if read == 0 {
+                    break;
+                }
+            }
+            socket.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8(request).unwrap()
+        });
+
+        (port, receiver)
+    }
+
+    fn header<'a>(request: &'a str, name: &str) -> Option<&'a str> {
+        request.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.eq_ignore_ascii_case(name).then(|| value.trim())
+        })
+    }
+
+    fn webhook(url: String) -> Webhook {
+        Webhook::new(
+            "user".to_string(),
+            url,
+            "whsec_test".to_string(),
+            WebhookEvent::TODO_EVENTS.to_vec(),
+        )
+    }
+
+    #[actix_web::test]
+    async fn posts_signed_deliveries() {
+        let (port, receiver) =
+            receive_once("HTTP/1.1 204 No Content\r\nConnection: close\r\n\r\n").await;
+        let webhook = webhook(format!("http://127.0.0.1:{}/hooks", port));
+        let delivery = WebhookDelivery::new(&webhook, WebhookEvent::TodoCreated, None);
+        let client = build_webhook_client(WebhookTargets::AnyAddress).unwrap();
+
+        let outcome = post_delivery(&client, &webhook, &delivery).await;
+        let request = receiver.await.unwrap();
+
+        assert_eq!(outcome.response_code, Some(204));
+        assert_eq!(outcome.error, None);
+        assert!(request.starts_with("POST /hooks HTTP/1.1\r\n"));
+        assert!(request.ends_with(&delivery.payload));
+        assert_eq!(header(&request, "x-webhook-event"), Some("todo.created"));
+        assert_eq!(
+            header(&request, "x-webhook-delivery"),
+            Some(delivery.id.as_str())
+        );
+
+        let timestamp: i64 = header(&request, "x-webhook-timestamp")
+            .unwrap()
+            .parse()
+            .unwrap();
+        let expected = format!(
+            "sha256={}",
+            sign_payload(&webhook.secret, timestamp, &delivery.payload)
+        );
+        assert_eq!(header(&request, "x-signature"), Some(expected.as_str()));
+    }
+
+    #[actix_web::test]
+    async fn records_error_responses_without_their_body() {
+        let (port, receiver) = receive_once(
+            "HTTP/1.1 500 Internal Server Error\r\nContent-Length: 6\r\n\
+             Connection: close\r\n\r\nsecret",
+        )
+        .await;
+        let webhook = webhook(format!("http://127.0.0.1:{}/hooks", port));
+        let mut delivery = WebhookDelivery::new(&webhook, WebhookEvent::TodoUpdated, None);
+        let client = build_webhook_client(WebhookTargets::AnyAddress).unwrap();
+
+        let outcome = post_delivery(&client, &webhook, &delivery).await;
+        receiver.await.unwrap();
+        delivery.record(&outcome, Utc::now());
+
+        assert_eq!(outcome.response_code, Some(500));
+        assert_eq!(
+            outcome.error.as_deref(),
+            Some("Receiver responded with 500 Internal Server Error")
+        );
+        assert_eq!(delivery.status, DeliveryStatus::Pending);
+    }
+
+    #[actix_web::test]
+    async fn does_not_follow_redirects() {
+        let (port, receiver) = receive_once(
+            "HTTP/1.1 302 Found\r\nLocation: http://169.254.169.254/\r\n\
+             Content-Length: 0\r\nConnection: close\r\n\r\n",
+        )
+        .await;
+        let webhook = webhook(format!("http://127.0.0.1:{}/hooks", port));
+        let delivery = WebhookDelivery::new(&webhook, WebhookEvent::TodoDeleted, None);
+        let client = build_webhook_client(WebhookTargets::AnyAddress).unwrap();
+
+        let outcome = post_delivery(&client, &webhook, &delivery).await;
+        receiver.await.unwrap();
+
+        assert_eq!(outcome.response_code, Some(302));
+        assert!(!outcome.is_success());
+    }
+
+    #[actix_web::test]
+    async fn public_only_client_refuses_hosts_resolving_to_private_addresses() {
+        let webhook = webhook("http://localhost:9/hooks".to_string());
+        let delivery = WebhookDelivery::new(&webhook, WebhookEvent::TodoCreated, None);
+        let client = build_webhook_client(WebhookTargets::PublicOnly).unwrap();
+
+        let outcome = post_delivery(&client, &webhook, &delivery).await;
+
+        assert_eq!(outcome.response_code, None);
+        assert!(outcome.error.is_some());
+    }
+}
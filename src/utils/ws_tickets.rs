@@ -0,0 +1,128 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use uuid::Uuid;
+
+use crate::middleware::auth_middleware::AuthenticatedUser;
+
+pub const TICKET_TTL: Duration = Duration::from_secs(30);
+
+// Short-lived, single-use tickets for opening a WebSocket. Browsers can't set
+// headers on the handshake, and a ticket in the URL is harmless in access logs
+// where a bearer token wouldn't be.
+pub struct WsTicketStore {
+    tickets: Mutex<HashMap<String, (AuthenticatedUser, Instant)>>,
+}
+
+impl WsTicketStore {
+    pub fn new() -> Self {
+        WsTicketStore {
+            tickets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn issue(&self, user: AuthenticatedUser) -> String {
+        self.issue_at(user, Instant::now())
+    }
+
+    // The ticket's user, if it was issued and hasn't been used or expired yet
+    pub fn redeem(&self, ticket: &str) -> Option<AuthenticatedUser> {
+        self.redeem_at(ticket, Instant::now())
+    }
+
+    fn issue_at(&self, user: AuthenticatedUser, now: Instant) -> String {
+        let ticket = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+
+        let mut tickets = self.tickets.lock().unwrap_or_else(|e| e.into_inner());
+        tickets.retain(|_, (_, issued_at)| now.duration_since(*issued_at) < TICKET_TTL);
+        tickets.insert(ticket.clone(), (user, now));
+
+        ticket
+    }
+
+    fn redeem_at(&self, ticket: &str, now: Instant) -> Option<AuthenticatedUser> {
+        let mut tickets = self.tickets.lock().unwrap_or_else(|e| e.into_inner());
+        let (user, issued_at) = tickets.remove(ticket)?;
+
+        (now.duration_since(issued_at) < TICKET_TTL).then_some(user)
+    }
+}
+
+impl Default for WsTicketStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user() -> AuthenticatedUser {
+        AuthenticatedUser {
+            user_id: Uuid::new_v4(),
+            email: "user@example.com".to_string(),
+            role: "user".to_string(),
+        }
+    }
+
+    fn stored(store: &WsTicketStore) -> usize {
+        store.tickets.lock().unwrap().len()
+    }
+
+    #[test]
+    fn tickets_can_be_used_once() {
+        let store = WsTicketStore::new();
+        let user = user();
+        let ticket = store.issue(user.clone());
+
+        assert_eq!(store.redeem(&ticket).map(|u| u.user_id), Some(user.user_id));
+        assert!(store.redeem(&ticket).is_none());
+    }
+
+    #[test]
+    fn unknown_tickets_are_rejected() {
+        let store = WsTicketStore::new();
+        store.issue(user());
+
+        assert!(store.redeem("").is_none());
+        assert!(store.redeem("made-up").is_none());
+    }
+
+    #[test]
+    fn tickets_expire() {
+        let store = WsTicketStore::new();
+        let issued_at = Instant::now();
+        let fresh = store.issue_at(user(), issued_at);
+        let stale = store.issue_at(user(), issued_at);
+
+        assert!(
+            store
+                .redeem_at(&fresh, issued_at + TICKET_TTL - Duration::from_secs(1))
+                .is_some()
+        );
+        assert!(store.redeem_at(&stale, issued_at + TICKET_TTL).is_none());
+        // Expired tickets are gone, not just refused
+        assert_eq!(stored(&store), 0);
+    }
+
+    #[test]
+    fn issuing_drops_expired_tickets() {
+        let store = WsTicketStore::new();
+        let issued_at = Instant::now();
+        store.issue_at(user(), issued_at);
+        let kept = store.issue_at(user(), issued_at + TICKET_TTL - Duration::from_secs(1));
+
+        store.issue_at(user(), issued_at + TICKET_TTL);
+
+        assert_eq!(stored(&store), 2);
+        assert!(
+            store
+                .redeem_at(&kept, issued_at + TICKET_TTL + Duration::from_secs(1))
+                .is_some()
+        );
+    }
+}